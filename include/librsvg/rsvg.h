@@ -757,10 +757,23 @@ gboolean rsvg_handle_has_sub (RsvgHandle *handle, const char *id);
  * @RSVG_UNIT_PT: points, or 1/72 inch
  * @RSVG_UNIT_PC: picas, or 1/6 inch (12 points)
  * @RSVG_UNIT_CH:
+ * @RSVG_UNIT_REM: font size of the root element (since 2.63)
+ * @RSVG_UNIT_VW: 1% of the width of the outermost viewport (since 2.63)
+ * @RSVG_UNIT_VH: 1% of the height of the outermost viewport (since 2.63)
+ * @RSVG_UNIT_VMIN: 1% of the smaller dimension of the outermost viewport (since 2.63)
+ * @RSVG_UNIT_VMAX: 1% of the larger dimension of the outermost viewport (since 2.63)
+ * @RSVG_UNIT_LH: line height of the current element (since 2.63)
+ * @RSVG_UNIT_RLH: line height of the root element (since 2.63)
+ * @RSVG_UNIT_IC: advance measure of a CJK ideograph in the current font (since 2.63)
+ * @RSVG_UNIT_CAP: cap height of the current font (since 2.63)
+ * @RSVG_UNIT_Q: quarter-millimeters (since 2.63)
  *
  * Units for the `RsvgLength` struct.  These have the same meaning as [CSS length
  * units](https://www.w3.org/TR/CSS21/syndata.html#length-units).
  *
+ * Librsvg does not return lengths with the units added in 2.63; it resolves them to
+ * pixels, or to a percentage for the units that depend on the viewport.
+ *
  * If you test for the values of this enum, please note that librsvg may add other units in the future
  * as its support for CSS improves.  Please make your code handle unknown units gracefully (e.g. with
  * a `default` case in a `switch()` statement).
@@ -784,6 +797,16 @@ typedef enum {
     RSVG_UNIT_PT,
     RSVG_UNIT_PC,
     RSVG_UNIT_CH,
+    RSVG_UNIT_REM,
+    RSVG_UNIT_VW,
+    RSVG_UNIT_VH,
+    RSVG_UNIT_VMIN,
+    RSVG_UNIT_VMAX,
+    RSVG_UNIT_LH,
+    RSVG_UNIT_RLH,
+    RSVG_UNIT_IC,
+    RSVG_UNIT_CAP,
+    RSVG_UNIT_Q,
 } RsvgUnit;
 
 /**
@@ -946,6 +969,217 @@ gboolean rsvg_handle_get_intrinsic_size_in_pixels (RsvgHandle *handle,
                                                    gdouble    *out_width,
                                                    gdouble    *out_height);
 
+/* Document tree */
+
+/**
+ * RsvgElement:
+ *
+ * A read-only reference to an element in the document tree of an [class@Rsvg.Handle].
+ *
+ * Get the toplevel element with [method@Rsvg.Handle.get_root_element], and walk the tree
+ * from there with [method@Rsvg.Element.get_first_child],
+ * [method@Rsvg.Element.get_next_sibling], and [method@Rsvg.Element.get_parent].  Text
+ * nodes are skipped while walking; use [method@Rsvg.Element.get_text_content] to get at
+ * the text.
+ *
+ * The names, ids, classes, and attribute values are returned as they appear in the SVG
+ * source, before any CSS is applied.
+ *
+ * An `RsvgElement` keeps the document tree alive, so it can be used even after the
+ * [class@Rsvg.Handle] it came from is freed.  Free it with [method@Rsvg.Element.free].
+ *
+ * Since: 2.63
+ */
+typedef struct _RsvgElement RsvgElement;
+
+RSVG_API
+GType rsvg_element_get_type (void);
+#define RSVG_TYPE_ELEMENT (rsvg_element_get_type ())
+
+/**
+ * rsvg_handle_get_root_element:
+ * @handle: An [class@Rsvg.Handle]
+ *
+ * Gets the toplevel element of the SVG document, usually an `<svg>` element.
+ *
+ * API ordering: This function must be called on a fully-loaded @handle.  See
+ * the section "[API ordering](class.Handle.html#api-ordering)" for details.
+ *
+ * Returns: (transfer full) (nullable): the root element, or `NULL` if the @handle is
+ * not loaded.  Free it with [method@Rsvg.Element.free].
+ *
+ * Since: 2.63
+ */
+RSVG_API
+RsvgElement *rsvg_handle_get_root_element (RsvgHandle *handle);
+
+/**
+ * rsvg_element_copy:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Makes a copy of @element that refers to the same element in the document.
+ *
+ * Returns: (transfer full): a copy of @element
+ *
+ * Since: 2.63
+ */
+RSVG_API
+RsvgElement *rsvg_element_copy (RsvgElement *element);
+
+/**
+ * rsvg_element_free:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Frees @element.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+void rsvg_element_free (RsvgElement *element);
+
+/**
+ * rsvg_element_get_name:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the local name of the element, like `rect` or `g`.
+ *
+ * Returns: (transfer full): the element's name.  Free it with `g_free()`.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+char *rsvg_element_get_name (RsvgElement *element);
+
+/**
+ * rsvg_element_get_namespace:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the namespace URI of the element.  For SVG elements this is
+ * `http://www.w3.org/2000/svg`.
+ *
+ * Returns: (transfer full): the element's namespace URI.  Free it with `g_free()`.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+char *rsvg_element_get_namespace (RsvgElement *element);
+
+/**
+ * rsvg_element_get_id:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the value of the element's `id` attribute.
+ *
+ * Returns: (transfer full) (nullable): the element's id, or `NULL` if it does not
+ * have one.  Free it with `g_free()`.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+char *rsvg_element_get_id (RsvgElement *element);
+
+/**
+ * rsvg_element_get_classes:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the whitespace-separated names in the element's `class` attribute.
+ *
+ * Returns: (transfer full) (array zero-terminated=1): the class names; the array is
+ * empty if the element has no classes.  Free it with `g_strfreev()`.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+char **rsvg_element_get_classes (RsvgElement *element);
+
+/**
+ * rsvg_element_get_attribute:
+ * @element: An [struct@Rsvg.Element]
+ * @name: The name of an attribute
+ *
+ * Gets the raw value of an attribute.  Attributes in a namespace other than the
+ * element's own are named with their prefix, like `xlink:href` or `xml:space`.
+ *
+ * Returns: (transfer full) (nullable): the attribute's value, or `NULL` if the element
+ * does not have that attribute.  Free it with `g_free()`.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+char *rsvg_element_get_attribute (RsvgElement *element, const char *name);
+
+/**
+ * rsvg_element_get_attribute_names:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the names of all the element's attributes, in document order.  The names are
+ * formatted as for [method@Rsvg.Element.get_attribute].
+ *
+ * Returns: (transfer full) (array zero-terminated=1): the attribute names.  Free them
+ * with `g_strfreev()`.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+char **rsvg_element_get_attribute_names (RsvgElement *element);
+
+/**
+ * rsvg_element_get_parent:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the parent of @element.
+ *
+ * Returns: (transfer full) (nullable): the parent element, or `NULL` for the root
+ * element.  Free it with [method@Rsvg.Element.free].
+ *
+ * Since: 2.63
+ */
+RSVG_API
+RsvgElement *rsvg_element_get_parent (RsvgElement *element);
+
+/**
+ * rsvg_element_get_first_child:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the first child element of @element.
+ *
+ * Returns: (transfer full) (nullable): the first child element, or `NULL` if @element
+ * has no child elements.  Free it with [method@Rsvg.Element.free].
+ *
+ * Since: 2.63
+ */
+RSVG_API
+RsvgElement *rsvg_element_get_first_child (RsvgElement *element);
+
+/**
+ * rsvg_element_get_next_sibling:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the element that follows @element within its parent.
+ *
+ * Returns: (transfer full) (nullable): the next sibling element, or `NULL` if @element
+ * is the last one.  Free it with [method@Rsvg.Element.free].
+ *
+ * Since: 2.63
+ */
+RSVG_API
+RsvgElement *rsvg_element_get_next_sibling (RsvgElement *element);
+
+/**
+ * rsvg_element_get_text_content:
+ * @element: An [struct@Rsvg.Element]
+ *
+ * Gets the concatenated text of all the descendants of @element, without
+ * whitespace normalization.
+ *
+ * Returns: (transfer full): the text content, which may be an empty string.  Free it
+ * with `g_free()`.
+ *
+ * Since: 2.63
+ */
+RSVG_API
+char *rsvg_element_get_text_content (RsvgElement *element);
+
 /* GIO APIs */
 
 /**
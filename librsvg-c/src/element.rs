@@ -0,0 +1,194 @@
+//! C API for walking the document tree of an `RsvgHandle`.
+//!
+//! This is the implementation of the `RsvgElement` boxed type and of the
+//! `rsvg_element_*` family of functions.  An `RsvgElement` is a thin wrapper
+//! over [`rsvg::ElementRef`].
+
+use std::ptr;
+
+use glib::prelude::*;
+use glib::translate::*;
+
+/// Rust version of the `RsvgElement` boxed type in C.
+#[derive(Clone, glib::Boxed)]
+#[boxed_type(name = "RsvgElement")]
+pub struct RsvgElement(rsvg::ElementRef);
+
+impl From<rsvg::ElementRef> for RsvgElement {
+    fn from(element: rsvg::ElementRef) -> RsvgElement {
+        RsvgElement(element)
+    }
+}
+
+fn get_element_ref<'a>(element: *const RsvgElement) -> &'a rsvg::ElementRef {
+    let element = unsafe { &*element };
+    &element.0
+}
+
+fn new_element(element: Option<rsvg::ElementRef>) -> *mut RsvgElement {
+    element.map(RsvgElement::from).to_glib_full()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_type() -> glib::ffi::GType {
+    RsvgElement::static_type().into_glib()
+}
+
+#[unsafe(no_mangle)]
+#[allow(unsafe_op_in_unsafe_fn)]
+pub unsafe extern "C" fn rsvg_element_copy(element: *const RsvgElement) -> *mut RsvgElement {
+    rsvg_return_val_if_fail! {
+        rsvg_element_copy => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    glib::gobject_ffi::g_boxed_copy(rsvg_element_get_type(), element as glib::ffi::gconstpointer)
+        as *mut RsvgElement
+}
+
+#[unsafe(no_mangle)]
+#[allow(unsafe_op_in_unsafe_fn)]
+pub unsafe extern "C" fn rsvg_element_free(element: *mut RsvgElement) {
+    rsvg_return_if_fail! {
+        rsvg_element_free;
+
+        !element.is_null(),
+    }
+
+    glib::gobject_ffi::g_boxed_free(rsvg_element_get_type(), element as glib::ffi::gpointer);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_name(element: *const RsvgElement) -> *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_name => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    get_element_ref(element).name().to_glib_full()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_namespace(
+    element: *const RsvgElement,
+) -> *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_namespace => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    get_element_ref(element).namespace().to_glib_full()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_id(element: *const RsvgElement) -> *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_id => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    get_element_ref(element).id().to_glib_full()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_classes(
+    element: *const RsvgElement,
+) -> *mut *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_classes => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    glib::StrV::from(get_element_ref(element).classes()).into_raw()
+}
+
+#[unsafe(no_mangle)]
+#[allow(unsafe_op_in_unsafe_fn)]
+pub unsafe extern "C" fn rsvg_element_get_attribute(
+    element: *const RsvgElement,
+    name: *const libc::c_char,
+) -> *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_attribute => ptr::null_mut();
+
+        !element.is_null(),
+        !name.is_null(),
+    }
+
+    let name: String = from_glib_none(name);
+    get_element_ref(element).attribute(&name).to_glib_full()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_attribute_names(
+    element: *const RsvgElement,
+) -> *mut *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_attribute_names => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    let names: Vec<String> = get_element_ref(element)
+        .attributes()
+        .into_iter()
+        .map(|(name, _value)| name)
+        .collect();
+
+    glib::StrV::from(names).into_raw()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_parent(element: *const RsvgElement) -> *mut RsvgElement {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_parent => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    new_element(get_element_ref(element).parent())
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_first_child(
+    element: *const RsvgElement,
+) -> *mut RsvgElement {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_first_child => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    new_element(get_element_ref(element).children().next())
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_next_sibling(
+    element: *const RsvgElement,
+) -> *mut RsvgElement {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_next_sibling => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    new_element(get_element_ref(element).next_sibling())
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_element_get_text_content(
+    element: *const RsvgElement,
+) -> *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_element_get_text_content => ptr::null_mut();
+
+        !element.is_null(),
+    }
+
+    get_element_ref(element).text_content().to_glib_full()
+}
@@ -44,6 +44,7 @@ use rsvg::c_api_only::{Session, rsvg_log};
 use rsvg::{CairoRenderer, IntrinsicDimensions, Length, Loader, LoadingError, SvgHandle};
 
 use super::dpi::Dpi;
+use super::element::RsvgElement;
 use super::messages::{rsvg_g_critical, rsvg_g_warning};
 
 #[cfg(feature = "pixbuf")]
@@ -654,6 +655,11 @@ impl CHandle {
         Ok(handle.has_element_with_id(id)?)
     }
 
+    fn get_root_element(&self) -> Result<rsvg::ElementRef, RenderingError> {
+        let handle = self.get_handle_ref()?;
+        Ok(handle.root())
+    }
+
     fn get_dimensions_or_empty(&self) -> RsvgDimensionData {
         self.get_dimensions_sub(None)
             .unwrap_or_else(|_| RsvgDimensionData::empty())
@@ -1235,6 +1241,24 @@ pub unsafe extern "C" fn rsvg_handle_has_sub(
     rhandle.has_sub(&id).unwrap_or(false).into_glib()
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsvg_handle_get_root_element(
+    handle: *const RsvgHandle,
+) -> *mut RsvgElement {
+    rsvg_return_val_if_fail! {
+        rsvg_handle_get_root_element => ptr::null_mut();
+
+        is_rsvg_handle(handle),
+    }
+
+    let rhandle = get_rust_handle(handle);
+
+    match rhandle.get_root_element() {
+        Ok(element) => RsvgElement::from(element).to_glib_full(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn rsvg_handle_render_cairo(
     handle: *const RsvgHandle,
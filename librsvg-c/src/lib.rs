@@ -18,6 +18,7 @@ pub use handle::{
     rsvg_handle_get_intrinsic_dimensions,
     rsvg_handle_get_intrinsic_size_in_pixels,
     rsvg_handle_get_position_sub,
+    rsvg_handle_get_root_element,
     rsvg_handle_has_sub,
     rsvg_handle_internal_set_testing,
     rsvg_handle_new_from_data,
@@ -42,6 +43,23 @@ pub use handle::rsvg_handle_get_pixbuf_sub;
 
 pub use dpi::{rsvg_set_default_dpi, rsvg_set_default_dpi_x_y};
 
+#[rustfmt::skip]
+pub use element::{
+    rsvg_element_copy,
+    rsvg_element_free,
+    rsvg_element_get_attribute,
+    rsvg_element_get_attribute_names,
+    rsvg_element_get_classes,
+    rsvg_element_get_first_child,
+    rsvg_element_get_id,
+    rsvg_element_get_name,
+    rsvg_element_get_namespace,
+    rsvg_element_get_next_sibling,
+    rsvg_element_get_parent,
+    rsvg_element_get_text_content,
+    rsvg_element_get_type,
+};
+
 #[rustfmt::skip]
 #[cfg(feature = "pixbuf")]
 pub use pixbuf_utils::{
@@ -56,6 +74,7 @@ pub use pixbuf_utils::{
 mod messages;
 
 mod dpi;
+pub mod element;
 pub mod handle;
 
 #[cfg(feature = "pixbuf")]
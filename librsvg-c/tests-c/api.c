@@ -557,6 +557,69 @@ handle_has_sub (void)
     g_object_unref (handle);
 }
 
+static void
+element_tree (void)
+{
+    RsvgHandle *handle = load_test_document ("example.svg");
+
+    RsvgElement *root = rsvg_handle_get_root_element (handle);
+    g_assert_nonnull (root);
+    g_object_unref (handle);
+
+    char *name = rsvg_element_get_name (root);
+    g_assert_cmpstr (name, ==, "svg");
+    g_free (name);
+
+    char *ns = rsvg_element_get_namespace (root);
+    g_assert_cmpstr (ns, ==, "http://www.w3.org/2000/svg");
+    g_free (ns);
+
+    g_assert_null (rsvg_element_get_id (root));
+    g_assert_null (rsvg_element_get_parent (root));
+
+    RsvgElement *one = rsvg_element_get_first_child (root);
+    g_assert_nonnull (one);
+
+    char *id = rsvg_element_get_id (one);
+    g_assert_cmpstr (id, ==, "one");
+    g_free (id);
+
+    char *fill = rsvg_element_get_attribute (one, "fill");
+    g_assert_cmpstr (fill, ==, "rgb(0,255,0)");
+    g_free (fill);
+
+    g_assert_null (rsvg_element_get_attribute (one, "stroke"));
+
+    char **names = rsvg_element_get_attribute_names (one);
+    g_assert_cmpuint (g_strv_length (names), ==, 6);
+    g_assert_cmpstr (names[0], ==, "id");
+    g_strfreev (names);
+
+    char **classes = rsvg_element_get_classes (one);
+    g_assert_cmpuint (g_strv_length (classes), ==, 0);
+    g_strfreev (classes);
+
+    RsvgElement *two = rsvg_element_get_next_sibling (one);
+    g_assert_nonnull (two);
+    g_assert_null (rsvg_element_get_next_sibling (two));
+
+    RsvgElement *parent = rsvg_element_get_parent (two);
+    name = rsvg_element_get_name (parent);
+    g_assert_cmpstr (name, ==, "svg");
+    g_free (name);
+
+    RsvgElement *copy = rsvg_element_copy (two);
+    id = rsvg_element_get_id (copy);
+    g_assert_cmpstr (id, ==, "two");
+    g_free (id);
+
+    rsvg_element_free (copy);
+    rsvg_element_free (parent);
+    rsvg_element_free (two);
+    rsvg_element_free (one);
+    rsvg_element_free (root);
+}
+
 #ifdef HAVE_PIXBUF
 static void
 test_get_pixbuf (gboolean sub)
@@ -1796,6 +1859,7 @@ add_api_tests (void)
     g_test_add_func ("/api/handle_new_from_stream_sync", handle_new_from_stream_sync);
     g_test_add_func ("/api/handle_read_stream_sync", handle_read_stream_sync);
     g_test_add_func ("/api/handle_has_sub", handle_has_sub);
+    g_test_add_func ("/api/element_tree", element_tree);
     g_test_add_func ("/api/dimensions_and_position", dimensions_and_position);
     g_test_add_func ("/api/set_size_callback", set_size_callback);
     g_test_add_func ("/api/reset_size_callback", reset_size_callback);
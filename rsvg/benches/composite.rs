@@ -30,6 +30,7 @@ fn bench_composite(c: &mut Criterion) {
                 &input_surface,
                 &input_2_surface,
                 &mut output_surface,
+                None,
                 bounds,
                 0.5,
                 0.5,
@@ -0,0 +1,103 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use rsvg::{CairoRenderer, Loader, SvgHandle};
+
+const SURFACE_SIDE: i32 = 512;
+
+/// Filters to benchmark, as `(name, primitives)`.
+///
+/// Each filter gets applied to a rectangle with a gradient that covers the whole surface.
+const FILTERS: &[(&str, &str)] = &[
+    (
+        "feColorMatrix",
+        r#"<feColorMatrix type="matrix" values="0.5 0.2 0.1 0 0  0.1 0.5 0.2 0 0  0.2 0.1 0.5 0 0  0 0 0 1 0"/>"#,
+    ),
+    (
+        "feComponentTransfer",
+        r#"<feComponentTransfer>
+             <feFuncR type="gamma" amplitude="2" exponent="0.5" offset="0.1"/>
+             <feFuncG type="table" tableValues="0 0.5 0.2 1"/>
+             <feFuncB type="discrete" tableValues="0 0.5 1"/>
+           </feComponentTransfer>"#,
+    ),
+    (
+        "feComposite arithmetic",
+        r#"<feOffset dx="20" dy="20" result="offset"/>
+           <feComposite in="SourceGraphic" in2="offset" operator="arithmetic" k1="0.5" k2="0.5" k3="0.5" k4="0.1"/>"#,
+    ),
+    (
+        "feConvolveMatrix",
+        r#"<feConvolveMatrix order="3" kernelMatrix="1 2 1  2 4 2  1 2 1"/>"#,
+    ),
+    (
+        "feMorphology",
+        r#"<feMorphology operator="dilate" radius="3"/>"#,
+    ),
+    (
+        "feDisplacementMap",
+        r#"<feTurbulence baseFrequency="0.05" result="noise"/>
+           <feDisplacementMap in="SourceGraphic" in2="noise" scale="20" xChannelSelector="R" yChannelSelector="G"/>"#,
+    ),
+    (
+        "feTurbulence",
+        r#"<feTurbulence type="fractalNoise" baseFrequency="0.02" numOctaves="4"/>"#,
+    ),
+];
+
+fn load_filter_document(primitives: &str) -> SvgHandle {
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SURFACE_SIDE}" height="{SURFACE_SIDE}">
+  <defs>
+    <linearGradient id="gradient" x1="0" y1="0" x2="1" y2="1">
+      <stop offset="0" stop-color="red"/>
+      <stop offset="0.5" stop-color="lime" stop-opacity="0.5"/>
+      <stop offset="1" stop-color="blue"/>
+    </linearGradient>
+    <filter id="filter" x="0" y="0" width="1" height="1">
+      {primitives}
+    </filter>
+  </defs>
+  <rect width="100%" height="100%" fill="url(#gradient)" filter="url(#filter)"/>
+</svg>"#
+    );
+
+    let bytes = glib::Bytes::from_owned(svg.into_bytes());
+    let stream = gio::MemoryInputStream::from_bytes(&bytes);
+
+    Loader::new()
+        .read_stream(&stream, None::<&gio::File>, None::<&gio::Cancellable>)
+        .unwrap()
+}
+
+fn render(renderer: &CairoRenderer) {
+    let surface =
+        cairo::ImageSurface::create(cairo::Format::ARgb32, SURFACE_SIDE, SURFACE_SIDE).unwrap();
+    let cr = cairo::Context::new(&surface).unwrap();
+
+    let viewport =
+        cairo::Rectangle::new(0.0, 0.0, f64::from(SURFACE_SIDE), f64::from(SURFACE_SIDE));
+    renderer.render_document(&cr, &viewport).unwrap();
+}
+
+fn bench_filters(c: &mut Criterion) {
+    for (name, primitives) in FILTERS {
+        let handle = load_filter_document(primitives);
+
+        let mut group = c.benchmark_group(*name);
+
+        group.bench_function("global thread pool", |b| {
+            let renderer = CairoRenderer::new(&handle);
+            b.iter(|| render(&renderer))
+        });
+
+        group.bench_function("single thread", |b| {
+            let renderer = CairoRenderer::new(&handle).with_threads(1);
+            b.iter(|| render(&renderer))
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_filters);
+criterion_main!(benches);
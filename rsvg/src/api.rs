@@ -9,9 +9,10 @@ use std::fmt;
 // Here we only re-export stuff in the public API.
 pub use crate::{
     accept_language::{AcceptLanguage, Language},
-    drawing_ctx::Viewport,
+    drawing_ctx::{FilterResolution, RenderQuality, TextMode, Viewport},
     error::{ImplementationLimit, LoadingError},
     length::{LengthUnit, RsvgLength as Length},
+    structure::SwitchBranch,
 };
 
 // Don't merge these in the "pub use" above!  They are not part of the public API!
@@ -22,13 +23,16 @@ use crate::{
     dpi::Dpi,
     drawing_ctx::SvgNesting,
     error::InternalRenderingError,
-    length::NormalizeParams,
-    node::{CascadedValues, Node},
+    length::{Normalize, NormalizeParams, ULength},
+    node::{CascadedValues, Node, NodeBorrow},
+    properties::{ComputedValues, FillOpacity, Opacity, StrokeOpacity},
     rsvg_log,
     session::Session,
+    unit_interval::UnitInterval,
     url_resolver::UrlResolver,
 };
 
+use markup5ever::QualName;
 use url::Url;
 
 use std::path::Path;
@@ -117,6 +121,7 @@ impl fmt::Display for RenderingError {
 pub struct Loader {
     unlimited_size: bool,
     keep_image_data: bool,
+    filter_cache_budget: usize,
     session: Session,
 }
 
@@ -131,6 +136,9 @@ impl Loader {
     ///   surfaces that support including image data in compressed
     ///   formats, like PDF.
     ///
+    /// * [`with_filter_cache`](#method.with_filter_cache) defaults to
+    ///   `0`, which disables caching of filter results.
+    ///
     /// # Example:
     ///
     /// ```
@@ -145,6 +153,7 @@ impl Loader {
         Self {
             unlimited_size: false,
             keep_image_data: false,
+            filter_cache_budget: 0,
             session: Session::default(),
         }
     }
@@ -158,6 +167,7 @@ impl Loader {
         Self {
             unlimited_size: false,
             keep_image_data: false,
+            filter_cache_budget: 0,
             session,
         }
     }
@@ -223,6 +233,35 @@ impl Loader {
         self
     }
 
+    /// Enables caching of the results of filter primitives.
+    ///
+    /// Some filter primitives like `feTurbulence` or `feImage` produce the same pixels
+    /// every time they are rendered, as long as the document and the scale at which it
+    /// is rendered do not change.  If you render the same [`SvgHandle`] repeatedly,
+    /// for example to redraw a widget, the handle can keep those results around and
+    /// reuse them.
+    ///
+    /// The `budget` is the maximum number of bytes of image data that the cache will
+    /// hold; the least recently used results get discarded when the budget is exceeded.
+    /// A budget of `0`, the default, disables the cache.
+    ///
+    /// Call [`SvgHandle::clear_caches`] to release the cached data.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use rsvg;
+    ///
+    /// let svg_handle = rsvg::Loader::new()
+    ///     .with_filter_cache(16 * 1024 * 1024)
+    ///     .read_path("example.svg")
+    ///     .unwrap();
+    /// ```
+    pub fn with_filter_cache(mut self, budget: usize) -> Self {
+        self.filter_cache_budget = budget;
+        self
+    }
+
     /// Reads an SVG document from `path`.
     ///
     /// # Example:
@@ -297,7 +336,8 @@ impl Loader {
 
         let load_options = LoadOptions::new(UrlResolver::new(base_url))
             .with_unlimited_size(self.unlimited_size)
-            .keep_image_data(self.keep_image_data);
+            .keep_image_data(self.keep_image_data)
+            .with_filter_cache_budget(self.filter_cache_budget);
 
         Ok(SvgHandle {
             document: Document::load_from_stream(
@@ -346,6 +386,15 @@ impl SvgHandle {
         }
     }
 
+    /// Discards the cached results of filter primitives.
+    ///
+    /// This only has an effect if the handle was loaded with
+    /// [`Loader::with_filter_cache`].  Use it to release memory when you
+    /// know that you will not render the handle again soon.
+    pub fn clear_caches(&self) {
+        self.document.clear_caches();
+    }
+
     /// Sets a CSS stylesheet to use for an SVG document.
     ///
     /// During the CSS cascade, the specified stylesheet will be used
@@ -365,6 +414,36 @@ impl SvgHandle {
         self.document.cascade(&[stylesheet]);
         Ok(())
     }
+
+    /// Returns the root element of the SVG document.
+    ///
+    /// This is the toplevel `<svg>` element.  You can use the returned [`ElementRef`]
+    /// to walk the document tree and inspect its elements.
+    pub fn root(&self) -> ElementRef {
+        let root = self.document.root();
+
+        ElementRef {
+            node: root.clone(),
+            root,
+        }
+    }
+
+    /// Returns the computed values of the CSS properties of an element.
+    ///
+    /// These are the values that the element gets after the CSS cascade, taking into
+    /// account stylesheets, `style` attributes, presentation attributes, and inheritance
+    /// from the element's ancestors.
+    ///
+    /// Note that the `id` must be a plain fragment identifier like `#foo`, with
+    /// a leading `#` character.
+    pub fn computed_style(&self, id: &str) -> Result<ComputedStyle, RenderingError> {
+        let node_id = self.get_node_id(id)?;
+        let node = self.lookup_node(&node_id)?;
+
+        let values = node.borrow_element().get_computed_values().clone();
+
+        Ok(ComputedStyle { values })
+    }
 }
 
 // Private methods go here
@@ -432,12 +511,275 @@ impl SvgHandle {
     }
 }
 
+/// A read-only reference to an element in an SVG document.
+///
+/// Get the root element with [`SvgHandle::root`], and walk the tree from there with
+/// [`ElementRef::children`] and [`ElementRef::parent`].  The element names, ids,
+/// classes and attributes are returned as they appear in the SVG source, before
+/// any CSS is applied.
+///
+/// An `ElementRef` keeps the whole document tree alive, so it may outlive the
+/// [`SvgHandle`] it came from.
+#[derive(Clone, PartialEq)]
+pub struct ElementRef {
+    node: Node,
+
+    // The tree's child links are strong and its parent links are weak, so hold on
+    // to the root to be able to walk up from any element.
+    root: Node,
+}
+
+impl ElementRef {
+    /// Returns the local name of the element, like `"rect"` or `"g"`.
+    pub fn name(&self) -> String {
+        self.node.borrow_element().element_name().local.to_string()
+    }
+
+    /// Returns the namespace URI of the element.
+    ///
+    /// For SVG elements this is `"http://www.w3.org/2000/svg"`.
+    pub fn namespace(&self) -> String {
+        self.node.borrow_element().element_name().ns.to_string()
+    }
+
+    /// Returns the value of the element's `id` attribute, if it has one.
+    pub fn id(&self) -> Option<String> {
+        self.node.borrow_element().get_id().map(String::from)
+    }
+
+    /// Returns the whitespace-separated names in the element's `class` attribute.
+    pub fn classes(&self) -> Vec<String> {
+        self.node
+            .borrow_element()
+            .get_class()
+            .map(|class| class.split_whitespace().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the raw value of an attribute.
+    ///
+    /// Attributes in a namespace other than the element's own are named with their
+    /// prefix, like `"xlink:href"` or `"xml:space"`.
+    pub fn attribute(&self, name: &str) -> Option<String> {
+        self.node
+            .borrow_element()
+            .get_attributes()
+            .iter()
+            .find(|(attr, _)| qualified_name(attr) == name)
+            .map(|(_, value)| value.to_string())
+    }
+
+    /// Returns the names and raw values of all the element's attributes, in document
+    /// order.
+    ///
+    /// Names are formatted as for [`ElementRef::attribute`].
+    pub fn attributes(&self) -> Vec<(String, String)> {
+        self.node
+            .borrow_element()
+            .get_attributes()
+            .iter()
+            .map(|(attr, value)| (qualified_name(&attr), value.to_string()))
+            .collect()
+    }
+
+    /// Returns the parent element, or `None` for the root element.
+    pub fn parent(&self) -> Option<ElementRef> {
+        self.node.parent().map(|node| ElementRef {
+            node,
+            root: self.root.clone(),
+        })
+    }
+
+    /// Returns an iterator over the element's child elements.
+    ///
+    /// Text nodes are skipped; use [`ElementRef::text_content`] to get at the text.
+    pub fn children(&self) -> ElementChildren {
+        ElementChildren {
+            next: self.node.first_child(),
+            root: self.root.clone(),
+        }
+    }
+
+    /// Returns the next sibling element, or `None` if this is the last one.
+    pub fn next_sibling(&self) -> Option<ElementRef> {
+        ElementChildren {
+            next: self.node.next_sibling(),
+            root: self.root.clone(),
+        }
+        .next()
+    }
+
+    /// Returns the concatenated text of all the element's descendants.
+    ///
+    /// The text is returned as it appears in the SVG source, without whitespace
+    /// normalization.
+    pub fn text_content(&self) -> String {
+        self.node
+            .descendants()
+            .filter(|node| node.is_chars())
+            .map(|node| node.borrow_chars().get_string())
+            .collect()
+    }
+}
+
+impl fmt::Debug for ElementRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElementRef")
+            .field("name", &self.name())
+            .field("id", &self.id())
+            .finish()
+    }
+}
+
+/// Iterator over the child elements of an [`ElementRef`].
+///
+/// This is returned by [`ElementRef::children`].
+pub struct ElementChildren {
+    next: Option<Node>,
+    root: Node,
+}
+
+impl Iterator for ElementChildren {
+    type Item = ElementRef;
+
+    fn next(&mut self) -> Option<ElementRef> {
+        while let Some(node) = self.next.take() {
+            self.next = node.next_sibling();
+
+            if node.is_element() {
+                return Some(ElementRef {
+                    node,
+                    root: self.root.clone(),
+                });
+            }
+        }
+
+        None
+    }
+}
+
+fn qualified_name(name: &QualName) -> String {
+    match name.prefix {
+        Some(ref prefix) => format!("{}:{}", prefix, name.local),
+        None => name.local.to_string(),
+    }
+}
+
+/// Computed values of the CSS properties of an element.
+///
+/// This is returned by [`SvgHandle::computed_style`].  Property values can be obtained
+/// as strings in CSS syntax with [`ComputedStyle::property`], or as typed values for a
+/// few commonly-used properties.
+#[derive(Debug, Clone)]
+pub struct ComputedStyle {
+    values: ComputedValues,
+}
+
+impl ComputedStyle {
+    /// Returns the computed value of a property in CSS syntax.
+    ///
+    /// For example, an element with `fill="red"` has a `fill` of `"rgb(255, 0, 0)"`.
+    /// Only longhand properties are supported; ask for `font-size` instead of `font`.
+    ///
+    /// Returns `None` for unknown property names, and for values which librsvg cannot
+    /// serialize yet, like gradient functions or `calc()` expressions.
+    pub fn property(&self, name: &str) -> Option<String> {
+        self.values.to_css_string(name)
+    }
+
+    /// Returns the names and computed values of all the supported properties, in CSS
+    /// syntax and sorted by name.
+    ///
+    /// Properties whose values librsvg cannot serialize yet are still listed, with a
+    /// value of `None`, so that they don't go missing when debugging the cascade.
+    pub fn properties(&self) -> Vec<(String, Option<String>)> {
+        self.values
+            .to_css_strings()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    /// Returns the computed value of the `opacity` property, from 0.0 to 1.0.
+    pub fn opacity(&self) -> f64 {
+        let Opacity(UnitInterval(o)) = self.values.opacity();
+        o
+    }
+
+    /// Returns the computed value of the `fill-opacity` property, from 0.0 to 1.0.
+    pub fn fill_opacity(&self) -> f64 {
+        let FillOpacity(UnitInterval(o)) = self.values.fill_opacity();
+        o
+    }
+
+    /// Returns the computed value of the `stroke-opacity` property, from 0.0 to 1.0.
+    pub fn stroke_opacity(&self) -> f64 {
+        let StrokeOpacity(UnitInterval(o)) = self.values.stroke_opacity();
+        o
+    }
+
+    /// Returns the computed value of the `font-family` property.
+    ///
+    /// This is a comma-separated list of family names.
+    pub fn font_family(&self) -> String {
+        self.values.font_family().0
+    }
+
+    /// Returns whether the element gets rendered at all, i.e. whether its `display`
+    /// property is something other than `none`.
+    pub fn is_displayed(&self) -> bool {
+        self.values.is_displayed()
+    }
+
+    /// Returns whether the element is visible, i.e. whether its `visibility` property
+    /// is `visible`.
+    pub fn is_visible(&self) -> bool {
+        self.values.is_visible()
+    }
+
+    /// Returns the element's own transform, from its `transform` attribute or property.
+    ///
+    /// This does not include the transforms of the element's ancestors.
+    pub fn transform(&self) -> cairo::Matrix {
+        let t = self.values.transform();
+        cairo::Matrix::new(t.xx, t.yx, t.xy, t.yy, t.x0, t.y0)
+    }
+}
+
+/// Bounding boxes of an element, as computed by [`CairoRenderer::geometry_for_all_elements`].
+///
+/// As with [`CairoRenderer::geometry_for_layer`], the "ink rectangle" is the bounding box
+/// that would be painted for fully stroked and filled elements, and the "logical
+/// rectangle" just takes into account the unstroked paths and text outlines.
+#[derive(Debug, Clone)]
+pub struct ElementGeometry {
+    /// The element that was measured.
+    pub element: ElementRef,
+
+    /// Ink rectangle in the element's user space, i.e. after applying its `transform`.
+    pub ink_rect_in_user_space: cairo::Rectangle,
+
+    /// Logical rectangle in the element's user space, i.e. after applying its `transform`.
+    pub logical_rect_in_user_space: cairo::Rectangle,
+
+    /// Ink rectangle in the coordinate system of the viewport.
+    pub ink_rect_in_viewport: cairo::Rectangle,
+
+    /// Logical rectangle in the coordinate system of the viewport.
+    pub logical_rect_in_viewport: cairo::Rectangle,
+}
+
 /// Can render an `SvgHandle` to a Cairo context.
 pub struct CairoRenderer<'a> {
     pub(crate) handle: &'a SvgHandle,
     pub(crate) dpi: Dpi,
     user_language: UserLanguage,
     cancellable: Option<gio::Cancellable>,
+    text_mode: Option<TextMode>,
+    supported_extensions: Vec<String>,
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    quality: RenderQuality,
+    filter_resolution: FilterResolution,
     is_testing: bool,
 }
 
@@ -493,6 +835,11 @@ impl<'a> CairoRenderer<'a> {
             dpi: Dpi::new(DEFAULT_DPI_X, DEFAULT_DPI_Y),
             user_language: UserLanguage::new(&Language::FromEnvironment),
             cancellable: None,
+            text_mode: None,
+            supported_extensions: Vec::new(),
+            thread_pool: None,
+            quality: RenderQuality::Normal,
+            filter_resolution: FilterResolution::Device,
             is_testing: false,
         }
     }
@@ -555,6 +902,133 @@ impl<'a> CairoRenderer<'a> {
         }
     }
 
+    /// Configures how text gets drawn to the Cairo context.
+    ///
+    /// By default, text is converted to paths before drawing it, except when rendering
+    /// to a PDF surface, where text is drawn as glyphs so that it remains selectable
+    /// and searchable in PDF viewers.
+    ///
+    /// Use [`TextMode::Outlines`] to always convert text to paths, for example, to
+    /// produce PDF files that do not depend on fonts at all.  Use [`TextMode::Glyphs`]
+    /// to always draw text as glyphs from fonts.
+    pub fn with_text_rendering_mode(self, text_mode: TextMode) -> Self {
+        CairoRenderer {
+            text_mode: Some(text_mode),
+            ..self
+        }
+    }
+
+    /// Declares extensions as supported for the `requiredExtensions` attribute.
+    ///
+    /// Children of a `<switch>` element can have a `requiredExtensions` attribute with a
+    /// list of URIs; the first child whose extensions are all supported gets rendered.
+    /// Librsvg only implements a few extensions by itself, so by default, `<switch>`
+    /// children that require other extensions are skipped.
+    ///
+    /// This lets you declare other extension URIs as supported at rendering time.  For
+    /// example, declaring `http://www.w3.org/1999/xhtml` makes `<switch>` choose
+    /// `<foreignObject>` children with XHTML content, which librsvg only lays out in a
+    /// very limited way.  You can also choose among variants of a document:
+    ///
+    /// ```xml
+    /// <switch>
+    ///   <g requiredExtensions="http://example.com/variants/pro">...</g>
+    ///   <g>...</g>
+    /// </switch>
+    /// ```
+    ///
+    /// You can use [`CairoRenderer::switch_branches`] to find out which child of each
+    /// `<switch>` gets rendered.
+    pub fn with_supported_extensions(self, extensions: &[&str]) -> Self {
+        CairoRenderer {
+            supported_extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            ..self
+        }
+    }
+
+    /// Configures the number of threads used to render filter effects.
+    ///
+    /// Filter primitives like `feColorMatrix` or `feTurbulence` process bands of pixel
+    /// rows in parallel.  By default they use rayon's global thread pool, which has one
+    /// thread per CPU.  This method makes the renderer use its own thread pool with
+    /// `num_threads` threads instead; pass 1 to render filters with a single thread, for
+    /// example, if your application already renders several documents in parallel.  A
+    /// value of 0 means one thread per CPU.
+    ///
+    /// If the thread pool cannot be created, the global thread pool is used.
+    pub fn with_threads(self, num_threads: usize) -> Self {
+        let thread_pool = match rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+        {
+            Ok(pool) => Some(Arc::new(pool)),
+
+            Err(e) => {
+                rsvg_log!(
+                    self.handle.session,
+                    "could not create thread pool for rendering: {}",
+                    e
+                );
+                None
+            }
+        };
+
+        CairoRenderer {
+            thread_pool,
+            ..self
+        }
+    }
+
+    /// Configures the trade-off between rendering speed and accuracy.
+    ///
+    /// The default is [`RenderQuality::Normal`], which uses the approximations that the
+    /// SVG specification allows to make rendering faster.  Use [`RenderQuality::Draft`]
+    /// for quick previews, and [`RenderQuality::Best`] when accuracy matters more than
+    /// speed, for example, when rendering for print.
+    ///
+    /// The quality drives several choices consistently: the resolution at which filter
+    /// effects are computed, how Gaussian blurs are computed, the interpolation used to
+    /// scale images, and the antialiasing of shapes and text.  See the variants of
+    /// [`RenderQuality`] for details.
+    pub fn with_quality(self, quality: RenderQuality) -> Self {
+        CairoRenderer { quality, ..self }
+    }
+
+    /// Configures the resolution at which filter effects are computed.
+    ///
+    /// Filters normally run at the resolution of the output, so the time to compute them
+    /// grows with the square of the zoom factor.  Use [`FilterResolution::Capped`] or
+    /// [`FilterResolution::MaxPixels`] to bound that time, for example, when zooming into
+    /// a map; filters then run at a lower resolution and their results get scaled up with
+    /// high-quality interpolation.
+    ///
+    /// The legacy `filterRes` attribute of `<filter>` elements is honored in addition to
+    /// this option.  Note that [`RenderQuality::Draft`] also halves the resolution of
+    /// filters.
+    pub fn with_filter_resolution(self, filter_resolution: FilterResolution) -> Self {
+        CairoRenderer {
+            filter_resolution,
+            ..self
+        }
+    }
+
+    /// Returns which child of each `<switch>` element in the document gets rendered.
+    ///
+    /// The conditional processing attributes of the children of each `<switch>`
+    /// (`requiredExtensions`, `requiredFeatures`, and `systemLanguage`) are evaluated with
+    /// this renderer's language and supported extensions.  The result has one entry for
+    /// each `<switch>`, in document order.  This is useful for debugging documents that
+    /// use `<switch>` to select among variants.
+    ///
+    /// Note that this lists all the `<switch>` elements in the document, even those that
+    /// would not be rendered, for example, because they are inside another `<switch>`'s
+    /// children that did not get chosen.
+    pub fn switch_branches(&self) -> Vec<SwitchBranch> {
+        self.handle
+            .document
+            .get_switch_branches(&self.rendering_options())
+    }
+
     /// Queries the `width`, `height`, and `viewBox` attributes in an SVG document.
     ///
     /// If you are calling this function to compute a scaling factor to render the SVG,
@@ -564,18 +1038,43 @@ impl<'a> CairoRenderer<'a> {
     /// See also [`intrinsic_size_in_pixels`], which does the conversion to pixels if
     /// possible.
     ///
+    /// A `width` or `height` with a math function like `calc(1in + 2em)` gets reported in
+    /// [`LengthUnit::Px`] units, resolved with the renderer's DPI.  If the math function
+    /// has a percentage in it, it gets reported as `100%`, since there is no way to
+    /// represent the mix of units with a single [`Length`].
+    ///
+    /// Likewise, a `width` or `height` with viewport units like `100vw` gets reported as
+    /// `100%`, since those units refer to the viewport where the document gets rendered.
+    /// The other units added in librsvg 2.63, like `rem`, `lh`, or `cap`, get reported in
+    /// [`LengthUnit::Px`] units, so that existing callers don't get units they don't know.
+    ///
     /// [`render_document`]: #method.render_document
     /// [`intrinsic_size_in_pixels`]: #method.intrinsic_size_in_pixels
     pub fn intrinsic_dimensions(&self) -> IntrinsicDimensions {
         let d = self.handle.document.get_intrinsic_dimensions();
 
         IntrinsicDimensions {
-            width: Into::into(d.width),
-            height: Into::into(d.height),
+            width: self.length_to_api(&d.width),
+            height: self.length_to_api(&d.height),
             vbox: d.vbox.map(|v| cairo::Rectangle::from(*v)),
         }
     }
 
+    fn length_to_api<N: Normalize>(&self, l: &ULength<N>) -> Length {
+        if l.has_viewport_units() || (l.is_calc() && l.has_percentage()) {
+            Length::new(1.0, LengthUnit::Percent)
+        } else if !l.is_calc() && l.unit.is_in_public_api() {
+            Length::from(l.clone())
+        } else {
+            let viewport = Viewport::new(self.dpi, 0.0, 0.0);
+            let root = self.handle.document.root();
+            let cascaded = CascadedValues::new_from_node(&root);
+            let params = NormalizeParams::new(cascaded.get(), &viewport);
+
+            Length::new(l.to_user(&params), LengthUnit::Px)
+        }
+    }
+
     /// Converts the SVG document's intrinsic dimensions to pixels, if possible.
     ///
     /// Returns `Some(width, height)` in pixel units if the SVG document has `width` and
@@ -610,6 +1109,11 @@ impl<'a> CairoRenderer<'a> {
             user_language: self.user_language.clone(),
             svg_nesting: SvgNesting::Standalone,
             testing: self.is_testing,
+            text_mode: self.text_mode,
+            supported_extensions: self.supported_extensions.clone(),
+            thread_pool: self.thread_pool.clone(),
+            quality: self.quality,
+            filter_resolution: self.filter_resolution,
         }
     }
 
@@ -705,6 +1209,38 @@ impl<'a> CairoRenderer<'a> {
             .render_layer(cr, node, viewport, &self.rendering_options())?)
     }
 
+    /// Finds the elements that would receive a pointer event at a point
+    ///
+    /// The `viewport` gives the position and size at which the whole SVG document would
+    /// be rendered, as for `render_document`, and `(x, y)` is a point in that same
+    /// coordinate system; for example, the position of a mouse click in a widget that
+    /// shows the rendered document.
+    ///
+    /// An element is hit if the point is inside the region given by its `pointer-events`
+    /// property: its fill, its stroke, or its bounding box, depending on the property's
+    /// value and on the element's `visibility`.  Transforms and clipping paths are taken
+    /// into account; opacity, masks, and filters are not.
+    ///
+    /// Returns the ids of the hit elements, without a leading `#`, with the topmost
+    /// element first.  Only shapes, text, and images can be hit; elements without an
+    /// `id` attribute are left out of the list.
+    ///
+    /// Content instantiated by a `<use>` element is reported as the outermost `<use>`
+    /// element, like events get retargeted in the DOM.  For example, if a map shows
+    /// several `<use id="pin-42" href="#pin"/>` instances of a `<symbol id="pin">`, this
+    /// returns the id of the instance that is under the point.
+    pub fn elements_at_point(
+        &self,
+        viewport: &cairo::Rectangle,
+        x: f64,
+        y: f64,
+    ) -> Result<Vec<String>, RenderingError> {
+        Ok(self
+            .handle
+            .document
+            .elements_at_point(viewport, x, y, &self.rendering_options())?)
+    }
+
     /// Computes the (ink_rect, logical_rect) of a single SVG element
     ///
     /// While `geometry_for_layer` computes the geometry of an SVG element subtree with
@@ -748,6 +1284,57 @@ impl<'a> CairoRenderer<'a> {
             .get_geometry_for_element(node, &self.rendering_options())?)
     }
 
+    /// Computes the geometry of all the elements in the SVG in a single pass
+    ///
+    /// This is much faster than calling `geometry_for_layer` or `geometry_for_element`
+    /// for each element, since the document is only laid out once.
+    ///
+    /// The `viewport` gives the position and size at which the whole SVG document would
+    /// be rendered, as for `render_document`; the rectangles "in viewport" are in that
+    /// same coordinate system.  The rectangles "in user space" are in each element's own
+    /// coordinate system, as for the `getBBox()` DOM method.
+    ///
+    /// If `ids_only` is true, only the elements that have an `id` attribute are
+    /// returned.  Elements without any geometry, like empty groups or the ones that are
+    /// not rendered because they are inside `<defs>` or have `display: none`, are never
+    /// returned.  Elements which are rendered more than once, like those referenced by
+    /// several `<use>` elements, appear once per instance.
+    ///
+    /// The elements are returned in the order in which they finish rendering, so each
+    /// element comes after all of its descendants.
+    pub fn geometry_for_all_elements(
+        &self,
+        viewport: &cairo::Rectangle,
+        ids_only: bool,
+    ) -> Result<Vec<ElementGeometry>, RenderingError> {
+        let document = &self.handle.document;
+        let root = document.root();
+
+        let geometries = document.get_geometries(viewport, ids_only, &self.rendering_options())?;
+
+        Ok(geometries
+            .into_iter()
+            .map(|g| ElementGeometry {
+                element: ElementRef {
+                    node: g.node,
+                    root: root.clone(),
+                },
+                ink_rect_in_user_space: cairo::Rectangle::from(
+                    g.user_space.ink_rect.unwrap_or_default(),
+                ),
+                logical_rect_in_user_space: cairo::Rectangle::from(
+                    g.user_space.rect.unwrap_or_default(),
+                ),
+                ink_rect_in_viewport: cairo::Rectangle::from(
+                    g.viewport_space.ink_rect.unwrap_or_default(),
+                ),
+                logical_rect_in_viewport: cairo::Rectangle::from(
+                    g.viewport_space.rect.unwrap_or_default(),
+                ),
+            })
+            .collect())
+    }
+
     /// Renders a single SVG element to a given viewport
     ///
     /// This function can be used to extract individual element subtrees and render them,
@@ -91,6 +91,15 @@ impl CairoPath {
         CairoPath(Vec::new())
     }
 
+    /// Creates a path from segments whose coordinates are known to be suitable for Cairo.
+    pub fn from_segments(segments: Vec<PathSegment>) -> CairoPath {
+        CairoPath(segments)
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
     pub fn to_cairo_context(&self, cr: &cairo::Context) -> Result<(), Box<InternalRenderingError>> {
         for segment in &self.0 {
             match *segment {
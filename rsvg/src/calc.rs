@@ -0,0 +1,469 @@
+//! CSS math functions in lengths: `calc()`, `min()`, `max()`, and `clamp()`.
+//!
+//! The terms of a math function may have different units, like in `calc(100% - 2em)`, so
+//! they can only be added together when the length gets normalized for rendering.  A
+//! [`CssLength`][crate::length::CssLength] with such an expression keeps it as a tree of
+//! [`CalcNode`], which gets evaluated with a function that resolves each of its lengths
+//! to user-space units.
+//!
+//! CSS Values and Units 4: <https://www.w3.org/TR/css-values-4/#math>
+
+use cssparser::{Parser, Token, match_ignore_ascii_case};
+
+use crate::error::*;
+use crate::length::LengthUnit;
+use crate::limits;
+use crate::parsers::finite_f32;
+
+/// A node in the expression tree of a math function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcNode {
+    /// A plain number, like the `2` in `calc(2 * 1em)`.
+    Number(f64),
+
+    /// A length or a percentage.
+    Length(f64, LengthUnit),
+
+    /// Sum of the terms; subtractions are stored as the sum of a negated term.
+    Sum(Vec<CalcNode>),
+
+    /// Product of two terms.  At most one of them is a length.
+    Product(Box<CalcNode>, Box<CalcNode>),
+
+    /// Quotient of two terms.  The divisor is a number.
+    Quotient(Box<CalcNode>, Box<CalcNode>),
+
+    /// `min()`
+    Min(Vec<CalcNode>),
+
+    /// `max()`
+    Max(Vec<CalcNode>),
+
+    /// `clamp(minimum, value, maximum)`
+    Clamp(Box<CalcNode>, Box<CalcNode>, Box<CalcNode>),
+}
+
+/// The type of the result of a [`CalcNode`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum CalcType {
+    Number,
+    Length,
+}
+
+impl CalcNode {
+    /// Evaluates the expression.
+    ///
+    /// The `resolve` function converts the value and unit of each length in the expression
+    /// to a number, normally a user-space distance.
+    pub fn evaluate(&self, resolve: &dyn Fn(f64, LengthUnit) -> f64) -> f64 {
+        match *self {
+            CalcNode::Number(n) => n,
+
+            CalcNode::Length(l, unit) => resolve(l, unit),
+
+            CalcNode::Sum(ref terms) => terms.iter().map(|t| t.evaluate(resolve)).sum(),
+
+            CalcNode::Product(ref a, ref b) => a.evaluate(resolve) * b.evaluate(resolve),
+
+            CalcNode::Quotient(ref a, ref b) => a.evaluate(resolve) / b.evaluate(resolve),
+
+            CalcNode::Min(ref args) => args
+                .iter()
+                .map(|a| a.evaluate(resolve))
+                .fold(f64::INFINITY, f64::min),
+
+            CalcNode::Max(ref args) => args
+                .iter()
+                .map(|a| a.evaluate(resolve))
+                .fold(f64::NEG_INFINITY, f64::max),
+
+            // "clamp(MIN, VAL, MAX) is resolved as max(MIN, min(VAL, MAX))"
+            CalcNode::Clamp(ref min, ref val, ref max) => val
+                .evaluate(resolve)
+                .min(max.evaluate(resolve))
+                .max(min.evaluate(resolve)),
+        }
+    }
+
+    /// Returns a copy of the expression with each length replaced by the result of `f`.
+    pub fn map_lengths(&self, f: &dyn Fn(f64, LengthUnit) -> CalcNode) -> CalcNode {
+        let map_all = |nodes: &[CalcNode]| nodes.iter().map(|n| n.map_lengths(f)).collect();
+        let map_box = |node: &CalcNode| Box::new(node.map_lengths(f));
+
+        match *self {
+            CalcNode::Number(n) => CalcNode::Number(n),
+            CalcNode::Length(l, unit) => f(l, unit),
+            CalcNode::Sum(ref terms) => CalcNode::Sum(map_all(terms)),
+            CalcNode::Product(ref a, ref b) => CalcNode::Product(map_box(a), map_box(b)),
+            CalcNode::Quotient(ref a, ref b) => CalcNode::Quotient(map_box(a), map_box(b)),
+            CalcNode::Min(ref args) => CalcNode::Min(map_all(args)),
+            CalcNode::Max(ref args) => CalcNode::Max(map_all(args)),
+            CalcNode::Clamp(ref min, ref val, ref max) => {
+                CalcNode::Clamp(map_box(min), map_box(val), map_box(max))
+            }
+        }
+    }
+
+    /// Returns the distinct units of the lengths in the expression.
+    pub fn units(&self) -> Vec<LengthUnit> {
+        let mut units = Vec::new();
+        self.collect_units(&mut units);
+        units
+    }
+
+    fn collect_units(&self, units: &mut Vec<LengthUnit>) {
+        match *self {
+            CalcNode::Number(_) => (),
+
+            CalcNode::Length(_, unit) => {
+                if !units.contains(&unit) {
+                    units.push(unit);
+                }
+            }
+
+            CalcNode::Sum(ref nodes) | CalcNode::Min(ref nodes) | CalcNode::Max(ref nodes) => {
+                for node in nodes {
+                    node.collect_units(units);
+                }
+            }
+
+            CalcNode::Product(ref a, ref b) | CalcNode::Quotient(ref a, ref b) => {
+                a.collect_units(units);
+                b.collect_units(units);
+            }
+
+            CalcNode::Clamp(ref min, ref val, ref max) => {
+                min.collect_units(units);
+                val.collect_units(units);
+                max.collect_units(units);
+            }
+        }
+    }
+
+    /// Type-checks the expression, as in
+    /// <https://www.w3.org/TR/css-values-4/#calc-type-checking>.
+    fn check(&self) -> Result<CalcType, ValueErrorKind> {
+        match *self {
+            CalcNode::Number(_) => Ok(CalcType::Number),
+
+            CalcNode::Length(..) => Ok(CalcType::Length),
+
+            CalcNode::Sum(ref nodes) | CalcNode::Min(ref nodes) | CalcNode::Max(ref nodes) => {
+                check_same_type(nodes.iter())
+            }
+
+            CalcNode::Clamp(ref min, ref val, ref max) => {
+                check_same_type([&**min, &**val, &**max].into_iter())
+            }
+
+            CalcNode::Product(ref a, ref b) => match (a.check()?, b.check()?) {
+                (CalcType::Number, CalcType::Number) => Ok(CalcType::Number),
+                (CalcType::Length, CalcType::Length) => {
+                    Err(ValueErrorKind::parse_error("cannot multiply two lengths"))
+                }
+                _ => Ok(CalcType::Length),
+            },
+
+            CalcNode::Quotient(ref a, ref b) => {
+                let dividend = a.check()?;
+
+                if b.check()? != CalcType::Number {
+                    return Err(ValueErrorKind::parse_error("can only divide by a number"));
+                }
+
+                // The divisor has no lengths, so it is a constant.
+                if b.evaluate(&|l, _| l) == 0.0 {
+                    return Err(ValueErrorKind::value_error("division by zero"));
+                }
+
+                Ok(dividend)
+            }
+        }
+    }
+}
+
+fn check_same_type<'a>(
+    nodes: impl Iterator<Item = &'a CalcNode>,
+) -> Result<CalcType, ValueErrorKind> {
+    let mut result = None;
+
+    for node in nodes {
+        let t = node.check()?;
+
+        match result {
+            None => result = Some(t),
+            Some(r) if r == t => (),
+            Some(_) => {
+                return Err(ValueErrorKind::parse_error(
+                    "cannot mix numbers and lengths",
+                ));
+            }
+        }
+    }
+
+    result.ok_or_else(|| ValueErrorKind::parse_error("expected an argument"))
+}
+
+/// Parses the arguments of a math function, after its `Token::Function` has been consumed.
+///
+/// The `name` is the name of the function, like `calc`.
+pub fn parse_math_function<'i>(
+    name: &str,
+    parser: &mut Parser<'i, '_>,
+) -> Result<CalcNode, ParseError<'i>> {
+    let loc = parser.current_source_location();
+
+    let node = parse_function_arguments(name, parser, 0)?;
+    node.check().map_err(|e| loc.new_custom_error(e))?;
+
+    Ok(node)
+}
+
+/// Whether `name` is the name of a math function that can appear in lengths.
+pub fn is_math_function(name: &str) -> bool {
+    match_ignore_ascii_case! { name,
+        "calc" | "min" | "max" | "clamp" => true,
+        _ => false,
+    }
+}
+
+fn parse_function_arguments<'i>(
+    name: &str,
+    parser: &mut Parser<'i, '_>,
+    depth: usize,
+) -> Result<CalcNode, ParseError<'i>> {
+    if depth > limits::MAX_CALC_NESTING_DEPTH {
+        return Err(parser.new_custom_error(ValueErrorKind::value_error(
+            "math function is nested too deeply",
+        )));
+    }
+
+    parser.parse_nested_block(|p| {
+        match_ignore_ascii_case! { name,
+            "calc" => parse_sum(p, depth),
+
+            "min" => Ok(CalcNode::Min(p.parse_comma_separated(|p| parse_sum(p, depth))?)),
+
+            "max" => Ok(CalcNode::Max(p.parse_comma_separated(|p| parse_sum(p, depth))?)),
+
+            "clamp" => {
+                let min = parse_sum(p, depth)?;
+                p.expect_comma()?;
+                let val = parse_sum(p, depth)?;
+                p.expect_comma()?;
+                let max = parse_sum(p, depth)?;
+
+                Ok(CalcNode::Clamp(Box::new(min), Box::new(val), Box::new(max)))
+            },
+
+            _ => Err(p.new_custom_error(ValueErrorKind::parse_error(
+                "expected calc(), min(), max(), or clamp()",
+            ))),
+        }
+    })
+}
+
+/// Parses `<calc-sum> = <calc-product> [ [ '+' | '-' ] <calc-product> ]*`
+fn parse_sum<'i>(parser: &mut Parser<'i, '_>, depth: usize) -> Result<CalcNode, ParseError<'i>> {
+    let mut terms = vec![parse_product(parser, depth)?];
+
+    loop {
+        let op = parser.try_parse(|p| -> Result<bool, ParseError<'i>> {
+            let token = p.next()?.clone();
+
+            match token {
+                Token::Delim('+') => Ok(false),
+                Token::Delim('-') => Ok(true),
+                _ => Err(p.new_unexpected_token_error(token)),
+            }
+        });
+
+        let Ok(negate) = op else {
+            break;
+        };
+
+        let term = parse_product(parser, depth)?;
+
+        if negate {
+            terms.push(CalcNode::Product(
+                Box::new(CalcNode::Number(-1.0)),
+                Box::new(term),
+            ));
+        } else {
+            terms.push(term);
+        }
+    }
+
+    if terms.len() == 1 {
+        Ok(terms.remove(0))
+    } else {
+        Ok(CalcNode::Sum(terms))
+    }
+}
+
+/// Parses `<calc-product> = <calc-value> [ [ '*' | '/' ] <calc-value> ]*`
+fn parse_product<'i>(
+    parser: &mut Parser<'i, '_>,
+    depth: usize,
+) -> Result<CalcNode, ParseError<'i>> {
+    let mut node = parse_value(parser, depth)?;
+
+    loop {
+        let op = parser.try_parse(|p| -> Result<bool, ParseError<'i>> {
+            let token = p.next()?.clone();
+
+            match token {
+                Token::Delim('*') => Ok(true),
+                Token::Delim('/') => Ok(false),
+                _ => Err(p.new_unexpected_token_error(token)),
+            }
+        });
+
+        let Ok(is_product) = op else {
+            break;
+        };
+
+        let rhs = parse_value(parser, depth)?;
+
+        node = if is_product {
+            CalcNode::Product(Box::new(node), Box::new(rhs))
+        } else {
+            CalcNode::Quotient(Box::new(node), Box::new(rhs))
+        };
+    }
+
+    Ok(node)
+}
+
+/// Parses a number, a length, a percentage, a parenthesized sum, or a nested math function.
+fn parse_value<'i>(parser: &mut Parser<'i, '_>, depth: usize) -> Result<CalcNode, ParseError<'i>> {
+    let token = parser.next()?.clone();
+
+    match token {
+        Token::Number { value, .. } => {
+            let n = finite_f32(value).map_err(|e| parser.new_custom_error(e))?;
+            Ok(CalcNode::Number(f64::from(n)))
+        }
+
+        Token::Percentage { unit_value, .. } => {
+            let n = finite_f32(unit_value).map_err(|e| parser.new_custom_error(e))?;
+            Ok(CalcNode::Length(f64::from(n), LengthUnit::Percent))
+        }
+
+        Token::Dimension {
+            value, ref unit, ..
+        } => {
+            let n = finite_f32(value).map_err(|e| parser.new_custom_error(e))?;
+
+            match LengthUnit::from_css_unit(unit) {
+                Some(unit) => Ok(CalcNode::Length(f64::from(n), unit)),
+                None => Err(parser.new_unexpected_token_error(token.clone())),
+            }
+        }
+
+        Token::ParenthesisBlock => {
+            if depth + 1 > limits::MAX_CALC_NESTING_DEPTH {
+                return Err(parser.new_custom_error(ValueErrorKind::value_error(
+                    "math function is nested too deeply",
+                )));
+            }
+
+            parser.parse_nested_block(|p| parse_sum(p, depth + 1))
+        }
+
+        Token::Function(ref name) if is_math_function(name) => {
+            parse_function_arguments(name, parser, depth + 1)
+        }
+
+        _ => Err(parser.new_unexpected_token_error(token)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cssparser::ParserInput;
+
+    fn parse(s: &str) -> Result<CalcNode, ()> {
+        let mut input = ParserInput::new(s);
+        let mut parser = Parser::new(&mut input);
+
+        let name = parser.expect_function().map_err(|_| ())?.clone();
+        let node = parse_math_function(&name, &mut parser).map_err(|_| ())?;
+        parser.expect_exhausted().map_err(|_| ())?;
+
+        Ok(node)
+    }
+
+    fn evaluate(s: &str) -> f64 {
+        parse(s).unwrap().evaluate(&|l, unit| match unit {
+            LengthUnit::Px => l,
+            LengthUnit::Em => l * 10.0,
+            LengthUnit::Percent => l * 200.0,
+            _ => unreachable!(),
+        })
+    }
+
+    #[test]
+    fn evaluates_arithmetic() {
+        assert_eq!(evaluate("calc(1px + 2px)"), 3.0);
+        assert_eq!(evaluate("calc(1em - 2px)"), 8.0);
+        assert_eq!(evaluate("calc(50% - 2 * 1em)"), 80.0);
+        assert_eq!(evaluate("calc((1px + 1em) / 2)"), 5.5);
+        assert_eq!(evaluate("calc(2 * (1px + 2px) * 3)"), 18.0);
+        assert_eq!(evaluate("CALC(1px)"), 1.0);
+    }
+
+    #[test]
+    fn evaluates_comparison_functions() {
+        assert_eq!(evaluate("min(1em, 20px, 50%)"), 10.0);
+        assert_eq!(evaluate("max(1em, 20px, 5%)"), 20.0);
+        assert_eq!(evaluate("clamp(2px, 5%, 10px)"), 10.0);
+        assert_eq!(evaluate("clamp(2px, 1%, 10px)"), 2.0);
+        assert_eq!(evaluate("clamp(20px, 1px, 10px)"), 20.0);
+        assert_eq!(evaluate("calc(1px + min(1em, 3px))"), 4.0);
+    }
+
+    #[test]
+    fn collects_units() {
+        assert_eq!(parse("calc(2 * 3)").unwrap().units(), vec![]);
+        assert_eq!(
+            parse("calc(1px + 2px)").unwrap().units(),
+            vec![LengthUnit::Px]
+        );
+        assert_eq!(
+            parse("clamp(1em, 50%, 1em)").unwrap().units(),
+            vec![LengthUnit::Em, LengthUnit::Percent]
+        );
+    }
+
+    #[test]
+    fn detects_invalid_expressions() {
+        assert!(parse("calc()").is_err());
+        assert!(parse("calc(1px +)").is_err());
+        assert!(parse("calc(1px+2px)").is_err());
+        assert!(parse("calc(1px -2px)").is_err());
+        assert!(parse("calc(1px + 2)").is_err());
+        assert!(parse("calc(1px * 2px)").is_err());
+        assert!(parse("calc(1px / 1px)").is_err());
+        assert!(parse("calc(1px / 0)").is_err());
+        assert!(parse("calc(1deg)").is_err());
+        assert!(parse("min()").is_err());
+        assert!(parse("clamp(1px, 2px)").is_err());
+        assert!(parse("foo(1px)").is_err());
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let deep = format!(
+            "calc({}1px{})",
+            "(".repeat(limits::MAX_CALC_NESTING_DEPTH + 1),
+            ")".repeat(limits::MAX_CALC_NESTING_DEPTH + 1)
+        );
+        assert!(parse(&deep).is_err());
+
+        let ok = format!("calc({}1px{})", "(".repeat(10), ")".repeat(10));
+        assert_eq!(evaluate(&ok), 1.0);
+    }
+}
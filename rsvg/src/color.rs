@@ -6,6 +6,7 @@ use cssparser_color::{hsl_to_rgb, hwb_to_rgb};
 
 use crate::error::*;
 use crate::parsers::Parse;
+use crate::property_macros::ToCssValue;
 use crate::unit_interval::UnitInterval;
 use crate::util;
 
@@ -248,6 +249,27 @@ impl Parse for Color {
     }
 }
 
+impl ToCssValue for Color {
+    fn to_css_value(&self) -> Option<String> {
+        if let Color::CurrentColor = *self {
+            return Some(String::from("currentcolor"));
+        }
+
+        let RGBA {
+            red,
+            green,
+            blue,
+            alpha,
+        } = color_to_rgba(self);
+
+        if alpha < OPAQUE {
+            Some(format!("rgba({red}, {green}, {blue}, {alpha})"))
+        } else {
+            Some(format!("rgb({red}, {green}, {blue})"))
+        }
+    }
+}
+
 /// Normalizes `h` (a hue value in degrees) to be in the interval `[0.0, 1.0]`.
 ///
 /// Rust-cssparser (the cssparser-color crate) provides
@@ -13,25 +13,34 @@ use crate::rsvg_log;
 use crate::session::Session;
 
 // No extensions at the moment.
+//
+// Librsvg renders a small subset of XHTML inside foreignObject (see foreign_object.rs),
+// but not enough of CSS layout for the documents that test for XHTML support, so it
+// does not claim the "http://www.w3.org/1999/xhtml" extension.  Callers can declare it
+// as supported with CairoRenderer::with_supported_extensions().
 static IMPLEMENTED_EXTENSIONS: &[&str] = &[];
 
 #[derive(Debug, PartialEq)]
-pub struct RequiredExtensions(pub bool);
+pub struct RequiredExtensions(pub Vec<String>);
 
 impl RequiredExtensions {
     /// Parse a requiredExtensions attribute.
     ///
     /// <http://www.w3.org/TR/SVG/struct.html#RequiredExtensionsAttribute>
     pub fn from_attribute(s: &str) -> RequiredExtensions {
-        RequiredExtensions(
-            s.split_whitespace()
-                .all(|f| IMPLEMENTED_EXTENSIONS.binary_search(&f).is_ok()),
-        )
+        RequiredExtensions(s.split_whitespace().map(String::from).collect())
     }
 
     /// Evaluate a requiredExtensions value for conditional processing.
-    pub fn eval(&self) -> bool {
-        self.0
+    ///
+    /// Each of the required extensions must be either implemented by librsvg, or be
+    /// declared as supported by the caller in `supported_extensions`; see
+    /// [`CairoRenderer::with_supported_extensions`][crate::CairoRenderer::with_supported_extensions].
+    pub fn eval(&self, supported_extensions: &[String]) -> bool {
+        self.0.iter().all(|e| {
+            IMPLEMENTED_EXTENSIONS.binary_search(&e.as_str()).is_ok()
+                || supported_extensions.contains(e)
+        })
     }
 }
 
@@ -150,9 +159,29 @@ mod tests {
 
     #[test]
     fn required_extensions() {
-        assert_eq!(
-            RequiredExtensions::from_attribute("http://test.org/NotExisting/1.0"),
-            RequiredExtensions(false)
+        assert!(!RequiredExtensions::from_attribute("http://test.org/NotExisting/1.0").eval(&[]));
+
+        assert!(!RequiredExtensions::from_attribute("http://www.w3.org/1999/xhtml").eval(&[]));
+
+        let supported = vec![
+            String::from("http://example.com/pro"),
+            String::from("http://www.w3.org/1999/xhtml"),
+        ];
+
+        assert!(RequiredExtensions::from_attribute("http://example.com/pro").eval(&supported));
+
+        assert!(
+            RequiredExtensions::from_attribute(
+                "http://www.w3.org/1999/xhtml http://example.com/pro"
+            )
+            .eval(&supported)
+        );
+
+        assert!(
+            !RequiredExtensions::from_attribute(
+                "http://example.com/pro http://test.org/NotExisting/1.0"
+            )
+            .eval(&supported)
         );
     }
 
@@ -163,6 +192,11 @@ mod tests {
             RequiredFeatures(false)
         );
 
+        assert_eq!(
+            RequiredFeatures::from_attribute("http://www.w3.org/TR/SVG11/feature#Extensibility"),
+            RequiredFeatures(false)
+        );
+
         assert_eq!(
             RequiredFeatures::from_attribute("http://www.w3.org/TR/SVG11/feature#BasicFilter"),
             RequiredFeatures(true)
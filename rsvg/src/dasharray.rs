@@ -5,6 +5,7 @@ use cssparser::Parser;
 use crate::error::*;
 use crate::length::*;
 use crate::parsers::{Parse, optional_comma};
+use crate::property_macros::ToCssValue;
 
 #[derive(Debug, Default, PartialEq, Clone)]
 pub enum Dasharray {
@@ -39,6 +40,19 @@ impl Parse for Dasharray {
     }
 }
 
+impl ToCssValue for Dasharray {
+    fn to_css_value(&self) -> Option<String> {
+        match *self {
+            Dasharray::None => Some(String::from("none")),
+            Dasharray::Array(ref dashes) => dashes
+                .iter()
+                .map(|d| d.to_css_value())
+                .collect::<Option<Vec<_>>>()
+                .map(|dashes| dashes.join(", ")),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
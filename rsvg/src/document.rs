@@ -20,11 +20,13 @@ use crate::borrow_element_as;
 use crate::css::{self, Origin, Stylesheet};
 use crate::dpi::Dpi;
 use crate::drawing_ctx::{
-    DrawingMode, RenderingConfiguration, SvgNesting, draw_tree, with_saved_cr,
+    DrawingMode, FilterResolution, NodeGeometry, RenderQuality, RenderingConfiguration, SvgNesting,
+    TextMode, collect_geometries, draw_tree, hit_test_tree, with_saved_cr,
 };
 use crate::error::{
     AcquireError, InternalRenderingError, LoadingDepthError, LoadingError, NodeIdError,
 };
+use crate::filters::FilterCache;
 use crate::io::{self, BinaryData};
 use crate::is_element_of_type;
 use crate::limits;
@@ -32,7 +34,7 @@ use crate::node::{CascadedValues, Node, NodeBorrow, NodeData};
 use crate::rect::Rect;
 use crate::rsvg_log;
 use crate::session::Session;
-use crate::structure::IntrinsicDimensions;
+use crate::structure::{IntrinsicDimensions, Switch, SwitchBranch};
 use crate::surface_utils::shared_surface::SharedImageSurface;
 use crate::url_resolver::{AllowedUrl, UrlResolver};
 use crate::xml::{Attributes, xml_load_from_possibly_compressed_stream};
@@ -83,6 +85,10 @@ pub struct LoadOptions {
 
     /// Whether to keep original (undecoded) image data to embed in Cairo PDF surfaces.
     pub keep_image_data: bool,
+
+    /// Memory budget in bytes for caching the output of filter primitives; zero disables
+    /// the cache.
+    pub filter_cache_budget: usize,
 }
 
 impl LoadOptions {
@@ -92,6 +98,7 @@ impl LoadOptions {
             url_resolver,
             unlimited_size: false,
             keep_image_data: false,
+            filter_cache_budget: 0,
         }
     }
 
@@ -113,6 +120,14 @@ impl LoadOptions {
         self
     }
 
+    /// Sets the memory budget for caching the output of filter primitives.
+    ///
+    /// See [`FilterCache`] for details.
+    pub fn with_filter_cache_budget(mut self, budget: usize) -> Self {
+        self.filter_cache_budget = budget;
+        self
+    }
+
     /// Creates a new `LoadOptions` with a different `url resolver`.
     ///
     /// This is used when loading a referenced file that may in turn cause other files
@@ -125,6 +140,7 @@ impl LoadOptions {
             url_resolver,
             unlimited_size: self.unlimited_size,
             keep_image_data: self.keep_image_data,
+            filter_cache_budget: self.filter_cache_budget,
         }
     }
 }
@@ -139,6 +155,11 @@ pub struct RenderingOptions {
     pub user_language: UserLanguage,
     pub svg_nesting: SvgNesting,
     pub testing: bool,
+    pub text_mode: Option<TextMode>,
+    pub supported_extensions: Vec<String>,
+    pub thread_pool: Option<Arc<rayon::ThreadPool>>,
+    pub quality: RenderQuality,
+    pub filter_resolution: FilterResolution,
 }
 
 impl RenderingOptions {
@@ -151,6 +172,11 @@ impl RenderingOptions {
             svg_nesting: self.svg_nesting,
             testing: self.testing,
             measuring,
+            text_mode: self.text_mode,
+            supported_extensions: self.supported_extensions.clone(),
+            thread_pool: self.thread_pool.clone(),
+            quality: self.quality,
+            filter_resolution: self.filter_resolution,
         }
     }
 }
@@ -189,6 +215,9 @@ pub struct Document {
     /// [`crate::SvgHandle::set_stylesheet`] is called after loading
     /// the document.
     needs_cascade: Cell<bool>,
+
+    /// Outputs of filter primitives that can be reused across renders.
+    filter_cache: RefCell<FilterCache>,
 }
 
 impl Document {
@@ -302,6 +331,9 @@ impl Document {
     pub fn cascade(&self, extra: &[Stylesheet]) {
         self.needs_cascade.set(false);
 
+        // Cached filter outputs may depend on the old styles.
+        self.clear_caches();
+
         let stylesheets = {
             static UA_STYLESHEETS: OnceLock<Vec<Stylesheet>> = OnceLock::new();
             UA_STYLESHEETS.get_or_init(|| {
@@ -326,6 +358,11 @@ impl Document {
         );
     }
 
+    /// Discards data that is kept across renders to speed them up.
+    pub fn clear_caches(&self) {
+        self.filter_cache.borrow_mut().clear();
+    }
+
     pub fn get_intrinsic_dimensions(&self) -> IntrinsicDimensions {
         self.ensure_is_cascaded();
 
@@ -335,6 +372,47 @@ impl Document {
         borrow_element_as!(self.root(), Svg).get_intrinsic_dimensions(values)
     }
 
+    /// Evaluates the conditions of every `<switch>` in the document, in document order,
+    /// to find out which of their children would be rendered.
+    pub fn get_switch_branches(&self, options: &RenderingOptions) -> Vec<SwitchBranch> {
+        self.ensure_is_cascaded();
+
+        self.root()
+            .descendants()
+            .filter(|node| is_element_of_type!(node, Switch))
+            .map(|node| {
+                let child = Switch::matching_child(
+                    &node,
+                    &options.user_language,
+                    &options.supported_extensions,
+                    &self.session,
+                );
+
+                let switch_id = node.borrow_element().get_id().map(String::from);
+
+                match child {
+                    Some((index, child)) => {
+                        let elt = child.borrow_element();
+
+                        SwitchBranch {
+                            switch_id,
+                            child_index: Some(index),
+                            child_element_name: Some(elt.element_name().local.to_string()),
+                            child_id: elt.get_id().map(String::from),
+                        }
+                    }
+
+                    None => SwitchBranch {
+                        switch_id,
+                        child_index: None,
+                        child_element_name: None,
+                        child_id: None,
+                    },
+                }
+            })
+            .collect()
+    }
+
     pub fn render_document(
         &self,
         cr: &cairo::Context,
@@ -492,6 +570,85 @@ impl Document {
         .map_err(|err| *err)
     }
 
+    /// Computes the bounding boxes of all the rendered elements in a single pass.
+    ///
+    /// The bounding boxes in viewport space are for the whole document rendered into
+    /// the `viewport`, as with [`Document::render_document`].  If `ids_only` is true,
+    /// elements without an `id` attribute are left out.
+    pub fn get_geometries(
+        &self,
+        viewport: &cairo::Rectangle,
+        ids_only: bool,
+        options: &RenderingOptions,
+    ) -> Result<Vec<NodeGeometry>, InternalRenderingError> {
+        self.ensure_is_cascaded();
+
+        let root = self.root();
+        let viewport = Rect::from(*viewport);
+
+        let target = cairo::ImageSurface::create(cairo::Format::Rgb24, 1, 1)?;
+        let cr = cairo::Context::new(&target)?;
+
+        let config = options.to_rendering_configuration(false);
+        let cancellable = config.cancellable.clone();
+
+        collect_geometries(
+            self.session.clone(),
+            DrawingMode::LimitToStack {
+                node: root.clone(),
+                root,
+            },
+            &cr,
+            viewport,
+            config,
+            &mut AcquiredNodes::new(self, cancellable),
+            ids_only,
+        )
+        .map_err(|err| *err)
+    }
+
+    /// Returns the ids of the elements whose `pointer-events` region contains the point
+    /// `(x, y)`, with the topmost element first.
+    ///
+    /// The point is in the same coordinate system as the `viewport`, as if the whole
+    /// document were rendered into it.
+    pub fn elements_at_point(
+        &self,
+        viewport: &cairo::Rectangle,
+        x: f64,
+        y: f64,
+        options: &RenderingOptions,
+    ) -> Result<Vec<String>, InternalRenderingError> {
+        self.ensure_is_cascaded();
+
+        let root = self.root();
+        let viewport = Rect::from(*viewport);
+
+        // Unbounded, so that the point is never outside of the surface's extents.
+        let target = cairo::RecordingSurface::create(cairo::Content::ColorAlpha, None)?;
+        let cr = cairo::Context::new(&target)?;
+
+        let config = options.to_rendering_configuration(false);
+        let cancellable = config.cancellable.clone();
+
+        let mut ids = hit_test_tree(
+            self.session.clone(),
+            DrawingMode::LimitToStack {
+                node: root.clone(),
+                root,
+            },
+            &cr,
+            viewport,
+            config,
+            &mut AcquiredNodes::new(self, cancellable),
+            (x, y),
+        )
+        .map_err(|err| *err)?;
+
+        ids.reverse();
+        Ok(ids)
+    }
+
     /// Wrapper for [`draw_tree`].  This just ensures that the document
     /// is cascaded before rendering.
     fn draw_tree(
@@ -1000,6 +1157,11 @@ impl<'i> AcquiredNodes<'i> {
         }
     }
 
+    /// Returns the cache for the outputs of filter primitives in the document.
+    pub fn filter_cache(&self) -> &RefCell<FilterCache> {
+        &self.document.filter_cache
+    }
+
     pub fn lookup_resource(&self, url: &str) -> Result<Resource, LoadingError> {
         self.document
             .lookup_resource(url, self.cancellable.as_ref())
@@ -1252,6 +1414,9 @@ impl DocumentBuilder {
                         session: session.clone(),
                         ids,
                         resources: RefCell::new(Resources::new(load_limiter)),
+                        filter_cache: RefCell::new(FilterCache::new(
+                            load_options.filter_cache_budget,
+                        )),
                         load_options,
                         stylesheets,
                         needs_cascade: Cell::new(true),
@@ -1289,6 +1454,54 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn filter_cache_is_keyed_by_quality_and_can_be_cleared() {
+        let bytes = glib::Bytes::from_static(
+            br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter" x="0" y="0" width="1" height="1">
+    <feTurbulence baseFrequency="0.05" numOctaves="2"/>
+  </filter>
+  <rect x="10" y="10" width="80" height="80" filter="url(#filter)"/>
+</svg>
+"##,
+        );
+        let stream = gio::MemoryInputStream::from_bytes(&bytes);
+
+        let handle = crate::api::Loader::new()
+            .with_filter_cache(1024 * 1024)
+            .read_stream(&stream, None::<&gio::File>, None::<&gio::Cancellable>)
+            .unwrap();
+
+        let render = |quality| {
+            let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 100, 100).unwrap();
+            let cr = cairo::Context::new(&surface).unwrap();
+
+            crate::api::CairoRenderer::new(&handle)
+                .with_quality(quality)
+                .render_document(&cr, &cairo::Rectangle::new(0.0, 0.0, 100.0, 100.0))
+                .unwrap();
+        };
+
+        let hits = || handle.document.filter_cache.borrow().hits();
+
+        render(RenderQuality::Normal);
+        assert_eq!(hits(), 0);
+
+        render(RenderQuality::Normal);
+        assert_eq!(hits(), 1);
+
+        render(RenderQuality::Best);
+        assert_eq!(hits(), 1);
+
+        render(RenderQuality::Best);
+        assert_eq!(hits(), 2);
+
+        handle.clear_caches();
+        render(RenderQuality::Normal);
+        assert_eq!(hits(), 2);
+    }
+
     #[test]
     fn ignores_stylesheet_with_invalid_utf8() {
         let handle = crate::api::Loader::new()
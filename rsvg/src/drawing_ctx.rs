@@ -6,7 +6,10 @@ use pango::prelude::FontMapExt;
 use regex::{Captures, Regex};
 use std::cell::RefCell;
 use std::convert::TryFrom;
+use std::f64::consts::PI;
+use std::mem;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::{borrow::Cow, sync::OnceLock};
 
 use crate::accept_language::UserLanguage;
@@ -18,12 +21,13 @@ use crate::document::{AcquiredNodes, NodeId, RenderingOptions};
 use crate::dpi::Dpi;
 use crate::element::{DrawResult, Element, ElementData};
 use crate::error::{AcquireError, ImplementationLimit, InternalRenderingError, InvalidTransform};
-use crate::filters::{self, FilterPlan, FilterSpec, InputRequirements};
+use crate::filters::{self, FilterPlan, FilterResolveError, FilterSpec, InputRequirements};
 use crate::float_eq_cairo::ApproxEqCairo;
 use crate::gradient::{GradientVariant, SpreadMethod, UserSpaceGradient};
 use crate::layout::{
-    ClipPath, Filter, Group, Image, Layer, LayerKind, LayoutViewport, Shape, StackingContext,
-    Stroke, Text, TextSpan, element_can_be_used_inside_use_inside_clip_path,
+    ClipPath, Filter, Group, Image, Layer, LayerKind, LayoutViewport, Mask, MaskLayer, MaskSource,
+    Shape, StackingContext, Stroke, Text, TextSpan,
+    element_can_be_used_inside_use_inside_clip_path,
 };
 use crate::length::*;
 use crate::limits;
@@ -32,12 +36,15 @@ use crate::node::{CascadedValues, Node, NodeBorrow, NodeDraw};
 use crate::paint_server::{PaintSource, UserSpacePaintSource};
 use crate::pattern::UserSpacePattern;
 use crate::properties::{
-    ClipRule, ComputedValues, FillRule, ImageRendering, MaskType, MixBlendMode, Opacity,
-    PaintTarget, ShapeRendering, StrokeLinecap, StrokeLinejoin, TextRendering,
+    BgSize, ClipRule, CompositingOperator, ComputedValues, FillRule, ImageRendering, MaskType,
+    MaskingMode, MixBlendMode, Opacity, PaintTarget, PointerTargets, Repetition, ShapeRendering,
+    StrokeLinecap, StrokeLinejoin, TextRendering,
 };
 use crate::rect::{IRect, Rect, rect_to_transform};
 use crate::rsvg_log;
 use crate::session::Session;
+use crate::stroke_join::{self, JoinOutlines};
+use crate::surface_utils::ImageSurfaceDataExt;
 use crate::surface_utils::shared_surface::{
     ExclusiveImageSurface, Interpolation, SharedImageSurface, SurfaceType,
 };
@@ -122,6 +129,14 @@ pub struct Viewport {
     /// Corners of the current coordinate space.
     pub vbox: ViewBox,
 
+    /// Size of the outermost viewport, for the `vw`, `vh`, `vmin`, and `vmax` units.
+    ///
+    /// This is the viewport established by the outermost `<svg>` element, in its own user
+    /// units, so that those units don't depend on the size at which the document gets
+    /// rendered.  It is `None` until that element establishes its viewport; lengths in
+    /// the outermost `<svg>` itself refer to the rectangle being rendered into.
+    pub outer_vbox: Option<ViewBox>,
+
     /// The viewport's coordinate system, or "user coordinate system" in SVG terms.
     pub transform: ValidTransform,
 }
@@ -130,9 +145,12 @@ impl Viewport {
     /// FIXME: this is just used in Handle::with_height_to_user(), and in length.rs's test suite.
     /// Find a way to do this without involving a default identity transform.
     pub fn new(dpi: Dpi, view_box_width: f64, view_box_height: f64) -> Viewport {
+        let vbox = ViewBox::from(Rect::from_size(view_box_width, view_box_height));
+
         Viewport {
             dpi,
-            vbox: ViewBox::from(Rect::from_size(view_box_width, view_box_height)),
+            vbox,
+            outer_vbox: Some(vbox),
             transform: Default::default(),
         }
     }
@@ -152,12 +170,14 @@ impl Viewport {
             CoordUnits::ObjectBoundingBox => Viewport {
                 dpi: self.dpi,
                 vbox: ViewBox::from(Rect::from_size(1.0, 1.0)),
+                outer_vbox: self.outer_vbox,
                 transform: self.transform,
             },
 
             CoordUnits::UserSpaceOnUse => Viewport {
                 dpi: self.dpi,
                 vbox: self.vbox,
+                outer_vbox: self.outer_vbox,
                 transform: self.transform,
             },
         }
@@ -168,6 +188,7 @@ impl Viewport {
         Viewport {
             dpi: self.dpi,
             vbox: ViewBox::from(Rect::from_size(width, height)),
+            outer_vbox: self.outer_vbox,
             transform: self.transform,
         }
     }
@@ -180,6 +201,7 @@ impl Viewport {
         Viewport {
             dpi: self.dpi,
             vbox: self.vbox,
+            outer_vbox: self.outer_vbox,
             transform,
         }
     }
@@ -194,6 +216,7 @@ impl Viewport {
         Ok(Viewport {
             dpi: self.dpi,
             vbox: self.vbox,
+            outer_vbox: self.outer_vbox,
             transform: composed_transform,
         })
     }
@@ -203,6 +226,115 @@ impl Viewport {
     }
 }
 
+/// How text gets drawn to the Cairo context.
+///
+/// See [`CairoRenderer::with_text_rendering_mode`][crate::CairoRenderer::with_text_rendering_mode].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextMode {
+    /// Convert text to paths before drawing it.
+    ///
+    /// The output will not depend on fonts at all, which is useful when sending PDF files
+    /// to a print shop.  However, text will not be selectable or searchable in PDF viewers.
+    Outlines,
+
+    /// Draw text as glyphs from fonts.
+    ///
+    /// For vector outputs like PDF, Cairo embeds subsets of the fonts in the file, along
+    /// with the mapping from glyphs to the original characters.  This keeps text
+    /// selectable and searchable, even for ligatures and bidirectional text.  Strokes
+    /// are still drawn as paths.
+    Glyphs,
+}
+
+/// Resolution at which filter effects are computed.
+///
+/// See [`CairoRenderer::with_filter_resolution`][crate::CairoRenderer::with_filter_resolution].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum FilterResolution {
+    /// Compute filters at the resolution of the output.
+    #[default]
+    Device,
+
+    /// Compute filters at this number of pixels per CSS pixel of the document, regardless
+    /// of the scale at which it is rendered.
+    ///
+    /// A value of 1.0 computes filters at the document's natural size.
+    Fixed(f64),
+
+    /// Compute filters at the resolution of the output, but at most at this number of
+    /// pixels per CSS pixel of the document.
+    ///
+    /// This bounds the time to compute filters when zooming into a document.
+    Capped(f64),
+
+    /// Compute filters at the resolution of the output, but reduce the resolution for
+    /// filters whose surfaces would have more than this number of pixels.
+    ///
+    /// A value of 0 means no limit, like [`FilterResolution::Device`].
+    MaxPixels(u32),
+}
+
+/// Trade-off between rendering speed and accuracy.
+///
+/// See [`CairoRenderer::with_quality`][crate::CairoRenderer::with_quality].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RenderQuality {
+    /// Render quickly, for example, for previews.
+    ///
+    /// Filters are computed at half the resolution, Gaussian blurs are approximated
+    /// with box blurs down to a standard deviation of one pixel, images are scaled with
+    /// nearest-neighbor interpolation, and shapes use Cairo's fast antialiasing.
+    Draft,
+
+    /// Use the approximations allowed by the SVG specification where they make
+    /// rendering faster, like approximating `feGaussianBlur` with box blurs.  The
+    /// `image-rendering`, `shape-rendering`, and `text-rendering` properties are used as
+    /// specified.
+    #[default]
+    Normal,
+
+    /// Render as accurately as possible, for example, for print.
+    ///
+    /// Gaussian blurs are computed with an exact algorithm at every standard deviation,
+    /// and they are computed along the axes of the user space even when it is rotated or
+    /// skewed.  Images and antialiased shapes use Cairo's best quality.
+    Best,
+}
+
+impl RenderQuality {
+    /// Adjusts the interpolation for images to the quality.
+    pub(crate) fn interpolation(self, image_rendering: ImageRendering) -> Interpolation {
+        match (self, Interpolation::from(image_rendering)) {
+            (RenderQuality::Draft, _) => Interpolation::Nearest,
+            (RenderQuality::Best, Interpolation::Smooth) => Interpolation::Best,
+            (_, interpolation) => interpolation,
+        }
+    }
+
+    /// Adjusts the antialiasing for shapes and text to the quality.
+    ///
+    /// If the `shape-rendering` or `text-rendering` properties turn off antialiasing,
+    /// it stays off.
+    pub(crate) fn antialias(self, antialias: cairo::Antialias) -> cairo::Antialias {
+        match (self, antialias) {
+            (_, cairo::Antialias::None) => cairo::Antialias::None,
+            (RenderQuality::Draft, _) => cairo::Antialias::Fast,
+            (RenderQuality::Best, _) => cairo::Antialias::Best,
+            (RenderQuality::Normal, antialias) => antialias,
+        }
+    }
+
+    /// Returns the scale factor for the resolution of filter effects.
+    fn filter_scale(self) -> f64 {
+        match self {
+            RenderQuality::Draft => 0.5,
+            RenderQuality::Normal | RenderQuality::Best => 1.0,
+        }
+    }
+}
+
 /// Values that stay constant during rendering with a DrawingCtx.
 #[derive(Clone)]
 pub struct RenderingConfiguration {
@@ -212,6 +344,26 @@ pub struct RenderingConfiguration {
     pub svg_nesting: SvgNesting,
     pub measuring: bool,
     pub testing: bool,
+
+    /// If `None`, text is drawn as glyphs only for PDF surfaces.
+    pub text_mode: Option<TextMode>,
+
+    /// Extra extensions for `requiredExtensions`, declared as supported by the caller.
+    pub supported_extensions: Vec<String>,
+
+    /// If `None`, filters use rayon's global thread pool.
+    pub thread_pool: Option<Arc<rayon::ThreadPool>>,
+
+    pub quality: RenderQuality,
+    pub filter_resolution: FilterResolution,
+}
+
+/// A surface that is being drawn to, below the current one in the [`DrawingCtx`] stack.
+struct StackedCr {
+    cr: cairo::Context,
+
+    /// Whether the surface starts a new background; see [`DrawingCtx::get_snapshot`].
+    is_backdrop_root: bool,
 }
 
 pub struct DrawingCtx {
@@ -219,9 +371,13 @@ pub struct DrawingCtx {
 
     initial_viewport: Viewport,
 
-    cr_stack: Rc<RefCell<Vec<cairo::Context>>>,
+    cr_stack: Rc<RefCell<Vec<StackedCr>>>,
     cr: cairo::Context,
 
+    /// Whether `cr` is the surface of a backdrop root, i.e. content drawn below it does
+    /// not show up in the `BackgroundImage` of filters.
+    is_backdrop_root: bool,
+
     drawsub_stack: Vec<Node>,
 
     config: RenderingConfiguration,
@@ -238,6 +394,9 @@ pub struct DrawingCtx {
     /// then subtract it from another local variable at trace points.  See the print_stack_depth()
     /// function.
     stack_ptr: *const u8,
+
+    /// Whether the tree is being drawn, or walked for some other purpose.
+    walk_mode: WalkMode,
 }
 
 pub enum DrawingMode {
@@ -246,6 +405,60 @@ pub enum DrawingMode {
     OnlyNode(Node),
 }
 
+/// What a [`DrawingCtx`] does as it walks the tree.
+///
+/// In the modes other than `Draw`, nothing gets painted and layers are never isolated
+/// into temporary surfaces, since neither affects the results.
+enum WalkMode {
+    /// Draw the elements.
+    Draw,
+
+    /// Find the elements at a point; see [`hit_test_tree`].
+    HitTest(HitTest),
+
+    /// Collect the bounding boxes of elements; see [`collect_geometries`].
+    CollectGeometries(GeometryCollector),
+}
+
+/// State for finding the elements at a point instead of drawing them.
+struct HitTest {
+    /// The point to test, in device space.
+    x: f64,
+    y: f64,
+
+    /// Ids of the elements whose region contains the point, in painting order.
+    hits: Vec<String>,
+
+    /// Number of nested `<use>` elements being walked.
+    use_depth: usize,
+
+    /// Id of the outermost `<use>` element being walked; it gets reported instead of
+    /// the elements that it instantiates.
+    use_id: Option<String>,
+}
+
+/// State for collecting the bounding boxes of elements instead of drawing them.
+struct GeometryCollector {
+    /// Transform of the toplevel `cr`, for the bounding boxes in viewport space.
+    user_transform: Transform,
+
+    /// Whether to leave out elements without an `id` attribute.
+    ids_only: bool,
+
+    geometries: Vec<NodeGeometry>,
+}
+
+/// Bounding boxes of an element, as collected by [`collect_geometries`].
+pub struct NodeGeometry {
+    pub node: Node,
+
+    /// Bounding box in the element's user space, i.e. after applying its `transform`.
+    pub user_space: BoundingBox,
+
+    /// Bounding box in the coordinate system of the viewport.
+    pub viewport_space: BoundingBox,
+}
+
 /// Whether an SVG document is being rendered standalone or referenced from an `<image>` element.
 ///
 /// Normally, the coordinate system used when rendering a toplevel SVG is determined from the
@@ -271,6 +484,116 @@ pub fn draw_tree(
     config: RenderingConfiguration,
     acquired_nodes: &mut AcquiredNodes<'_>,
 ) -> DrawResult {
+    walk_tree(
+        session,
+        mode,
+        cr,
+        viewport_rect,
+        config,
+        acquired_nodes,
+        WalkMode::Draw,
+    )
+    .map(|(bbox, _)| bbox)
+}
+
+/// The toplevel hit-testing routine.
+///
+/// This walks the tree like [`draw_tree`], but instead of drawing, it finds the elements
+/// whose region, as given by their `pointer-events` property, contains the `point`.  The
+/// point is in the user space of `cr`, like the `viewport_rect`.
+///
+/// Opacity, filters, masks, and markers do not affect the result; clipping paths and
+/// transforms do.
+///
+/// Returns the ids of the elements that were hit in painting order, so the topmost
+/// element is last.  Elements without an `id` attribute are left out.  Like events in the
+/// DOM, hits on content instantiated by a `<use>` element get retargeted to the outermost
+/// `<use>`, which is reported once per instance.
+pub fn hit_test_tree(
+    session: Session,
+    mode: DrawingMode,
+    cr: &cairo::Context,
+    viewport_rect: Rect,
+    config: RenderingConfiguration,
+    acquired_nodes: &mut AcquiredNodes<'_>,
+    point: (f64, f64),
+) -> Result<Vec<String>, Box<InternalRenderingError>> {
+    let (x, y) = cr.user_to_device(point.0, point.1);
+
+    let hit_test = HitTest {
+        x,
+        y,
+        hits: Vec::new(),
+        use_depth: 0,
+        use_id: None,
+    };
+
+    let (_bbox, walk_mode) = walk_tree(
+        session,
+        mode,
+        cr,
+        viewport_rect,
+        config,
+        acquired_nodes,
+        WalkMode::HitTest(hit_test),
+    )?;
+
+    match walk_mode {
+        WalkMode::HitTest(hit_test) => Ok(hit_test.hits),
+        _ => unreachable!(),
+    }
+}
+
+/// The toplevel routine for measuring all the elements at once.
+///
+/// This walks the tree like [`draw_tree`], but instead of drawing, it collects the
+/// bounding box of each element that gets rendered, in a single pass.  The bounding
+/// boxes in viewport space are in the user space of `cr`, like the `viewport_rect`.
+///
+/// Elements without any geometry, like the ones that are not rendered, are left out.
+/// If `ids_only` is true, elements without an `id` attribute are left out as well.  Elements
+/// that are rendered more than once, like those referenced by several `<use>`, appear
+/// once per instance.
+pub fn collect_geometries(
+    session: Session,
+    mode: DrawingMode,
+    cr: &cairo::Context,
+    viewport_rect: Rect,
+    config: RenderingConfiguration,
+    acquired_nodes: &mut AcquiredNodes<'_>,
+    ids_only: bool,
+) -> Result<Vec<NodeGeometry>, Box<InternalRenderingError>> {
+    let collector = GeometryCollector {
+        user_transform: Transform::from(cr.matrix()),
+        ids_only,
+        geometries: Vec::new(),
+    };
+
+    let (_bbox, walk_mode) = walk_tree(
+        session,
+        mode,
+        cr,
+        viewport_rect,
+        config,
+        acquired_nodes,
+        WalkMode::CollectGeometries(collector),
+    )?;
+
+    match walk_mode {
+        WalkMode::CollectGeometries(collector) => Ok(collector.geometries),
+        _ => unreachable!(),
+    }
+}
+
+fn walk_tree(
+    session: Session,
+    mode: DrawingMode,
+    cr: &cairo::Context,
+    viewport_rect: Rect,
+    config: RenderingConfiguration,
+    acquired_nodes: &mut AcquiredNodes<'_>,
+    walk_mode: WalkMode,
+) -> Result<(Box<BoundingBox>, WalkMode), Box<InternalRenderingError>> {
     let (drawsub_stack, node) = match mode {
         DrawingMode::LimitToStack { node, root } => (node.ancestors().collect(), root),
 
@@ -311,10 +634,12 @@ pub fn draw_tree(
     let initial_viewport = Viewport {
         dpi: config.dpi,
         vbox: ViewBox::from(viewport_rect),
+        outer_vbox: None,
         transform: valid_transform,
     };
 
     let mut draw_ctx = DrawingCtx::new(session, cr, &initial_viewport, config, drawsub_stack);
+    draw_ctx.walk_mode = walk_mode;
 
     let content_bbox =
         draw_ctx.draw_node_from_stack(&node, acquired_nodes, &cascaded, &initial_viewport)?;
@@ -324,7 +649,8 @@ pub fn draw_tree(
     if draw_ctx.is_rendering_cancelled() {
         Err(InternalRenderingError::Cancelled)?
     } else {
-        Ok(user_bbox)
+        let walk_mode = mem::replace(&mut draw_ctx.walk_mode, WalkMode::Draw);
+        Ok((user_bbox, walk_mode))
     }
 }
 
@@ -366,6 +692,7 @@ impl DrawingCtx {
             initial_viewport: *initial_viewport,
             cr_stack: Rc::new(RefCell::new(Vec::new())),
             cr: cr.clone(),
+            is_backdrop_root: true,
             drawsub_stack,
             config,
             recursion_depth: 0,
@@ -377,6 +704,8 @@ impl DrawingCtx {
             //
             // The pointer is otherwise meaningless and should never be dereferenced.
             stack_ptr: &stack_variable,
+
+            walk_mode: WalkMode::Draw,
         }
     }
 
@@ -390,20 +719,28 @@ impl DrawingCtx {
     /// Note that if this function is called, it means that a temporary surface is being used.
     /// That surface needs a viewport which starts with a special transform; see
     /// [`Viewport::with_explicit_transform`] and how it is used elsewhere.
+    ///
+    /// The new surface is a backdrop root, since masks and paint servers are drawn without
+    /// a background.  Isolated groups override this.
     fn nested(&self, cr: cairo::Context) -> Box<DrawingCtx> {
         let cr_stack = self.cr_stack.clone();
 
-        cr_stack.borrow_mut().push(self.cr.clone());
+        cr_stack.borrow_mut().push(StackedCr {
+            cr: self.cr.clone(),
+            is_backdrop_root: self.is_backdrop_root,
+        });
 
         Box::new(DrawingCtx {
             session: self.session.clone(),
             initial_viewport: self.initial_viewport,
             cr_stack,
             cr,
+            is_backdrop_root: true,
             drawsub_stack: self.drawsub_stack.clone(),
             config: self.config.clone(),
             recursion_depth: self.recursion_depth,
             stack_ptr: self.stack_ptr,
+            walk_mode: WalkMode::Draw,
         })
     }
 
@@ -432,6 +769,11 @@ impl DrawingCtx {
             user_language: self.config.user_language.clone(),
             svg_nesting,
             testing: self.config.testing,
+            text_mode: self.config.text_mode,
+            supported_extensions: self.config.supported_extensions.clone(),
+            thread_pool: self.config.thread_pool.clone(),
+            quality: self.config.quality,
+            filter_resolution: self.config.filter_resolution,
         }
     }
 
@@ -439,6 +781,10 @@ impl DrawingCtx {
         &self.config.user_language
     }
 
+    pub fn supported_extensions(&self) -> &[String] {
+        &self.config.supported_extensions
+    }
+
     pub fn toplevel_viewport(&self) -> Rect {
         *self.initial_viewport.vbox
     }
@@ -451,7 +797,7 @@ impl DrawingCtx {
         stacking_ctx: &StackingContext,
         clipping: bool,
     ) -> Result<ValidTransform, Box<InternalRenderingError>> {
-        if stacking_ctx.should_isolate() && !clipping {
+        if stacking_ctx.should_isolate() && !clipping && self.is_drawing() {
             let affines = CompositingAffines::new(
                 *viewport.transform,
                 *self.initial_viewport.transform,
@@ -476,6 +822,159 @@ impl DrawingCtx {
         self.config.testing
     }
 
+    /// Whether elements are being painted, as opposed to the tree being walked for
+    /// hit-testing or measuring.
+    pub fn is_drawing(&self) -> bool {
+        matches!(self.walk_mode, WalkMode::Draw)
+    }
+
+    pub fn is_hit_testing(&self) -> bool {
+        matches!(self.walk_mode, WalkMode::HitTest(_))
+    }
+
+    /// Returns the hit-test point in the current user space of `self.cr`, if it is inside
+    /// the current clipping region.
+    fn hit_test_point_in_clip(&self) -> Result<Option<(f64, f64)>, Box<InternalRenderingError>> {
+        let WalkMode::HitTest(ref hit_test) = self.walk_mode else {
+            return Ok(None);
+        };
+
+        let (x, y) = self.cr.device_to_user(hit_test.x, hit_test.y);
+
+        if self.cr.in_clip(x, y)? {
+            Ok(Some((x, y)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Records a hit for the element of `stacking_ctx` if the hit-test point is inside
+    /// one of the regions in `targets`.
+    ///
+    /// The current path of `self.cr` must be the element's outline, in the user space
+    /// given by its current matrix, and the stroke parameters must be already set.  The
+    /// `extents` are the element's bounding box in that same user space.
+    fn hit_test_current_path(
+        &mut self,
+        stacking_ctx: &StackingContext,
+        targets: PointerTargets,
+        extents: Option<Rect>,
+        non_scaling_stroke: bool,
+    ) -> Result<(), Box<InternalRenderingError>> {
+        let Some((x, y)) = self.hit_test_point_in_clip()? else {
+            return Ok(());
+        };
+
+        let cr = &self.cr;
+
+        let mut is_hit = (targets.fill && cr.in_fill(x, y)?)
+            || (targets.bounding_box && extents.is_some_and(|r| r.contains(x, y)));
+
+        if !is_hit && targets.stroke {
+            if non_scaling_stroke {
+                // The stroke gets drawn in the initial viewport's coordinate system.
+                let (device_x, device_y) = cr.user_to_device(x, y);
+                cr.set_matrix(self.initial_viewport.transform.into());
+                let (x, y) = cr.device_to_user(device_x, device_y);
+                is_hit = cr.in_stroke(x, y)?;
+            } else {
+                is_hit = cr.in_stroke(x, y)?;
+            }
+        }
+
+        if is_hit {
+            self.record_hit(stacking_ctx);
+        }
+
+        Ok(())
+    }
+
+    /// Whether the hit-test point is inside `rect`, which is in the current user space
+    /// of `self.cr`.
+    fn hit_test_rect(&self, rect: Rect) -> Result<bool, Box<InternalRenderingError>> {
+        Ok(self
+            .hit_test_point_in_clip()?
+            .is_some_and(|(x, y)| rect.contains(x, y)))
+    }
+
+    fn record_hit(&mut self, stacking_ctx: &StackingContext) {
+        let WalkMode::HitTest(ref mut hit_test) = self.walk_mode else {
+            return;
+        };
+
+        let id = if hit_test.use_depth > 0 {
+            hit_test.use_id.clone()
+        } else {
+            stacking_ctx.element_id.clone()
+        };
+
+        // Several elements of an instance may be hit, but the instance is reported once.
+        if let Some(id) = id
+            && hit_test.hits.last() != Some(&id)
+        {
+            hit_test.hits.push(id);
+        }
+    }
+
+    /// Starts walking the content instantiated by a `<use>` element with the given `id`.
+    fn enter_use_instance(&mut self, id: Option<&str>) {
+        if let WalkMode::HitTest(ref mut hit_test) = self.walk_mode {
+            if hit_test.use_depth == 0 {
+                hit_test.use_id = id.map(String::from);
+            }
+
+            hit_test.use_depth += 1;
+        }
+    }
+
+    fn leave_use_instance(&mut self) {
+        if let WalkMode::HitTest(ref mut hit_test) = self.walk_mode {
+            hit_test.use_depth -= 1;
+        }
+    }
+
+    /// Records the bounding box of an element that was just walked, if collecting
+    /// geometries.
+    ///
+    /// The `viewport` is the one in which the element was drawn, and `values` are the
+    /// element's computed values.
+    pub fn record_geometry(
+        &mut self,
+        node: &Node,
+        values: &ComputedValues,
+        viewport: &Viewport,
+        bbox: &BoundingBox,
+    ) {
+        let WalkMode::CollectGeometries(ref mut collector) = self.walk_mode else {
+            return;
+        };
+
+        if bbox.rect.is_none() && bbox.ink_rect.is_none() {
+            return;
+        }
+
+        if collector.ids_only && node.borrow_element().get_id().is_none() {
+            return;
+        }
+
+        let element_transform = values.transform().post_transform(&viewport.transform);
+        if !element_transform.is_invertible() {
+            return;
+        }
+
+        let mut user_space = BoundingBox::new().with_transform(element_transform);
+        user_space.insert(bbox);
+
+        let mut viewport_space = BoundingBox::new().with_transform(collector.user_transform);
+        viewport_space.insert(bbox);
+
+        collector.geometries.push(NodeGeometry {
+            node: node.clone(),
+            user_space,
+            viewport_space,
+        });
+    }
+
     fn size_for_temporary_surface(&self) -> (i32, i32) {
         let rect = self.toplevel_viewport();
 
@@ -491,26 +990,90 @@ impl DrawingCtx {
         (width.ceil().abs() as i32, height.ceil().abs() as i32)
     }
 
-    pub fn create_surface_for_toplevel_viewport(
+    /// Rounds a rectangle in the coordinates of temporary surfaces out to whole pixels,
+    /// and clips it to the toplevel viewport.
+    ///
+    /// Returns a 1x1 region if nothing is left, since Cairo and the filter code do not
+    /// deal with zero-sized surfaces.
+    fn region_for_temporary_surface(&self, rect: Rect) -> IRect {
+        let (w, h) = self.size_for_temporary_surface();
+
+        IRect::from(rect)
+            .intersection(&IRect::from_size(w, h))
+            .unwrap_or_else(|| IRect::from_size(1, 1))
+    }
+
+    /// Computes the region of a temporary surface that will be visible once it gets
+    /// composited onto `self.cr` with the `compositing` transform.
+    ///
+    /// See [`CompositingAffines`] for the coordinate systems involved.
+    fn visible_region_for_temporary_surface(
         &self,
+        compositing: Transform,
+    ) -> Result<IRect, Box<InternalRenderingError>> {
+        let saved_matrix = self.cr.matrix();
+
+        self.cr
+            .set_matrix(ValidTransform::try_from(compositing)?.into());
+        let extents = self.cr.clip_extents();
+        self.cr.set_matrix(saved_matrix);
+
+        let (x0, y0, x1, y1) = extents?;
+
+        Ok(self.region_for_temporary_surface(Rect::new(x0, y0, x1, y1)))
+    }
+
+    /// Creates an image surface that covers a region of the toplevel viewport.
+    ///
+    /// The surface has a device offset, so that code which draws to it, or which uses it
+    /// as a source, can keep using the coordinates of the whole toplevel viewport.  Note
+    /// that the offset gets lost when the surface is wrapped into a [`SharedImageSurface`].
+    fn create_surface_for_region(
+        &self,
+        region: IRect,
     ) -> Result<cairo::ImageSurface, Box<InternalRenderingError>> {
-        let (w, h) = self.size_for_temporary_surface();
+        let surface =
+            cairo::ImageSurface::create(cairo::Format::ARgb32, region.width(), region.height())?;
+        set_device_offset_for_region(&surface, region);
 
-        Ok(cairo::ImageSurface::create(cairo::Format::ARgb32, w, h)?)
+        Ok(surface)
     }
 
-    fn create_similar_surface_for_toplevel_viewport(
+    /// Rasterizes the `region` of a recording of a temporary surface.
+    ///
+    /// The result is similar to the current target, and has a device offset like the
+    /// surfaces from [`Self::create_similar_surface_for_region`].
+    fn rasterize_recording(
         &self,
-        surface: &cairo::Surface,
+        recording: &cairo::Surface,
+        region: IRect,
     ) -> Result<cairo::Surface, Box<InternalRenderingError>> {
-        let (w, h) = self.size_for_temporary_surface();
+        let surface = self.create_similar_surface_for_region(&self.cr.target(), region)?;
+
+        {
+            let cr = cairo::Context::new(&surface)?;
+            cr.set_source_surface(recording, 0.0, 0.0)?;
+            cr.paint()?;
+        }
+
+        Ok(surface)
+    }
 
-        Ok(cairo::Surface::create_similar(
+    /// Like [`Self::create_surface_for_region`], but creates a surface similar to another one.
+    fn create_similar_surface_for_region(
+        &self,
+        surface: &cairo::Surface,
+        region: IRect,
+    ) -> Result<cairo::Surface, Box<InternalRenderingError>> {
+        let surface = cairo::Surface::create_similar(
             surface,
             cairo::Content::ColorAlpha,
-            w,
-            h,
-        )?)
+            region.width(),
+            region.height(),
+        )?;
+        set_device_offset_for_region(&surface, region);
+
+        Ok(surface)
     }
 
     /// Creates a new coordinate space inside a viewport and sets a clipping rectangle.
@@ -558,9 +1121,13 @@ impl DrawingCtx {
                 let transform =
                     ValidTransform::try_from(current_viewport.transform.pre_transform(&t)).ok()?;
 
+                let vbox = vbox.unwrap_or(current_viewport.vbox);
+
                 Some(Viewport {
                     dpi: self.config.dpi,
-                    vbox: vbox.unwrap_or(current_viewport.vbox),
+                    vbox,
+                    // The first viewport to be pushed is the outermost `<svg>`'s.
+                    outer_vbox: current_viewport.outer_vbox.or(Some(vbox)),
                     transform,
                 })
             })
@@ -656,12 +1223,78 @@ impl DrawingCtx {
         Ok(())
     }
 
-    fn generate_cairo_mask(
+    /// Computes the area of the temporary surface where a mask may be non-transparent.
+    ///
+    /// This is the union of the areas of the mask's layers: the mask rectangle for layers
+    /// that reference a `<mask>` element, and the stroke bounding box for images and
+    /// gradients.  Layers that can't be resolved are transparent, so they don't add to
+    /// the area.  Returns an empty rectangle if nothing gets through the mask.
+    fn mask_bounds(
+        &self,
+        mask: &Mask,
+        viewport: &Viewport,
+        transform: Transform,
+        bbox: &BoundingBox,
+        acquired_nodes: &mut AcquiredNodes<'_>,
+    ) -> Result<Rect, Box<InternalRenderingError>> {
+        let area = match bbox.rect {
+            Some(r) if !r.is_empty() => r,
+            _ => return Ok(Rect::default()),
+        };
+
+        let mut bounds: Option<Rect> = None;
+
+        for layer in &mask.layers {
+            let layer_bounds = match layer.source {
+                MaskSource::Element(ref mask_node) => {
+                    let Ok(_mask_acquired) = acquired_nodes.acquire_ref(mask_node) else {
+                        continue;
+                    };
+
+                    let cascaded = CascadedValues::new_from_node(mask_node);
+                    let mask_element = borrow_element_as!(mask_node, Mask);
+
+                    match MaskGeometry::new(
+                        &mask_element,
+                        cascaded.get(),
+                        viewport,
+                        transform,
+                        bbox,
+                    )? {
+                        Some(geometry) => geometry
+                            .transform_for_mask
+                            .transform_rect(&geometry.clip_rect),
+                        None => continue,
+                    }
+                }
+
+                MaskSource::Image(_) | MaskSource::Gradient(_) => {
+                    transform.transform_rect(&bbox.ink_rect.unwrap_or(area))
+                }
+
+                MaskSource::Transparent => continue,
+            };
+
+            bounds = Some(match bounds {
+                Some(b) => b.union(&layer_bounds),
+                None => layer_bounds,
+            });
+        }
+
+        Ok(bounds.unwrap_or_default())
+    }
+
+    /// Renders all the layers of a mask into a surface whose alpha channel is the mask.
+    ///
+    /// The layers are composited from the bottom up, per each layer's `mask-composite`.
+    /// Only the `region` of the temporary surface being masked gets rendered.
+    fn generate_mask(
         &mut self,
-        mask_node: &Node,
+        mask: &Mask,
         viewport: &Viewport,
         transform: Transform,
         bbox: &BoundingBox,
+        region: IRect,
         acquired_nodes: &mut AcquiredNodes<'_>,
     ) -> Result<Option<cairo::ImageSurface>, Box<InternalRenderingError>> {
         if bbox.rect.is_none() {
@@ -670,6 +1303,69 @@ impl DrawingCtx {
             return Ok(None);
         }
 
+        let mask_surface = self.create_surface_for_region(region)?;
+
+        // Use a scope because mask_cr needs to release the
+        // reference to the surface before we return it
+        {
+            let mask_cr = cairo::Context::new(&mask_surface)?;
+
+            let bottom_layer = mask.layers.len() - 1;
+
+            for (i, layer) in mask.layers.iter().enumerate().rev() {
+                let layer_surface = match layer.source {
+                    MaskSource::Element(ref mask_node) => self.generate_cairo_mask(
+                        mask_node,
+                        layer.mode,
+                        viewport,
+                        transform,
+                        bbox,
+                        region,
+                        acquired_nodes,
+                    )?,
+
+                    MaskSource::Image(_) | MaskSource::Gradient(_) => self
+                        .generate_mask_image_layer(
+                            mask, layer, viewport, transform, bbox, region,
+                        )?,
+
+                    MaskSource::Transparent => None,
+                };
+
+                // The compositing operator of the bottom layer is ignored, as there is
+                // nothing below it.
+                let operator = if i == bottom_layer {
+                    cairo::Operator::Over
+                } else {
+                    cairo::Operator::from(layer.composite)
+                };
+
+                mask_cr.set_operator(operator);
+
+                if let Some(surf) = layer_surface {
+                    mask_cr.set_source_surface(&surf, 0.0, 0.0)?;
+                } else {
+                    mask_cr.set_source_rgba(0.0, 0.0, 0.0, 0.0);
+                }
+
+                mask_cr.paint()?;
+            }
+        }
+
+        Ok(Some(mask_surface))
+    }
+
+    /// Renders a mask layer that references a `<mask>` element.
+    fn generate_cairo_mask(
+        &mut self,
+        mask_node: &Node,
+        mode: MaskingMode,
+        viewport: &Viewport,
+        transform: Transform,
+        bbox: &BoundingBox,
+        region: IRect,
+        acquired_nodes: &mut AcquiredNodes<'_>,
+    ) -> Result<Option<cairo::ImageSurface>, Box<InternalRenderingError>> {
         let _mask_acquired = match acquired_nodes.acquire_ref(mask_node) {
             Ok(n) => n,
 
@@ -682,33 +1378,22 @@ impl DrawingCtx {
         let cascaded = CascadedValues::new_from_node(mask_node);
         let values = cascaded.get();
 
-        let mask_units = mask.get_units();
-
-        let mask_rect = {
-            let params = NormalizeParams::new(values, &viewport.with_units(mask_units));
-            mask.get_rect(&params)
-        };
-
-        let transform_for_mask =
-            ValidTransform::try_from(values.transform().post_transform(&transform))?;
-
-        let bbtransform = if let Ok(t) = rect_to_transform(&bbox.rect, mask_units)
-            .map_err(|_: ()| InvalidTransform)
-            .and_then(ValidTransform::try_from)
-        {
-            t
-        } else {
+        let Some(geometry) = MaskGeometry::new(&mask, values, viewport, transform, bbox)? else {
             return Ok(None);
         };
+        let MaskGeometry {
+            transform_for_mask,
+            bbtransform,
+            clip_rect,
+        } = geometry;
 
-        let mask_content_surface = self.create_surface_for_toplevel_viewport()?;
+        let mask_content_surface = self.create_surface_for_region(region)?;
 
         // Use a scope because mask_cr needs to release the
         // reference to the surface before we access the pixels
         {
             let mask_cr = cairo::Context::new(&mask_content_surface)?;
 
-            let clip_rect = (*bbtransform).transform_rect(&mask_rect);
             clip_to_rectangle(&mask_cr, &transform_for_mask, &clip_rect);
 
             let mask_viewport = if mask.get_content_units() == CoordUnits::ObjectBoundingBox {
@@ -754,14 +1439,209 @@ impl DrawingCtx {
 
         let tmp = SharedImageSurface::wrap(mask_content_surface, SurfaceType::SRgb)?;
 
-        let mask_result = match values.mask_type() {
+        let mask_type = match mode {
+            MaskingMode::Alpha => MaskType::Alpha,
+            MaskingMode::Luminance => MaskType::Luminance,
+            MaskingMode::MatchSource => values.mask_type(),
+        };
+
+        let mask_result = match mask_type {
             MaskType::Luminance => tmp.to_luminance_mask()?,
             MaskType::Alpha => tmp.extract_alpha(IRect::from_size(tmp.width(), tmp.height()))?,
+        }
+        .into_image_surface()?;
+
+        set_device_offset_for_region(&mask_result, region);
+
+        Ok(Some(mask_result))
+    }
+
+    /// Renders a mask layer that uses a raster image or a gradient function.
+    ///
+    /// The image is sized and positioned relative to the object bounding box per the
+    /// layer's `mask-size` and `mask-position`, tiled per its `mask-repeat`, and clipped
+    /// to the stroke bounding box.
+    fn generate_mask_image_layer(
+        &self,
+        mask: &Mask,
+        layer: &MaskLayer,
+        viewport: &Viewport,
+        transform: Transform,
+        bbox: &BoundingBox,
+        region: IRect,
+    ) -> Result<Option<cairo::ImageSurface>, Box<InternalRenderingError>> {
+        let area = match bbox.rect {
+            Some(r) if !r.is_empty() => r,
+            _ => return Ok(None),
         };
 
-        let mask = mask_result.into_image_surface()?;
+        let clip = bbox.ink_rect.unwrap_or(area);
+
+        let params = NormalizeParams::from_values(&mask.normalize_values, viewport);
+
+        let intrinsic_size = match layer.source {
+            MaskSource::Image(ref image) => {
+                Some((f64::from(image.width()), f64::from(image.height())))
+            }
+            _ => None,
+        };
+
+        let (mut tile_width, mut tile_height) =
+            mask_tile_size(&layer.size, intrinsic_size, &area, &params);
+
+        // "round" rescales the tiles so that a whole number of them fits in the area
+        let round_x = layer.repeat.x == Repetition::Round && tile_width > 0.0;
+        let round_y = layer.repeat.y == Repetition::Round && tile_height > 0.0;
+
+        if round_x {
+            let n = (area.width() / tile_width).round().max(1.0);
+            let new_width = area.width() / n;
+
+            if !round_y && mask_size_height_is_auto(&layer.size) {
+                tile_height *= new_width / tile_width;
+            }
+
+            tile_width = new_width;
+        }
+
+        if round_y {
+            let n = (area.height() / tile_height).round().max(1.0);
+            let new_height = area.height() / n;
+
+            if !round_x && mask_size_width_is_auto(&layer.size) {
+                tile_width *= new_height / tile_height;
+            }
+
+            tile_height = new_height;
+        }
+
+        if !(tile_width > 0.0 && tile_height > 0.0) {
+            return Ok(None);
+        }
+
+        let (offset_x, offset_y) = layer.position.to_user(
+            &params,
+            area.width() - tile_width,
+            area.height() - tile_height,
+        );
+
+        let (origin_x, step_x, repeat_x) =
+            mask_tile_axis(layer.repeat.x, area.x0, area.width(), tile_width, offset_x);
+        let (origin_y, step_y, repeat_y) = mask_tile_axis(
+            layer.repeat.y,
+            area.y0,
+            area.height(),
+            tile_height,
+            offset_y,
+        );
+
+        // Render a single tile, including the spacing around it, at device resolution.
+
+        let scale_x = transform.xx.hypot(transform.yx);
+        let scale_y = transform.xy.hypot(transform.yy);
+
+        let tile_pixel_width = (step_x * scale_x).ceil().max(1.0);
+        let tile_pixel_height = (step_y * scale_y).ceil().max(1.0);
+
+        if tile_pixel_width > f64::from(limits::MAX_MASK_TILE_SIZE)
+            || tile_pixel_height > f64::from(limits::MAX_MASK_TILE_SIZE)
+        {
+            rsvg_log!(
+                self.session,
+                "mask image tile of {}x{} pixels is too big; ignoring mask layer",
+                tile_pixel_width,
+                tile_pixel_height
+            );
+            return Ok(None);
+        }
+
+        let tile_surface = cairo::ImageSurface::create(
+            cairo::Format::ARgb32,
+            tile_pixel_width as i32,
+            tile_pixel_height as i32,
+        )?;
+
+        {
+            let cr = cairo::Context::new(&tile_surface)?;
+            cr.scale(tile_pixel_width / step_x, tile_pixel_height / step_y);
+
+            match layer.source {
+                MaskSource::Image(ref image) => {
+                    cr.rectangle(0.0, 0.0, tile_width, tile_height);
+                    cr.clip();
+                    cr.scale(
+                        tile_width / f64::from(image.width()),
+                        tile_height / f64::from(image.height()),
+                    );
+                    image.set_as_source_surface(&cr, 0.0, 0.0)?;
+                    cr.paint()?;
+                }
+
+                MaskSource::Gradient(ref gradient) => {
+                    let tile_rect = Rect::from_size(tile_width, tile_height);
+
+                    if let Some(g) = gradient.to_user_space(
+                        &tile_rect,
+                        &params,
+                        UnitInterval::clamp(1.0),
+                        &mask.current_color,
+                    ) {
+                        let paint_area = Rect::from_size(tile_pixel_width, tile_pixel_height);
+                        set_gradient_on_cairo(&cr, &g, &paint_area)?;
+                        cr.rectangle(0.0, 0.0, tile_width, tile_height);
+                        cr.fill()?;
+                    }
+                }
+
+                MaskSource::Element(_) | MaskSource::Transparent => unreachable!(),
+            }
+        }
+
+        // Paint the tiles over the area, clipped to the stroke bounding box and to the
+        // strip of the positioning area along the axes that are not repeated.
+
+        let layer_surface = self.create_surface_for_region(region)?;
+
+        {
+            let cr = cairo::Context::new(&layer_surface)?;
+            cr.set_matrix(ValidTransform::try_from(transform)?.into());
+
+            cr.rectangle(clip.x0, clip.y0, clip.width(), clip.height());
+            cr.clip();
+
+            if !repeat_x {
+                cr.rectangle(origin_x, clip.y0, tile_width, clip.height());
+                cr.clip();
+            }
+
+            if !repeat_y {
+                cr.rectangle(clip.x0, origin_y, clip.width(), tile_height);
+                cr.clip();
+            }
+
+            let pattern = cairo::SurfacePattern::create(&tile_surface);
+            pattern.set_extend(cairo::Extend::Repeat);
 
-        Ok(Some(mask))
+            let pattern_transform = Transform::new_translate(-origin_x, -origin_y)
+                .post_scale(tile_pixel_width / step_x, tile_pixel_height / step_y);
+            pattern.set_matrix(ValidTransform::try_from(pattern_transform)?.into());
+
+            cr.set_source(&pattern)?;
+            cr.paint()?;
+        }
+
+        match layer.mode {
+            MaskingMode::Luminance => {
+                let surface = SharedImageSurface::wrap(layer_surface, SurfaceType::SRgb)?;
+                let mask_result = surface.to_luminance_mask()?.into_image_surface()?;
+                set_device_offset_for_region(&mask_result, region);
+
+                Ok(Some(mask_result))
+            }
+
+            // Images and gradients are used as alpha masks unless specified otherwise
+            MaskingMode::Alpha | MaskingMode::MatchSource => Ok(Some(layer_surface)),
+        }
     }
 
     fn is_rendering_cancelled(&self) -> bool {
@@ -793,18 +1673,23 @@ impl DrawingCtx {
         Ok(())
     }
 
-    fn filter_current_surface(
+    /// Filters what has been drawn to the current surface, or what is behind it.
+    ///
+    /// For [`FilterSource::CurrentSurface`], the current surface is expected to be a
+    /// recording surface, so that only the filter effects region needs to be rasterized.
+    /// For [`FilterSource::Backdrop`], the region gets composited from the surfaces in the
+    /// stack, like the `BackgroundImage` of filters.  The result covers that region, with a
+    /// device offset so that it can be composited like a temporary surface.
+    fn filter_surface(
         &mut self,
+        source: FilterSource,
         acquired_nodes: &mut AcquiredNodes<'_>,
         filter: &Filter,
         viewport: &Viewport,
         element_name: &str,
         bbox: &BoundingBox,
+        visible_region: IRect,
     ) -> Result<cairo::Surface, Box<InternalRenderingError>> {
-        let surface_to_filter = SharedImageSurface::copy_from_surface(
-            &cairo::ImageSurface::try_from(self.cr.target()).unwrap(),
-        )?;
-
         let stroke_paint_source = Rc::new(filter.stroke_paint_source.to_user_space(
             &bbox.rect,
             viewport,
@@ -825,25 +1710,199 @@ impl DrawingCtx {
             &viewport.with_units(CoordUnits::UserSpaceOnUse),
         );
 
-        let filtered_surface = self
-            .run_filters(
-                viewport,
-                surface_to_filter,
-                filter,
-                acquired_nodes,
-                element_name,
-                &user_space_params,
-                stroke_paint_source,
-                fill_paint_source,
-                bbox,
-            )?
-            .into_image_surface()?;
+        let filter_specs = self.resolve_filter_specs(
+            acquired_nodes,
+            filter,
+            viewport,
+            element_name,
+            &user_space_params,
+        );
+
+        // The filters can only see their effects region, so there is no need to rasterize
+        // anything outside of it.  If the filters are in error, the content is rendered
+        // unfiltered, so only what is visible is needed.
+        let region = match filter_specs {
+            Ok(ref specs) => {
+                let effects_region = specs
+                    .iter()
+                    .filter_map(|spec| spec.effects_region(bbox, &viewport.transform))
+                    .reduce(|a, b| a.union(&b));
+
+                self.region_for_temporary_surface(effects_region.unwrap_or_default())
+            }
+
+            Err(_) => visible_region,
+        };
+
+        let scale = match filter_specs {
+            Ok(ref specs) => self.filter_scale(specs, bbox, viewport, region),
+            Err(_) => (1.0, 1.0),
+        };
+
+        // SVG1.1: "Zero values disable rendering of the element which referenced the filter."
+        if scale.0 <= 0.0 || scale.1 <= 0.0 {
+            let surface = self.create_surface_for_region(region)?;
+            let generic_surface: &cairo::Surface = &surface; // deref to Surface
+            return Ok(generic_surface.clone());
+        }
+
+        let filter_region = FilterRegion { region, scale };
+
+        let surface_to_filter = match source {
+            FilterSource::CurrentSurface => {
+                let surface = cairo::ImageSurface::create(
+                    cairo::Format::ARgb32,
+                    filter_region.width(),
+                    filter_region.height(),
+                )?;
+
+                {
+                    let cr = cairo::Context::new(&surface)?;
+                    cr.set_matrix(ValidTransform::try_from(filter_region.transform())?.into());
+                    cr.set_source_surface(self.cr.target(), 0.0, 0.0)?;
+                    cr.paint()?;
+                }
+
+                SharedImageSurface::wrap(surface, SurfaceType::SRgb)?
+            }
+
+            FilterSource::Backdrop => self.get_snapshot(filter_region)?,
+        };
+
+        // The filters work in the pixel coordinates of surface_to_filter.
+        let filter_viewport = viewport.with_explicit_transform(ValidTransform::try_from(
+            viewport
+                .transform
+                .post_transform(&filter_region.transform()),
+        )?);
+
+        let filtered_surface = self.run_filters(
+            &filter_viewport,
+            surface_to_filter,
+            filter_specs,
+            acquired_nodes,
+            element_name,
+            stroke_paint_source,
+            fill_paint_source,
+            bbox,
+            filter_region,
+        )?;
+
+        let filtered_surface = filter_region.to_device_pixels(filtered_surface)?;
+
+        set_device_offset_for_region(&filtered_surface, region);
 
         let generic_surface: &cairo::Surface = &filtered_surface; // deref to Surface
 
         Ok(generic_surface.clone())
     }
 
+    /// Paints `surface` over the result of a `backdrop-filter`.
+    ///
+    /// The filtered backdrop is clipped to the bounding box of the element, so the result
+    /// is what gets composited for the element.
+    fn draw_over_filtered_backdrop(
+        &mut self,
+        surface: &cairo::Surface,
+        acquired_nodes: &mut AcquiredNodes<'_>,
+        backdrop_filter: &Filter,
+        viewport: &Viewport,
+        element_name: &str,
+        bbox: &BoundingBox,
+        visible_region: IRect,
+    ) -> Result<cairo::Surface, Box<InternalRenderingError>> {
+        let backdrop = self.filter_surface(
+            FilterSource::Backdrop,
+            acquired_nodes,
+            backdrop_filter,
+            viewport,
+            element_name,
+            bbox,
+            visible_region,
+        )?;
+
+        let composited = self.create_surface_for_region(visible_region)?;
+
+        {
+            let cr = cairo::Context::new(&composited)?;
+
+            if let Some(rect) = bbox.rect {
+                cr.save()?;
+                clip_to_rectangle(&cr, &viewport.transform, &rect);
+                cr.set_source_surface(&backdrop, 0.0, 0.0)?;
+                cr.paint()?;
+                cr.restore()?;
+            }
+
+            cr.set_source_surface(surface, 0.0, 0.0)?;
+            cr.paint()?;
+        }
+
+        let generic_surface: &cairo::Surface = &composited; // deref to Surface
+
+        Ok(generic_surface.clone())
+    }
+
+    /// Computes the resolution at which filters run, relative to the toplevel viewport.
+    ///
+    /// This honors the `filterRes` attributes of the filters, the rendering quality, and
+    /// the filter resolution requested for the renderer, in that order.
+    fn filter_scale(
+        &self,
+        specs: &[FilterSpec],
+        bbox: &BoundingBox,
+        viewport: &Viewport,
+        region: IRect,
+    ) -> (f64, f64) {
+        // All the filters run on the same surfaces, so use the lowest resolution that any
+        // of them asks for.
+        let (mut scale_x, mut scale_y) = specs
+            .iter()
+            .filter_map(|spec| {
+                spec.effects_region(bbox, &viewport.transform)
+                    .and_then(|r| spec.filter_res_scale(&r))
+            })
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1)))
+            .unwrap_or((1.0, 1.0));
+
+        let quality_scale = self.config.quality.filter_scale();
+        scale_x *= quality_scale;
+        scale_y *= quality_scale;
+
+        // Pixels of the toplevel viewport per CSS pixel of the document.
+        let initial = self.initial_viewport.transform;
+        let device_scale = (initial.xx * initial.yy - initial.xy * initial.yx)
+            .abs()
+            .sqrt();
+
+        let renderer_scale = match self.config.filter_resolution {
+            FilterResolution::Device => 1.0,
+
+            FilterResolution::Fixed(pixels_per_css_pixel) if pixels_per_css_pixel > 0.0 => {
+                pixels_per_css_pixel / device_scale
+            }
+
+            FilterResolution::Capped(pixels_per_css_pixel) if pixels_per_css_pixel > 0.0 => {
+                (pixels_per_css_pixel / device_scale).min(1.0)
+            }
+
+            FilterResolution::MaxPixels(max_pixels) if max_pixels > 0 => {
+                let pixels =
+                    f64::from(region.width()) * scale_x * f64::from(region.height()) * scale_y;
+
+                if pixels > f64::from(max_pixels) {
+                    (f64::from(max_pixels) / pixels).sqrt()
+                } else {
+                    1.0
+                }
+            }
+
+            _ => 1.0,
+        };
+
+        (scale_x * renderer_scale, scale_y * renderer_scale)
+    }
+
     fn draw_in_optional_new_viewport(
         &mut self,
         acquired_nodes: &mut AcquiredNodes<'_>,
@@ -862,6 +1921,48 @@ impl DrawingCtx {
         }
     }
 
+    /// Walks the contents of a layer while hit-testing.
+    ///
+    /// Opacity, masks, filters, and blend modes do not change which elements get hit, so
+    /// the layer is never isolated into a temporary surface.  A clipping path in object
+    /// space needs the bounding box of the layer's contents, so the hits from inside the
+    /// layer are discarded afterwards if the hit-test point is outside the clipping path.
+    fn hit_test_layer(
+        &mut self,
+        stacking_ctx: &StackingContext,
+        acquired_nodes: &mut AcquiredNodes<'_>,
+        viewport: &Viewport,
+        layout_viewport: &Option<LayoutViewport>,
+        draw_fn: &mut dyn FnMut(&mut AcquiredNodes<'_>, &mut DrawingCtx, &Viewport) -> DrawResult,
+    ) -> DrawResult {
+        let num_hits_before = match self.walk_mode {
+            WalkMode::HitTest(ref hit_test) => hit_test.hits.len(),
+            _ => 0,
+        };
+
+        let bbox =
+            self.draw_in_optional_new_viewport(acquired_nodes, viewport, layout_viewport, draw_fn)?;
+
+        if stacking_ctx.clip_in_object_space.is_some() {
+            let is_inside_clip = with_saved_cr(&self.cr.clone(), || {
+                self.cr.set_matrix(viewport.transform.into());
+                self.clip_to_node(
+                    &stacking_ctx.clip_in_object_space,
+                    acquired_nodes,
+                    viewport,
+                    &bbox,
+                )?;
+                Ok(self.hit_test_point_in_clip()?.is_some())
+            })?;
+
+            if !is_inside_clip && let WalkMode::HitTest(ref mut hit_test) = self.walk_mode {
+                hit_test.hits.truncate(num_hits_before);
+            }
+        }
+
+        Ok(bbox)
+    }
+
     fn draw_layer_internal(
         &mut self,
         stacking_ctx: &StackingContext,
@@ -877,6 +1978,19 @@ impl DrawingCtx {
 
         let viewport = viewport.with_composed_transform(stacking_ctx_transform)?;
 
+        // The non-scaling-size, non-rotation, and fixed-position effects modify the
+        // user space that the element establishes.
+        let effect_transform = stacking_ctx.vector_effect.user_space_transform(
+            &viewport.transform,
+            &stacking_ctx.transform,
+            &self.initial_viewport.transform,
+        );
+
+        let viewport = Viewport {
+            transform: ValidTransform::try_from(effect_transform)?,
+            ..viewport
+        };
+
         if clipping {
             self.draw_in_optional_new_viewport(acquired_nodes, &viewport, &layout_viewport, draw_fn)
         } else {
@@ -893,7 +2007,15 @@ impl DrawingCtx {
                     self.apply_clip_path(&viewport, clip_path)?;
                 }
 
-                let res = if stacking_ctx.should_isolate() {
+                let res = if self.is_hit_testing() {
+                    self.hit_test_layer(
+                        stacking_ctx,
+                        acquired_nodes,
+                        &viewport,
+                        &layout_viewport,
+                        draw_fn,
+                    )
+                } else if stacking_ctx.should_isolate() && self.is_drawing() {
                     self.print_stack_depth("DrawingCtx::draw_layer_internal should_isolate=true");
 
                     // Compute our assortment of affines
@@ -904,23 +2026,24 @@ impl DrawingCtx {
                         self.cr_stack.borrow().len(),
                     ));
 
-                    // Create temporary surface and its cr
+                    // Record the content first.  The temporary surface only needs to cover
+                    // what will be visible, what has content, and what the mask lets
+                    // through; filters have their own effects region.  None of those are
+                    // known until the content's bounding box is.
 
-                    let cr = match stacking_ctx.filter {
-                        None => cairo::Context::new(
-                            &self
-                                .create_similar_surface_for_toplevel_viewport(&self.cr.target())?,
-                        )?,
-                        Some(_) => {
-                            cairo::Context::new(self.create_surface_for_toplevel_viewport()?)?
-                        }
-                    };
+                    let visible_region =
+                        self.visible_region_for_temporary_surface(affines.compositing)?;
+
+                    let recording =
+                        cairo::RecordingSurface::create(cairo::Content::ColorAlpha, None)?;
+                    let cr = cairo::Context::new(&recording)?;
 
                     let transform_for_temporary_surface =
                         ValidTransform::try_from(affines.for_temporary_surface)?;
 
                     let (source_surface, mut res, bbox) = {
                         let mut temporary_draw_ctx = self.nested(cr.clone());
+                        temporary_draw_ctx.is_backdrop_root = stacking_ctx.is_backdrop_root();
 
                         let viewport_for_temporary_surface = Viewport::with_explicit_transform(
                             &viewport,
@@ -944,22 +2067,64 @@ impl DrawingCtx {
                             )
                         };
 
-                        if let Some(ref filter) = stacking_ctx.filter {
-                            let filtered_surface = temporary_draw_ctx.filter_current_surface(
+                        // FIXME: "res" was declared mutable above so that we could overwrite it
+                        // with the result of filtering, so that if filtering produces an error,
+                        // then the masking below wouldn't take place.  Test for that and fix this;
+                        // we are *not* modifying res in case of error.
+                        let source_surface = if let Some(ref filter) = stacking_ctx.filter {
+                            Some(temporary_draw_ctx.filter_surface(
+                                FilterSource::CurrentSurface,
                                 acquired_nodes,
                                 filter,
                                 &viewport_for_temporary_surface,
                                 &stacking_ctx.element_name,
                                 &bbox,
-                            )?;
-
-                            // FIXME: "res" was declared mutable above so that we could overwrite it
-                            // with the result of filtering, so that if filtering produces an error,
-                            // then the masking below wouldn't take place.  Test for that and fix this;
-                            // we are *not* modifying res in case of error.
-                            (filtered_surface, res, bbox)
+                                visible_region,
+                            )?)
                         } else {
-                            (temporary_draw_ctx.cr.target(), res, bbox)
+                            None
+                        };
+
+                        let source_surface =
+                            if let Some(ref backdrop_filter) = stacking_ctx.backdrop_filter {
+                                Some(temporary_draw_ctx.draw_over_filtered_backdrop(
+                                    source_surface.as_ref().unwrap_or(&recording),
+                                    acquired_nodes,
+                                    backdrop_filter,
+                                    &viewport_for_temporary_surface,
+                                    &stacking_ctx.element_name,
+                                    &bbox,
+                                    visible_region,
+                                )?)
+                            } else {
+                                source_surface
+                            };
+
+                        (source_surface, res, bbox)
+                    };
+
+                    let mask_bounds = match (&stacking_ctx.mask, &res) {
+                        (Some(mask), Ok(bbox)) => Some(self.mask_bounds(
+                            mask,
+                            &viewport,
+                            affines.for_temporary_surface,
+                            bbox,
+                            acquired_nodes,
+                        )?),
+                        _ => None,
+                    };
+
+                    // Filtered content already covers its own region; otherwise, only the
+                    // part of the recording with content needs to be rasterized.
+                    let (source_surface, region) = match source_surface {
+                        Some(surface) => (surface, layer_region(visible_region, None, mask_bounds)),
+
+                        None => {
+                            let (x, y, width, height) = recording.ink_extents();
+                            let content = Rect::new(x, y, x + width, y + height);
+                            let region = layer_region(visible_region, Some(content), mask_bounds);
+
+                            (self.rasterize_recording(&recording, region)?, region)
                         }
                     };
 
@@ -998,15 +2163,16 @@ impl DrawingCtx {
 
                     // Mask
 
-                    if let Some(ref mask_node) = stacking_ctx.mask {
+                    if let Some(ref mask) = stacking_ctx.mask {
                         self.print_stack_depth("DrawingCtx::draw_layer_internal creating mask");
 
                         res = res.and_then(|bbox| {
-                            self.generate_cairo_mask(
-                                mask_node,
+                            self.generate_mask(
+                                mask,
                                 &viewport,
                                 affines.for_temporary_surface,
                                 &bbox,
+                                region,
                                 acquired_nodes,
                             )
                             .and_then(|mask_surf| {
@@ -1134,8 +2300,7 @@ impl DrawingCtx {
         &mut self,
         acquired_nodes: &mut AcquiredNodes<'_>,
         specs: &[FilterSpec],
-        source_image_width: i32,
-        source_image_height: i32,
+        filter_region: FilterRegion,
         stroke_paint_source: Rc<UserSpacePaintSource>,
         fill_paint_source: Rc<UserSpacePaintSource>,
         viewport: &Viewport,
@@ -1144,15 +2309,15 @@ impl DrawingCtx {
 
         let background_image =
             if requirements.needs_background_image || requirements.needs_background_alpha {
-                Some(self.get_snapshot(source_image_width, source_image_height)?)
+                Some(self.get_snapshot(filter_region)?)
             } else {
                 None
             };
 
         let stroke_paint_image = if requirements.needs_stroke_paint_image {
             Some(self.get_paint_source_surface(
-                source_image_width,
-                source_image_height,
+                filter_region.width(),
+                filter_region.height(),
                 acquired_nodes,
                 &stroke_paint_source,
                 viewport,
@@ -1163,8 +2328,8 @@ impl DrawingCtx {
 
         let fill_paint_image = if requirements.needs_fill_paint_image {
             Some(self.get_paint_source_surface(
-                source_image_width,
-                source_image_height,
+                filter_region.width(),
+                filter_region.height(),
                 acquired_nodes,
                 &fill_paint_source,
                 viewport,
@@ -1180,21 +2345,20 @@ impl DrawingCtx {
             background_image,
             stroke_paint_image,
             fill_paint_image,
-        )?))
-    }
-
-    fn run_filters(
-        &mut self,
-        viewport: &Viewport,
-        surface_to_filter: SharedImageSurface,
-        filter: &Filter,
+            self.config.thread_pool.clone(),
+            self.config.quality,
+        )?))
+    }
+
+    /// Resolves the items of a `filter` property into filter specs.
+    fn resolve_filter_specs(
+        &self,
         acquired_nodes: &mut AcquiredNodes<'_>,
+        filter: &Filter,
+        viewport: &Viewport,
         node_name: &str,
         user_space_params: &NormalizeParams,
-        stroke_paint_source: Rc<UserSpacePaintSource>,
-        fill_paint_source: Rc<UserSpacePaintSource>,
-        node_bbox: &BoundingBox,
-    ) -> Result<SharedImageSurface, Box<InternalRenderingError>> {
+    ) -> Result<Vec<FilterSpec>, FilterResolveError> {
         let session = self.session();
 
         // We try to convert each item in the filter_list to a FilterSpec.
@@ -1205,7 +2369,7 @@ impl DrawingCtx {
         //
         // So, run through the filter_list and collect into a Result<Vec<FilterSpec>>.
         // This will return an Err if any of the conversions failed.
-        let filter_specs = filter
+        filter
             .filter_list
             .iter()
             .map(|filter_value| {
@@ -1218,15 +2382,27 @@ impl DrawingCtx {
                     node_name,
                 )
             })
-            .collect::<Result<Vec<FilterSpec>, _>>();
+            .collect::<Result<Vec<FilterSpec>, _>>()
+    }
 
+    fn run_filters(
+        &mut self,
+        viewport: &Viewport,
+        surface_to_filter: SharedImageSurface,
+        filter_specs: Result<Vec<FilterSpec>, FilterResolveError>,
+        acquired_nodes: &mut AcquiredNodes<'_>,
+        node_name: &str,
+        stroke_paint_source: Rc<UserSpacePaintSource>,
+        fill_paint_source: Rc<UserSpacePaintSource>,
+        node_bbox: &BoundingBox,
+        filter_region: FilterRegion,
+    ) -> Result<SharedImageSurface, Box<InternalRenderingError>> {
         match filter_specs {
             Ok(specs) => {
                 let plan = self.make_filter_plan(
                     acquired_nodes,
                     &specs,
-                    surface_to_filter.width(),
-                    surface_to_filter.height(),
+                    filter_region,
                     stroke_paint_source,
                     fill_paint_source,
                     viewport,
@@ -1370,15 +2546,19 @@ impl DrawingCtx {
         Ok(true)
     }
 
+    /// Sets a paint server as the source of the cr.
+    ///
+    /// The `paint_area` is the extents, in device space, of what is going to be painted.
     fn set_paint_source(
         &mut self,
         paint_source: &UserSpacePaintSource,
+        paint_area: &Rect,
         acquired_nodes: &mut AcquiredNodes<'_>,
         viewport: &Viewport,
     ) -> Result<bool, Box<InternalRenderingError>> {
         match *paint_source {
             UserSpacePaintSource::Gradient(ref gradient, _c) => {
-                set_gradient_on_cairo(&self.cr, gradient)?;
+                set_gradient_on_cairo(&self.cr, gradient, paint_area)?;
                 Ok(true)
             }
             UserSpacePaintSource::Pattern(ref pattern, ref c) => {
@@ -1415,8 +2595,14 @@ impl DrawingCtx {
 
             // FIXME: we are ignoring any error
 
-            let had_paint_server =
-                temporary_draw_ctx.set_paint_source(paint_source, acquired_nodes, viewport)?;
+            let paint_area = Rect::from_size(f64::from(width), f64::from(height));
+
+            let had_paint_server = temporary_draw_ctx.set_paint_source(
+                paint_source,
+                &paint_area,
+                acquired_nodes,
+                viewport,
+            )?;
             if had_paint_server {
                 temporary_draw_ctx.cr.paint()?;
             }
@@ -1499,20 +2685,63 @@ impl DrawingCtx {
             return Ok(viewport.empty_bbox());
         }
 
-        cr.set_antialias(cairo::Antialias::from(shape.shape_rendering));
+        cr.set_antialias(
+            self.config
+                .quality
+                .antialias(cairo::Antialias::from(shape.shape_rendering)),
+        );
 
         setup_cr_for_stroke(&cr, &shape.stroke);
 
         cr.set_fill_rule(cairo::FillRule::from(shape.fill_rule));
 
+        let joins = {
+            let stroke_transform = if shape.stroke.non_scaling {
+                // The stroke gets drawn in the initial viewport's coordinate system.
+                let initial = self
+                    .initial_viewport
+                    .transform
+                    .invert()
+                    .ok_or(InvalidTransform)?;
+                Transform::multiply(&transform, &initial)
+            } else {
+                Transform::identity()
+            };
+
+            stroke_join::join_outlines(&shape.path.cairo_path, &shape.stroke, &stroke_transform)
+        };
+
         path_helper.set()?;
         let bbox = compute_stroke_and_fill_box(
             &cr,
             &shape.stroke,
             &shape.stroke_paint,
+            joins.as_ref(),
             &self.initial_viewport,
         )?;
 
+        let paint_area = device_ink_rect(&cr, &bbox);
+
+        if self.is_hit_testing() {
+            let targets = stacking_ctx.pointer_events.targets(
+                stacking_ctx.is_visible,
+                !matches!(shape.fill_paint, UserSpacePaintSource::None),
+                !matches!(shape.stroke_paint, UserSpacePaintSource::None),
+            );
+
+            self.hit_test_current_path(
+                stacking_ctx,
+                targets,
+                shape.path.extents,
+                shape.stroke.non_scaling,
+            )?;
+        }
+
+        if !self.is_drawing() {
+            path_helper.unset();
+            return Ok(bbox);
+        }
+
         if stacking_ctx.is_visible {
             for &target in &shape.paint_order.targets {
                 // fill and stroke operations will preserve the path.
@@ -1520,8 +2749,12 @@ impl DrawingCtx {
                 match target {
                     PaintTarget::Fill => {
                         path_helper.set()?;
-                        let had_paint_server =
-                            self.set_paint_source(&shape.fill_paint, acquired_nodes, viewport)?;
+                        let had_paint_server = self.set_paint_source(
+                            &shape.fill_paint,
+                            &paint_area,
+                            acquired_nodes,
+                            viewport,
+                        )?;
                         if had_paint_server {
                             cr.fill_preserve()?;
                         }
@@ -1533,10 +2766,14 @@ impl DrawingCtx {
                             cr.set_matrix(self.initial_viewport.transform.into());
                         }
 
-                        let had_paint_server =
-                            self.set_paint_source(&shape.stroke_paint, acquired_nodes, viewport)?;
+                        let had_paint_server = self.set_paint_source(
+                            &shape.stroke_paint,
+                            &paint_area,
+                            acquired_nodes,
+                            viewport,
+                        )?;
                         if had_paint_server {
-                            cr.stroke_preserve()?;
+                            stroke_preserve(&cr, joins.as_ref())?;
                         }
                     }
 
@@ -1569,7 +2806,7 @@ impl DrawingCtx {
         let ptn = image.surface.to_cairo_pattern();
         ptn.set_extend(cairo::Extend::Pad);
 
-        let interpolation = Interpolation::from(image.image_rendering);
+        let interpolation = self.config.quality.interpolation(image.image_rendering);
 
         ptn.set_filter(cairo::Filter::from(interpolation));
         cr.set_matrix(viewport.transform.into());
@@ -1614,7 +2851,32 @@ impl DrawingCtx {
             overflow: image.overflow,
         };
 
-        if stacking_ctx.is_visible {
+        if self.is_hit_testing() {
+            // The image's region is its whole rectangle, which is always painted.
+            let targets = stacking_ctx
+                .pointer_events
+                .targets(stacking_ctx.is_visible, true, false);
+
+            if targets.fill || targets.bounding_box {
+                self.with_discrete_layer(
+                    stacking_ctx,
+                    acquired_nodes,
+                    viewport,
+                    None,
+                    false,
+                    &mut |_an, dc, new_viewport| {
+                        dc.cr.set_matrix(new_viewport.transform.into());
+                        if dc.hit_test_rect(image.rect)? {
+                            dc.record_hit(stacking_ctx);
+                        }
+
+                        Ok(bounds.clone())
+                    },
+                )?;
+            }
+
+            Ok(bounds)
+        } else if stacking_ctx.is_visible && self.is_drawing() {
             self.with_discrete_layer(
                 stacking_ctx,
                 acquired_nodes,
@@ -1663,7 +2925,13 @@ impl DrawingCtx {
         clipping: bool,
         viewport: &Viewport,
     ) -> DrawResult {
-        let path = pango_layout_to_cairo_path(span.x, span.y, &span.layout, span.gravity)?;
+        let path = pango_layout_to_cairo_path(
+            span.x,
+            span.y,
+            &span.layout,
+            span.gravity,
+            span.glyph_transform,
+        )?;
         if path.is_empty() {
             // Empty strings, or only-whitespace text, get turned into empty paths.
             // In that case, we really want to return "no bounds" rather than an
@@ -1673,11 +2941,18 @@ impl DrawingCtx {
 
         // #851 - We can't just render all text as paths for PDF; it
         // needs the actual text content so text is selectable by PDF
-        // viewers.
-        let can_use_text_as_path = self.cr.target().type_() != cairo::SurfaceType::Pdf;
+        // viewers.  Callers can still ask for outlines explicitly.
+        let can_use_text_as_path = match self.config.text_mode {
+            Some(TextMode::Outlines) => true,
+            Some(TextMode::Glyphs) => false,
+            None => self.cr.target().type_() != cairo::SurfaceType::Pdf,
+        };
 
-        self.cr
-            .set_antialias(cairo::Antialias::from(span.text_rendering));
+        self.cr.set_antialias(
+            self.config
+                .quality
+                .antialias(cairo::Antialias::from(span.text_rendering)),
+        );
 
         setup_cr_for_stroke(&self.cr, &span.stroke);
 
@@ -1688,23 +2963,31 @@ impl DrawingCtx {
             return Ok(viewport.empty_bbox());
         }
 
+        let joins = stroke_join::join_outlines(&path, &span.stroke, &Transform::identity());
+
         path.to_cairo_context(&self.cr)?;
         let bbox = compute_stroke_and_fill_box(
             &self.cr,
             &span.stroke,
             &span.stroke_paint,
+            joins.as_ref(),
             &self.initial_viewport,
         )?;
+        let paint_area = device_ink_rect(&self.cr, &bbox);
         self.cr.new_path();
 
-        if span.is_visible {
+        if span.is_visible && self.is_drawing() {
             self.link_tag_begin(&span.link_target);
 
             for &target in &span.paint_order.targets {
                 match target {
                     PaintTarget::Fill => {
-                        let had_paint_server =
-                            self.set_paint_source(&span.fill_paint, acquired_nodes, viewport)?;
+                        let had_paint_server = self.set_paint_source(
+                            &span.fill_paint,
+                            &paint_area,
+                            acquired_nodes,
+                            viewport,
+                        )?;
 
                         if had_paint_server {
                             if can_use_text_as_path {
@@ -1716,6 +2999,8 @@ impl DrawingCtx {
 
                                 let matrix = self.cr.matrix();
 
+                                self.cr.transform(span.glyph_transform.into());
+
                                 let rotation_from_gravity = span.gravity.to_rotation();
                                 if !rotation_from_gravity.approx_eq_cairo(0.0) {
                                     self.cr.rotate(-rotation_from_gravity);
@@ -1730,12 +3015,16 @@ impl DrawingCtx {
                     }
 
                     PaintTarget::Stroke => {
-                        let had_paint_server =
-                            self.set_paint_source(&span.stroke_paint, acquired_nodes, viewport)?;
+                        let had_paint_server = self.set_paint_source(
+                            &span.stroke_paint,
+                            &paint_area,
+                            acquired_nodes,
+                            viewport,
+                        )?;
 
                         if had_paint_server {
                             path.to_cairo_context(&self.cr)?;
-                            self.cr.stroke()?;
+                            stroke_preserve(&self.cr, joins.as_ref())?;
                             self.cr.new_path();
                         }
                     }
@@ -1764,10 +3053,51 @@ impl DrawingCtx {
             viewport,
             None,
             clipping,
-            &mut |an, dc, new_viewport| dc.paint_text_spans(text, an, clipping, new_viewport),
+            &mut |an, dc, new_viewport| {
+                if dc.is_hit_testing() && !clipping {
+                    dc.hit_test_text_spans(text, stacking_ctx, new_viewport)
+                } else {
+                    dc.paint_text_spans(text, an, clipping, new_viewport)
+                }
+            },
         )
     }
 
+    /// Hit-tests the spans of a text element.
+    ///
+    /// Text is hit-tested by character cells, not by the outlines of glyphs, so each
+    /// span's region is its rectangle.  The text element gets a single hit even if the
+    /// point is inside several spans.
+    fn hit_test_text_spans(
+        &mut self,
+        text: &Text,
+        stacking_ctx: &StackingContext,
+        viewport: &Viewport,
+    ) -> DrawResult {
+        self.cr.set_matrix(viewport.transform.into());
+
+        for span in &text.spans {
+            let targets = stacking_ctx.pointer_events.targets(
+                span.is_visible,
+                !matches!(span.fill_paint, UserSpacePaintSource::None),
+                !matches!(span.stroke_paint, UserSpacePaintSource::None),
+            );
+
+            if let Some(extents) = span.extents
+                && (targets.fill || targets.stroke || targets.bounding_box)
+                && self.hit_test_rect(extents)?
+            {
+                self.record_hit(stacking_ctx);
+                break;
+            }
+        }
+
+        match text.extents {
+            Some(extents) => Ok(Box::new(viewport.empty_bbox().with_rect(extents))),
+            None => Ok(viewport.empty_bbox()),
+        }
+    }
+
     fn paint_text_spans(
         &mut self,
         text: &Text,
@@ -1785,41 +3115,45 @@ impl DrawingCtx {
         Ok(bbox)
     }
 
-    pub fn get_snapshot(
+    /// Composites the surfaces in the stack into an image of a `region` of the toplevel
+    /// viewport, for the `BackgroundImage` input of filters.
+    ///
+    /// Only the surfaces from the topmost backdrop root upwards are used.  A backdrop root
+    /// is the toplevel surface, or that of a group with `enable-background: new` or
+    /// `isolation: isolate`; see <https://www.w3.org/TR/filter-effects-1/#AccessBackgroundImage>
+    /// and <https://drafts.fxtf.org/filter-effects-2/#BackdropRoot>.
+    fn get_snapshot(
         &self,
-        width: i32,
-        height: i32,
+        filter_region: FilterRegion,
     ) -> Result<SharedImageSurface, Box<InternalRenderingError>> {
-        // TODO: as far as I can tell this should not render elements past the last (topmost) one
-        // with enable-background: new (because technically we shouldn't have been caching them).
-        // Right now there are no enable-background checks whatsoever.
-        //
-        // Addendum: SVG 2 has deprecated the enable-background property, and replaced it with an
-        // "isolation" property from the CSS Compositing and Blending spec.
-        //
-        // Deprecation:
-        //   https://www.w3.org/TR/filter-effects-1/#AccessBackgroundImage
-        //
-        // BackgroundImage, BackgroundAlpha in the "in" attribute of filter primitives:
-        //   https://www.w3.org/TR/filter-effects-1/#attr-valuedef-in-backgroundimage
-        //
-        // CSS Compositing and Blending, "isolation" property:
-        //   https://www.w3.org/TR/compositing-1/#isolation
-        let mut surface = ExclusiveImageSurface::new(width, height, SurfaceType::SRgb)?;
+        let mut surface = ExclusiveImageSurface::new(
+            filter_region.width(),
+            filter_region.height(),
+            SurfaceType::SRgb,
+        )?;
 
         surface.draw(&mut |cr| {
-            // TODO: apparently DrawingCtx.cr_stack is just a way to store pairs of
-            // (surface, transform).  Can we turn it into a DrawingCtx.surface_stack
-            // instead?  See what CSS isolation would like to call that; are the pairs just
-            // stacking contexts instead, or the result of rendering stacking contexts?
-            for (depth, draw) in self.cr_stack.borrow().iter().enumerate() {
+            let cr_stack = self.cr_stack.borrow();
+
+            let backdrop_root = cr_stack
+                .iter()
+                .rposition(|stacked| stacked.is_backdrop_root)
+                .unwrap_or(0);
+
+            for (depth, stacked) in cr_stack.iter().enumerate().skip(backdrop_root) {
+                let draw = &stacked.cr;
+
                 let affines = CompositingAffines::new(
                     Transform::from(draw.matrix()),
                     *self.initial_viewport.transform,
                     depth,
                 );
 
-                cr.set_matrix(ValidTransform::try_from(affines.for_snapshot)?.into());
+                let for_snapshot = affines
+                    .for_snapshot
+                    .post_transform(&filter_region.transform());
+
+                cr.set_matrix(ValidTransform::try_from(for_snapshot)?.into());
                 cr.set_source_surface(draw.target(), 0.0, 0.0)?;
                 cr.paint()?;
             }
@@ -1835,7 +3169,7 @@ impl DrawingCtx {
         node: &Node,
         acquired_nodes: &mut AcquiredNodes<'_>,
         cascaded: &CascadedValues<'_>,
-        transform: ValidTransform,
+        viewport: &Viewport,
         width: i32,
         height: i32,
     ) -> Result<SharedImageSurface, Box<InternalRenderingError>> {
@@ -1845,13 +3179,12 @@ impl DrawingCtx {
 
         {
             let cr = cairo::Context::new(&surface)?;
-            cr.set_matrix(transform.into());
+            cr.set_matrix(viewport.transform.into());
 
             self.cr = cr;
             let viewport = Viewport {
-                dpi: self.config.dpi,
-                transform,
                 vbox: ViewBox::from(Rect::from_size(f64::from(width), f64::from(height))),
+                ..*viewport
             };
 
             // FIXME: if this returns an error, we will not restore the self.cr as per below
@@ -1974,6 +3307,8 @@ impl DrawingCtx {
             None
         };
 
+        self.enter_use_instance(use_element.get_id());
+
         let res = if let Some((vbox, preserve_aspect_ratio)) = defines_a_viewport {
             // <symbol> and <svg> define a viewport, as described in the specification:
             // https://www.w3.org/TR/SVG2/struct.html#UseElement
@@ -1983,11 +3318,13 @@ impl DrawingCtx {
 
             let child_values = elt.get_computed_values();
 
+            // The use element's x/y go in the stacking context's transform, so that
+            // vector-effect modifies the user space with the origin at that point.
             let stacking_ctx = Box::new(StackingContext::new(
                 self,
                 acquired_nodes,
                 &use_element,
-                Transform::identity(),
+                Transform::new_translate(use_rect.x0, use_rect.y0),
                 None,
                 values,
                 viewport,
@@ -1995,7 +3332,7 @@ impl DrawingCtx {
 
             let layout_viewport = LayoutViewport {
                 vbox,
-                geometry: use_rect,
+                geometry: Rect::from_size(use_rect.width(), use_rect.height()),
                 preserve_aspect_ratio,
                 overflow: child_values.overflow(),
             };
@@ -2056,6 +3393,8 @@ impl DrawingCtx {
             )
         };
 
+        self.leave_use_instance();
+
         if let Ok(bbox) = res {
             let mut res_bbox = Box::new(BoundingBox::new().with_transform(*viewport.transform));
             res_bbox.insert(&bbox);
@@ -2129,9 +3468,14 @@ pub fn set_source_color_on_cairo(cr: &cairo::Context, color: &Color) {
     );
 }
 
+/// Sets a gradient as the source of `cr`.
+///
+/// The `paint_area` is the extents, in device space, of what is going to be painted with
+/// the gradient.  Only conic gradients use it; see [`set_conic_gradient_on_cairo`].
 fn set_gradient_on_cairo(
     cr: &cairo::Context,
     gradient: &UserSpaceGradient,
+    paint_area: &Rect,
 ) -> Result<(), Box<InternalRenderingError>> {
     let g = match gradient.variant {
         GradientVariant::Linear { x1, y1, x2, y2 } => {
@@ -2146,6 +3490,18 @@ fn set_gradient_on_cairo(
             fy,
             fr,
         } => cairo::Gradient::clone(&cairo::RadialGradient::new(fx, fy, fr, cx, cy, r)),
+
+        GradientVariant::Conic {
+            cx,
+            cy,
+            angle,
+            start,
+            end,
+        } => {
+            return set_conic_gradient_on_cairo(
+                cr, gradient, paint_area, cx, cy, angle, start, end,
+            );
+        }
     };
 
     g.set_matrix(ValidTransform::try_from(gradient.transform)?.into());
@@ -2168,13 +3524,94 @@ fn set_gradient_on_cairo(
     Ok(cr.set_source(&g)?)
 }
 
+/// Cairo has no conic gradients, so this renders one into an image that covers the
+/// part of `paint_area` inside the current clip region, and sets that as the source.
+///
+/// The `paint_area` is in device space; it is the extents of whatever is going to be
+/// painted with the gradient.  The image has one pixel per device pixel, but it is never
+/// larger than [`limits::MAX_CONIC_GRADIENT_SIZE`] on each side; bigger areas get a
+/// lower-resolution image that is scaled up.
+fn set_conic_gradient_on_cairo(
+    cr: &cairo::Context,
+    gradient: &UserSpaceGradient,
+    paint_area: &Rect,
+    cx: f64,
+    cy: f64,
+    angle: f64,
+    start: f64,
+    end: f64,
+) -> Result<(), Box<InternalRenderingError>> {
+    let to_device = Transform::from(cr.matrix());
+
+    let (x0, y0, x1, y1) = cr.clip_extents()?;
+    let clip = to_device.transform_rect(&Rect::new(x0, y0, x1, y1));
+
+    // Recording surfaces have an enormous clip region, so the paint area is what really
+    // bounds the image.  Work in floating point until the size is known to be small, as
+    // those coordinates don't fit in an i32.
+    let area = clip
+        .intersection(paint_area)
+        .map(|r| Rect::new(r.x0.floor(), r.y0.floor(), r.x1.ceil(), r.y1.ceil()))
+        .filter(|r| r.width().is_finite() && r.height().is_finite());
+
+    let Some(area) = area else {
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.0);
+        return Ok(());
+    };
+
+    let max_size = f64::from(limits::MAX_CONIC_GRADIENT_SIZE);
+    let width = area.width().min(max_size);
+    let height = area.height().min(max_size);
+    let scale_x = width / area.width();
+    let scale_y = height / area.height();
+
+    let to_gradient = to_device
+        .invert()
+        .ok_or(InvalidTransform)?
+        .post_transform(&gradient.transform);
+
+    let mut surface = ExclusiveImageSurface::new(width as i32, height as i32, SurfaceType::SRgb)?;
+
+    surface.modify(&mut |data, stride| {
+        for y in 0..height as u32 {
+            for x in 0..width as u32 {
+                let (gx, gy) = to_gradient.transform_point(
+                    area.x0 + (f64::from(x) + 0.5) / scale_x,
+                    area.y0 + (f64::from(y) + 0.5) / scale_y,
+                );
+
+                // 0 points up, and angles grow clockwise
+                let a = (gx - cx).atan2(cy - gy) - angle;
+                let turn = a.rem_euclid(2.0 * PI) / (2.0 * PI);
+
+                let pixel = gradient.color_at((turn - start) / (end - start));
+
+                data.set_pixel(stride, pixel, x, y);
+            }
+        }
+    });
+
+    let surface = surface.share()?;
+
+    let pattern = surface.to_cairo_pattern();
+    let pattern_transform = to_device
+        .post_translate(-area.x0, -area.y0)
+        .post_scale(scale_x, scale_y);
+    pattern.set_matrix(ValidTransform::try_from(pattern_transform)?.into());
+
+    Ok(cr.set_source(&pattern)?)
+}
+
 /// Converts a Pango layout to a Cairo path on the specified cr starting at (x, y).
 /// Does not clear the current path first.
+///
+/// The `glyph_transform` is applied to the glyphs relative to (x, y).
 fn pango_layout_to_cairo(
     x: f64,
     y: f64,
     layout: &pango::Layout,
     gravity: pango::Gravity,
+    glyph_transform: ValidTransform,
     cr: &cairo::Context,
 ) {
     let rotation_from_gravity = gravity.to_rotation();
@@ -2187,6 +3624,11 @@ fn pango_layout_to_cairo(
     cr.move_to(x, y);
 
     let matrix = cr.matrix();
+
+    // Pango uses the current point as the origin for the layout, so this transforms the
+    // glyphs around (x, y).
+    cr.transform(glyph_transform.into());
+
     if let Some(rot) = rotation {
         cr.rotate(rot);
     }
@@ -2202,11 +3644,12 @@ pub fn pango_layout_to_cairo_path(
     y: f64,
     layout: &pango::Layout,
     gravity: pango::Gravity,
+    glyph_transform: ValidTransform,
 ) -> Result<CairoPath, Box<InternalRenderingError>> {
     let surface = cairo::RecordingSurface::create(cairo::Content::ColorAlpha, None)?;
     let cr = cairo::Context::new(&surface)?;
 
-    pango_layout_to_cairo(x, y, layout, gravity, &cr);
+    pango_layout_to_cairo(x, y, layout, gravity, glyph_transform, &cr);
 
     let cairo_path = cr.copy_path()?;
     Ok(CairoPath::from_cairo(cairo_path))
@@ -2280,10 +3723,150 @@ impl CompositingAffines {
     }
 }
 
+/// What [`DrawingCtx::filter_surface`] runs the filters on.
+#[derive(Copy, Clone)]
+enum FilterSource {
+    /// What has been drawn to the current surface, for the `filter` property.
+    CurrentSurface,
+
+    /// What has been drawn below the current surface, for the `backdrop-filter` property.
+    Backdrop,
+}
+
+/// Where the contents of a `<mask>` element get drawn for an element being masked.
+struct MaskGeometry {
+    /// Transform from the mask's user space to the temporary surface.
+    transform_for_mask: ValidTransform,
+
+    /// Transform for `maskUnits="objectBoundingBox"`, or the identity.
+    bbtransform: ValidTransform,
+
+    /// The mask's `x`/`y`/`width`/`height` rectangle, in the mask's user space.
+    clip_rect: Rect,
+}
+
+impl MaskGeometry {
+    /// Returns `None` if the mask can't be used, for example, if it is in
+    /// `objectBoundingBox` units and the bounding box is empty.
+    fn new(
+        mask: &Mask,
+        values: &ComputedValues,
+        viewport: &Viewport,
+        transform: Transform,
+        bbox: &BoundingBox,
+    ) -> Result<Option<MaskGeometry>, Box<InternalRenderingError>> {
+        let mask_units = mask.get_units();
+
+        let mask_rect = {
+            let params = NormalizeParams::new(values, &viewport.with_units(mask_units));
+            mask.get_rect(&params)
+        };
+
+        let transform_for_mask =
+            ValidTransform::try_from(values.transform().post_transform(&transform))?;
+
+        let Ok(bbtransform) = rect_to_transform(&bbox.rect, mask_units)
+            .map_err(|_: ()| InvalidTransform)
+            .and_then(ValidTransform::try_from)
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(MaskGeometry {
+            transform_for_mask,
+            bbtransform,
+            clip_rect: bbtransform.transform_rect(&mask_rect),
+        }))
+    }
+}
+
+/// Computes the region of a temporary surface that needs to be rasterized for a layer.
+///
+/// Only the part of the `visible` region that has `content`, and that the `mask` may let
+/// through, is needed.  Both are in the coordinates of the temporary surface; `None`
+/// means that they don't restrict the region.
+///
+/// Returns a 1x1 region if nothing is left, like
+/// [`DrawingCtx::region_for_temporary_surface`].
+fn layer_region(visible: IRect, content: Option<Rect>, mask: Option<Rect>) -> IRect {
+    let rect = [content, mask]
+        .into_iter()
+        .flatten()
+        .try_fold(Rect::from(visible), |r, other| r.intersection(&other));
+
+    rect.and_then(|r| IRect::from(r).intersection(&visible))
+        .unwrap_or_else(|| IRect::from_size(1, 1))
+}
+
+/// Sets the device offset of a temporary surface that covers `region`.
+///
+/// See [`DrawingCtx::create_surface_for_region`].
+fn set_device_offset_for_region(surface: &cairo::Surface, region: IRect) {
+    surface.set_device_offset(-f64::from(region.x0), -f64::from(region.y0));
+}
+
+/// The surfaces on which filters run.
+///
+/// They cover a `region` of the toplevel viewport's pixels, but they may have a lower
+/// resolution than the viewport.
+#[derive(Copy, Clone)]
+struct FilterRegion {
+    /// Region in the toplevel viewport's pixels.
+    region: IRect,
+
+    /// Number of filter pixels per pixel of the toplevel viewport, in each direction.
+    scale: (f64, f64),
+}
+
+impl FilterRegion {
+    fn width(&self) -> i32 {
+        ((f64::from(self.region.width()) * self.scale.0).ceil() as i32).max(1)
+    }
+
+    fn height(&self) -> i32 {
+        ((f64::from(self.region.height()) * self.scale.1).ceil() as i32).max(1)
+    }
+
+    /// Returns the transform from the toplevel viewport's pixels to the filter's pixels.
+    fn transform(&self) -> Transform {
+        Transform::new_translate(-f64::from(self.region.x0), -f64::from(self.region.y0))
+            .post_scale(self.scale.0, self.scale.1)
+    }
+
+    /// Scales the output of the filters back to the resolution of the toplevel viewport.
+    ///
+    /// The returned surface has the size of the `region`.
+    fn to_device_pixels(
+        &self,
+        surface: SharedImageSurface,
+    ) -> Result<cairo::ImageSurface, cairo::Error> {
+        if self.scale == (1.0, 1.0) {
+            return surface.into_image_surface();
+        }
+
+        let output = cairo::ImageSurface::create(
+            cairo::Format::ARgb32,
+            self.region.width(),
+            self.region.height(),
+        )?;
+
+        {
+            let cr = cairo::Context::new(&output)?;
+            cr.scale(1.0 / self.scale.0, 1.0 / self.scale.1);
+            surface.set_as_source_surface(&cr, 0.0, 0.0)?;
+            cr.source().set_filter(cairo::Filter::Best);
+            cr.paint()?;
+        }
+
+        Ok(output)
+    }
+}
+
 fn compute_stroke_and_fill_extents(
     cr: &cairo::Context,
     stroke: &Stroke,
     stroke_paint_source: &UserSpacePaintSource,
+    joins: Option<&JoinOutlines>,
     initial_viewport: &Viewport,
 ) -> Result<PathExtents, Box<InternalRenderingError>> {
     // Dropping the precision of cairo's bezier subdivision, yielding 2x
@@ -2332,7 +3915,10 @@ fn compute_stroke_and_fill_extents(
         if let Some(matrix) = backup_matrix {
             cr.set_matrix(matrix);
         }
-        Some(Rect::new(x0, y0, x1, y1))
+
+        // Cairo computed the extents with bevel joins; add the parts that we draw.
+        let extents = Rect::new(x0, y0, x1, y1);
+        Some(joins.map_or(extents, |j| extents.union(&j.extents)))
     } else {
         None
     };
@@ -2357,10 +3943,11 @@ fn compute_stroke_and_fill_box(
     cr: &cairo::Context,
     stroke: &Stroke,
     stroke_paint_source: &UserSpacePaintSource,
+    joins: Option<&JoinOutlines>,
     initial_viewport: &Viewport,
 ) -> DrawResult {
     let extents =
-        compute_stroke_and_fill_extents(cr, stroke, stroke_paint_source, initial_viewport)?;
+        compute_stroke_and_fill_extents(cr, stroke, stroke_paint_source, joins, initial_viewport)?;
 
     let ink_rect = match (extents.fill, extents.stroke) {
         (None, None) => None,
@@ -2382,6 +3969,14 @@ fn compute_stroke_and_fill_box(
     Ok(Box::new(bbox))
 }
 
+/// Returns the ink rectangle of a bounding box computed with the current matrix of `cr`,
+/// transformed to device space.
+fn device_ink_rect(cr: &cairo::Context, bbox: &BoundingBox) -> Rect {
+    bbox.ink_rect
+        .map(|r| Transform::from(cr.matrix()).transform_rect(&r))
+        .unwrap_or_default()
+}
+
 fn setup_cr_for_stroke(cr: &cairo::Context, stroke: &Stroke) {
     cr.set_line_width(stroke.width);
     cr.set_miter_limit(stroke.miter_limit.0);
@@ -2397,6 +3992,37 @@ fn setup_cr_for_stroke(cr: &cairo::Context, stroke: &Stroke) {
     }
 }
 
+/// Strokes the current path and preserves it, adding the `joins` that Cairo can't draw.
+///
+/// The stroke and the join outlines overlap, so they can't just be painted one after
+/// the other with a translucent source.  Instead, they get drawn to an alpha-only
+/// group which is then used as a mask for the stroke's source.
+fn stroke_preserve(
+    cr: &cairo::Context,
+    joins: Option<&JoinOutlines>,
+) -> Result<(), Box<InternalRenderingError>> {
+    let Some(joins) = joins else {
+        cr.stroke_preserve()?;
+        return Ok(());
+    };
+
+    let path = cr.copy_path()?;
+
+    cr.push_group_with_content(cairo::Content::Alpha);
+    cr.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+    cr.stroke()?;
+
+    joins.path.to_cairo_context(cr)?;
+    cr.set_fill_rule(cairo::FillRule::Winding);
+    cr.fill()?;
+
+    let mask = cr.pop_group()?;
+    cr.mask(&mask)?;
+
+    cr.append_path(&path);
+    Ok(())
+}
+
 /// escape quotes and backslashes with backslash
 fn escape_link_target(value: &str) -> Cow<'_, str> {
     let regex = {
@@ -2413,6 +4039,98 @@ fn escape_link_target(value: &str) -> Cow<'_, str> {
     })
 }
 
+/// Computes the size of a mask image per `mask-size`.
+///
+/// Raster images have an intrinsic size; gradients don't, so they take the size of the
+/// positioning `area` for automatic dimensions.
+fn mask_tile_size(
+    size: &BgSize,
+    intrinsic_size: Option<(f64, f64)>,
+    area: &Rect,
+    params: &NormalizeParams,
+) -> (f64, f64) {
+    let (area_width, area_height) = (area.width(), area.height());
+
+    match (size, intrinsic_size) {
+        (BgSize::Cover | BgSize::Contain, Some((w, h))) if w > 0.0 && h > 0.0 => {
+            let sx = area_width / w;
+            let sy = area_height / h;
+
+            let scale = if *size == BgSize::Cover {
+                sx.max(sy)
+            } else {
+                sx.min(sy)
+            };
+
+            (w * scale, h * scale)
+        }
+
+        (BgSize::Cover | BgSize::Contain, _) => (area_width, area_height),
+
+        (BgSize::Size(width, height), _) => {
+            let width = match width {
+                LengthOrAuto::Length(l) => Some(l.to_user_relative_to(params, area_width)),
+                LengthOrAuto::Auto => None,
+            };
+
+            let height = match height {
+                LengthOrAuto::Length(l) => Some(l.to_user_relative_to(params, area_height)),
+                LengthOrAuto::Auto => None,
+            };
+
+            match (width, height, intrinsic_size) {
+                (Some(w), Some(h), _) => (w, h),
+
+                (Some(w), None, Some((iw, ih))) if iw > 0.0 => (w, w * ih / iw),
+                (None, Some(h), Some((iw, ih))) if ih > 0.0 => (h * iw / ih, h),
+                (None, None, Some((iw, ih))) => (iw, ih),
+
+                (Some(w), None, _) => (w, area_height),
+                (None, Some(h), _) => (area_width, h),
+                (None, None, _) => (area_width, area_height),
+            }
+        }
+    }
+}
+
+fn mask_size_width_is_auto(size: &BgSize) -> bool {
+    matches!(size, BgSize::Size(LengthOrAuto::Auto, _))
+}
+
+fn mask_size_height_is_auto(size: &BgSize) -> bool {
+    matches!(size, BgSize::Size(_, LengthOrAuto::Auto))
+}
+
+/// Computes how mask tiles get laid out along one axis of the positioning area.
+///
+/// Returns the coordinate of the origin of one of the tiles, the distance between the
+/// starts of consecutive tiles, and whether the tiles repeat along this axis at all.
+fn mask_tile_axis(
+    repetition: Repetition,
+    area_start: f64,
+    area_size: f64,
+    tile_size: f64,
+    offset: f64,
+) -> (f64, f64, bool) {
+    match repetition {
+        Repetition::Repeat | Repetition::Round => (area_start + offset, tile_size, true),
+
+        Repetition::NoRepeat => (area_start + offset, tile_size, false),
+
+        Repetition::Space => {
+            let n = (area_size / tile_size).floor();
+
+            if n >= 2.0 {
+                let spacing = (area_size - n * tile_size) / (n - 1.0);
+                (area_start, tile_size + spacing, true)
+            } else {
+                // Only one tile fits; it is positioned per mask-position.
+                (area_start + offset, tile_size, false)
+            }
+        }
+    }
+}
+
 fn clip_to_rectangle(cr: &cairo::Context, transform: &ValidTransform, r: &Rect) {
     cr.set_matrix((*transform).into());
 
@@ -2420,6 +4138,17 @@ fn clip_to_rectangle(cr: &cairo::Context, transform: &ValidTransform, r: &Rect)
     cr.clip();
 }
 
+impl From<CompositingOperator> for cairo::Operator {
+    fn from(op: CompositingOperator) -> cairo::Operator {
+        match op {
+            CompositingOperator::Add => cairo::Operator::Over,
+            CompositingOperator::Subtract => cairo::Operator::Out,
+            CompositingOperator::Intersect => cairo::Operator::In,
+            CompositingOperator::Exclude => cairo::Operator::Xor,
+        }
+    }
+}
+
 impl From<SpreadMethod> for cairo::Extend {
     fn from(s: SpreadMethod) -> cairo::Extend {
         match s {
@@ -2436,6 +4165,10 @@ impl From<StrokeLinejoin> for cairo::LineJoin {
             StrokeLinejoin::Miter => cairo::LineJoin::Miter,
             StrokeLinejoin::Round => cairo::LineJoin::Round,
             StrokeLinejoin::Bevel => cairo::LineJoin::Bevel,
+
+            // Cairo doesn't support these; we stroke with bevel joins and
+            // fill the rest of the join ourselves.  See the stroke_join module.
+            StrokeLinejoin::Arcs | StrokeLinejoin::MiterClip => cairo::LineJoin::Bevel,
         }
     }
 }
@@ -2541,3 +4274,59 @@ pub struct PathExtents {
     /// Extents for the stroked path, or `None` if the path is empty or zero-width.
     pub stroke: Option<Rect>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_region_is_limited_by_content_and_mask() {
+        let visible = IRect::from_size(1000, 1000);
+
+        assert_eq!(layer_region(visible, None, None), visible);
+
+        assert_eq!(
+            layer_region(visible, Some(Rect::new(10.5, 20.0, 50.0, 60.5)), None),
+            IRect::new(10, 20, 50, 61)
+        );
+
+        assert_eq!(
+            layer_region(
+                visible,
+                Some(Rect::new(10.0, 20.0, 50.0, 60.0)),
+                Some(Rect::new(30.0, 0.0, 2000.0, 40.0))
+            ),
+            IRect::new(30, 20, 50, 40)
+        );
+
+        assert_eq!(
+            layer_region(visible, Some(Rect::new(-500.0, -500.0, 5000.0, 20.0)), None),
+            IRect::new(0, 0, 1000, 20)
+        );
+    }
+
+    #[test]
+    fn layer_region_is_one_pixel_if_nothing_is_left() {
+        let visible = IRect::from_size(1000, 1000);
+        let one_pixel = IRect::from_size(1, 1);
+
+        assert_eq!(
+            layer_region(visible, Some(Rect::new(2000.0, 0.0, 2100.0, 100.0)), None),
+            one_pixel
+        );
+
+        assert_eq!(
+            layer_region(
+                visible,
+                Some(Rect::new(10.0, 10.0, 20.0, 20.0)),
+                Some(Rect::new(30.0, 30.0, 40.0, 40.0))
+            ),
+            one_pixel
+        );
+
+        assert_eq!(
+            layer_region(visible, None, Some(Rect::default())),
+            one_pixel
+        );
+    }
+}
@@ -32,6 +32,7 @@ use crate::filters::{
     tile::FeTile,
     turbulence::FeTurbulence,
 };
+use crate::foreign_object::ForeignObject;
 use crate::gradient::{LinearGradient, RadialGradient, Stop};
 use crate::image::Image;
 use crate::layout::Layer;
@@ -144,6 +145,7 @@ pub enum ElementData {
     ClipPath(Box<ClipPath>),
     Ellipse(Box<Ellipse>),
     Filter(Box<Filter>),
+    ForeignObject(Box<ForeignObject>),
     Group(Box<Group>),
     Image(Box<Image>),
     Line(Box<Line>),
@@ -252,7 +254,7 @@ fn get_element_creators() -> &'static HashMap<&'static str, (ElementDataCreateFn
             /* ("font-face-name",   ), */
             /* ("font-face-src",    ), */
             /* ("font-face-uri",    ), */
-            /* ("foreignObject",    ), */
+            ("foreignObject",       create_foreign_object,        Default),
             ("g",                   create_group,                 Default),
             /* ("glyph",            ), */
             /* ("glyphRef",         ), */
@@ -373,10 +375,15 @@ impl Element {
         self.values = values.clone();
     }
 
-    pub fn get_cond(&self, user_language: &UserLanguage, session: &Session) -> bool {
+    pub fn get_cond(
+        &self,
+        user_language: &UserLanguage,
+        supported_extensions: &[String],
+        session: &Session,
+    ) -> bool {
         self.required_extensions
             .as_ref()
-            .map(|v| v.eval())
+            .map(|v| v.eval(supported_extensions))
             .unwrap_or(true)
             && self
                 .required_features
@@ -544,6 +551,7 @@ impl ElementData {
             ClipPath(d) =>             &**d,
             Ellipse(d) =>              &**d,
             Filter(d) =>               &**d,
+            ForeignObject(d) =>        &**d,
             Group(d) =>                &**d,
             Image(d) =>                &**d,
             Line(d) =>                 &**d,
@@ -616,6 +624,7 @@ impl ElementData {
             ClipPath(d) =>             &**d,
             Ellipse(d) =>              &**d,
             Filter(d) =>               &**d,
+            ForeignObject(d) =>        &**d,
             Group(d) =>                &**d,
             Image(d) =>                &**d,
             Line(d) =>                 &**d,
@@ -717,6 +726,7 @@ mod creators {
     e!(create_fe_tile,                  FeTile);
     e!(create_fe_turbulence,            FeTurbulence);
     e!(create_filter,                   Filter);
+    e!(create_foreign_object,           ForeignObject);
     e!(create_group,                    Group);
     e!(create_image,                    Image);
     e!(create_line,                     Line);
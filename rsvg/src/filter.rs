@@ -14,7 +14,7 @@ use crate::filter_func::FilterFunction;
 use crate::filters::{FilterResolveError, FilterSpec};
 use crate::length::*;
 use crate::node::{Node, NodeBorrow};
-use crate::parsers::{Parse, ParseValue};
+use crate::parsers::{NumberOptionalNumber, Parse, ParseValue};
 use crate::rect::Rect;
 use crate::rsvg_log;
 use crate::session::Session;
@@ -29,6 +29,7 @@ pub struct Filter {
     height: ULength<Vertical>,
     filter_units: CoordUnits,
     primitive_units: CoordUnits,
+    filter_res: Option<NumberOptionalNumber<u32>>,
 }
 
 /// A `<filter>` element definition in user-space coordinates.
@@ -36,6 +37,11 @@ pub struct UserSpaceFilter {
     pub rect: Rect,
     pub filter_units: CoordUnits,
     pub primitive_units: CoordUnits,
+
+    /// Size in pixels of the filter effects region, from the legacy `filterRes` attribute.
+    ///
+    /// SVG1.1: <https://www.w3.org/TR/SVG11/filters.html#FilterElementFilterResAttribute>
+    pub filter_res: Option<NumberOptionalNumber<u32>>,
 }
 
 impl Default for Filter {
@@ -48,6 +54,7 @@ impl Default for Filter {
             height: ULength::<Vertical>::parse_str("120%").unwrap(),
             filter_units: CoordUnits::ObjectBoundingBox,
             primitive_units: CoordUnits::UserSpaceOnUse,
+            filter_res: None,
         }
     }
 }
@@ -69,6 +76,7 @@ impl Filter {
             rect,
             filter_units: self.filter_units,
             primitive_units: self.primitive_units,
+            filter_res: self.filter_res,
         }
     }
 }
@@ -91,6 +99,9 @@ impl ElementTrait for Filter {
                 expanded_name!("", "primitiveUnits") => {
                     set_attribute(&mut self.primitive_units, attr.parse(value), session)
                 }
+                expanded_name!("", "filterRes") => {
+                    set_attribute(&mut self.filter_res, attr.parse(value), session)
+                }
                 _ => (),
             }
         }
@@ -227,7 +238,7 @@ fn extract_filter_from_filter_node(
         };
 
         for p in primitives {
-            user_space_primitives.push(p.into_user_space(&params));
+            user_space_primitives.push(p.into_user_space(&params).with_node(&primitive_node));
         }
     }
 
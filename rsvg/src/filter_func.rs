@@ -264,7 +264,11 @@ fn parse_sepia<'i>(parser: &mut Parser<'i, '_>) -> Result<FilterFunction, ParseE
 impl Blur {
     fn to_filter_spec(&self, params: &NormalizeParams) -> FilterSpec {
         // The 0.0 default is from the spec
-        let std_dev = self.std_deviation.map(|l| l.to_user(params)).unwrap_or(0.0);
+        let std_dev = self
+            .std_deviation
+            .as_ref()
+            .map(|l| l.to_user(params))
+            .unwrap_or(0.0);
 
         let user_space_filter = Filter::default().to_user_space(params);
 
@@ -437,9 +441,13 @@ impl DropShadow {
     /// See <https://www.w3.org/TR/filter-effects/#dropshadowEquivalent>.
     fn to_filter_spec(&self, params: &NormalizeParams, default_color: Color) -> FilterSpec {
         let user_space_filter = Filter::default().to_user_space(params);
-        let dx = self.dx.map(|l| l.to_user(params)).unwrap_or(0.0);
-        let dy = self.dy.map(|l| l.to_user(params)).unwrap_or(0.0);
-        let std_dev = self.std_deviation.map(|l| l.to_user(params)).unwrap_or(0.0);
+        let dx = self.dx.as_ref().map(|l| l.to_user(params)).unwrap_or(0.0);
+        let dy = self.dy.as_ref().map(|l| l.to_user(params)).unwrap_or(0.0);
+        let std_dev = self
+            .std_deviation
+            .as_ref()
+            .map(|l| l.to_user(params))
+            .unwrap_or(0.0);
         let std_deviation = NumberOptionalNumber(std_dev, std_dev);
         let color = self
             .color
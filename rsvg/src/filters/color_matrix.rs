@@ -12,9 +12,7 @@ use crate::properties::ColorInterpolationFilters;
 use crate::rect::IRect;
 use crate::rsvg_log;
 use crate::session::Session;
-use crate::surface_utils::{
-    ImageSurfaceDataExt, Pixel, iterators::Pixels, shared_surface::ExclusiveImageSurface,
-};
+use crate::surface_utils::{ImageSurfaceDataExt, Pixel, shared_surface::ExclusiveImageSurface};
 use crate::util::clamp;
 use crate::xml::Attributes;
 
@@ -207,8 +205,10 @@ impl ColorMatrix {
             input_1.surface().surface_type(),
         )?;
 
-        surface.modify(&mut |data, stride| {
-            for (x, y, pixel) in Pixels::within(input_1.surface(), bounds) {
+        surface.modify_rows(ctx.thread_pool(), bounds, &|row, y| {
+            for x in bounds.x_range() {
+                let x = x as u32;
+                let pixel = input_1.surface().get_pixel(x, y);
                 let alpha = f64::from(pixel.a) / 255f64;
 
                 let pixel_vec = if alpha == 0.0 {
@@ -236,7 +236,7 @@ impl ColorMatrix {
                     a: ((new_alpha * 255f64) + 0.5) as u8,
                 };
 
-                data.set_pixel(stride, output_pixel, x, y);
+                row.set_pixel(0, output_pixel, x, 0);
             }
         });
 
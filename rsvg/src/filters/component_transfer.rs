@@ -12,9 +12,7 @@ use crate::parsers::{CommaSeparatedList, Parse, ParseValue};
 use crate::properties::ColorInterpolationFilters;
 use crate::rect::IRect;
 use crate::session::Session;
-use crate::surface_utils::{
-    ImageSurfaceDataExt, Pixel, iterators::Pixels, shared_surface::ExclusiveImageSurface,
-};
+use crate::surface_utils::{ImageSurfaceDataExt, Pixel, shared_surface::ExclusiveImageSurface};
 use crate::util::clamp;
 use crate::xml::Attributes;
 
@@ -334,8 +332,10 @@ impl ComponentTransfer {
         let compute_a = |alpha| compute_a(&params_a, alpha);
 
         // Do the actual processing.
-        surface.modify(&mut |data, stride| {
-            for (x, y, pixel) in Pixels::within(input_1.surface(), bounds) {
+        surface.modify_rows(ctx.thread_pool(), bounds, &|row, y| {
+            for x in bounds.x_range() {
+                let x = x as u32;
+                let pixel = input_1.surface().get_pixel(x, y);
                 let alpha = f64::from(pixel.a) / 255f64;
                 let new_alpha = compute_a(alpha);
 
@@ -346,7 +346,7 @@ impl ComponentTransfer {
                     a: ((new_alpha * 255f64) + 0.5) as u8,
                 };
 
-                data.set_pixel(stride, output_pixel, x, y);
+                row.set_pixel(0, output_pixel, x, 0);
             }
         });
 
@@ -99,6 +99,7 @@ impl Composite {
         let surface = if self.operator == Operator::Arithmetic {
             input_1.surface().compose_arithmetic(
                 input_2.surface(),
+                ctx.thread_pool(),
                 bounds,
                 self.k1,
                 self.k2,
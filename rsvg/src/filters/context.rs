@@ -3,6 +3,7 @@ use std::rc::Rc;
 
 use crate::bbox::BoundingBox;
 use crate::coord_units::CoordUnits;
+use crate::drawing_ctx::{RenderQuality, Viewport};
 use crate::filter::UserSpaceFilter;
 use crate::parsers::CustomIdent;
 use crate::properties::ColorInterpolationFilters;
@@ -88,6 +89,45 @@ pub struct FilterContext {
     paffine: Transform,
 }
 
+/// Computes the transform from `filterUnits` or `primitiveUnits` to pixels.
+///
+/// The `bbox_rect` can be empty (for example, if the filter is applied to an empty group).
+/// However, with `userSpaceOnUse` it's still possible to create images with a filter.
+fn units_affine(units: CoordUnits, bbox_rect: &Rect, transform: &Transform) -> Transform {
+    match units {
+        CoordUnits::UserSpaceOnUse => *transform,
+        CoordUnits::ObjectBoundingBox => Transform::new_unchecked(
+            bbox_rect.width(),
+            0.0,
+            0.0,
+            bbox_rect.height(),
+            bbox_rect.x0,
+            bbox_rect.y0,
+        )
+        .post_transform(transform),
+    }
+}
+
+/// Computes the filter effects region in the pixel coordinates given by `transform`.
+///
+/// Unlike the effects region of a [`FilterContext`], this one is not clipped to the
+/// source surface; it is used to decide how big that surface needs to be.  Returns
+/// `None` if the filter's transform is not invertible.
+pub fn effects_region(
+    filter: &UserSpaceFilter,
+    node_bbox: &BoundingBox,
+    transform: &Transform,
+) -> Option<Rect> {
+    let bbox_rect = node_bbox.rect.unwrap_or_default();
+    let affine = units_affine(filter.filter_units, &bbox_rect, transform);
+
+    if !affine.is_invertible() {
+        return None;
+    }
+
+    Some(affine.transform_rect(&filter.rect))
+}
+
 impl FilterContext {
     /// Creates a new `FilterContext`.
     pub fn new(
@@ -96,35 +136,9 @@ impl FilterContext {
         source_surface: SharedImageSurface,
         node_bbox: BoundingBox,
     ) -> Result<Self, FilterError> {
-        // The rect can be empty (for example, if the filter is applied to an empty group).
-        // However, with userSpaceOnUse it's still possible to create images with a filter.
         let bbox_rect = node_bbox.rect.unwrap_or_default();
-
-        let affine = match filter.filter_units {
-            CoordUnits::UserSpaceOnUse => *plan.viewport.transform,
-            CoordUnits::ObjectBoundingBox => Transform::new_unchecked(
-                bbox_rect.width(),
-                0.0,
-                0.0,
-                bbox_rect.height(),
-                bbox_rect.x0,
-                bbox_rect.y0,
-            )
-            .post_transform(&plan.viewport.transform),
-        };
-
-        let paffine = match filter.primitive_units {
-            CoordUnits::UserSpaceOnUse => *plan.viewport.transform,
-            CoordUnits::ObjectBoundingBox => Transform::new_unchecked(
-                bbox_rect.width(),
-                0.0,
-                0.0,
-                bbox_rect.height(),
-                bbox_rect.x0,
-                bbox_rect.y0,
-            )
-            .post_transform(&plan.viewport.transform),
-        };
+        let affine = units_affine(filter.filter_units, &bbox_rect, &plan.viewport.transform);
+        let paffine = units_affine(filter.primitive_units, &bbox_rect, &plan.viewport.transform);
 
         if !(affine.is_invertible() && paffine.is_invertible()) {
             return Err(FilterError::InvalidParameter(
@@ -167,6 +181,23 @@ impl FilterContext {
         &self.plan.session
     }
 
+    /// Returns the thread pool in which to process pixels in parallel.
+    ///
+    /// If this is `None`, rayon's global thread pool should be used.
+    pub fn thread_pool(&self) -> Option<&rayon::ThreadPool> {
+        self.plan.thread_pool.as_deref()
+    }
+
+    /// Returns the viewport that was current when the filter was invoked.
+    pub fn viewport(&self) -> &Viewport {
+        &self.plan.viewport
+    }
+
+    /// Returns the trade-off between speed and accuracy requested for rendering.
+    pub fn quality(&self) -> RenderQuality {
+        self.plan.quality
+    }
+
     /// Returns the surface corresponding to the source graphic.
     #[inline]
     pub fn source_graphic(&self) -> &SharedImageSurface {
@@ -3,7 +3,7 @@ use markup5ever::{expanded_name, local_name, ns};
 use nalgebra::{DMatrix, Dyn, VecStorage};
 
 use crate::bench_only::{
-    EdgeMode, ExclusiveImageSurface, ImageSurfaceDataExt, Pixel, PixelRectangle, Pixels,
+    EdgeMode, ExclusiveImageSurface, ImageSurfaceDataExt, Pixel, PixelRectangle,
 };
 use crate::document::AcquiredNodes;
 use crate::element::{ElementTrait, set_attribute};
@@ -225,8 +225,11 @@ impl ConvolveMatrix {
             input_1.surface().surface_type(),
         )?;
 
-        surface.modify(&mut |data, stride| {
-            for (x, y, pixel) in Pixels::within(&input_surface, bounds) {
+        surface.modify_rows(ctx.thread_pool(), bounds, &|row, y| {
+            for x in bounds.x_range() {
+                let x = x as u32;
+                let pixel = input_surface.get_pixel(x, y);
+
                 // Compute the convolution rectangle bounds.
                 let kernel_bounds = IRect::new(
                     x as i32 - target_x as i32,
@@ -285,7 +288,7 @@ impl ConvolveMatrix {
                     a: ((clamped_a * 255.0) + 0.5) as u8,
                 };
 
-                data.set_pixel(stride, output_pixel, x, y);
+                row.set_pixel(0, output_pixel, x, 0);
             }
         });
 
@@ -10,7 +10,10 @@ use crate::parsers::{Parse, ParseValue};
 use crate::properties::ColorInterpolationFilters;
 use crate::rect::IRect;
 use crate::session::Session;
-use crate::surface_utils::{iterators::Pixels, shared_surface::ExclusiveImageSurface};
+use crate::surface_utils::{
+    ImageSurfaceDataExt, Pixel,
+    shared_surface::{ExclusiveImageSurface, SharedImageSurface},
+};
 use crate::xml::Attributes;
 
 use super::bounds::BoundsBuilder;
@@ -111,8 +114,11 @@ impl DisplacementMap {
             input_1.surface().surface_type(),
         )?;
 
-        surface.draw(&mut |cr| {
-            for (x, y, displacement_pixel) in Pixels::within(&displacement_surface, bounds) {
+        surface.modify_rows(ctx.thread_pool(), bounds, &|row, y| {
+            for x in bounds.x_range() {
+                let x = x as u32;
+                let displacement_pixel = displacement_surface.get_pixel(x, y);
+
                 let get_value = |channel| match channel {
                     ColorChannel::R => displacement_pixel.r,
                     ColorChannel::G => displacement_pixel.g,
@@ -125,23 +131,15 @@ impl DisplacementMap {
                 let dx = process(get_value(self.x_channel_selector));
                 let dy = process(get_value(self.y_channel_selector));
 
-                let x = f64::from(x);
-                let y = f64::from(y);
                 let ox = sx * dx;
                 let oy = sy * dy;
 
-                // Doing this in a loop doesn't look too bad performance wise, and allows not to
-                // manually implement bilinear or other interpolation.
-                cr.rectangle(x, y, 1.0, 1.0);
-                cr.reset_clip();
-                cr.clip();
+                let output_pixel =
+                    sample_bilinear(input_1.surface(), f64::from(x) + ox, f64::from(y) + oy);
 
-                input_1.surface().set_as_source_surface(&cr, -ox, -oy)?;
-                cr.paint()?;
+                row.set_pixel(0, output_pixel, x, 0);
             }
-
-            Ok(())
-        })?;
+        });
 
         Ok(FilterOutput {
             surface: surface.share()?,
@@ -156,6 +154,49 @@ impl DisplacementMap {
     }
 }
 
+/// Samples a surface with bilinear interpolation, like Cairo does for a surface that is
+/// translated by a fractional amount.
+///
+/// The `x` and `y` are in pixel coordinates, so `(0.0, 0.0)` is exactly the top-left
+/// pixel.  Pixels outside of the surface are transparent.
+fn sample_bilinear(surface: &SharedImageSurface, x: f64, y: f64) -> Pixel {
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let fx = x - x0;
+    let fy = y - y0;
+
+    let get = |px: f64, py: f64| {
+        if px >= 0.0
+            && py >= 0.0
+            && px < f64::from(surface.width())
+            && py < f64::from(surface.height())
+        {
+            surface.get_pixel(px as u32, py as u32)
+        } else {
+            Pixel::default()
+        }
+    };
+
+    let p00 = get(x0, y0);
+    let p10 = get(x0 + 1.0, y0);
+    let p01 = get(x0, y0 + 1.0);
+    let p11 = get(x0 + 1.0, y0 + 1.0);
+
+    let interpolate = |c00: u8, c10: u8, c01: u8, c11: u8| {
+        let top = f64::from(c00) * (1.0 - fx) + f64::from(c10) * fx;
+        let bottom = f64::from(c01) * (1.0 - fx) + f64::from(c11) * fx;
+
+        (top * (1.0 - fy) + bottom * fy + 0.5) as u8
+    };
+
+    Pixel {
+        r: interpolate(p00.r, p10.r, p01.r, p11.r),
+        g: interpolate(p00.g, p10.g, p01.g, p11.g),
+        b: interpolate(p00.b, p10.b, p01.b, p11.b),
+        a: interpolate(p00.a, p10.a, p01.a, p11.a),
+    }
+}
+
 impl FilterEffect for FeDisplacementMap {
     fn resolve(
         &self,
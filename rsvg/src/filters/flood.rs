@@ -21,6 +21,7 @@ pub struct FeFlood {
 }
 
 /// Resolved `feFlood` primitive for rendering.
+#[derive(Clone, PartialEq)]
 pub struct Flood {
     pub color: Color,
 }
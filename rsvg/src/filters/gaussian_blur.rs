@@ -1,20 +1,30 @@
 use std::cmp::min;
 use std::f64;
 
+use float_cmp::approx_eq;
 use markup5ever::{expanded_name, local_name, ns};
 use nalgebra::{DMatrix, Dyn, VecStorage};
+use rayon::prelude::*;
+use wide::f64x4;
 
 use crate::document::AcquiredNodes;
+use crate::drawing_ctx::RenderQuality;
 use crate::element::{ElementTrait, set_attribute};
+use crate::error::InvalidTransform;
 use crate::node::{CascadedValues, Node};
 use crate::parsers::{NumberOptionalNumber, ParseValue};
 use crate::properties::ColorInterpolationFilters;
-use crate::rect::IRect;
+use crate::rect::{IRect, Rect};
+use crate::rsvg_log;
 use crate::session::Session;
 use crate::surface_utils::{
-    EdgeMode,
-    shared_surface::{BlurDirection, Horizontal, SharedImageSurface, Vertical},
+    EdgeMode, ImageSurfaceDataExt, Pixel, PixelOps,
+    iterators::PixelRectangle,
+    shared_surface::{
+        BlurDirection, ExclusiveImageSurface, Horizontal, SharedImageSurface, Vertical,
+    },
 };
+use crate::transform::{Transform, ValidTransform};
 use crate::xml::Attributes;
 
 use super::bounds::BoundsBuilder;
@@ -204,6 +214,378 @@ fn gaussian_blur(
     )?)
 }
 
+/// Coefficients of the recursive approximation to the gaussian by Young and van Vliet.
+///
+/// The filter runs a forward and a backward pass over each line of pixels, and its cost
+/// does not depend on the standard deviation.  See I. T. Young and L. J. van Vliet,
+/// "Recursive implementation of the Gaussian filter", Signal Processing 44 (1995).
+struct RecursiveGaussian {
+    b: f64,
+    b1: f64,
+    b2: f64,
+    b3: f64,
+
+    /// Number of samples to extend each line with at both ends, so that the edge mode
+    /// gets applied before the recursion starts.
+    padding: usize,
+}
+
+impl RecursiveGaussian {
+    fn new(std_deviation: f64) -> RecursiveGaussian {
+        let variance = std_deviation * std_deviation;
+
+        // The paper's formula for the filter's scale `q` makes the blur about 10% wider
+        // than requested, so find the `q` that gives exactly the requested variance.  The
+        // variance grows monotonically with `q`, and it is larger than the requested one
+        // at the upper bound used here.
+        let (mut low, mut high) = (0.0, 2.0 * std_deviation + 3.0);
+
+        for _ in 0..64 {
+            let q = (low + high) / 2.0;
+
+            if RecursiveGaussian::with_scale(q, 0).variance() < variance {
+                low = q;
+            } else {
+                high = q;
+            }
+        }
+
+        // Past four standard deviations the response is negligible.
+        let padding = (std_deviation * 4.0).ceil() as usize;
+
+        RecursiveGaussian::with_scale((low + high) / 2.0, padding)
+    }
+
+    fn with_scale(q: f64, padding: usize) -> RecursiveGaussian {
+        let q2 = q * q;
+        let q3 = q2 * q;
+
+        let b0 = 1.57825 + 2.44413 * q + 1.4281 * q2 + 0.422205 * q3;
+        let b1 = (2.44413 * q + 2.85619 * q2 + 1.26661 * q3) / b0;
+        let b2 = -(1.4281 * q2 + 1.26661 * q3) / b0;
+        let b3 = 0.422205 * q3 / b0;
+
+        RecursiveGaussian {
+            b: 1.0 - (b1 + b2 + b3),
+            b1,
+            b2,
+            b3,
+            padding,
+        }
+    }
+
+    /// Computes the variance of the impulse response of both passes together.
+    ///
+    /// The response of one pass has the generating function `H(z) = b / A(z)`, with
+    /// `A(z) = 1 - b1 z - b2 z² - b3 z³`; its mean is `H'(1)` and its variance is
+    /// `H''(1) + H'(1) - H'(1)²`.  The backward pass doubles the variance.
+    fn variance(&self) -> f64 {
+        let a1 = -(self.b1 + 2.0 * self.b2 + 3.0 * self.b3);
+        let a2 = -(2.0 * self.b2 + 6.0 * self.b3);
+
+        let mean = -a1 / self.b;
+        let second_factorial_moment = -a2 / self.b + 2.0 * a1 * a1 / (self.b * self.b);
+
+        2.0 * (second_factorial_moment + mean - mean * mean)
+    }
+
+    /// Blurs a line of premultiplied RGBA samples in place.
+    ///
+    /// The four channels of a sample are filtered together, one per SIMD lane.
+    fn blur(&self, line: &mut [f64x4]) {
+        let step = |sample: &mut f64x4, w: &mut [f64x4; 3]| {
+            let [w1, w2, w3] = *w;
+
+            *sample = *sample * self.b + w1 * self.b1 + w2 * self.b2 + w3 * self.b3;
+
+            *w = [*sample, w1, w2];
+        };
+
+        // Both passes start from the steady state for the sample at the edge, which the
+        // filter leaves unchanged for a line of constant samples.
+        if let Some(&first) = line.first() {
+            let mut w = [first; 3];
+            line.iter_mut().for_each(|sample| step(sample, &mut w));
+        }
+
+        if let Some(&last) = line.last() {
+            let mut w = [last; 3];
+            line.iter_mut()
+                .rev()
+                .for_each(|sample| step(sample, &mut w));
+        }
+    }
+}
+
+/// Applies the recursive approximation of the gaussian blur in one direction.
+fn recursive_gaussian_blur<B: BlurDirection>(
+    surface: &SharedImageSurface,
+    bounds: IRect,
+    std_deviation: f64,
+    edge_mode: EdgeMode,
+    thread_pool: Option<&rayon::ThreadPool>,
+) -> Result<SharedImageSurface, FilterError> {
+    let mut output_surface =
+        ExclusiveImageSurface::new(surface.width(), surface.height(), surface.surface_type())?;
+
+    if bounds.is_empty() {
+        return Ok(output_surface.share()?);
+    }
+
+    let filter = RecursiveGaussian::new(std_deviation);
+
+    let (line_len, num_lines) = if B::IS_VERTICAL {
+        (bounds.height() as usize, bounds.width() as usize)
+    } else {
+        (bounds.width() as usize, bounds.height() as usize)
+    };
+
+    // Returns the sample at position `i` of a line, where `i` may be out of bounds.
+    let sample = |line: usize, i: isize| -> f64x4 {
+        let len = line_len as isize;
+
+        let i = match edge_mode {
+            EdgeMode::None if i < 0 || i >= len => return f64x4::splat(0.0),
+            EdgeMode::None => i,
+            EdgeMode::Duplicate => i.clamp(0, len - 1),
+            EdgeMode::Wrap => i.rem_euclid(len),
+        };
+
+        let (x, y) = if B::IS_VERTICAL {
+            (bounds.x0 + line as i32, bounds.y0 + i as i32)
+        } else {
+            (bounds.x0 + i as i32, bounds.y0 + line as i32)
+        };
+
+        surface.get_pixel(x as u32, y as u32).to_f64x4()
+    };
+
+    // The blurred lines, one after the other.
+    let mut blurred = vec![f64x4::splat(0.0); line_len * num_lines];
+
+    let blur_lines = || {
+        let padding = filter.padding as isize;
+
+        blurred
+            .par_chunks_mut(line_len)
+            .enumerate()
+            .for_each(|(line, output)| {
+                let mut samples: Vec<f64x4> = (-padding..line_len as isize + padding)
+                    .map(|i| sample(line, i))
+                    .collect();
+
+                filter.blur(&mut samples);
+
+                output.copy_from_slice(&samples[filter.padding..filter.padding + line_len]);
+            })
+    };
+
+    match thread_pool {
+        Some(pool) => pool.install(blur_lines),
+        None => blur_lines(),
+    }
+
+    output_surface.modify_rows(thread_pool, bounds, &|row, y| {
+        let y = y as i32 - bounds.y0;
+
+        for x in bounds.x_range() {
+            let (line, i) = if B::IS_VERTICAL {
+                (x - bounds.x0, y)
+            } else {
+                (y, x - bounds.x0)
+            };
+
+            let sample = blurred[line as usize * line_len + i as usize];
+
+            // Keep the result a valid premultiplied color.
+            let a = sample.to_array()[3].clamp(0.0, 255.0);
+            let output_pixel = Pixel::from_f64x4(sample.min(f64x4::splat(a)));
+
+            row.set_pixel(0, output_pixel, x as u32, 0);
+        }
+    });
+
+    Ok(output_surface.share()?)
+}
+
+/// Applies the gaussian blur in one direction without approximating it with box blurs.
+///
+/// Small deviations use an explicit kernel; larger ones use the recursive filter.
+fn exact_gaussian_blur<B: BlurDirection>(
+    surface: &SharedImageSurface,
+    bounds: IRect,
+    std_deviation: f64,
+    edge_mode: EdgeMode,
+    thread_pool: Option<&rayon::ThreadPool>,
+) -> Result<SharedImageSurface, FilterError> {
+    if std_deviation >= 2.0 {
+        recursive_gaussian_blur::<B>(surface, bounds, std_deviation, edge_mode, thread_pool)
+    } else if std_deviation > 0.0 {
+        gaussian_blur(surface, bounds, std_deviation, edge_mode, B::IS_VERTICAL)
+    } else {
+        Ok(surface.clone())
+    }
+}
+
+/// Applies the gaussian blur along the axes of a rotated or skewed user space.
+///
+/// The surface gets resampled so that the user space's axes are aligned with the pixels,
+/// then it gets blurred, and finally it gets resampled back.
+///
+/// The `edge_mode` applies to the edges of `bounds`, not to the edges of the resampled
+/// surface, whose corners are transparent.  So, the input gets extended with the edge mode
+/// before resampling, far enough for the blur to not see past the extension.
+fn resampled_gaussian_blur(
+    surface: &SharedImageSurface,
+    bounds: IRect,
+    paffine: Transform,
+    (std_x, std_y): (f64, f64),
+    edge_mode: EdgeMode,
+    thread_pool: Option<&rayon::ThreadPool>,
+) -> Result<SharedImageSurface, FilterError> {
+    // Pixels per user space unit along each of its axes.
+    let scale_x = paffine.xx.hypot(paffine.yx);
+    let scale_y = paffine.xy.hypot(paffine.yy);
+
+    if edge_mode != EdgeMode::None && !bounds.is_empty() {
+        // The blur is negligible past four standard deviations, and a step along the
+        // aligned axes is at most as long as the same step along the device axes.
+        let reach = 4.0 * (std_x * scale_x + std_y * scale_y);
+        let margin = reach.ceil() as i32 + 1;
+
+        let extended_bounds = IRect::new(
+            bounds.x0 - margin,
+            bounds.y0 - margin,
+            bounds.x1 + margin,
+            bounds.y1 + margin,
+        );
+
+        let mut extended_surface = ExclusiveImageSurface::new(
+            extended_bounds.width(),
+            extended_bounds.height(),
+            surface.surface_type(),
+        )?;
+
+        extended_surface.modify(&mut |data, stride| {
+            for (x, y, pixel) in PixelRectangle::within(surface, bounds, extended_bounds, edge_mode)
+            {
+                data.set_pixel(
+                    stride,
+                    pixel,
+                    (x - extended_bounds.x0) as u32,
+                    (y - extended_bounds.y0) as u32,
+                );
+            }
+        });
+
+        let blurred = resampled_gaussian_blur(
+            &extended_surface.share()?,
+            IRect::from_size(extended_bounds.width(), extended_bounds.height()),
+            paffine,
+            (std_x, std_y),
+            EdgeMode::None,
+            thread_pool,
+        )?;
+
+        let output_surface =
+            cairo::ImageSurface::create(cairo::Format::ARgb32, surface.width(), surface.height())?;
+
+        {
+            let cr = cairo::Context::new(&output_surface)?;
+            let r = cairo::Rectangle::from(bounds);
+            cr.rectangle(r.x(), r.y(), r.width(), r.height());
+            cr.clip();
+
+            blurred.set_as_source_surface(
+                &cr,
+                f64::from(extended_bounds.x0),
+                f64::from(extended_bounds.y0),
+            )?;
+            cr.paint()?;
+        }
+
+        return Ok(SharedImageSurface::wrap(
+            output_surface,
+            surface.surface_type(),
+        )?);
+    }
+
+    let linear = Transform::new_unchecked(paffine.xx, paffine.yx, paffine.xy, paffine.yy, 0.0, 0.0);
+    let to_aligned = ValidTransform::try_from(linear)?
+        .invert()
+        .ok_or(InvalidTransform)?
+        .post_scale(scale_x, scale_y);
+
+    let aligned_bounds = IRect::from(to_aligned.transform_rect(&Rect::from(bounds)));
+    let to_aligned =
+        to_aligned.post_translate(-f64::from(aligned_bounds.x0), -f64::from(aligned_bounds.y0));
+    let aligned_bounds = IRect::from_size(aligned_bounds.width(), aligned_bounds.height());
+
+    let bounds_rect = cairo::Rectangle::from(bounds);
+
+    let aligned_surface = cairo::ImageSurface::create(
+        cairo::Format::ARgb32,
+        aligned_bounds.width(),
+        aligned_bounds.height(),
+    )?;
+
+    {
+        let cr = cairo::Context::new(&aligned_surface)?;
+        cr.set_matrix(ValidTransform::try_from(to_aligned)?.into());
+        cr.rectangle(
+            bounds_rect.x(),
+            bounds_rect.y(),
+            bounds_rect.width(),
+            bounds_rect.height(),
+        );
+        cr.clip();
+
+        surface.set_as_source_surface(&cr, 0.0, 0.0)?;
+        cr.paint()?;
+    }
+
+    let aligned_surface = SharedImageSurface::wrap(aligned_surface, surface.surface_type())?;
+
+    let blurred = exact_gaussian_blur::<Horizontal>(
+        &aligned_surface,
+        aligned_bounds,
+        std_x * scale_x,
+        edge_mode,
+        thread_pool,
+    )?;
+    let blurred = exact_gaussian_blur::<Vertical>(
+        &blurred,
+        aligned_bounds,
+        std_y * scale_y,
+        edge_mode,
+        thread_pool,
+    )?;
+
+    let output_surface =
+        cairo::ImageSurface::create(cairo::Format::ARgb32, surface.width(), surface.height())?;
+
+    {
+        let cr = cairo::Context::new(&output_surface)?;
+        cr.rectangle(
+            bounds_rect.x(),
+            bounds_rect.y(),
+            bounds_rect.width(),
+            bounds_rect.height(),
+        );
+        cr.clip();
+
+        let from_aligned = to_aligned.invert().ok_or(InvalidTransform)?;
+        cr.set_matrix(ValidTransform::try_from(from_aligned)?.into());
+        blurred.set_as_source_surface(&cr, 0.0, 0.0)?;
+        cr.paint()?;
+    }
+
+    Ok(SharedImageSurface::wrap(
+        output_surface,
+        surface.surface_type(),
+    )?)
+}
+
 impl GaussianBlur {
     pub fn render(
         &self,
@@ -229,6 +611,13 @@ impl GaussianBlur {
             });
         }
 
+        if ctx.quality() == RenderQuality::Best {
+            return Ok(FilterOutput {
+                surface: self.render_exact(input_1.surface(), bounds, ctx)?,
+                bounds,
+            });
+        }
+
         let (std_x, std_y) = ctx.paffine().transform_distance(std_x, std_y);
 
         // The deviation can become negative here due to the transform.
@@ -239,9 +628,17 @@ impl GaussianBlur {
         // (so the image is alpha-only). We can use this to not waste time processing the other
         // channels.
 
+        // The spec says for deviation >= 2.0 three box blurs can be used as an optimization.
+        // For draft quality, they get used for smaller deviations as well; below one pixel
+        // the boxes would be too small to approximate anything, so the exact kernel is kept.
+        let box_blur_threshold = if ctx.quality() == RenderQuality::Draft {
+            1.0
+        } else {
+            2.0
+        };
+
         // Horizontal convolution.
-        let horiz_result_surface = if std_x >= 2.0 {
-            // The spec says for deviation >= 2.0 three box blurs can be used as an optimization.
+        let horiz_result_surface = if std_x >= box_blur_threshold {
             three_box_blurs::<Horizontal>(input_1.surface(), bounds, std_x)?
         } else if std_x != 0.0 {
             gaussian_blur(input_1.surface(), bounds, std_x, self.edge_mode, false)?
@@ -250,8 +647,7 @@ impl GaussianBlur {
         };
 
         // Vertical convolution.
-        let output_surface = if std_y >= 2.0 {
-            // The spec says for deviation >= 2.0 three box blurs can be used as an optimization.
+        let output_surface = if std_y >= box_blur_threshold {
             three_box_blurs::<Vertical>(&horiz_result_surface, bounds, std_y)?
         } else if std_y != 0.0 {
             gaussian_blur(&horiz_result_surface, bounds, std_y, self.edge_mode, true)?
@@ -265,6 +661,78 @@ impl GaussianBlur {
         })
     }
 
+    /// Blurs without approximations, for [`RenderQuality::Best`].
+    fn render_exact(
+        &self,
+        surface: &SharedImageSurface,
+        bounds: IRect,
+        ctx: &FilterContext,
+    ) -> Result<SharedImageSurface, FilterError> {
+        let NumberOptionalNumber(std_x, std_y) = self.std_deviation;
+        let (std_x, std_y) = (std_x.max(0.0), std_y.max(0.0));
+
+        let paffine = ctx.paffine();
+        let thread_pool = ctx.thread_pool();
+
+        // Pixels per user space unit along each of its axes.
+        let scale_x = paffine.xx.hypot(paffine.yx);
+        let scale_y = paffine.xy.hypot(paffine.yy);
+
+        // Keep the blur within the size limit of the explicit kernels; the recursive
+        // filter would not mind, but the lines it pads to apply the edge mode would get huge.
+        let max_std_deviation = (MAXIMUM_KERNEL_SIZE / 2) as f64;
+
+        if std_x * scale_x > max_std_deviation || std_y * scale_y > max_std_deviation {
+            rsvg_log!(
+                ctx.session(),
+                "feGaussianBlur stdDeviation of ({}, {}) pixels is too large; limiting it to {}",
+                std_x * scale_x,
+                std_y * scale_y,
+                max_std_deviation
+            );
+        }
+
+        let std_x = std_x.min(max_std_deviation / scale_x);
+        let std_y = std_y.min(max_std_deviation / scale_y);
+
+        let is_axis_aligned = paffine.yx == 0.0 && paffine.xy == 0.0;
+
+        // A blur that is circular in device space does not care about the axes.
+        let is_circular = approx_eq!(
+            f64,
+            paffine.xx * paffine.xy + paffine.yx * paffine.yy,
+            0.0,
+            epsilon = 1e-9 * scale_x * scale_y
+        ) && approx_eq!(f64, std_x * scale_x, std_y * scale_y);
+
+        if is_axis_aligned || is_circular {
+            let horiz_result_surface = exact_gaussian_blur::<Horizontal>(
+                surface,
+                bounds,
+                std_x * scale_x,
+                self.edge_mode,
+                thread_pool,
+            )?;
+
+            exact_gaussian_blur::<Vertical>(
+                &horiz_result_surface,
+                bounds,
+                std_y * scale_y,
+                self.edge_mode,
+                thread_pool,
+            )
+        } else {
+            resampled_gaussian_blur(
+                surface,
+                bounds,
+                paffine,
+                (std_x, std_y),
+                self.edge_mode,
+                thread_pool,
+            )
+        }
+    }
+
     pub fn get_input_requirements(&self) -> InputRequirements {
         self.in1.get_requirements()
     }
@@ -288,3 +756,52 @@ impl FilterEffect for FeGaussianBlur {
         }])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recursive_gaussian_keeps_constant_lines() {
+        let filter = RecursiveGaussian::new(3.0);
+
+        let mut line = vec![f64x4::new([10.0, 20.0, 30.0, 40.0]); 50];
+        filter.blur(&mut line);
+
+        for sample in line {
+            for (value, expected) in sample.to_array().into_iter().zip([10.0, 20.0, 30.0, 40.0]) {
+                assert!(approx_eq!(f64, value, expected, epsilon = 1e-6));
+            }
+        }
+    }
+
+    #[test]
+    fn recursive_gaussian_has_requested_deviation() {
+        for std_deviation in [2.0, 5.0, 20.0] {
+            let filter = RecursiveGaussian::new(std_deviation);
+
+            let len = (std_deviation * 20.0) as usize + 1;
+            let center = len / 2;
+
+            let mut line = vec![f64x4::splat(0.0); len];
+            line[center] = f64x4::splat(1.0);
+            filter.blur(&mut line);
+
+            let sum: f64 = line.iter().map(|sample| sample.to_array()[3]).sum();
+            let variance: f64 = line
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| sample.to_array()[3] * (i as f64 - center as f64).powi(2))
+                .sum::<f64>()
+                / sum;
+
+            assert!(approx_eq!(f64, sum, 1.0, epsilon = 1e-4));
+            assert!(approx_eq!(
+                f64,
+                variance.sqrt(),
+                std_deviation,
+                epsilon = 0.01 * std_deviation
+            ));
+        }
+    }
+}
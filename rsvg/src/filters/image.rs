@@ -12,7 +12,7 @@ use crate::properties::ComputedValues;
 use crate::rect::Rect;
 use crate::rsvg_log;
 use crate::session::Session;
-use crate::surface_utils::shared_surface::{Interpolation, SharedImageSurface, SurfaceType};
+use crate::surface_utils::shared_surface::{SharedImageSurface, SurfaceType};
 use crate::transform::ValidTransform;
 use crate::viewbox::ViewBox;
 use crate::xml::Attributes;
@@ -38,6 +38,7 @@ struct ImageParams {
 }
 
 /// Resolved `feImage` primitive for rendering.
+#[derive(Clone)]
 pub struct Image {
     aspect: AspectRatio,
     source: Source,
@@ -45,6 +46,7 @@ pub struct Image {
 }
 
 /// What a feImage references for rendering.
+#[derive(Clone, PartialEq)]
 enum Source {
     /// Nothing is referenced; ignore the filter.
     None,
@@ -77,6 +79,16 @@ impl ElementTrait for FeImage {
     }
 }
 
+/// Compares the parameters for caching the primitive's output.
+///
+/// The computed values are not compared, since they come from the `feImage` element
+/// itself, which is already part of the cache key.
+impl PartialEq for Image {
+    fn eq(&self, other: &Image) -> bool {
+        self.aspect == other.aspect && self.source == other.source
+    }
+}
+
 impl Image {
     pub fn render(
         &self,
@@ -140,15 +152,18 @@ impl Image {
         let cascaded =
             CascadedValues::new_from_values(referenced_node, &self.feimage_values, None, None);
 
-        let interpolation = Interpolation::from(self.feimage_values.image_rendering());
+        let interpolation = ctx
+            .quality()
+            .interpolation(self.feimage_values.image_rendering());
 
         let paffine = ValidTransform::try_from(ctx.paffine())?;
+        let viewport = ctx.viewport().with_explicit_transform(paffine);
 
         let image = draw_ctx.draw_node_to_surface(
             referenced_node,
             acquired_nodes,
             &cascaded,
-            paffine,
+            &viewport,
             ctx.source_graphic().width(),
             ctx.source_graphic().height(),
         )?;
@@ -206,7 +221,9 @@ impl Image {
 
         // FIXME: overflow is not used but it should be
         // let overflow = self.feimage_values.overflow();
-        let interpolation = Interpolation::from(self.feimage_values.image_rendering());
+        let interpolation = ctx
+            .quality()
+            .interpolation(self.feimage_values.image_rendering());
 
         let surface =
             ctx.source_graphic()
@@ -636,20 +636,28 @@ macro_rules! impl_lighting_filter {
                         let first_pixel = (first_row as usize) * output_stride;
                         let one_past_last_pixel = (one_past_last_row as usize) * output_stride;
 
-                        output_slice[first_pixel..one_past_last_pixel]
-                            .par_chunks_mut(output_stride)
-                            .zip(first_row..one_past_last_row)
-                            .for_each(|(slice, y)| {
-                                for x in bounds.x0 as u32 + 1..bounds.x1 as u32 - 1 {
-                                    compute_output_pixel(
-                                        slice,
-                                        y,
-                                        x,
-                                        y,
-                                        Normal::interior(&input_surface, bounds, x, y),
-                                    );
-                                }
-                            });
+                        let rows = &mut output_slice[first_pixel..one_past_last_pixel];
+
+                        let process = || {
+                            rows.par_chunks_mut(output_stride)
+                                .zip(first_row..one_past_last_row)
+                                .for_each(|(slice, y)| {
+                                    for x in bounds.x0 as u32 + 1..bounds.x1 as u32 - 1 {
+                                        compute_output_pixel(
+                                            slice,
+                                            y,
+                                            x,
+                                            y,
+                                            Normal::interior(&input_surface, bounds, x, y),
+                                        );
+                                    }
+                                })
+                        };
+
+                        match ctx.thread_pool() {
+                            Some(pool) => pool.install(process),
+                            None => process(),
+                        }
                     }
                 }
 
@@ -2,26 +2,30 @@
 
 use cssparser::{BasicParseError, Parser};
 use markup5ever::{expanded_name, local_name, ns};
+use std::collections::VecDeque;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Instant;
 
 use crate::bbox::BoundingBox;
 use crate::document::AcquiredNodes;
-use crate::drawing_ctx::{DrawingCtx, Viewport};
+use crate::drawing_ctx::{DrawingCtx, RenderQuality, Viewport};
 use crate::element::{ElementTrait, set_attribute};
 use crate::error::{InternalRenderingError, ParseError};
 use crate::filter::UserSpaceFilter;
 use crate::length::*;
 use crate::node::Node;
 use crate::parse_identifiers;
-use crate::parsers::{CustomIdent, Parse, ParseValue};
+use crate::parsers::{CustomIdent, NumberOptionalNumber, Parse, ParseValue};
 use crate::properties::ColorInterpolationFilters;
+use crate::rect::Rect;
 use crate::rsvg_log;
 use crate::session::Session;
 use crate::surface_utils::{
     EdgeMode,
     shared_surface::{SharedImageSurface, SurfaceType},
 };
+use crate::transform::Transform;
 use crate::xml::Attributes;
 
 mod bounds;
@@ -75,6 +79,38 @@ pub struct FilterSpec {
     pub primitives: Vec<UserSpacePrimitive>,
 }
 
+impl FilterSpec {
+    /// Computes the filter effects region in the pixel coordinates given by `transform`.
+    ///
+    /// Returns `None` if the region cannot be computed because the filter's transform is
+    /// not invertible.
+    pub fn effects_region(&self, node_bbox: &BoundingBox, transform: &Transform) -> Option<Rect> {
+        context::effects_region(&self.user_space_filter, node_bbox, transform)
+    }
+
+    /// Computes the scale factors that give the filter effects region the size in pixels
+    /// from the legacy `filterRes` attribute.
+    ///
+    /// The `effects_region` is the result of [`FilterSpec::effects_region`] for the pixel
+    /// coordinates to be scaled.  Returns `None` if the filter does not have `filterRes`.
+    ///
+    /// The scale factors are at most 1.0; `filterRes` can lower the resolution of a
+    /// filter, but computing it at more than the output's resolution would just allocate
+    /// larger surfaces for no visible benefit.
+    pub fn filter_res_scale(&self, effects_region: &Rect) -> Option<(f64, f64)> {
+        let NumberOptionalNumber(res_x, res_y) = self.user_space_filter.filter_res?;
+
+        if effects_region.is_empty() {
+            return None;
+        }
+
+        Some((
+            (f64::from(res_x) / effects_region.width()).min(1.0),
+            (f64::from(res_y) / effects_region.height()).min(1.0),
+        ))
+    }
+}
+
 /// Parameters using while rendering a whole `filter` property.
 ///
 /// The `filter` property may contain a single primitive, like `filter="blur(2px)"`, or a
@@ -114,6 +150,14 @@ pub struct FilterPlan {
     ///
     /// Filter Effects 1: <https://www.w3.org/TR/filter-effects/#attr-valuedef-in-fillpaint>
     fill_paint_image: Option<SharedImageSurface>,
+
+    /// Thread pool for primitives that process pixels in parallel.
+    ///
+    /// If this is `None`, rayon's global thread pool gets used.
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+
+    /// Whether primitives should trade accuracy for speed.
+    quality: RenderQuality,
 }
 
 impl FilterPlan {
@@ -124,6 +168,8 @@ impl FilterPlan {
         background_image: Option<SharedImageSurface>,
         stroke_paint_image: Option<SharedImageSurface>,
         fill_paint_image: Option<SharedImageSurface>,
+        thread_pool: Option<Arc<rayon::ThreadPool>>,
+        quality: RenderQuality,
     ) -> Result<FilterPlan, Box<InternalRenderingError>> {
         assert_eq!(
             requirements.needs_background_image || requirements.needs_background_alpha,
@@ -146,6 +192,8 @@ impl FilterPlan {
             background_image,
             stroke_paint_image,
             fill_paint_image,
+            thread_pool,
+            quality,
         })
     }
 }
@@ -302,6 +350,11 @@ pub struct UserSpacePrimitive {
     result: Option<CustomIdent>,
 
     params: PrimitiveParams,
+
+    /// The `feFoo` element from which the primitive was resolved.
+    ///
+    /// This is `None` for primitives that come from filter functions like `blur()`.
+    node: Option<Node>,
 }
 
 /// An enumeration of possible inputs for a filter primitive.
@@ -379,16 +432,158 @@ impl ResolvedPrimitive {
             height,
             result: self.primitive.result,
             params: self.params,
+            node: None,
         }
     }
 }
 
 impl UserSpacePrimitive {
+    /// Records the `feFoo` element from which the primitive was resolved.
+    pub fn with_node(self, node: &Node) -> UserSpacePrimitive {
+        UserSpacePrimitive {
+            node: Some(node.clone()),
+            ..self
+        }
+    }
+
     /// Validates attributes and returns the `BoundsBuilder` for bounds computation.
     #[inline]
     fn get_bounds(&self, ctx: &FilterContext) -> BoundsBuilder {
         BoundsBuilder::new(self.x, self.y, self.width, self.height, ctx.paffine())
     }
+
+    /// Returns the key for the primitive's output in a [`FilterCache`].
+    ///
+    /// Only primitives that come from an element, and whose output does not depend on
+    /// their inputs, can be cached; this returns `None` for the others.
+    fn cache_key(&self, ctx: &FilterContext) -> Option<FilterCacheKey> {
+        let node = self.node.clone()?;
+
+        let params = match self.params {
+            PrimitiveParams::Flood(ref p) => CachedParams::Flood(p.clone()),
+            PrimitiveParams::Image(ref p) => CachedParams::Image(p.clone()),
+            PrimitiveParams::Turbulence(ref p) => CachedParams::Turbulence(p.clone()),
+            _ => return None,
+        };
+
+        let source = ctx.source_graphic();
+
+        Some(FilterCacheKey {
+            node,
+            params,
+            subregion: [self.x, self.y, self.width, self.height],
+            effects_region: ctx.effects_region(),
+            paffine: ctx.paffine(),
+            surface_size: (source.width(), source.height()),
+            quality: ctx.quality(),
+        })
+    }
+}
+
+/// Resolved parameters of the primitives whose output can be cached.
+#[derive(Clone, PartialEq)]
+enum CachedParams {
+    Flood(flood::Flood),
+    Image(image::Image),
+    Turbulence(turbulence::Turbulence),
+}
+
+/// Identifies the output of a primitive in a [`FilterCache`].
+///
+/// Besides the primitive's element and parameters, this has everything that determines
+/// where the output's pixels end up: the primitive subregion, the filter effects region,
+/// the transform to device pixels, and the size of the filter's surfaces.  It also has
+/// the rendering quality, since that changes how primitives like `feImage` render.
+#[derive(Clone, PartialEq)]
+struct FilterCacheKey {
+    node: Node,
+    params: CachedParams,
+    subregion: [Option<f64>; 4],
+    effects_region: Rect,
+    paffine: Transform,
+    surface_size: (i32, i32),
+    quality: RenderQuality,
+}
+
+/// Cache for the outputs of filter primitives that do not depend on their inputs.
+///
+/// Primitives like `feTurbulence`, `feFlood`, or `feImage` produce the same output every
+/// time a document gets rendered at the same scale.  A document can keep one of these
+/// caches to avoid recomputing them across renders.  The cache has a memory budget in
+/// bytes; when it is exceeded, the least recently used outputs get discarded.  A budget
+/// of zero disables the cache.
+#[derive(Default)]
+pub struct FilterCache {
+    budget: usize,
+    size: usize,
+
+    /// Number of times that a cached output was used.
+    #[cfg(test)]
+    hits: usize,
+
+    /// Cached outputs, with the most recently used at the back.
+    entries: VecDeque<(FilterCacheKey, FilterOutput)>,
+}
+
+impl FilterCache {
+    /// Creates an empty cache with a memory budget in bytes.
+    pub fn new(budget: usize) -> FilterCache {
+        FilterCache {
+            budget,
+            ..Default::default()
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.budget > 0
+    }
+
+    fn get(&mut self, key: &FilterCacheKey) -> Option<FilterOutput> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(index).unwrap();
+        let output = entry.1.clone();
+        self.entries.push_back(entry);
+
+        #[cfg(test)]
+        {
+            self.hits += 1;
+        }
+
+        Some(output)
+    }
+
+    /// Returns the number of times that a cached output was used instead of rendering a
+    /// primitive.  This is not reset by [`FilterCache::clear`].
+    #[cfg(test)]
+    pub(crate) fn hits(&self) -> usize {
+        self.hits
+    }
+
+    fn insert(&mut self, key: FilterCacheKey, output: FilterOutput) {
+        let output_size = memory_size(&output);
+
+        if output_size > self.budget {
+            return;
+        }
+
+        while self.size + output_size > self.budget {
+            let (_, evicted) = self.entries.pop_front().unwrap();
+            self.size -= memory_size(&evicted);
+        }
+
+        self.size += output_size;
+        self.entries.push_back((key, output));
+    }
+
+    /// Discards all the cached outputs.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+    }
+}
+
+fn memory_size(output: &FilterOutput) -> usize {
+    output.surface.stride() as usize * output.surface.height() as usize
 }
 
 impl Primitive {
@@ -464,8 +659,43 @@ pub fn render(
             for user_space_primitive in &filter.primitives {
                 let start = Instant::now();
 
-                match render_primitive(user_space_primitive, &filter_ctx, acquired_nodes, draw_ctx)
-                {
+                let cache_key = if acquired_nodes.filter_cache().borrow().is_enabled() {
+                    user_space_primitive.cache_key(&filter_ctx)
+                } else {
+                    None
+                };
+
+                let cached_output = cache_key
+                    .as_ref()
+                    .and_then(|key| acquired_nodes.filter_cache().borrow_mut().get(key));
+
+                let result = if let Some(output) = cached_output {
+                    rsvg_log!(
+                        session,
+                        "(using cached output for filter primitive {})",
+                        user_space_primitive.params.name()
+                    );
+
+                    Ok(output)
+                } else {
+                    let result = render_primitive(
+                        user_space_primitive,
+                        &filter_ctx,
+                        acquired_nodes,
+                        draw_ctx,
+                    );
+
+                    if let (Some(key), Ok(output)) = (cache_key, &result) {
+                        acquired_nodes
+                            .filter_cache()
+                            .borrow_mut()
+                            .insert(key, output.clone());
+                    }
+
+                    result
+                };
+
+                match result {
                     Ok(output) => {
                         let elapsed = start.elapsed();
                         rsvg_log!(
@@ -582,6 +812,7 @@ mod tests {
     use super::*;
 
     use crate::color::{Color, RGBA};
+    use crate::coord_units::CoordUnits;
     use crate::document::Document;
     use crate::dpi::Dpi;
     use crate::node::NodeBorrow;
@@ -661,4 +892,50 @@ mod tests {
             }
         );
     }
+
+    fn spec_with_filter_res(filter_res: Option<NumberOptionalNumber<u32>>) -> FilterSpec {
+        FilterSpec {
+            name: String::from("test"),
+            user_space_filter: UserSpaceFilter {
+                rect: Rect::new(0.0, 0.0, 100.0, 50.0),
+                filter_units: CoordUnits::UserSpaceOnUse,
+                primitive_units: CoordUnits::UserSpaceOnUse,
+                filter_res,
+            },
+            primitives: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn filter_res_scale_lowers_resolution() {
+        let region = Rect::new(0.0, 0.0, 100.0, 50.0);
+
+        assert_eq!(spec_with_filter_res(None).filter_res_scale(&region), None);
+
+        assert_eq!(
+            spec_with_filter_res(Some(NumberOptionalNumber(50, 10))).filter_res_scale(&region),
+            Some((0.5, 0.2))
+        );
+
+        assert_eq!(
+            spec_with_filter_res(Some(NumberOptionalNumber(0, 0))).filter_res_scale(&region),
+            Some((0.0, 0.0))
+        );
+
+        assert_eq!(
+            spec_with_filter_res(Some(NumberOptionalNumber(50, 10)))
+                .filter_res_scale(&Rect::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn filter_res_scale_does_not_raise_resolution() {
+        let region = Rect::new(0.0, 0.0, 100.0, 50.0);
+
+        assert_eq!(
+            spec_with_filter_res(Some(NumberOptionalNumber(100_000, 25))).filter_res_scale(&region),
+            Some((1.0, 0.5))
+        );
+    }
 }
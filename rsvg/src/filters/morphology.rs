@@ -13,8 +13,7 @@ use crate::properties::ColorInterpolationFilters;
 use crate::rect::IRect;
 use crate::session::Session;
 use crate::surface_utils::{
-    EdgeMode, ImageSurfaceDataExt, Pixel,
-    iterators::{PixelRectangle, Pixels},
+    EdgeMode, ImageSurfaceDataExt, Pixel, iterators::PixelRectangle,
     shared_surface::ExclusiveImageSurface,
 };
 use crate::xml::Attributes;
@@ -120,8 +119,10 @@ impl Morphology {
             input_1.surface().surface_type(),
         )?;
 
-        surface.modify(&mut |data, stride| {
-            for (x, y, _pixel) in Pixels::within(input_1.surface(), bounds) {
+        surface.modify_rows(ctx.thread_pool(), bounds, &|row, y| {
+            for x in bounds.x_range() {
+                let x = x as u32;
+
                 // Compute the kernel rectangle bounds.
                 let kernel_bounds = IRect::new(
                     (f64::from(x) - rx).floor() as i32,
@@ -157,7 +158,7 @@ impl Morphology {
                     output_pixel.a = op(output_pixel.a, pixel.a);
                 }
 
-                data.set_pixel(stride, output_pixel, x, y);
+                row.set_pixel(0, output_pixel, x, 0);
             }
         });
 
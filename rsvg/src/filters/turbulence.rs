@@ -54,7 +54,7 @@ pub struct FeTurbulence {
 }
 
 /// Resolved `feTurbulence` primitive for rendering.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Turbulence {
     base_frequency: NumberOptionalNumber<f64>,
     num_octaves: i32,
@@ -412,38 +412,38 @@ impl Turbulence {
             surface_type,
         )?;
 
-        surface.modify(&mut |data, stride| {
-            for y in bounds.y_range() {
-                for x in bounds.x_range() {
-                    let point = affine.transform_point(f64::from(x), f64::from(y));
-                    let point = [point.0, point.1];
-
-                    let generate = |color_channel| {
-                        let v = noise_generator.turbulence(
-                            color_channel,
-                            point,
-                            f64::from(x - bounds.x0),
-                            f64::from(y - bounds.y0),
-                        );
+        surface.modify_rows(ctx.thread_pool(), bounds, &|row, y| {
+            let y = y as i32;
+
+            for x in bounds.x_range() {
+                let point = affine.transform_point(f64::from(x), f64::from(y));
+                let point = [point.0, point.1];
 
-                        let v = match self.type_ {
-                            NoiseType::FractalNoise => (v * 255.0 + 255.0) / 2.0,
-                            NoiseType::Turbulence => v * 255.0,
-                        };
+                let generate = |color_channel| {
+                    let v = noise_generator.turbulence(
+                        color_channel,
+                        point,
+                        f64::from(x - bounds.x0),
+                        f64::from(y - bounds.y0),
+                    );
 
-                        (clamp(v, 0.0, 255.0) + 0.5) as u8
+                    let v = match self.type_ {
+                        NoiseType::FractalNoise => (v * 255.0 + 255.0) / 2.0,
+                        NoiseType::Turbulence => v * 255.0,
                     };
 
-                    let pixel = Pixel {
-                        r: generate(0),
-                        g: generate(1),
-                        b: generate(2),
-                        a: generate(3),
-                    }
-                    .premultiply();
+                    (clamp(v, 0.0, 255.0) + 0.5) as u8
+                };
 
-                    data.set_pixel(stride, pixel, x as u32, y as u32);
+                let pixel = Pixel {
+                    r: generate(0),
+                    g: generate(1),
+                    b: generate(2),
+                    a: generate(3),
                 }
+                .premultiply();
+
+                row.set_pixel(0, pixel, x as u32, 0);
             }
         });
 
@@ -6,12 +6,14 @@
 use cast::{f64, u16};
 use cssparser::{Parser, Token};
 
+use crate::calc::CalcNode;
 use crate::error::*;
 use crate::length::*;
 use crate::parse_identifiers;
 use crate::parsers::{Parse, finite_f32};
 use crate::properties::ComputedValues;
 use crate::property_defs::{FontStretch, FontStyle, FontVariant};
+use crate::property_macros::ToCssValue;
 
 /// `font` shorthand property.
 ///
@@ -196,7 +198,7 @@ pub enum FontSize {
 impl FontSize {
     pub fn value(&self) -> Length<Both> {
         match self {
-            FontSize::Value(s) => *s,
+            FontSize::Value(s) => s.clone(),
             _ => unreachable!(),
         }
     }
@@ -206,19 +208,23 @@ impl FontSize {
 
         let parent = v.font_size().value();
 
+        // For the root element itself, this is the initial value of font-size.
+        let root = v.root_font_size().value();
+
         // The parent must already have resolved to an absolute unit
         assert!(
             parent.unit != LengthUnit::Percent
                 && parent.unit != LengthUnit::Em
                 && parent.unit != LengthUnit::Ex
+                && parent.unit != LengthUnit::Rem
         );
 
         use FontSize::*;
 
         #[rustfmt::skip]
         let new_size = match self {
-            Smaller => Length::<Both>::new(parent.length / 1.2,  parent.unit),
-            Larger  => Length::<Both>::new(parent.length * 1.2,  parent.unit),
+            Smaller => parent.scaled(1.0 / 1.2),
+            Larger  => parent.scaled(1.2),
             XXSmall => Length::<Both>::new(compute_points(-3.0), LengthUnit::In),
             XSmall  => Length::<Both>::new(compute_points(-2.0), LengthUnit::In),
             Small   => Length::<Both>::new(compute_points(-1.0), LengthUnit::In),
@@ -227,21 +233,38 @@ impl FontSize {
             XLarge  => Length::<Both>::new(compute_points(2.0),  LengthUnit::In),
             XXLarge => Length::<Both>::new(compute_points(3.0),  LengthUnit::In),
 
+            // Resolve the relative terms against the parent, so that the computed
+            // value only has absolute units.
+            Value(s) if s.is_calc() => {
+                let calc = s.to_calc_node().map_lengths(&|l, unit| match unit {
+                    LengthUnit::Percent | LengthUnit::Em => parent.scaled(l).to_calc_node(),
+                    LengthUnit::Ex => parent.scaled(l / 2.0).to_calc_node(),
+                    LengthUnit::Rem => root.scaled(l).to_calc_node(),
+                    _ => CalcNode::Length(l, unit),
+                });
+
+                Length::<Both>::from_calc_node(calc)
+            }
+
             Value(s) if s.unit == LengthUnit::Percent => {
-                Length::<Both>::new(parent.length * s.length, parent.unit)
+                parent.scaled(s.length)
             }
 
             Value(s) if s.unit == LengthUnit::Em => {
-                Length::<Both>::new(parent.length * s.length, parent.unit)
+                parent.scaled(s.length)
             }
 
             Value(s) if s.unit == LengthUnit::Ex => {
                 // FIXME: it would be nice to know the actual Ex-height
                 // of the font.
-                Length::<Both>::new(parent.length * s.length / 2.0, parent.unit)
+                parent.scaled(s.length / 2.0)
+            }
+
+            Value(s) if s.unit == LengthUnit::Rem => {
+                root.scaled(s.length)
             }
 
-            Value(s) => *s,
+            Value(s) => s.clone(),
         };
 
         FontSize::Value(new_size)
@@ -275,6 +298,25 @@ impl Parse for FontSize {
     }
 }
 
+impl ToCssValue for FontSize {
+    fn to_css_value(&self) -> Option<String> {
+        let keyword = match *self {
+            FontSize::Smaller => "smaller",
+            FontSize::Larger => "larger",
+            FontSize::XXSmall => "xx-small",
+            FontSize::XSmall => "x-small",
+            FontSize::Small => "small",
+            FontSize::Medium => "medium",
+            FontSize::Large => "large",
+            FontSize::XLarge => "x-large",
+            FontSize::XXLarge => "xx-large",
+            FontSize::Value(ref l) => return l.to_css_value(),
+        };
+
+        Some(String::from(keyword))
+    }
+}
+
 /// `font-weight` property.
 ///
 /// CSS Fonts 3: <https://www.w3.org/TR/css-fonts-3/#propdef-font-weight>
@@ -315,6 +357,18 @@ impl Parse for FontWeight {
     }
 }
 
+impl ToCssValue for FontWeight {
+    fn to_css_value(&self) -> Option<String> {
+        match *self {
+            FontWeight::Normal => Some(String::from("normal")),
+            FontWeight::Bold => Some(String::from("bold")),
+            FontWeight::Bolder => Some(String::from("bolder")),
+            FontWeight::Lighter => Some(String::from("lighter")),
+            FontWeight::Weight(w) => Some(w.to_string()),
+        }
+    }
+}
+
 impl FontWeight {
     #[rustfmt::skip]
     pub fn compute(&self, v: &Self) -> Self {
@@ -381,7 +435,7 @@ pub enum LetterSpacing {
 impl LetterSpacing {
     pub fn value(&self) -> Length<Horizontal> {
         match self {
-            LetterSpacing::Value(s) => *s,
+            LetterSpacing::Value(s) => s.clone(),
             _ => unreachable!(),
         }
     }
@@ -389,7 +443,7 @@ impl LetterSpacing {
     pub fn compute(&self) -> Self {
         let spacing = match self {
             LetterSpacing::Normal => Length::<Horizontal>::new(0.0, LengthUnit::Px),
-            LetterSpacing::Value(s) => *s,
+            LetterSpacing::Value(s) => s.clone(),
         };
 
         LetterSpacing::Value(spacing)
@@ -414,6 +468,15 @@ impl Parse for LetterSpacing {
     }
 }
 
+impl ToCssValue for LetterSpacing {
+    fn to_css_value(&self) -> Option<String> {
+        match *self {
+            LetterSpacing::Normal => Some(String::from("normal")),
+            LetterSpacing::Value(ref l) => l.to_css_value(),
+        }
+    }
+}
+
 /// `line-height` property.
 ///
 /// CSS2: <https://www.w3.org/TR/CSS2/visudet.html#propdef-line-height>
@@ -428,7 +491,7 @@ pub enum LineHeight {
 impl LineHeight {
     pub fn value(&self) -> Length<Both> {
         match self {
-            LineHeight::Length(l) => *l,
+            LineHeight::Length(l) => l.clone(),
             _ => unreachable!(),
         }
     }
@@ -440,10 +503,10 @@ impl LineHeight {
             LineHeight::Normal => LineHeight::Length(font_size),
 
             LineHeight::Number(f) | LineHeight::Percentage(f) => {
-                LineHeight::Length(Length::new(font_size.length * f64(f), font_size.unit))
+                LineHeight::Length(font_size.scaled(f64(f)))
             }
 
-            LineHeight::Length(l) => LineHeight::Length(l),
+            LineHeight::Length(ref l) => LineHeight::Length(l.clone()),
         }
     }
 
@@ -486,6 +549,19 @@ impl Parse for LineHeight {
     }
 }
 
+impl ToCssValue for LineHeight {
+    fn to_css_value(&self) -> Option<String> {
+        match *self {
+            LineHeight::Normal => Some(String::from("normal")),
+            LineHeight::Number(n) => f64::from(n).to_css_value(),
+            LineHeight::Length(ref l) => l.to_css_value(),
+            LineHeight::Percentage(p) => {
+                f64::from(p * 100.0).to_css_value().map(|p| format!("{p}%"))
+            }
+        }
+    }
+}
+
 /// `font-family` property.
 ///
 /// SVG1.1: <https://www.w3.org/TR/SVG11/text.html#FontFamilyProperty>
@@ -525,6 +601,12 @@ impl Parse for FontFamily {
     }
 }
 
+impl ToCssValue for FontFamily {
+    fn to_css_value(&self) -> Option<String> {
+        Some(self.0.clone())
+    }
+}
+
 impl FontFamily {
     pub fn as_str(&self) -> &str {
         &self.0
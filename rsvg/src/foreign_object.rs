@@ -0,0 +1,755 @@
+//! The `foreignObject` element, with a minimal subset of XHTML.
+//!
+//! Diagramming tools like draw.io and Mermaid put the labels for their shapes inside
+//! `<foreignObject>` elements, as simple XHTML content.  Librsvg does not have an HTML
+//! engine, but it can lay out a small subset of XHTML flow content with Pango, inside
+//! the viewport given by the element's `x`/`y`/`width`/`height`:
+//!
+//! * Text content of elements in the XHTML namespace.  Elements from other namespaces
+//!   are ignored, along with their children.
+//!
+//! * Elements with `display: block`, which includes `<div>` and `<p>` per the user agent
+//!   stylesheet, start a new paragraph.  Paragraphs are stacked vertically from the top
+//!   of the viewport.
+//!
+//! * `<br>` forces a line break.
+//!
+//! * All other elements, like `<span>`, `<b>`, `<i>`, are inline and only contribute their
+//!   styles to the text inside them.
+//!
+//! * The supported properties are the `font` properties, `color`, `text-decoration`,
+//!   `letter-spacing`, `text-align`, `line-height`, `white-space`, `direction`,
+//!   `display: none`, and `visibility`.  With `text-align: justify`, text decorations
+//!   are not drawn under the spaces between words.
+//!
+//! Everything else, like margins, padding, borders, backgrounds, and flex or table
+//! layout, is ignored.
+
+use markup5ever::{local_name, ns};
+use std::ops::Range;
+
+use crate::color::resolve_color;
+use crate::document::AcquiredNodes;
+use crate::drawing_ctx::{DrawingCtx, FontOptions, Viewport, create_pango_context};
+use crate::element::{DrawResult, ElementTrait};
+use crate::error::*;
+use crate::layout::{self, FontProperties, Layer, LayerKind, StackingContext, Stroke, TextSpan};
+use crate::length::*;
+use crate::node::{CascadedValues, Node, NodeBorrow};
+use crate::paint_server::UserSpacePaintSource;
+use crate::properties::{ComputedValues, Direction, Display, TextAlign, WhiteSpace, XmlLang};
+use crate::rect::Rect;
+use crate::rsvg_log;
+use crate::session::Session;
+use crate::text::{GlyphOrientation, PangoUnits, add_pango_attributes, compute_text_box};
+use crate::transform::ValidTransform;
+use crate::unit_interval::UnitInterval;
+
+/// The `<foreignObject>` element.
+///
+/// Note that its x/y/width/height are properties in SVG2, so they are
+/// defined as part of [the properties machinery](properties.rs).
+#[derive(Default)]
+pub struct ForeignObject;
+
+impl ElementTrait for ForeignObject {
+    fn layout(
+        &self,
+        node: &Node,
+        acquired_nodes: &mut AcquiredNodes<'_>,
+        cascaded: &CascadedValues<'_>,
+        viewport: &Viewport,
+        draw_ctx: &mut DrawingCtx,
+    ) -> Result<Option<Layer>, Box<InternalRenderingError>> {
+        let values = cascaded.get();
+        let params = NormalizeParams::new(values, viewport);
+
+        let x = values.x().0.to_user(&params);
+        let y = values.y().0.to_user(&params);
+
+        // "auto" computes to zero for foreignObject, which disables rendering.
+        let w = match values.width().0 {
+            LengthOrAuto::Length(l) => l.to_user(&params),
+            LengthOrAuto::Auto => 0.0,
+        };
+        let h = match values.height().0 {
+            LengthOrAuto::Length(l) => l.to_user(&params),
+            LengthOrAuto::Auto => 0.0,
+        };
+
+        if !(w > 0.0 && h > 0.0) {
+            return Ok(None);
+        }
+
+        let rect = Rect::new(x, y, x + w, y + h);
+
+        let mut builder = FlowBuilder::new(values);
+        collect_flow_content(&mut builder, node, cascaded);
+        let paragraphs = builder.finish();
+
+        let layout_context = LayoutContext {
+            font_options: draw_ctx.get_font_options(),
+            viewport: *viewport,
+            session: draw_ctx.session().clone(),
+        };
+
+        let mut spans = Vec::new();
+        let mut paragraph_y = rect.y0;
+
+        for paragraph in &paragraphs {
+            paragraph_y +=
+                layout_paragraph(paragraph, &rect, paragraph_y, &layout_context, &mut spans);
+        }
+
+        let extents = spans
+            .iter()
+            .filter_map(|span| span.extents)
+            .reduce(|a, b| a.union(&b));
+
+        let clip_rect = if values.overflow().overflow_allowed() {
+            None
+        } else {
+            Some(rect)
+        };
+
+        let elt = node.borrow_element();
+        let stacking_ctx = StackingContext::new(
+            draw_ctx,
+            acquired_nodes,
+            &elt,
+            values.transform(),
+            clip_rect,
+            values,
+            viewport,
+        );
+
+        Ok(Some(Layer {
+            kind: LayerKind::Text(Box::new(layout::Text { spans, extents })),
+            stacking_ctx,
+        }))
+    }
+
+    fn draw(
+        &self,
+        node: &Node,
+        acquired_nodes: &mut AcquiredNodes<'_>,
+        cascaded: &CascadedValues<'_>,
+        viewport: &Viewport,
+        draw_ctx: &mut DrawingCtx,
+        clipping: bool,
+    ) -> DrawResult {
+        let layer = self.layout(node, acquired_nodes, cascaded, viewport, draw_ctx)?;
+
+        if let Some(layer) = layer {
+            draw_ctx.draw_layer(&layer, acquired_nodes, clipping, viewport)
+        } else {
+            Ok(viewport.empty_bbox())
+        }
+    }
+}
+
+/// The state of a layout operation for the content of a `<foreignObject>`.
+struct LayoutContext {
+    /// Font options from the DrawingCtx.
+    font_options: FontOptions,
+
+    /// For normalizing lengths.
+    viewport: Viewport,
+
+    /// Session metadata for the document
+    session: Session,
+}
+
+/// A run of text with uniform styles, as byte offsets into its paragraph's text.
+struct Segment {
+    start: usize,
+    end: usize,
+    values: ComputedValues,
+}
+
+/// Text from a block of XHTML flow content; it gets laid out as a single Pango layout.
+struct Paragraph {
+    /// Computed values of the block element that contains the paragraph.
+    values: ComputedValues,
+
+    text: String,
+
+    segments: Vec<Segment>,
+
+    /// Whether the last character is a space produced by collapsing white space, which
+    /// must be removed if a line break follows it.
+    trailing_collapsible_space: bool,
+}
+
+impl Paragraph {
+    fn new(values: &ComputedValues) -> Paragraph {
+        Paragraph {
+            values: values.clone(),
+            text: String::new(),
+            segments: Vec::new(),
+            trailing_collapsible_space: false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Appends text, processing white space per the `white-space` property.
+    ///
+    /// <https://www.w3.org/TR/css-text-3/#white-space-processing>
+    fn push_text(&mut self, text: &str, values: &ComputedValues) {
+        let start = self.text.len();
+
+        match values.white_space() {
+            WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::BreakSpaces => {
+                self.text.push_str(text);
+                self.trailing_collapsible_space = false;
+            }
+
+            white_space => {
+                let preserve_newlines = white_space == WhiteSpace::PreLine;
+
+                for c in text.chars() {
+                    match c {
+                        '\n' if preserve_newlines => {
+                            self.trim_trailing_space();
+                            self.text.push('\n');
+                        }
+
+                        ' ' | '\t' | '\n' | '\r' => {
+                            if !(self.text.is_empty() || self.text.ends_with([' ', '\n'])) {
+                                self.text.push(' ');
+                                self.trailing_collapsible_space = true;
+                            }
+                        }
+
+                        c => {
+                            self.text.push(c);
+                            self.trailing_collapsible_space = false;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.push_segment(start, values);
+    }
+
+    /// Appends a forced line break, as for the `<br>` element.
+    fn push_line_break(&mut self, values: &ComputedValues) {
+        self.trim_trailing_space();
+
+        let start = self.text.len();
+        self.text.push('\n');
+        self.push_segment(start, values);
+    }
+
+    fn push_segment(&mut self, start: usize, values: &ComputedValues) {
+        let end = self.text.len();
+
+        if end > start {
+            self.segments.push(Segment {
+                start,
+                end,
+                values: values.clone(),
+            });
+        }
+    }
+
+    fn trim_trailing_space(&mut self) {
+        if !self.trailing_collapsible_space {
+            return;
+        }
+
+        self.text.pop();
+        self.trailing_collapsible_space = false;
+
+        let len = self.text.len();
+
+        if let Some(segment) = self.segments.last_mut() {
+            segment.end = segment.end.min(len);
+
+            if segment.start >= segment.end {
+                self.segments.pop();
+            }
+        }
+    }
+}
+
+/// Accumulates the paragraphs from XHTML flow content.
+struct FlowBuilder {
+    paragraphs: Vec<Paragraph>,
+    current: Paragraph,
+}
+
+impl FlowBuilder {
+    fn new(values: &ComputedValues) -> FlowBuilder {
+        FlowBuilder {
+            paragraphs: Vec::new(),
+            current: Paragraph::new(values),
+        }
+    }
+
+    /// Finishes the current paragraph and starts a new one within a block with the
+    /// specified `values`.
+    fn break_paragraph(&mut self, values: &ComputedValues) {
+        let mut paragraph = std::mem::replace(&mut self.current, Paragraph::new(values));
+        paragraph.trim_trailing_space();
+
+        if !paragraph.is_empty() {
+            self.paragraphs.push(paragraph);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Paragraph> {
+        self.current.trim_trailing_space();
+
+        if !self.current.is_empty() {
+            self.paragraphs.push(self.current);
+        }
+
+        self.paragraphs
+    }
+}
+
+/// Walks the XHTML children of `node` and accumulates their text into paragraphs.
+fn collect_flow_content(builder: &mut FlowBuilder, node: &Node, cascaded: &CascadedValues<'_>) {
+    for child in node.children() {
+        if child.is_chars() {
+            let text = child.borrow_chars().get_string();
+            builder.current.push_text(&text, cascaded.get());
+            continue;
+        }
+
+        if !child.is_element() || child.borrow_element().element_name().ns != ns!(html) {
+            continue;
+        }
+
+        let child_cascaded = cascaded.clone_with_node(&child);
+        let child_values = child_cascaded.get();
+
+        if child.borrow_element().element_name().local == local_name!("br") {
+            builder.current.push_line_break(child_values);
+            continue;
+        }
+
+        match child_values.display() {
+            Display::None => (),
+
+            Display::Block => {
+                builder.break_paragraph(child_values);
+                collect_flow_content(builder, &child, &child_cascaded);
+                builder.break_paragraph(cascaded.get());
+            }
+
+            _ => collect_flow_content(builder, &child, &child_cascaded),
+        }
+    }
+}
+
+/// Computes the Pango alignment for the `text-align` property.
+///
+/// Returns whether the paragraph should be justified as well.
+fn pango_alignment(text_align: TextAlign, direction: Direction) -> (pango::Alignment, bool) {
+    let (start, end) = match direction {
+        Direction::Ltr => (pango::Alignment::Left, pango::Alignment::Right),
+        Direction::Rtl => (pango::Alignment::Right, pango::Alignment::Left),
+    };
+
+    match text_align {
+        TextAlign::Start => (start, false),
+        TextAlign::End => (end, false),
+        TextAlign::Left => (pango::Alignment::Left, false),
+        TextAlign::Right => (pango::Alignment::Right, false),
+        TextAlign::Center => (pango::Alignment::Center, false),
+        TextAlign::Justify => (start, true),
+    }
+}
+
+fn create_paragraph_pango_context(
+    font_options: &FontOptions,
+    xml_lang: &XmlLang,
+    direction: pango::Direction,
+) -> pango::Context {
+    let pango_context = create_pango_context(font_options);
+
+    if let XmlLang(Some(ref lang)) = *xml_lang {
+        pango_context.set_language(Some(&pango::Language::from_string(lang.as_str())));
+    }
+
+    pango_context.set_base_dir(direction);
+
+    pango_context
+}
+
+/// Lays out a paragraph inside the `rect` of the `<foreignObject>`, with its top at `y`.
+///
+/// Appends one text span for each run of text to `spans`, and returns the height of
+/// the paragraph.
+fn layout_paragraph(
+    paragraph: &Paragraph,
+    rect: &Rect,
+    y: f64,
+    layout_context: &LayoutContext,
+    spans: &mut Vec<TextSpan>,
+) -> f64 {
+    let pango_scale = f64::from(pango::SCALE);
+
+    let values = &paragraph.values;
+    let params = NormalizeParams::new(values, &layout_context.viewport);
+    let direction = values.direction();
+
+    let pango_context = create_paragraph_pango_context(
+        &layout_context.font_options,
+        &values.xml_lang(),
+        pango::Direction::from(direction),
+    );
+
+    let layout = pango::Layout::new(&pango_context);
+    let attr_list = pango::AttrList::new();
+
+    for (i, segment) in paragraph.segments.iter().enumerate() {
+        let segment_params = NormalizeParams::new(&segment.values, &layout_context.viewport);
+        let props = FontProperties::new(&segment.values, &segment_params);
+
+        let (Some(font_size), Some(letter_spacing)) = (
+            PangoUnits::from_pixels(props.font_size),
+            PangoUnits::from_pixels(props.letter_spacing),
+        ) else {
+            rsvg_log!(
+                &layout_context.session,
+                "font-size {} or letter-spacing {} is out of bounds; ignoring span",
+                props.font_size,
+                props.letter_spacing
+            );
+            continue;
+        };
+
+        add_pango_attributes(
+            &attr_list,
+            &props,
+            GlyphOrientation::Horizontal,
+            segment.start,
+            segment.end,
+            font_size,
+            letter_spacing,
+        );
+
+        // Pango splits runs where the attributes change, even if the fonts are the same.
+        // We never draw this layout directly, so use the foreground alpha to make sure
+        // that each run comes from a single segment.
+        let alpha = if i % 2 == 0 { u16::MAX } else { u16::MAX - 1 };
+        let mut attr: pango::Attribute = pango::AttrInt::new_foreground_alpha(alpha).upcast();
+        attr.set_start_index(u32::try_from(segment.start).expect("index must fit in u32"));
+        attr.set_end_index(u32::try_from(segment.end).expect("index must fit in u32"));
+        attr_list.insert(attr);
+    }
+
+    layout.set_attributes(Some(&attr_list));
+    layout.set_text(&paragraph.text);
+    layout.set_auto_dir(false);
+
+    let (alignment, justify) = pango_alignment(values.text_align(), direction);
+    layout.set_alignment(alignment);
+    layout.set_justify(justify);
+
+    let wraps = !matches!(values.white_space(), WhiteSpace::Pre | WhiteSpace::NoWrap);
+
+    let width = if wraps {
+        PangoUnits::from_pixels(rect.width())
+    } else {
+        None
+    };
+
+    let has_width = width.is_some();
+
+    if let Some(width) = width {
+        layout.set_width(width.0);
+        layout.set_wrap(pango::WrapMode::WordChar);
+    }
+
+    // Pango's line spacing is a factor of the height of each line's font, not of the
+    // font size, so this is exact only for fonts whose ascent + descent is 1em.
+    let font_size = values.font_size().to_user(&params);
+    let line_height = values.line_height().to_user(&params);
+    if font_size > 0.0 {
+        layout.set_line_spacing((line_height / font_size) as f32);
+    }
+
+    let (_, logical) = layout.extents();
+    let layout_width = f64::from(logical.width()) / pango_scale;
+    let layout_height = f64::from(logical.height()) / pango_scale;
+
+    // Without a width, Pango aligns lines with respect to the widest one, so we have
+    // to align the whole layout within the viewport ourselves.
+    let x = if has_width {
+        rect.x0
+    } else {
+        let available = rect.width() - layout_width;
+
+        let offset = match alignment {
+            pango::Alignment::Center => available / 2.0,
+            pango::Alignment::Right => available,
+            _ => 0.0,
+        };
+
+        rect.x0 + offset - f64::from(logical.x()) / pango_scale
+    };
+
+    let mut iter = layout.iter();
+
+    loop {
+        if let Some(run) = iter.run_readonly() {
+            let (_, run_logical) = iter.run_extents();
+            let baseline = y + f64::from(iter.baseline()) / pango_scale;
+
+            let item = run.item();
+            let start = item.offset() as usize;
+            let end = start + item.length() as usize;
+            let is_rtl = item.analysis().level() % 2 == 1;
+
+            // Justification stretches the spaces between words, but each run gets laid
+            // out again by itself without them.  So, lay out each word of a justified run
+            // by itself, where the paragraph's layout put it.
+            let pieces = if justify {
+                word_ranges(&paragraph.text, start..end)
+            } else {
+                vec![start..end]
+            };
+
+            for piece in pieces {
+                let leading_x = if justify {
+                    let pos = layout
+                        .index_to_pos(i32::try_from(piece.start).expect("index must fit in i32"));
+                    f64::from(pos.x())
+                } else if is_rtl {
+                    f64::from(run_logical.x() + run_logical.width())
+                } else {
+                    f64::from(run_logical.x())
+                };
+
+                if let Some(span) = layout_run(
+                    paragraph,
+                    piece,
+                    is_rtl,
+                    x + leading_x / pango_scale,
+                    baseline,
+                    layout_context,
+                ) {
+                    spans.push(span);
+                }
+            }
+        }
+
+        if !iter.next_run() {
+            break;
+        }
+    }
+
+    layout_height
+}
+
+/// Splits a `range` of the `text` into the ranges of its words, without the white space
+/// between them.
+fn word_ranges(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut word_start = None;
+
+    for (i, c) in text[range.clone()].char_indices() {
+        let i = range.start + i;
+
+        match (c.is_whitespace(), word_start) {
+            (true, Some(start)) => {
+                words.push(start..i);
+                word_start = None;
+            }
+
+            (false, None) => word_start = Some(i),
+
+            _ => (),
+        }
+    }
+
+    if let Some(start) = word_start {
+        words.push(start..range.end);
+    }
+
+    words
+}
+
+/// Creates a text span for a run of text from a paragraph's layout.
+///
+/// The run is laid out again by itself with the styles of the segment it comes from,
+/// and positioned so that its leading edge is at `leading_x` and its baseline is at
+/// `baseline`.  The leading edge is the left one for left-to-right text, and the right one
+/// for right-to-left text.
+fn layout_run(
+    paragraph: &Paragraph,
+    range: Range<usize>,
+    is_rtl: bool,
+    leading_x: f64,
+    baseline: f64,
+    layout_context: &LayoutContext,
+) -> Option<TextSpan> {
+    let segment = paragraph
+        .segments
+        .iter()
+        .find(|s| s.start <= range.start && range.start < s.end)?;
+
+    let text = &paragraph.text[range];
+    let values = &segment.values;
+    let params = NormalizeParams::new(values, &layout_context.viewport);
+    let props = FontProperties::new(values, &params);
+
+    let font_size = PangoUnits::from_pixels(props.font_size)?;
+    let letter_spacing = PangoUnits::from_pixels(props.letter_spacing)?;
+
+    let direction = if is_rtl {
+        pango::Direction::Rtl
+    } else {
+        pango::Direction::Ltr
+    };
+
+    let pango_context =
+        create_paragraph_pango_context(&layout_context.font_options, &props.xml_lang, direction);
+
+    let layout = pango::Layout::new(&pango_context);
+    let attr_list = pango::AttrList::new();
+    add_pango_attributes(
+        &attr_list,
+        &props,
+        GlyphOrientation::Horizontal,
+        0,
+        text.len(),
+        font_size,
+        letter_spacing,
+    );
+
+    layout.set_attributes(Some(&attr_list));
+    layout.set_text(text);
+    layout.set_auto_dir(false);
+
+    let x = if is_rtl {
+        let (width, _) = layout.size();
+        leading_x - f64::from(width) / f64::from(pango::SCALE)
+    } else {
+        leading_x
+    };
+
+    let y = baseline - f64::from(layout.baseline()) / f64::from(pango::SCALE);
+    let gravity = pango::Gravity::South;
+    let extents = compute_text_box(&layout, x, y, gravity);
+
+    // In XHTML, text is painted with the `color` property, not with `fill`.
+    let current_color = values.color().0;
+    let fill_paint = UserSpacePaintSource::SolidColor(resolve_color(
+        &current_color,
+        UnitInterval::clamp(1.0),
+        &current_color,
+    ));
+
+    Some(TextSpan {
+        layout,
+        gravity,
+        glyph_transform: ValidTransform::default(),
+        extents,
+        is_visible: values.is_visible(),
+        x,
+        y,
+        paint_order: values.paint_order(),
+        stroke: Stroke::new(values, &params),
+        stroke_paint: UserSpacePaintSource::None,
+        fill_paint,
+        text_rendering: values.text_rendering(),
+        link_target: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::document::Document;
+
+    fn collect_paragraphs(svg: &'static [u8]) -> Vec<String> {
+        let document = Document::load_from_bytes(svg);
+        let node = document.lookup_internal_node("fo").unwrap();
+        let cascaded = CascadedValues::new_from_node(&node);
+
+        let mut builder = FlowBuilder::new(cascaded.get());
+        collect_flow_content(&mut builder, &node, &cascaded);
+
+        builder
+            .finish()
+            .into_iter()
+            .map(|paragraph| paragraph.text)
+            .collect()
+    }
+
+    #[test]
+    fn collapses_white_space_and_breaks_blocks() {
+        assert_eq!(
+            collect_paragraphs(
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <foreignObject id="fo" width="100" height="100">
+    <div xmlns="http://www.w3.org/1999/xhtml">
+      Hello   <span>big
+      world</span>
+      <p>second <b>line</b>  <br/>  third</p>
+      tail
+    </div>
+  </foreignObject>
+</svg>
+"#
+            ),
+            vec!["Hello big world", "second line\nthird", "tail"]
+        );
+    }
+
+    #[test]
+    fn honors_display_none_and_preformatted_text() {
+        assert_eq!(
+            collect_paragraphs(
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <foreignObject id="fo" width="100" height="100">
+    <div xmlns="http://www.w3.org/1999/xhtml">
+      <span style="display: none">hidden</span>
+      <div style="white-space: pre">a  b</div>
+      <svg:text xmlns:svg="http://www.w3.org/2000/svg">ignored</svg:text>
+    </div>
+  </foreignObject>
+</svg>
+"#
+            ),
+            vec!["a  b"]
+        );
+    }
+
+    #[test]
+    fn splits_words_without_white_space() {
+        let text = "ab  cd\nef ";
+
+        assert_eq!(word_ranges(text, 0..text.len()), vec![0..2, 4..6, 7..9]);
+        assert_eq!(word_ranges(text, 1..5), vec![1..2, 4..5]);
+        assert_eq!(word_ranges(text, 2..4), vec![]);
+    }
+
+    #[test]
+    fn alignment_depends_on_direction() {
+        assert_eq!(
+            pango_alignment(TextAlign::Start, Direction::Rtl),
+            (pango::Alignment::Right, false)
+        );
+        assert_eq!(
+            pango_alignment(TextAlign::Left, Direction::Rtl),
+            (pango::Alignment::Left, false)
+        );
+        assert_eq!(
+            pango_alignment(TextAlign::Justify, Direction::Ltr),
+            (pango::Alignment::Left, true)
+        );
+    }
+}
@@ -3,7 +3,7 @@
 use cssparser::Parser;
 use markup5ever::{ExpandedName, LocalName, Namespace, expanded_name, local_name, ns};
 
-use crate::color::{Color, resolve_color};
+use crate::color::{Color, color_to_rgba, resolve_color};
 use crate::coord_units;
 use crate::coord_units::CoordUnits;
 use crate::document::{AcquiredNodes, NodeId, NodeStack};
@@ -18,6 +18,7 @@ use crate::parsers::{Parse, ParseValue};
 use crate::rect::{Rect, rect_to_transform};
 use crate::rsvg_log;
 use crate::session::Session;
+use crate::surface_utils::Pixel;
 use crate::transform::{Transform, TransformAttribute};
 use crate::unit_interval::UnitInterval;
 use crate::xml::Attributes;
@@ -78,7 +79,7 @@ impl ElementTrait for Stop {
 /// These will be composed together with UnreseolvedVariant from fallback
 /// nodes (referenced with e.g. `<linearGradient xlink:href="#fallback">`) to form
 /// a final, resolved Variant.
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 enum UnresolvedVariant {
     Linear {
         x1: Option<Length<Horizontal>>,
@@ -134,6 +135,19 @@ pub enum GradientVariant {
         fy: f64,
         fr: f64,
     },
+
+    /// Only produced by the `conic-gradient()` CSS function; there is no SVG element for it.
+    ///
+    /// The gradient goes clockwise around `(cx, cy)`, starting at `angle` radians from
+    /// the upward direction.  The offsets of the stops are relative to the `(start, end)`
+    /// interval, in fractions of a full turn.
+    Conic {
+        cx: f64,
+        cy: f64,
+        angle: f64,
+        start: f64,
+        end: f64,
+    },
 }
 
 impl UnresolvedVariant {
@@ -167,7 +181,7 @@ impl UnresolvedVariant {
     }
 
     fn is_resolved(&self) -> bool {
-        match *self {
+        match self {
             UnresolvedVariant::Linear { x1, y1, x2, y2 } => {
                 x1.is_some() && y1.is_some() && x2.is_some() && y2.is_some()
             }
@@ -191,7 +205,7 @@ impl UnresolvedVariant {
     }
 
     fn resolve_from_fallback(&self, fallback: &UnresolvedVariant) -> UnresolvedVariant {
-        match (*self, *fallback) {
+        match (self.clone(), fallback.clone()) {
             (
                 UnresolvedVariant::Linear { x1, y1, x2, y2 },
                 UnresolvedVariant::Linear {
@@ -233,14 +247,14 @@ impl UnresolvedVariant {
                 fr: fr.or(f_fr),
             },
 
-            _ => *self, // If variants are of different types, then nothing to resolve
+            _ => self.clone(), // If variants are of different types, then nothing to resolve
         }
     }
 
     // https://www.w3.org/TR/SVG/pservers.html#LinearGradients
     // https://www.w3.org/TR/SVG/pservers.html#RadialGradients
     fn resolve_from_defaults(&self) -> UnresolvedVariant {
-        match self {
+        match self.clone() {
             UnresolvedVariant::Linear { x1, y1, x2, y2 } => UnresolvedVariant::Linear {
                 x1: x1.or_else(|| Some(Length::<Horizontal>::parse_str("0%").unwrap())),
                 y1: y1.or_else(|| Some(Length::<Vertical>::parse_str("0%").unwrap())),
@@ -261,8 +275,8 @@ impl UnresolvedVariant {
                 let r = r.or_else(|| Some(Length::<Both>::parse_str("50%").unwrap()));
 
                 // fx and fy fall back to the presentational value of cx and cy
-                let fx = fx.or(cx);
-                let fy = fy.or(cy);
+                let fx = fx.or_else(|| cx.clone());
+                let fy = fy.or_else(|| cy.clone());
                 let fr = fr.or_else(|| Some(Length::<Both>::parse_str("0%").unwrap()));
 
                 UnresolvedVariant::Radial {
@@ -345,6 +359,63 @@ pub struct UserSpaceGradient {
     pub variant: GradientVariant,
 }
 
+impl UserSpaceGradient {
+    /// Computes the color at an offset along the gradient, after applying the spread method.
+    ///
+    /// This is for gradients that Cairo cannot render by itself.  The colors of the stops
+    /// are interpolated with premultiplied alpha, and the result is premultiplied.
+    pub fn color_at(&self, offset: f64) -> Pixel {
+        let offset = match self.spread {
+            SpreadMethod::Pad => offset.clamp(0.0, 1.0),
+            SpreadMethod::Repeat => offset.rem_euclid(1.0),
+            SpreadMethod::Reflect => {
+                let t = offset.rem_euclid(2.0);
+                if t > 1.0 { 2.0 - t } else { t }
+            }
+        };
+
+        let premultiplied = |stop: &ColorStop| {
+            let rgba = color_to_rgba(&stop.color);
+            let a = f64::from(rgba.alpha);
+
+            [
+                f64::from(rgba.red) / 255.0 * a,
+                f64::from(rgba.green) / 255.0 * a,
+                f64::from(rgba.blue) / 255.0 * a,
+                a,
+            ]
+        };
+
+        let color = match self.stops.iter().position(|s| s.offset.0 > offset) {
+            None => match self.stops.last() {
+                Some(stop) => premultiplied(stop),
+                None => [0.0; 4],
+            },
+
+            Some(0) => premultiplied(&self.stops[0]),
+
+            Some(i) => {
+                let (a, b) = (&self.stops[i - 1], &self.stops[i]);
+                let t = (offset - a.offset.0) / (b.offset.0 - a.offset.0);
+
+                let ca = premultiplied(a);
+                let cb = premultiplied(b);
+
+                [0, 1, 2, 3].map(|c| ca[c] + (cb[c] - ca[c]) * t)
+            }
+        };
+
+        let to_u8 = |v: f64| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+
+        Pixel {
+            r: to_u8(color[0]),
+            g: to_u8(color[1]),
+            b: to_u8(color[2]),
+            a: to_u8(color[3]),
+        }
+    }
+}
+
 impl UnresolvedGradient {
     fn into_resolved(self) -> ResolvedGradient {
         assert!(self.is_resolved());
@@ -485,10 +556,10 @@ struct Unresolved {
 impl LinearGradient {
     fn get_unresolved_variant(&self) -> UnresolvedVariant {
         UnresolvedVariant::Linear {
-            x1: self.x1,
-            y1: self.y1,
-            x2: self.x2,
-            y2: self.y2,
+            x1: self.x1.clone(),
+            y1: self.y1.clone(),
+            x2: self.x2.clone(),
+            y2: self.y2.clone(),
         }
     }
 }
@@ -496,12 +567,12 @@ impl LinearGradient {
 impl RadialGradient {
     fn get_unresolved_variant(&self) -> UnresolvedVariant {
         UnresolvedVariant::Radial {
-            cx: self.cx,
-            cy: self.cy,
-            r: self.r,
-            fx: self.fx,
-            fy: self.fy,
-            fr: self.fr,
+            cx: self.cx.clone(),
+            cy: self.cy.clone(),
+            r: self.r.clone(),
+            fx: self.fx.clone(),
+            fy: self.fy.clone(),
+            fr: self.fr.clone(),
         }
     }
 }
@@ -670,7 +741,7 @@ impl ResolvedGradient {
         let gradient_transform = self.transform.to_transform();
         let transform = transform.pre_transform(&gradient_transform).invert()?;
 
-        let variant = match self.variant {
+        let variant = match &self.variant {
             ResolvedGradientVariant::Linear { x1, y1, x2, y2 } => GradientVariant::Linear {
                 x1: x1.to_user(&params),
                 y1: y1.to_user(&params),
@@ -753,4 +824,41 @@ mod tests {
         let gradient = unresolved.gradient.resolve_from_defaults();
         assert!(gradient.is_resolved());
     }
+
+    #[test]
+    fn computes_color_at_offset() {
+        use crate::color::RGBA;
+
+        let stop = |offset, r, g, b, a| ColorStop {
+            offset: UnitInterval::clamp(offset),
+            color: Color::Rgba(RGBA::new(r, g, b, a)),
+        };
+
+        let mut gradient = UserSpaceGradient {
+            transform: Transform::identity(),
+            spread: SpreadMethod::Pad,
+            stops: vec![stop(0.25, 255, 0, 0, 1.0), stop(0.75, 0, 0, 255, 0.5)],
+            variant: GradientVariant::Conic {
+                cx: 0.0,
+                cy: 0.0,
+                angle: 0.0,
+                start: 0.0,
+                end: 1.0,
+            },
+        };
+
+        let red = Pixel::new(255, 0, 0, 255);
+        let blue = Pixel::new(0, 0, 128, 128);
+
+        assert_eq!(gradient.color_at(0.0), red);
+        assert_eq!(gradient.color_at(-1.0), red);
+        assert_eq!(gradient.color_at(1.0), blue);
+        assert_eq!(gradient.color_at(0.5), Pixel::new(128, 0, 64, 191));
+
+        gradient.spread = SpreadMethod::Repeat;
+        assert_eq!(gradient.color_at(1.25), red);
+
+        gradient.spread = SpreadMethod::Reflect;
+        assert_eq!(gradient.color_at(1.25), blue);
+    }
 }
@@ -0,0 +1,1111 @@
+//! CSS gradient functions - `linear-gradient()`, `radial-gradient()`, `conic-gradient()`.
+//!
+//! Unlike the `<linearGradient>` and `<radialGradient>` elements, which are paint servers
+//! referenced by id, these are written inline in property values, for example in
+//! `mask-image: linear-gradient(to right, black, transparent)` or
+//! `fill: conic-gradient(red, blue)`.  Their geometry is not
+//! given in user space; it is resolved against a box, like the object bounding box of
+//! the element that uses the gradient.
+//!
+//! CSS Images 3: <https://www.w3.org/TR/css-images-3/#gradients>
+//!
+//! A [`GradientFunction`] can resolve itself into a [`UserSpaceGradient`], which is the
+//! same type that the gradient elements produce, so that they can be rendered in the
+//! same way.  Conic gradients have no equivalent in Cairo, so the drawing code rasterizes
+//! them by itself.
+
+use std::f64::consts::PI;
+
+use cssparser::{Parser, Token, match_ignore_ascii_case};
+
+use crate::angle::Angle;
+use crate::color::{Color, resolve_color};
+use crate::error::*;
+use crate::gradient::{ColorStop, GradientVariant, SpreadMethod, UserSpaceGradient};
+use crate::length::*;
+use crate::parse_identifiers;
+use crate::parsers::{Parse, finite_f32};
+use crate::position::Position;
+use crate::rect::Rect;
+use crate::transform::Transform;
+use crate::unit_interval::UnitInterval;
+
+/// CSS gradient functions from CSS Images 3.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GradientFunction {
+    Linear(LinearGradientFunction),
+    Radial(RadialGradientFunction),
+    Conic(ConicGradientFunction),
+}
+
+/// A color stop in a gradient function.
+///
+/// The position is along the gradient ray; percentages are relative to the ray's length.
+/// Stops without a position get distributed evenly between their neighbors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientFunctionStop {
+    pub color: Color,
+    pub position: Option<Length<Both>>,
+}
+
+/// Direction of the gradient line in `linear-gradient()`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LinearGradientDirection {
+    /// An explicit angle, or `to <side>`.  `0deg` points up, and angles grow clockwise.
+    Angle(Angle),
+
+    /// `to <corner>`; the actual angle depends on the aspect ratio of the box.
+    Corner { right: bool, bottom: bool },
+}
+
+/// Parameters of `linear-gradient()` and `repeating-linear-gradient()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearGradientFunction {
+    pub direction: LinearGradientDirection,
+    pub stops: Vec<GradientFunctionStop>,
+    pub repeating: bool,
+}
+
+/// Ending shape of `radial-gradient()`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RadialGradientShape {
+    Circle,
+    Ellipse,
+}
+
+/// Size of the ending shape of `radial-gradient()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RadialGradientSize {
+    ClosestSide,
+    FarthestSide,
+    ClosestCorner,
+    FarthestCorner,
+    Circle(ULength<Both>),
+    Ellipse(ULength<Horizontal>, ULength<Vertical>),
+}
+
+/// Parameters of `radial-gradient()` and `repeating-radial-gradient()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialGradientFunction {
+    pub shape: RadialGradientShape,
+    pub size: RadialGradientSize,
+    pub position: Position,
+    pub stops: Vec<GradientFunctionStop>,
+    pub repeating: bool,
+}
+
+/// Parameters of `conic-gradient()` and `repeating-conic-gradient()`.
+///
+/// The positions of the color stops are angles.  They are stored as percentages of a
+/// full turn, so that `90deg` is the same as `25%`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConicGradientFunction {
+    pub from: Angle,
+    pub position: Position,
+    pub stops: Vec<GradientFunctionStop>,
+    pub repeating: bool,
+}
+
+fn parse_color_stops<'i, 't, P>(
+    parser: &mut Parser<'i, 't>,
+    parse_position: P,
+) -> Result<Vec<GradientFunctionStop>, ParseError<'i>>
+where
+    P: Fn(&mut Parser<'i, 't>) -> Result<Length<Both>, ParseError<'i>> + Copy,
+{
+    let loc = parser.current_source_location();
+
+    let mut stops = Vec::new();
+
+    loop {
+        let color = Color::parse(parser)?;
+        let position = parser.try_parse(parse_position).ok();
+
+        let has_position = position.is_some();
+
+        stops.push(GradientFunctionStop { color, position });
+
+        // "red 10% 20%" is a shortcut for two stops of the same color
+        if has_position && let Ok(second) = parser.try_parse(parse_position) {
+            stops.push(GradientFunctionStop {
+                color,
+                position: Some(second),
+            });
+        }
+
+        if parser.is_exhausted() {
+            break;
+        }
+
+        parser.expect_comma()?;
+    }
+
+    if stops.len() < 2 {
+        return Err(loc.new_custom_error(ValueErrorKind::parse_error(
+            "gradient functions need at least two color stops",
+        )));
+    }
+
+    Ok(stops)
+}
+
+fn parse_linear_direction<'i>(
+    parser: &mut Parser<'i, '_>,
+) -> Result<LinearGradientDirection, ParseError<'i>> {
+    if let Ok(angle) = parser.try_parse(Angle::parse) {
+        return Ok(LinearGradientDirection::Angle(angle));
+    }
+
+    let loc = parser.current_source_location();
+
+    parser.expect_ident_matching("to")?;
+
+    // (horizontal, vertical); true means right or bottom, respectively.
+    let mut horizontal = None;
+    let mut vertical = None;
+
+    for i in 0..2 {
+        let side = if i == 0 {
+            parse_side(parser)?
+        } else if let Ok(side) = parser.try_parse(parse_side) {
+            side
+        } else {
+            break;
+        };
+
+        let (slot, value) = match side {
+            Side::Left => (&mut horizontal, false),
+            Side::Right => (&mut horizontal, true),
+            Side::Top => (&mut vertical, false),
+            Side::Bottom => (&mut vertical, true),
+        };
+
+        if slot.is_some() {
+            return Err(
+                loc.new_custom_error(ValueErrorKind::parse_error("expected a side or a corner"))
+            );
+        }
+
+        *slot = Some(value);
+    }
+
+    let direction = match (horizontal, vertical) {
+        (Some(right), Some(bottom)) => LinearGradientDirection::Corner { right, bottom },
+        (Some(false), None) => LinearGradientDirection::Angle(Angle::from_degrees(270.0)),
+        (Some(true), None) => LinearGradientDirection::Angle(Angle::from_degrees(90.0)),
+        (None, Some(false)) => LinearGradientDirection::Angle(Angle::from_degrees(0.0)),
+        (None, Some(true)) => LinearGradientDirection::Angle(Angle::from_degrees(180.0)),
+        (None, None) => unreachable!("at least one side was parsed"),
+    };
+
+    Ok(direction)
+}
+
+#[derive(Copy, Clone)]
+enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+fn parse_side<'i>(parser: &mut Parser<'i, '_>) -> Result<Side, ParseError<'i>> {
+    Ok(parse_identifiers!(
+        parser,
+        "left" => Side::Left,
+        "right" => Side::Right,
+        "top" => Side::Top,
+        "bottom" => Side::Bottom,
+    )?)
+}
+
+fn parse_linear<'i>(
+    parser: &mut Parser<'i, '_>,
+    repeating: bool,
+) -> Result<GradientFunction, ParseError<'i>> {
+    let direction = if let Ok(direction) = parser.try_parse(parse_linear_direction) {
+        parser.expect_comma()?;
+        direction
+    } else {
+        // "to bottom"
+        LinearGradientDirection::Angle(Angle::from_degrees(180.0))
+    };
+
+    let stops = parse_color_stops(parser, Length::<Both>::parse)?;
+
+    Ok(GradientFunction::Linear(LinearGradientFunction {
+        direction,
+        stops,
+        repeating,
+    }))
+}
+
+fn parse_radial_size_keyword<'i>(
+    parser: &mut Parser<'i, '_>,
+) -> Result<RadialGradientSize, ParseError<'i>> {
+    Ok(parse_identifiers!(
+        parser,
+        "closest-side" => RadialGradientSize::ClosestSide,
+        "farthest-side" => RadialGradientSize::FarthestSide,
+        "closest-corner" => RadialGradientSize::ClosestCorner,
+        "farthest-corner" => RadialGradientSize::FarthestCorner,
+    )?)
+}
+
+fn parse_radial_shape<'i>(
+    parser: &mut Parser<'i, '_>,
+) -> Result<RadialGradientShape, ParseError<'i>> {
+    Ok(parse_identifiers!(
+        parser,
+        "circle" => RadialGradientShape::Circle,
+        "ellipse" => RadialGradientShape::Ellipse,
+    )?)
+}
+
+fn parse_radial_size<'i>(
+    parser: &mut Parser<'i, '_>,
+) -> Result<RadialGradientSize, ParseError<'i>> {
+    if let Ok(size) = parser.try_parse(parse_radial_size_keyword) {
+        return Ok(size);
+    }
+
+    let first = ULength::<Both>::parse(parser)?;
+
+    if let Ok(second) = parser.try_parse(ULength::<Vertical>::parse) {
+        Ok(RadialGradientSize::Ellipse(first.cast(), second))
+    } else {
+        Ok(RadialGradientSize::Circle(first))
+    }
+}
+
+/// Parses the `[<ending-shape> || <size>]? [at <position>]?` part of `radial-gradient()`.
+fn parse_radial_geometry<'i>(
+    parser: &mut Parser<'i, '_>,
+) -> Result<(RadialGradientShape, RadialGradientSize, Position), ParseError<'i>> {
+    let loc = parser.current_source_location();
+
+    let mut shape = parser.try_parse(parse_radial_shape).ok();
+    let size = parser.try_parse(parse_radial_size).ok();
+    if shape.is_none() {
+        shape = parser.try_parse(parse_radial_shape).ok();
+    }
+
+    let position = if parser.try_parse(|p| p.expect_ident_matching("at")).is_ok() {
+        Some(Position::parse(parser)?)
+    } else {
+        None
+    };
+
+    if shape.is_none() && size.is_none() && position.is_none() {
+        return Err(loc.new_custom_error(ValueErrorKind::parse_error(
+            "expected a shape, a size, or a position",
+        )));
+    }
+
+    let shape = match (shape, &size) {
+        (Some(shape), _) => shape,
+        (None, Some(RadialGradientSize::Circle(_))) => RadialGradientShape::Circle,
+        (None, _) => RadialGradientShape::Ellipse,
+    };
+
+    let size = size.unwrap_or(RadialGradientSize::FarthestCorner);
+
+    let valid = match (shape, &size) {
+        (RadialGradientShape::Circle, RadialGradientSize::Ellipse(..)) => false,
+        (RadialGradientShape::Circle, RadialGradientSize::Circle(l)) => !l.has_percentage(),
+        (RadialGradientShape::Ellipse, RadialGradientSize::Circle(_)) => false,
+        _ => true,
+    };
+
+    if !valid {
+        return Err(loc.new_custom_error(ValueErrorKind::parse_error(
+            "invalid size for the gradient shape",
+        )));
+    }
+
+    Ok((shape, size, position.unwrap_or_else(Position::center)))
+}
+
+fn parse_radial<'i>(
+    parser: &mut Parser<'i, '_>,
+    repeating: bool,
+) -> Result<GradientFunction, ParseError<'i>> {
+    let (shape, size, position) = if let Ok(geometry) = parser.try_parse(parse_radial_geometry) {
+        parser.expect_comma()?;
+        geometry
+    } else {
+        (
+            RadialGradientShape::Ellipse,
+            RadialGradientSize::FarthestCorner,
+            Position::center(),
+        )
+    };
+
+    let stops = parse_color_stops(parser, Length::<Both>::parse)?;
+
+    Ok(GradientFunction::Radial(RadialGradientFunction {
+        shape,
+        size,
+        position,
+        stops,
+        repeating,
+    }))
+}
+
+/// Parses the position of a color stop in `conic-gradient()`, as a fraction of a turn.
+///
+/// This does not use [`Angle::parse`], since that one normalizes angles to less than a
+/// full turn, and `360deg` is a perfectly good position for the last stop.
+fn parse_conic_stop_position<'i>(
+    parser: &mut Parser<'i, '_>,
+) -> Result<Length<Both>, ParseError<'i>> {
+    let loc = parser.current_source_location();
+
+    let turns = match *parser.next()? {
+        Token::Percentage { unit_value, .. } => {
+            f64::from(finite_f32(unit_value).map_err(|e| loc.new_custom_error(e))?)
+        }
+
+        Token::Number { value, .. } if value == 0.0 => 0.0,
+
+        Token::Dimension {
+            value, ref unit, ..
+        } => {
+            let value = f64::from(finite_f32(value).map_err(|e| loc.new_custom_error(e))?);
+
+            match_ignore_ascii_case! { unit.as_ref(),
+                "deg" => value / 360.0,
+                "grad" => value / 400.0,
+                "rad" => value / (2.0 * PI),
+                "turn" => value,
+                _ => return Err(loc.new_custom_error(ValueErrorKind::parse_error(
+                    "expected an angle or a percentage",
+                ))),
+            }
+        }
+
+        ref t => return Err(loc.new_unexpected_token_error(t.clone())),
+    };
+
+    Ok(Length::new(turns, LengthUnit::Percent))
+}
+
+/// Parses the `[from <angle>]? [at <position>]?` part of `conic-gradient()`.
+fn parse_conic_geometry<'i>(
+    parser: &mut Parser<'i, '_>,
+) -> Result<(Angle, Position), ParseError<'i>> {
+    let loc = parser.current_source_location();
+
+    let from = if parser
+        .try_parse(|p| p.expect_ident_matching("from"))
+        .is_ok()
+    {
+        Some(Angle::parse(parser)?)
+    } else {
+        None
+    };
+
+    let position = if parser.try_parse(|p| p.expect_ident_matching("at")).is_ok() {
+        Some(Position::parse(parser)?)
+    } else {
+        None
+    };
+
+    if from.is_none() && position.is_none() {
+        return Err(loc.new_custom_error(ValueErrorKind::parse_error(
+            "expected an angle or a position",
+        )));
+    }
+
+    Ok((
+        from.unwrap_or_else(|| Angle::new(0.0)),
+        position.unwrap_or_else(Position::center),
+    ))
+}
+
+fn parse_conic<'i>(
+    parser: &mut Parser<'i, '_>,
+    repeating: bool,
+) -> Result<GradientFunction, ParseError<'i>> {
+    let (from, position) = if let Ok(geometry) = parser.try_parse(parse_conic_geometry) {
+        parser.expect_comma()?;
+        geometry
+    } else {
+        (Angle::new(0.0), Position::center())
+    };
+
+    let stops = parse_color_stops(parser, parse_conic_stop_position)?;
+
+    Ok(GradientFunction::Conic(ConicGradientFunction {
+        from,
+        position,
+        stops,
+        repeating,
+    }))
+}
+
+#[derive(Copy, Clone)]
+enum Kind {
+    Linear,
+    Radial,
+    Conic,
+}
+
+impl Parse for GradientFunction {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
+        let loc = parser.current_source_location();
+        let name = parser.expect_function()?.clone();
+
+        let (kind, repeating) = match_ignore_ascii_case! { name.as_ref(),
+            "linear-gradient" => (Kind::Linear, false),
+            "repeating-linear-gradient" => (Kind::Linear, true),
+            "radial-gradient" => (Kind::Radial, false),
+            "repeating-radial-gradient" => (Kind::Radial, true),
+            "conic-gradient" => (Kind::Conic, false),
+            "repeating-conic-gradient" => (Kind::Conic, true),
+            _ => return Err(loc.new_unexpected_token_error(Token::Function(name))),
+        };
+
+        parser.parse_nested_block(|p| match kind {
+            Kind::Linear => parse_linear(p, repeating),
+            Kind::Radial => parse_radial(p, repeating),
+            Kind::Conic => parse_conic(p, repeating),
+        })
+    }
+}
+
+/// Resolves the positions of the color stops along a gradient ray.
+///
+/// Returns the stops, and the `(start, end)` interval along the ray, as fractions of its
+/// length, which the offsets of the stops are relative to.  The interval is `(0, 1)`
+/// unless some stops are outside the ray, or the gradient is repeating.
+fn resolve_stops(
+    stops: &[GradientFunctionStop],
+    ray_length: f64,
+    params: &NormalizeParams,
+    opacity: UnitInterval,
+    current_color: &Color,
+    repeating: bool,
+    allow_negative: bool,
+) -> (Vec<ColorStop>, f64, f64) {
+    let n = stops.len();
+    assert!(n >= 2);
+
+    let mut positions: Vec<Option<f64>> = stops
+        .iter()
+        .map(|s| {
+            s.position
+                .as_ref()
+                .map(|p| p.to_user_relative_to(params, ray_length) / ray_length)
+        })
+        .collect();
+
+    if positions[0].is_none() {
+        positions[0] = Some(0.0);
+    }
+
+    if positions[n - 1].is_none() {
+        positions[n - 1] = Some(1.0);
+    }
+
+    // A stop cannot come before the stops that precede it.
+    let mut max_so_far = f64::MIN;
+    for p in positions.iter_mut().flatten() {
+        if *p < max_so_far {
+            *p = max_so_far;
+        }
+        max_so_far = *p;
+    }
+
+    // Distribute the stops without a position evenly between the ones that have one.
+    let mut i = 1;
+    while i < n {
+        if positions[i].is_none() {
+            let start = i - 1;
+            let mut end = i;
+            while positions[end].is_none() {
+                end += 1;
+            }
+
+            let a = positions[start].unwrap();
+            let b = positions[end].unwrap();
+
+            for (j, p) in positions.iter_mut().enumerate().take(end).skip(i) {
+                *p = Some(a + (b - a) * (j - start) as f64 / (end - start) as f64);
+            }
+
+            i = end;
+        }
+
+        i += 1;
+    }
+
+    let positions: Vec<f64> = positions.into_iter().flatten().collect();
+
+    let (mut start, mut end) = if repeating {
+        (positions[0], positions[n - 1])
+    } else {
+        (positions[0].min(0.0), positions[n - 1].max(1.0))
+    };
+
+    if !allow_negative {
+        start = start.max(0.0);
+    }
+
+    if end - start <= f64::EPSILON {
+        end = start + 1.0;
+    }
+
+    let stops = stops
+        .iter()
+        .zip(positions)
+        .map(|(stop, p)| ColorStop {
+            offset: UnitInterval::clamp((p - start) / (end - start)),
+            color: resolve_color(&stop.color, opacity, current_color),
+        })
+        .collect();
+
+    (stops, start, end)
+}
+
+fn spread_method(repeating: bool) -> SpreadMethod {
+    if repeating {
+        SpreadMethod::Repeat
+    } else {
+        SpreadMethod::Pad
+    }
+}
+
+impl LinearGradientFunction {
+    fn to_user_space(
+        &self,
+        rect: &Rect,
+        params: &NormalizeParams,
+        opacity: UnitInterval,
+        current_color: &Color,
+    ) -> Option<UserSpaceGradient> {
+        let (w, h) = rect.size();
+
+        let angle = match self.direction {
+            LinearGradientDirection::Angle(a) => a.radians(),
+
+            // The gradient line is perpendicular to the diagonal that joins the two
+            // corners next to the one the gradient points to.
+            LinearGradientDirection::Corner { right, bottom } => {
+                let sx = if right { 1.0 } else { -1.0 };
+                let sy = if bottom { 1.0 } else { -1.0 };
+                (sx * h).atan2(-sy * w)
+            }
+        };
+
+        let (sin, cos) = angle.sin_cos();
+
+        // Length of the gradient line such that the corners of the box get the colors
+        // of the first and last stops.
+        let length = (w * sin).abs() + (h * cos).abs();
+        if length <= f64::EPSILON {
+            return None;
+        }
+
+        let (dx, dy) = (sin * length, -cos * length);
+        let (cx, cy) = ((rect.x0 + rect.x1) / 2.0, (rect.y0 + rect.y1) / 2.0);
+        let (x1, y1) = (cx - dx / 2.0, cy - dy / 2.0);
+
+        let (stops, start, end) = resolve_stops(
+            &self.stops,
+            length,
+            params,
+            opacity,
+            current_color,
+            self.repeating,
+            true,
+        );
+
+        Some(UserSpaceGradient {
+            transform: Transform::identity(),
+            spread: spread_method(self.repeating),
+            stops,
+            variant: GradientVariant::Linear {
+                x1: x1 + dx * start,
+                y1: y1 + dy * start,
+                x2: x1 + dx * end,
+                y2: y1 + dy * end,
+            },
+        })
+    }
+}
+
+impl RadialGradientFunction {
+    fn to_user_space(
+        &self,
+        rect: &Rect,
+        params: &NormalizeParams,
+        opacity: UnitInterval,
+        current_color: &Color,
+    ) -> Option<UserSpaceGradient> {
+        let (w, h) = rect.size();
+        let (px, py) = self.position.to_user(params, w, h);
+        let (cx, cy) = (rect.x0 + px, rect.y0 + py);
+
+        let (left, right) = ((cx - rect.x0).abs(), (rect.x1 - cx).abs());
+        let (top, bottom) = ((cy - rect.y0).abs(), (rect.y1 - cy).abs());
+
+        let closest = (left.min(right), top.min(bottom));
+        let farthest = (left.max(right), top.max(bottom));
+
+        let (rx, ry) = match (self.shape, &self.size) {
+            (RadialGradientShape::Circle, RadialGradientSize::ClosestSide) => {
+                let r = closest.0.min(closest.1);
+                (r, r)
+            }
+
+            (RadialGradientShape::Circle, RadialGradientSize::FarthestSide) => {
+                let r = farthest.0.max(farthest.1);
+                (r, r)
+            }
+
+            (RadialGradientShape::Circle, RadialGradientSize::ClosestCorner) => {
+                let r = closest.0.hypot(closest.1);
+                (r, r)
+            }
+
+            (RadialGradientShape::Circle, RadialGradientSize::FarthestCorner) => {
+                let r = farthest.0.hypot(farthest.1);
+                (r, r)
+            }
+
+            (RadialGradientShape::Ellipse, RadialGradientSize::ClosestSide) => closest,
+
+            (RadialGradientShape::Ellipse, RadialGradientSize::FarthestSide) => farthest,
+
+            // For ellipses, the corner sizes keep the aspect ratio of the corresponding
+            // side sizes, and scale the ellipse so it passes through the corner.
+            (RadialGradientShape::Ellipse, RadialGradientSize::ClosestCorner) => (
+                closest.0 * std::f64::consts::SQRT_2,
+                closest.1 * std::f64::consts::SQRT_2,
+            ),
+
+            (RadialGradientShape::Ellipse, RadialGradientSize::FarthestCorner) => (
+                farthest.0 * std::f64::consts::SQRT_2,
+                farthest.1 * std::f64::consts::SQRT_2,
+            ),
+
+            (_, RadialGradientSize::Circle(r)) => {
+                let r = r.to_user(params);
+                (r, r)
+            }
+
+            (_, RadialGradientSize::Ellipse(rx, ry)) => (
+                rx.to_user_relative_to(params, w),
+                ry.to_user_relative_to(params, h),
+            ),
+        };
+
+        if rx <= f64::EPSILON || ry <= f64::EPSILON {
+            return None;
+        }
+
+        let (stops, start, end) = resolve_stops(
+            &self.stops,
+            rx,
+            params,
+            opacity,
+            current_color,
+            self.repeating,
+            false,
+        );
+
+        // The gradient is computed as a circle of radius rx; squash it vertically to get
+        // the ellipse.  The pattern's transform goes from user space to gradient space.
+        let transform = Transform::new_translate(-cx, -cy)
+            .post_scale(1.0, rx / ry)
+            .post_translate(cx, cy);
+
+        Some(UserSpaceGradient {
+            transform,
+            spread: spread_method(self.repeating),
+            stops,
+            variant: GradientVariant::Radial {
+                cx,
+                cy,
+                r: rx * end,
+                fx: cx,
+                fy: cy,
+                fr: rx * start,
+            },
+        })
+    }
+}
+
+impl ConicGradientFunction {
+    fn to_user_space(
+        &self,
+        rect: &Rect,
+        params: &NormalizeParams,
+        opacity: UnitInterval,
+        current_color: &Color,
+    ) -> Option<UserSpaceGradient> {
+        if rect.is_empty() {
+            return None;
+        }
+
+        let (w, h) = rect.size();
+        let (px, py) = self.position.to_user(params, w, h);
+
+        // Stop positions are already fractions of a turn, so the "ray" has unit length.
+        let (stops, start, end) = resolve_stops(
+            &self.stops,
+            1.0,
+            params,
+            opacity,
+            current_color,
+            self.repeating,
+            true,
+        );
+
+        Some(UserSpaceGradient {
+            transform: Transform::identity(),
+            spread: spread_method(self.repeating),
+            stops,
+            variant: GradientVariant::Conic {
+                cx: rect.x0 + px,
+                cy: rect.y0 + py,
+                angle: self.from.radians(),
+                start,
+                end,
+            },
+        })
+    }
+}
+
+impl GradientFunction {
+    /// Resolves the gradient against a box, usually an object bounding box.
+    ///
+    /// The colors of the stops get multiplied by `opacity`, as with `fill-opacity`.
+    ///
+    /// Returns `None` if the gradient is degenerate, for example, if the box is empty.
+    pub fn to_user_space(
+        &self,
+        rect: &Rect,
+        params: &NormalizeParams,
+        opacity: UnitInterval,
+        current_color: &Color,
+    ) -> Option<UserSpaceGradient> {
+        match self {
+            GradientFunction::Linear(g) => g.to_user_space(rect, params, opacity, current_color),
+            GradientFunction::Radial(g) => g.to_user_space(rect, params, opacity, current_color),
+            GradientFunction::Conic(g) => g.to_user_space(rect, params, opacity, current_color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::RGBA;
+    use crate::dpi::Dpi;
+    use crate::drawing_ctx::Viewport;
+    use crate::float_eq_cairo::ApproxEqCairo;
+    use crate::properties::ComputedValues;
+
+    fn black() -> Color {
+        Color::Rgba(RGBA::new(0, 0, 0, 1.0))
+    }
+
+    fn transparent() -> Color {
+        Color::Rgba(RGBA::new(0, 0, 0, 0.0))
+    }
+
+    fn params() -> NormalizeParams {
+        let viewport = Viewport::new(Dpi::new(96.0, 96.0), 100.0, 100.0);
+        NormalizeParams::new(&ComputedValues::default(), &viewport)
+    }
+
+    #[test]
+    fn parses_linear_gradient() {
+        assert_eq!(
+            GradientFunction::parse_str("linear-gradient(black, transparent)").unwrap(),
+            GradientFunction::Linear(LinearGradientFunction {
+                direction: LinearGradientDirection::Angle(Angle::from_degrees(180.0)),
+                stops: vec![
+                    GradientFunctionStop {
+                        color: black(),
+                        position: None,
+                    },
+                    GradientFunctionStop {
+                        color: transparent(),
+                        position: None,
+                    },
+                ],
+                repeating: false,
+            })
+        );
+
+        assert_eq!(
+            GradientFunction::parse_str(
+                "repeating-linear-gradient(to left top, black 10px, transparent 50%)"
+            )
+            .unwrap(),
+            GradientFunction::Linear(LinearGradientFunction {
+                direction: LinearGradientDirection::Corner {
+                    right: false,
+                    bottom: false,
+                },
+                stops: vec![
+                    GradientFunctionStop {
+                        color: black(),
+                        position: Some(Length::new(10.0, LengthUnit::Px)),
+                    },
+                    GradientFunctionStop {
+                        color: transparent(),
+                        position: Some(Length::new(0.5, LengthUnit::Percent)),
+                    },
+                ],
+                repeating: true,
+            })
+        );
+
+        assert_eq!(
+            GradientFunction::parse_str("linear-gradient(to right, black 10% 20%, transparent)")
+                .unwrap(),
+            GradientFunction::Linear(LinearGradientFunction {
+                direction: LinearGradientDirection::Angle(Angle::from_degrees(90.0)),
+                stops: vec![
+                    GradientFunctionStop {
+                        color: black(),
+                        position: Some(Length::new(0.1, LengthUnit::Percent)),
+                    },
+                    GradientFunctionStop {
+                        color: black(),
+                        position: Some(Length::new(0.2, LengthUnit::Percent)),
+                    },
+                    GradientFunctionStop {
+                        color: transparent(),
+                        position: None,
+                    },
+                ],
+                repeating: false,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_radial_gradient() {
+        let stops = vec![
+            GradientFunctionStop {
+                color: black(),
+                position: None,
+            },
+            GradientFunctionStop {
+                color: transparent(),
+                position: None,
+            },
+        ];
+
+        assert_eq!(
+            GradientFunction::parse_str("radial-gradient(black, transparent)").unwrap(),
+            GradientFunction::Radial(RadialGradientFunction {
+                shape: RadialGradientShape::Ellipse,
+                size: RadialGradientSize::FarthestCorner,
+                position: Position::center(),
+                stops: stops.clone(),
+                repeating: false,
+            })
+        );
+
+        assert_eq!(
+            GradientFunction::parse_str("radial-gradient(10px at left top, black, transparent)")
+                .unwrap(),
+            GradientFunction::Radial(RadialGradientFunction {
+                shape: RadialGradientShape::Circle,
+                size: RadialGradientSize::Circle(ULength::new(10.0, LengthUnit::Px)),
+                position: Position::top_left(),
+                stops: stops.clone(),
+                repeating: false,
+            })
+        );
+
+        assert_eq!(
+            GradientFunction::parse_str(
+                "repeating-radial-gradient(closest-side circle, black, transparent)"
+            )
+            .unwrap(),
+            GradientFunction::Radial(RadialGradientFunction {
+                shape: RadialGradientShape::Circle,
+                size: RadialGradientSize::ClosestSide,
+                position: Position::center(),
+                stops: stops.clone(),
+                repeating: true,
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_gradient_function_yields_error() {
+        assert!(GradientFunction::parse_str("linear-gradient()").is_err());
+        assert!(GradientFunction::parse_str("linear-gradient(black)").is_err());
+        assert!(
+            GradientFunction::parse_str("linear-gradient(to left right, black, white)").is_err()
+        );
+        assert!(GradientFunction::parse_str("linear-gradient(to, black, white)").is_err());
+        assert!(
+            GradientFunction::parse_str("radial-gradient(circle 10px 20px, black, white)").is_err()
+        );
+        assert!(GradientFunction::parse_str("radial-gradient(circle 10%, black, white)").is_err());
+        assert!(GradientFunction::parse_str("conic-gradient(black 10px, white)").is_err());
+        assert!(GradientFunction::parse_str("conic-gradient(from, black, white)").is_err());
+    }
+
+    #[test]
+    fn resolves_linear_gradient_to_box() {
+        let g = GradientFunction::parse_str("linear-gradient(to right, black, transparent 50%)")
+            .unwrap();
+        let rect = Rect::new(10.0, 20.0, 110.0, 70.0);
+
+        let u = g
+            .to_user_space(&rect, &params(), UnitInterval::clamp(1.0), &black())
+            .unwrap();
+
+        match u.variant {
+            GradientVariant::Linear { x1, y1, x2, y2 } => {
+                assert!(x1.approx_eq_cairo(10.0));
+                assert!(y1.approx_eq_cairo(45.0));
+                assert!(x2.approx_eq_cairo(110.0));
+                assert!(y2.approx_eq_cairo(45.0));
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(u.stops.len(), 2);
+        assert!(u.stops[0].offset.0.approx_eq_cairo(0.0));
+        assert!(u.stops[1].offset.0.approx_eq_cairo(0.5));
+    }
+
+    #[test]
+    fn resolves_stops_without_positions() {
+        let g = GradientFunction::parse_str(
+            "repeating-linear-gradient(to bottom, black 20%, white, black, white 80%)",
+        )
+        .unwrap();
+        let rect = Rect::from_size(100.0, 100.0);
+
+        let u = g
+            .to_user_space(&rect, &params(), UnitInterval::clamp(1.0), &black())
+            .unwrap();
+
+        // The repeating interval is 20%..80%, so the offsets get remapped to it.
+        let offsets: Vec<f64> = u.stops.iter().map(|s| s.offset.0).collect();
+        for (offset, expected) in offsets.iter().zip([0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0]) {
+            assert!(offset.approx_eq_cairo(expected));
+        }
+
+        match u.variant {
+            GradientVariant::Linear { y1, y2, .. } => {
+                assert!(y1.approx_eq_cairo(20.0));
+                assert!(y2.approx_eq_cairo(80.0));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn resolves_radial_gradient_to_box() {
+        let g = GradientFunction::parse_str("radial-gradient(closest-side, black, transparent)")
+            .unwrap();
+        let rect = Rect::new(0.0, 0.0, 100.0, 50.0);
+
+        let u = g
+            .to_user_space(&rect, &params(), UnitInterval::clamp(1.0), &black())
+            .unwrap();
+
+        match u.variant {
+            GradientVariant::Radial { cx, cy, r, fr, .. } => {
+                assert!(cx.approx_eq_cairo(50.0));
+                assert!(cy.approx_eq_cairo(25.0));
+                assert!(r.approx_eq_cairo(50.0));
+                assert!(fr.approx_eq_cairo(0.0));
+            }
+            _ => unreachable!(),
+        }
+
+        // The ellipse is twice as wide as it is tall
+        let (_, y) = u.transform.transform_point(50.0, 0.0);
+        assert!(y.approx_eq_cairo(-25.0));
+    }
+
+    #[test]
+    fn parses_conic_gradient() {
+        assert_eq!(
+            GradientFunction::parse_str(
+                "conic-gradient(from 90deg at left top, black 90deg, transparent 0.5turn 100%)"
+            )
+            .unwrap(),
+            GradientFunction::Conic(ConicGradientFunction {
+                from: Angle::from_degrees(90.0),
+                position: Position::top_left(),
+                stops: vec![
+                    GradientFunctionStop {
+                        color: black(),
+                        position: Some(Length::new(0.25, LengthUnit::Percent)),
+                    },
+                    GradientFunctionStop {
+                        color: transparent(),
+                        position: Some(Length::new(0.5, LengthUnit::Percent)),
+                    },
+                    GradientFunctionStop {
+                        color: transparent(),
+                        position: Some(Length::new(1.0, LengthUnit::Percent)),
+                    },
+                ],
+                repeating: false,
+            })
+        );
+    }
+
+    #[test]
+    fn resolves_conic_gradient_to_box() {
+        let g = GradientFunction::parse_str(
+            "repeating-conic-gradient(at 25% 50%, black 0deg, transparent 90deg)",
+        )
+        .unwrap();
+        let rect = Rect::new(0.0, 0.0, 100.0, 50.0);
+
+        let u = g
+            .to_user_space(&rect, &params(), UnitInterval::clamp(0.5), &black())
+            .unwrap();
+
+        match u.variant {
+            GradientVariant::Conic {
+                cx,
+                cy,
+                angle,
+                start,
+                end,
+            } => {
+                assert!(cx.approx_eq_cairo(25.0));
+                assert!(cy.approx_eq_cairo(25.0));
+                assert!(angle.approx_eq_cairo(0.0));
+                assert!(start.approx_eq_cairo(0.0));
+                assert!(end.approx_eq_cairo(0.25));
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(u.stops[0].color, Color::Rgba(RGBA::new(0, 0, 0, 0.5)));
+    }
+
+    #[test]
+    fn empty_box_yields_no_gradient() {
+        let g = GradientFunction::parse_str("linear-gradient(black, white)").unwrap();
+        assert!(
+            g.to_user_space(
+                &Rect::default(),
+                &params(),
+                UnitInterval::clamp(1.0),
+                &black()
+            )
+            .is_none()
+        );
+    }
+}
@@ -5,6 +5,7 @@ use cssparser::Parser;
 use crate::document::NodeId;
 use crate::error::*;
 use crate::parsers::Parse;
+use crate::property_macros::ToCssValue;
 
 /// Used where style properties take a funciri or "none"
 ///
@@ -46,6 +47,15 @@ impl Parse for Iri {
     }
 }
 
+impl ToCssValue for Iri {
+    fn to_css_value(&self) -> Option<String> {
+        match *self {
+            Iri::None => Some(String::from("none")),
+            Iri::Resource(ref node_id) => Some(format!("url({node_id})")),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
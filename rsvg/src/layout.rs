@@ -11,20 +11,25 @@ use crate::cairo_path::CairoPath;
 use crate::color::Color;
 use crate::coord_units::CoordUnits;
 use crate::dasharray::Dasharray;
-use crate::document::{AcquiredNode, AcquiredNodes};
+use crate::document::{AcquiredNode, AcquiredNodes, Resource};
 use crate::drawing_ctx::{DrawingCtx, FontOptions, Viewport, pango_layout_to_cairo_path};
 use crate::element::{Element, ElementData};
 use crate::error::{AcquireError, InternalRenderingError};
 use crate::filter::FilterValueList;
+use crate::gradient_func::GradientFunction;
 use crate::length::*;
 use crate::node::*;
 use crate::paint_server::{PaintSource, UserSpacePaintSource};
 use crate::path_builder::Path as SvgPath;
+use crate::position::Position;
 use crate::properties::{
-    self, ClipRule, ComputedValues, Direction, FillRule, FontFamily, FontStretch, FontStyle,
-    FontVariant, FontWeight, ImageRendering, Isolation, MixBlendMode, Opacity, Overflow,
-    PaintOrder, ShapeRendering, StrokeDasharray, StrokeLinecap, StrokeLinejoin, StrokeMiterlimit,
-    TextDecoration, TextRendering, UnicodeBidi, VectorEffect, XmlLang,
+    self, BgSize, ClipRule, CompositingOperator, ComputedValues, Direction, EnableBackground,
+    FillRule, FontFamily, FontStretch, FontStyle, FontVariant, FontWeight, ImageRendering,
+    Isolation, MaskComposite, MaskImage, MaskMode, MaskPosition, MaskReference, MaskRepeat,
+    MaskSize, MaskingMode, MixBlendMode, Opacity, Overflow, PaintOrder, PointerEvents, RepeatStyle,
+    ShapeRendering, StrokeDasharray, StrokeLinecap, StrokeLinejoin, StrokeMiterlimit,
+    TextCombineUpright, TextDecoration, TextOrientation, TextRendering, UnicodeBidi, VectorEffect,
+    XmlLang, layer_value,
 };
 use crate::rect::Rect;
 use crate::rsvg_log;
@@ -50,16 +55,21 @@ use crate::{borrow_element_as, is_element_of_type};
 /// render an element as an isolated group.
 pub struct StackingContext {
     pub element_name: String,
+    pub element_id: Option<String>,
     pub transform: Transform,
     pub is_visible: bool,
     pub opacity: Opacity,
     pub filter: Option<Filter>,
+    pub backdrop_filter: Option<Filter>,
     pub clip_rect: Option<Rect>,
     pub clip_in_object_space: Option<Node>,
     pub clip_path: Option<ClipPath>,
-    pub mask: Option<Node>,
+    pub mask: Option<Mask>,
     pub mix_blend_mode: MixBlendMode,
     pub isolation: Isolation,
+    pub enable_background: EnableBackground,
+    pub vector_effect: VectorEffect,
+    pub pointer_events: PointerEvents,
 
     /// Target from an `<a>` element
     pub link_target: Option<String>,
@@ -174,6 +184,8 @@ pub struct Image {
 pub struct TextSpan {
     pub layout: pango::Layout,
     pub gravity: pango::Gravity,
+    /// Extra transformation for the glyphs, relative to `(x, y)`.
+    pub glyph_transform: ValidTransform,
     pub extents: Option<Rect>,
     pub is_visible: bool,
     pub x: f64,
@@ -205,6 +217,8 @@ pub struct FontProperties {
     pub font_size: f64,
     pub letter_spacing: f64,
     pub text_decoration: TextDecoration,
+    pub text_orientation: TextOrientation,
+    pub text_combine_upright: TextCombineUpright,
 }
 
 pub struct Filter {
@@ -215,6 +229,162 @@ pub struct Filter {
     pub normalize_values: NormalizeValues,
 }
 
+/// The mask layers of an element, from its `mask-*` properties.
+///
+/// Layers whose `mask-image` is `none` or an image that could not be loaded are
+/// transparent black layers; they still take part in `mask-composite`.  Layers that
+/// reference a missing element, or one that is not a `<mask>`, are not included.  The first
+/// layer is the topmost one.
+pub struct Mask {
+    pub layers: Vec<MaskLayer>,
+    pub current_color: Color,
+    pub normalize_values: NormalizeValues,
+}
+
+/// One layer of a [`Mask`], with the values of the `mask-*` longhands that apply to it.
+pub struct MaskLayer {
+    pub source: MaskSource,
+    pub mode: MaskingMode,
+    pub position: Position,
+    pub size: BgSize,
+    pub repeat: RepeatStyle,
+    pub composite: CompositingOperator,
+}
+
+/// The image for a [`MaskLayer`].
+pub enum MaskSource {
+    Element(Node),
+    Image(SharedImageSurface),
+    Gradient(Box<GradientFunction>),
+
+    /// A transparent black layer, for `none` or for an image that could not be loaded.
+    Transparent,
+}
+
+fn get_mask(
+    values: &ComputedValues,
+    acquired_nodes: &mut AcquiredNodes<'_>,
+    element: &Element,
+    session: &Session,
+) -> Option<Mask> {
+    let MaskImage(images) = values.mask_image();
+    let MaskMode(modes) = values.mask_mode();
+    let MaskPosition(positions) = values.mask_position();
+    let MaskSize(sizes) = values.mask_size();
+    let MaskRepeat(repeats) = values.mask_repeat();
+    let MaskComposite(composites) = values.mask_composite();
+
+    let element_name = format!("{element}");
+
+    // https://drafts.fxtf.org/css-masking/#the-mask-image
+    //
+    // "A value of none counts as a transparent black image layer."  So do images that
+    // can't be loaded.  References to elements that are missing or are not a <mask> get
+    // ignored, just like the mask attribute always did.
+    let mut has_image = false;
+
+    let layers: Vec<MaskLayer> = images
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, image)| {
+            let is_none = image == MaskReference::None;
+
+            let source = resolve_mask_source(image, acquired_nodes, &element_name, session)?;
+            has_image |= !is_none;
+
+            Some(MaskLayer {
+                source,
+                mode: layer_value(&modes, i),
+                position: layer_value(&positions, i),
+                size: layer_value(&sizes, i),
+                repeat: layer_value(&repeats, i),
+                composite: layer_value(&composites, i),
+            })
+        })
+        .collect();
+
+    // An element is only masked if at least one of its layers is not none or ignored.
+    if !has_image {
+        return None;
+    }
+
+    Some(Mask {
+        layers,
+        current_color: values.color().0,
+        normalize_values: NormalizeValues::new(values),
+    })
+}
+
+/// Resolves the image of a mask layer, or returns `None` if the layer must be ignored.
+fn resolve_mask_source(
+    image: MaskReference,
+    acquired_nodes: &mut AcquiredNodes<'_>,
+    element_name: &str,
+    session: &Session,
+) -> Option<MaskSource> {
+    match image {
+        MaskReference::None => Some(MaskSource::Transparent),
+
+        MaskReference::Element(mask_id) => {
+            if let Ok(acquired) = acquired_nodes.acquire(element_name, &mask_id) {
+                let node = acquired.get();
+                match *node.borrow_element_data() {
+                    ElementData::Mask(_) => Some(MaskSource::Element(node.clone())),
+
+                    _ => {
+                        rsvg_log!(
+                            session,
+                            "element {} references \"{}\" which is not a mask",
+                            element_name,
+                            mask_id
+                        );
+
+                        None
+                    }
+                }
+            } else {
+                rsvg_log!(
+                    session,
+                    "element {} references nonexistent mask \"{}\"",
+                    element_name,
+                    mask_id
+                );
+
+                None
+            }
+        }
+
+        MaskReference::Image(url) => match acquired_nodes.lookup_resource(&url) {
+            Ok(Resource::Image(surface)) => Some(MaskSource::Image(surface)),
+
+            Ok(Resource::Document(_)) => {
+                rsvg_log!(
+                    session,
+                    "element {} uses SVG document \"{}\" as a mask image, which is not supported",
+                    element_name,
+                    url
+                );
+
+                Some(MaskSource::Transparent)
+            }
+
+            Err(e) => {
+                rsvg_log!(
+                    session,
+                    "element {} could not load mask image \"{}\": {}",
+                    element_name,
+                    url,
+                    e
+                );
+
+                Some(MaskSource::Transparent)
+            }
+        },
+
+        MaskReference::Gradient(gradient) => Some(MaskSource::Gradient(gradient)),
+    }
+}
+
 fn get_filter(
     values: &ComputedValues,
     acquired_nodes: &mut AcquiredNodes<'_>,
@@ -234,6 +404,25 @@ fn get_filter(
     }
 }
 
+fn get_backdrop_filter(
+    values: &ComputedValues,
+    acquired_nodes: &mut AcquiredNodes<'_>,
+    referencing_element_name: &str,
+    session: &Session,
+) -> Option<Filter> {
+    match values.backdrop_filter() {
+        properties::BackdropFilter::None => None,
+
+        properties::BackdropFilter::List(filter_list) => Some(get_filter_from_filter_list(
+            filter_list,
+            acquired_nodes,
+            referencing_element_name,
+            values,
+            session,
+        )),
+    }
+}
+
 fn get_filter_from_filter_list(
     filter_list: FilterValueList,
     acquired_nodes: &mut AcquiredNodes<'_>,
@@ -381,7 +570,13 @@ fn path_from_text(
     let mut result = CairoPath::empty();
 
     for span in &text_layout.spans {
-        let path = pango_layout_to_cairo_path(span.x, span.y, &span.layout, span.gravity)?;
+        let path = pango_layout_to_cairo_path(
+            span.x,
+            span.y,
+            &span.layout,
+            span.gravity,
+            span.glyph_transform,
+        )?;
 
         // FIXME: does the text-rendering property (for text antialiasing) apply to clipping paths?
 
@@ -674,11 +869,13 @@ impl StackingContext {
         let font_options = draw_ctx.get_font_options();
 
         let element_name = format!("{element}");
+        let element_id = element.get_id().map(String::from);
 
         let is_visible = values.is_visible();
 
         let opacity;
         let filter;
+        let backdrop_filter;
 
         match element.element_data {
             // "The opacity, filter and display properties do not apply to the mask element"
@@ -686,11 +883,14 @@ impl StackingContext {
             ElementData::Mask(_) => {
                 opacity = Opacity(UnitInterval::clamp(1.0));
                 filter = None;
+                backdrop_filter = None;
             }
 
             _ => {
                 opacity = values.opacity();
                 filter = get_filter(values, acquired_nodes, &element_name, &session);
+                backdrop_filter =
+                    get_backdrop_filter(values, acquired_nodes, &element_name, &session);
             }
         }
 
@@ -711,50 +911,31 @@ impl StackingContext {
         let clip_in_object_space =
             resolve_object_space_clip_path(values, acquired_nodes, &element_name);
 
-        let mask = values.mask().0.get().and_then(|mask_id| {
-            if let Ok(acquired) = acquired_nodes.acquire(&element_name, mask_id) {
-                let node = acquired.get();
-                match *node.borrow_element_data() {
-                    ElementData::Mask(_) => Some(node.clone()),
-
-                    _ => {
-                        rsvg_log!(
-                            session,
-                            "element {} references \"{}\" which is not a mask",
-                            element,
-                            mask_id
-                        );
-
-                        None
-                    }
-                }
-            } else {
-                rsvg_log!(
-                    session,
-                    "element {} references nonexistent mask \"{}\"",
-                    element,
-                    mask_id
-                );
-
-                None
-            }
-        });
+        let mask = get_mask(values, acquired_nodes, element, &session);
 
         let mix_blend_mode = values.mix_blend_mode();
         let isolation = values.isolation();
+        let enable_background = values.enable_background();
+        let vector_effect = values.vector_effect();
+        let pointer_events = values.pointer_events();
 
         StackingContext {
             element_name,
+            element_id,
             transform,
             is_visible,
             opacity,
             filter,
+            backdrop_filter,
             clip_rect,
             clip_in_object_space,
             clip_path,
             mask,
             mix_blend_mode,
             isolation,
+            enable_background,
+            vector_effect,
+            pointer_events,
             link_target: None,
         }
     }
@@ -789,7 +970,9 @@ impl StackingContext {
             Isolation::Auto => {
                 let is_opaque = approx_eq!(f64, opacity, 1.0);
                 !(is_opaque
+                    && self.enable_background == EnableBackground::Accumulate
                     && self.filter.is_none()
+                    && self.backdrop_filter.is_none()
                     && self.mask.is_none()
                     && self.mix_blend_mode == MixBlendMode::Normal
                     && self.clip_in_object_space.is_none())
@@ -797,6 +980,17 @@ impl StackingContext {
             Isolation::Isolate => true,
         }
     }
+
+    /// Whether the element starts a new background for the `BackgroundImage` and
+    /// `BackgroundAlpha` filter inputs of its descendants.
+    ///
+    /// The deprecated `enable-background: new`, `isolation: isolate`, and `backdrop-filter`
+    /// create a backdrop root; see <https://drafts.fxtf.org/filter-effects-2/#BackdropRoot>.
+    pub fn is_backdrop_root(&self) -> bool {
+        matches!(self.enable_background, EnableBackground::New(_))
+            || self.isolation == Isolation::Isolate
+            || self.backdrop_filter.is_some()
+    }
 }
 
 impl LayerKind {
@@ -823,7 +1017,7 @@ impl Stroke {
         let line_cap = values.stroke_line_cap();
         let line_join = values.stroke_line_join();
         let dash_offset = values.stroke_dashoffset().0.to_user(params);
-        let non_scaling = values.vector_effect() == VectorEffect::NonScalingStroke;
+        let non_scaling = values.vector_effect().non_scaling_stroke;
 
         let dashes = match values.stroke_dasharray() {
             StrokeDasharray(Dasharray::None) => Box::new([]),
@@ -863,6 +1057,8 @@ impl FontProperties {
             font_size: values.font_size().to_user(params),
             letter_spacing: values.letter_spacing().to_user(params),
             text_decoration: values.text_decoration(),
+            text_orientation: values.resolved_text_orientation(),
+            text_combine_upright: values.text_combine_upright(),
         }
     }
 }
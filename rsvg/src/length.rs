@@ -43,16 +43,24 @@
 //! [diag]: https://www.w3.org/TR/SVG/coords.html#Units
 
 use cssparser::{Parser, Token, match_ignore_ascii_case};
+use std::cell::OnceCell;
 use std::f64::consts::*;
 use std::fmt;
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
 
+use crate::calc::{CalcNode, parse_math_function};
 use crate::dpi::Dpi;
 use crate::drawing_ctx::Viewport;
 use crate::error::*;
 use crate::parsers::{Parse, finite_f32};
-use crate::properties::{ComputedValues, FontSize, TextOrientation, WritingMode};
+use crate::properties::{
+    ComputedValues, FontSize, LineHeight, RootFontValues, TextOrientation, WritingMode,
+};
+use crate::property_macros::ToCssValue;
 use crate::rect::Rect;
+use crate::text::{FontSelection, FontUnits};
 use crate::viewbox::ViewBox;
 
 /// Units for length values.
@@ -90,6 +98,86 @@ pub enum LengthUnit {
 
     /// Advance measure of a '0' character (depends on the text orientation)
     Ch,
+
+    /// Font size of the root element
+    Rem,
+
+    /// 1% of the width of the outermost viewport
+    Vw,
+
+    /// 1% of the height of the outermost viewport
+    Vh,
+
+    /// 1% of the smaller dimension of the outermost viewport
+    Vmin,
+
+    /// 1% of the larger dimension of the outermost viewport
+    Vmax,
+
+    /// Line height of the current element
+    Lh,
+
+    /// Line height of the root element
+    Rlh,
+
+    /// Advance measure of a CJK ideograph in the current font
+    Ic,
+
+    /// Cap height of the current font
+    Cap,
+
+    /// Quarter-millimeters
+    Q,
+}
+
+impl LengthUnit {
+    /// Parses the unit of a CSS dimension, like the `px` in `5px`.
+    pub(crate) fn from_css_unit(unit: &str) -> Option<LengthUnit> {
+        Some(match_ignore_ascii_case! {unit,
+            "px" => LengthUnit::Px,
+            "em" => LengthUnit::Em,
+            "ex" => LengthUnit::Ex,
+            "in" => LengthUnit::In,
+            "cm" => LengthUnit::Cm,
+            "mm" => LengthUnit::Mm,
+            "pt" => LengthUnit::Pt,
+            "pc" => LengthUnit::Pc,
+            "ch" => LengthUnit::Ch,
+            "rem" => LengthUnit::Rem,
+            "vw" => LengthUnit::Vw,
+            "vh" => LengthUnit::Vh,
+            "vmin" => LengthUnit::Vmin,
+            "vmax" => LengthUnit::Vmax,
+            "lh" => LengthUnit::Lh,
+            "rlh" => LengthUnit::Rlh,
+            "ic" => LengthUnit::Ic,
+            "cap" => LengthUnit::Cap,
+            "q" => LengthUnit::Q,
+
+            _ => return None,
+        })
+    }
+
+    /// Whether lengths in this unit can be returned from the public APIs.
+    ///
+    /// The units starting at [`LengthUnit::Rem`] were added in librsvg 2.63.  Existing
+    /// callers, especially those of the C API, don't know about them, so lengths in those
+    /// units get resolved before they are returned.
+    pub(crate) fn is_in_public_api(self) -> bool {
+        matches!(
+            self,
+            LengthUnit::Percent
+                | LengthUnit::Px
+                | LengthUnit::Em
+                | LengthUnit::Ex
+                | LengthUnit::In
+                | LengthUnit::Cm
+                | LengthUnit::Mm
+                | LengthUnit::Pt
+                | LengthUnit::Pc
+                | LengthUnit::Ch
+        )
+    }
 }
 
 /// A CSS length value.
@@ -174,6 +262,14 @@ pub trait Validate {
     fn validate(v: f64) -> Result<f64, ValueErrorKind> {
         Ok(v)
     }
+
+    /// Clamps the value of a math function to the acceptable range.
+    ///
+    /// Math functions like `calc()` can't be validated when parsing, since the value of
+    /// their terms is not known until they get normalized.
+    fn clamp(v: f64) -> f64 {
+        v
+    }
 }
 
 /// Used to implement `CssLength<N, Signed>`.
@@ -202,6 +298,10 @@ impl Validate for Unsigned {
             ))
         }
     }
+
+    fn clamp(v: f64) -> f64 {
+        v.max(0.0)
+    }
 }
 
 /// A CSS length value.
@@ -228,11 +328,14 @@ impl Validate for Unsigned {
 ///
 /// // Parsed
 /// let radius = ULength::<Both>::parse_str("5px").unwrap();
+///
+/// // Parsed with a math function
+/// let stroke_width = ULength::<Both>::parse_str("calc(1px + 0.1em)").unwrap();
 /// ```
 ///
 /// During the rendering phase, a `CssLength` needs to be converted to user-space
 /// coordinates with the [`CssLength::to_user`] method.
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct CssLength<N: Normalize, V: Validate> {
     /// Numeric part of the length
     pub length: f64,
@@ -240,6 +343,12 @@ pub struct CssLength<N: Normalize, V: Validate> {
     /// Unit part of the length
     pub unit: LengthUnit,
 
+    /// Math function whose terms have different units, like `calc(100% - 2em)`.
+    ///
+    /// If this is present, `length` and `unit` are meaningless.  Math functions whose
+    /// terms all have the same unit get evaluated while parsing, so they don't need this.
+    calc: Option<Arc<CalcNode>>,
+
     /// Dummy; used internally for the type parameter `N`
     orientation: PhantomData<N>,
 
@@ -266,6 +375,7 @@ pub const POINTS_PER_INCH: f64 = 72.0;
 const CM_PER_INCH: f64 = 2.54;
 const MM_PER_INCH: f64 = 25.4;
 const PICA_PER_INCH: f64 = 6.0;
+const Q_PER_INCH: f64 = MM_PER_INCH * 4.0;
 
 impl<N: Normalize, V: Validate> Parse for CssLength<N, V> {
     fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<CssLength<N, V>, ParseError<'i>> {
@@ -275,6 +385,11 @@ impl<N: Normalize, V: Validate> Parse for CssLength<N, V> {
         let token = parser.next()?.clone();
 
         match token {
+            Token::Function(ref name) => {
+                let calc = parse_math_function(name, parser)?;
+                return Ok(CssLength::from_calc_node(calc));
+            }
+
             Token::Number { value, .. } => {
                 l_value = value;
                 l_unit = LengthUnit::Px;
@@ -290,18 +405,9 @@ impl<N: Normalize, V: Validate> Parse for CssLength<N, V> {
             } => {
                 l_value = value;
 
-                l_unit = match_ignore_ascii_case! {unit.as_ref(),
-                    "px" => LengthUnit::Px,
-                    "em" => LengthUnit::Em,
-                    "ex" => LengthUnit::Ex,
-                    "in" => LengthUnit::In,
-                    "cm" => LengthUnit::Cm,
-                    "mm" => LengthUnit::Mm,
-                    "pt" => LengthUnit::Pt,
-                    "pc" => LengthUnit::Pc,
-                    "ch" => LengthUnit::Ch,
-
-                    _ => return Err(parser.new_unexpected_token_error(token)),
+                l_unit = match LengthUnit::from_css_unit(unit) {
+                    Some(u) => u,
+                    None => return Err(parser.new_unexpected_token_error(token.clone())),
                 };
             }
 
@@ -322,30 +428,53 @@ impl<N: Normalize, V: Validate> Parse for CssLength<N, V> {
 /// to keep a [`ComputedValues`] around.
 pub struct NormalizeValues {
     font_size: FontSize,
+    line_height: LineHeight,
+    root_font: Option<Rc<RootFontValues>>,
+    font: Option<Rc<FontSelection>>,
     is_vertical_text: bool,
 }
 
 impl NormalizeValues {
     pub fn new(values: &ComputedValues) -> NormalizeValues {
         let is_vertical_text = matches!(
-            (values.writing_mode(), values.text_orientation()),
+            (values.writing_mode(), values.resolved_text_orientation()),
             (WritingMode::VerticalLr, TextOrientation::Upright)
                 | (WritingMode::VerticalRl, TextOrientation::Upright)
         );
 
         NormalizeValues {
             font_size: values.font_size(),
+            line_height: values.line_height(),
+            root_font: values.root_font(),
+            font: values.font_selection(),
             is_vertical_text,
         }
     }
 }
 
+/// Sizes for the `lh`, `rem`, and `rlh` units, in user-space units.
+#[derive(Copy, Clone)]
+struct LineAndRootSizes {
+    line_height: f64,
+    root_font_size: f64,
+    root_line_height: f64,
+}
+
 /// Parameters to normalize [`Length`] values to user-space distances.
 pub struct NormalizeParams {
     vbox: ViewBox,
+    outer_vbox: ViewBox,
     font_size: f64,
     dpi: Dpi,
     is_vertical_text: bool,
+
+    // The following are only needed for the units that refer to the line height, the
+    // root element's font, or the font's metrics, so they get resolved on demand.
+    line_height: LineHeight,
+    root_font: Option<Rc<RootFontValues>>,
+    line_and_root_sizes: OnceCell<LineAndRootSizes>,
+    font: Option<Rc<FontSelection>>,
+    font_units: OnceCell<FontUnits>,
 }
 
 impl NormalizeParams {
@@ -357,23 +486,127 @@ impl NormalizeParams {
     }
 
     pub fn from_values(v: &NormalizeValues, viewport: &Viewport) -> NormalizeParams {
+        let vbox = viewport.vbox;
+        let outer_vbox = viewport.outer_vbox.unwrap_or(vbox);
+
+        // The following implies that our default font size is 12, which
+        // matches the default from the FontSize property.
+        let initial = NormalizeParams::for_font_lengths(12.0, 12.0, vbox, outer_vbox, viewport.dpi);
+        let font_size = v.font_size.value().to_user(&initial);
+
         NormalizeParams {
-            vbox: viewport.vbox,
-            font_size: font_size_from_values(v, viewport.dpi),
+            vbox,
+            outer_vbox,
+            font_size,
             dpi: viewport.dpi,
             is_vertical_text: v.is_vertical_text,
+            line_height: v.line_height.clone(),
+            root_font: v.root_font.clone(),
+            line_and_root_sizes: OnceCell::new(),
+            font: v.font.clone(),
+            font_units: OnceCell::new(),
         }
     }
 
-    /// Just used by rsvg-convert, where there is no font size nor viewport.
-    pub fn from_dpi(dpi: Dpi) -> NormalizeParams {
+    /// Parameters to resolve the values of `font-size` and `line-height` themselves.
+    ///
+    /// Those values get computed before the font is known, so the units which depend on
+    /// the font's metrics or on the line height fall back to `1em` here.
+    fn for_font_lengths(
+        em: f64,
+        rem: f64,
+        vbox: ViewBox,
+        outer_vbox: ViewBox,
+        dpi: Dpi,
+    ) -> NormalizeParams {
         NormalizeParams {
-            vbox: ViewBox::from(Rect::default()),
-            font_size: 1.0,
+            vbox,
+            outer_vbox,
+            font_size: em,
             dpi,
             is_vertical_text: false,
+            line_height: LineHeight::Normal,
+            root_font: None,
+            line_and_root_sizes: OnceCell::from(LineAndRootSizes {
+                line_height: em,
+                root_font_size: rem,
+                root_line_height: rem,
+            }),
+            font: None,
+            font_units: OnceCell::new(),
         }
     }
+
+    /// Just used by rsvg-convert, where there is no font size nor viewport.
+    pub fn from_dpi(dpi: Dpi) -> NormalizeParams {
+        let vbox = ViewBox::from(Rect::default());
+        NormalizeParams::for_font_lengths(1.0, 1.0, vbox, vbox, dpi)
+    }
+
+    /// Sizes of the `lh`, `rem`, and `rlh` units, which are only computed if a length
+    /// uses them.
+    fn line_and_root_sizes(&self) -> LineAndRootSizes {
+        *self.line_and_root_sizes.get_or_init(|| {
+            let with_font = |em, rem| {
+                NormalizeParams::for_font_lengths(em, rem, self.vbox, self.outer_vbox, self.dpi)
+            };
+
+            // The root element itself has no root_font yet; its own values are the root's.
+            let (root_font_size, root_line_height) = match self.root_font {
+                Some(ref root) => {
+                    let root_font_size = root.font_size.value().to_user(&with_font(12.0, 12.0));
+                    let root_line_height = line_height_to_user(
+                        &root.line_height,
+                        &with_font(root_font_size, root_font_size),
+                    );
+
+                    (root_font_size, root_line_height)
+                }
+
+                None => {
+                    let line_height = line_height_to_user(
+                        &self.line_height,
+                        &with_font(self.font_size, self.font_size),
+                    );
+
+                    (self.font_size, line_height)
+                }
+            };
+
+            let line_height = line_height_to_user(
+                &self.line_height,
+                &with_font(self.font_size, root_font_size),
+            );
+
+            LineAndRootSizes {
+                line_height,
+                root_font_size,
+                root_line_height,
+            }
+        })
+    }
+
+    /// Sizes of the `ic` and `cap` units, which are only measured if a length uses them.
+    fn font_units(&self) -> FontUnits {
+        *self.font_units.get_or_init(|| match self.font {
+            Some(ref font) => font.measure_units(self.font_size),
+
+            None => FontUnits {
+                ic: self.font_size,
+                cap: self.font_size,
+            },
+        })
+    }
+}
+
+fn line_height_to_user(line_height: &LineHeight, params: &NormalizeParams) -> f64 {
+    match *line_height {
+        LineHeight::Normal => params.font_size,
+
+        LineHeight::Number(f) | LineHeight::Percentage(f) => f64::from(f) * params.font_size,
+
+        LineHeight::Length(ref l) => l.to_user(params),
+    }
 }
 
 impl<N: Normalize, V: Validate> CssLength<N, V> {
@@ -395,11 +628,118 @@ impl<N: Normalize, V: Validate> CssLength<N, V> {
         CssLength {
             length: l,
             unit,
+            calc: None,
+            orientation: PhantomData,
+            validation: PhantomData,
+        }
+    }
+
+    /// Creates a CssLength from the expression tree of a math function.
+    ///
+    /// If all the lengths in the expression have the same unit, the expression gets
+    /// evaluated right away.  Otherwise, it is kept to be evaluated during normalization.
+    pub fn from_calc_node(calc: CalcNode) -> CssLength<N, V> {
+        match *calc.units() {
+            [] => CssLength::new(
+                <V as Validate>::clamp(calc.evaluate(&|l, _| l)),
+                LengthUnit::Px,
+            ),
+
+            [unit] => CssLength::new(<V as Validate>::clamp(calc.evaluate(&|l, _| l)), unit),
+
+            _ => CssLength {
+                length: 0.0,
+                unit: LengthUnit::Px,
+                calc: Some(Arc::new(calc)),
+                orientation: PhantomData,
+                validation: PhantomData,
+            },
+        }
+    }
+
+    /// Returns the length as the expression tree of a math function.
+    pub fn to_calc_node(&self) -> CalcNode {
+        match self.calc {
+            Some(ref calc) => CalcNode::clone(calc),
+            None => CalcNode::Length(self.length, self.unit),
+        }
+    }
+
+    /// Whether the length is a math function whose terms have different units.
+    ///
+    /// The `length` and `unit` fields of such a length are meaningless.
+    pub fn is_calc(&self) -> bool {
+        self.calc.is_some()
+    }
+
+    /// Whether the length is a percentage, or a math function with a percentage in it.
+    pub fn has_percentage(&self) -> bool {
+        match self.calc {
+            Some(ref calc) => calc.units().contains(&LengthUnit::Percent),
+            None => self.unit == LengthUnit::Percent,
+        }
+    }
+
+    /// Whether the length has a unit relative to the outermost viewport, like `vw`.
+    pub fn has_viewport_units(&self) -> bool {
+        let is_viewport_unit = |u: &LengthUnit| {
+            matches!(
+                u,
+                LengthUnit::Vw | LengthUnit::Vh | LengthUnit::Vmin | LengthUnit::Vmax
+            )
+        };
+
+        match self.calc {
+            Some(ref calc) => calc.units().iter().any(is_viewport_unit),
+            None => is_viewport_unit(&self.unit),
+        }
+    }
+
+    /// Converts the length into one with other type parameters.
+    ///
+    /// For example, this turns a `Length<Both>` into a `Length<Horizontal>`.
+    pub fn cast<M: Normalize, W: Validate>(&self) -> CssLength<M, W> {
+        CssLength {
+            length: self.length,
+            unit: self.unit,
+            calc: self.calc.clone(),
             orientation: PhantomData,
             validation: PhantomData,
         }
     }
 
+    /// Multiplies the length by a `factor`, keeping its unit.
+    pub fn scaled(&self, factor: f64) -> CssLength<N, V> {
+        match self.calc {
+            Some(ref calc) => CssLength::from_calc_node(CalcNode::Product(
+                Box::new(CalcNode::Number(factor)),
+                Box::new(CalcNode::clone(calc)),
+            )),
+
+            None => CssLength::new(self.length * factor, self.unit),
+        }
+    }
+
+    /// Resolves the length to a number with a function for each value and unit.
+    ///
+    /// Plain lengths just call `f` once; math functions call it for each of their terms.
+    fn resolve(&self, f: &dyn Fn(f64, LengthUnit) -> f64) -> f64 {
+        match self.calc {
+            Some(ref calc) => {
+                let v = calc.evaluate(f);
+
+                // Huge values may overflow while evaluating the expression.
+                if v.is_finite() {
+                    <V as Validate>::clamp(v)
+                } else {
+                    0.0
+                }
+            }
+
+            None => f(self.length, self.unit),
+        }
+    }
+
     /// Convert a Length with units into user-space coordinates.
     ///
     /// Lengths may come with non-pixel units, and when rendering, they need to be normalized
@@ -409,58 +749,103 @@ impl<N: Normalize, V: Validate> CssLength<N, V> {
     ///
     /// Those parameters can be obtained with [`NormalizeParams::new()`].
     pub fn to_user(&self, params: &NormalizeParams) -> f64 {
-        match self.unit {
-            LengthUnit::Px => self.length,
+        self.resolve(&|length, unit| Self::length_to_user(length, unit, params))
+    }
+
+    fn length_to_user(length: f64, unit: LengthUnit, params: &NormalizeParams) -> f64 {
+        match unit {
+            LengthUnit::Px => length,
 
             LengthUnit::Percent => {
-                self.length * <N as Normalize>::normalize(params.vbox.width(), params.vbox.height())
+                length * <N as Normalize>::normalize(params.vbox.width(), params.vbox.height())
             }
 
-            LengthUnit::Em => self.length * params.font_size,
+            LengthUnit::Em => length * params.font_size,
 
-            LengthUnit::Ex => self.length * params.font_size / 2.0,
+            LengthUnit::Ex => length * params.font_size / 2.0,
 
             // how far "0" advances the text, so it varies depending on orientation
             // we're using the 0.5em or 1.0em (based on orientation) fallback from the spec
             LengthUnit::Ch => {
                 if params.is_vertical_text {
-                    self.length * params.font_size
+                    length * params.font_size
                 } else {
-                    self.length * params.font_size / 2.0
+                    length * params.font_size / 2.0
                 }
             }
 
-            LengthUnit::In => self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y),
+            LengthUnit::In => length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y),
 
             LengthUnit::Cm => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / CM_PER_INCH
+                length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / CM_PER_INCH
             }
 
             LengthUnit::Mm => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / MM_PER_INCH
+                length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / MM_PER_INCH
             }
 
             LengthUnit::Pt => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y)
-                    / POINTS_PER_INCH
+                length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / POINTS_PER_INCH
             }
 
             LengthUnit::Pc => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y)
-                    / PICA_PER_INCH
+                length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / PICA_PER_INCH
+            }
+
+            LengthUnit::Q => {
+                length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / Q_PER_INCH
             }
+
+            LengthUnit::Rem => length * params.line_and_root_sizes().root_font_size,
+
+            LengthUnit::Vw => length * params.outer_vbox.width() / 100.0,
+
+            LengthUnit::Vh => length * params.outer_vbox.height() / 100.0,
+
+            LengthUnit::Vmin => {
+                length * params.outer_vbox.width().min(params.outer_vbox.height()) / 100.0
+            }
+
+            LengthUnit::Vmax => {
+                length * params.outer_vbox.width().max(params.outer_vbox.height()) / 100.0
+            }
+
+            LengthUnit::Lh => length * params.line_and_root_sizes().line_height,
+
+            LengthUnit::Rlh => length * params.line_and_root_sizes().root_line_height,
+
+            LengthUnit::Ic => length * params.font_units().ic,
+
+            LengthUnit::Cap => length * params.font_units().cap,
         }
     }
 
+    /// Convert a Length into user-space coordinates, with percentages relative to `reference`.
+    ///
+    /// Some CSS values, like the positions of color stops in gradient functions or the
+    /// `mask-size` property, have percentages that refer to a line or a box instead of
+    /// the viewport.
+    pub fn to_user_relative_to(&self, params: &NormalizeParams, reference: f64) -> f64 {
+        self.resolve(&|length, unit| match unit {
+            LengthUnit::Percent => length * reference,
+            _ => Self::length_to_user(length, unit, params),
+        })
+    }
+
     /// Converts a Length to points.  Pixels are taken to be respect with the DPI.
     ///
     /// # Panics
     ///
-    /// Will panic if the length is in Percent, Em, or Ex units.
+    /// Will panic if the length is in Percent, or in units relative to the font or the
+    /// viewport, like Em or Vw.
     pub fn to_points(&self, params: &NormalizeParams) -> f64 {
-        match self.unit {
+        self.resolve(&|length, unit| Self::length_to_points(length, unit, params))
+    }
+
+    fn length_to_points(length: f64, unit: LengthUnit, params: &NormalizeParams) -> f64 {
+        match unit {
             LengthUnit::Px => {
-                self.length / <N as Normalize>::normalize(params.dpi.x, params.dpi.y) * 72.0
+                length / <N as Normalize>::normalize(params.dpi.x, params.dpi.y) * 72.0
             }
 
             LengthUnit::Percent => {
@@ -475,19 +860,33 @@ impl<N: Normalize, V: Validate> CssLength<N, V> {
                 panic!("Cannot convert an Ex length into an absolute length");
             }
 
-            LengthUnit::In => self.length * POINTS_PER_INCH,
+            LengthUnit::In => length * POINTS_PER_INCH,
 
-            LengthUnit::Cm => self.length / CM_PER_INCH * POINTS_PER_INCH,
+            LengthUnit::Cm => length / CM_PER_INCH * POINTS_PER_INCH,
 
-            LengthUnit::Mm => self.length / MM_PER_INCH * POINTS_PER_INCH,
+            LengthUnit::Mm => length / MM_PER_INCH * POINTS_PER_INCH,
 
-            LengthUnit::Pt => self.length,
+            LengthUnit::Pt => length,
 
-            LengthUnit::Pc => self.length / PICA_PER_INCH * POINTS_PER_INCH,
+            LengthUnit::Pc => length / PICA_PER_INCH * POINTS_PER_INCH,
 
             LengthUnit::Ch => {
                 panic!("Cannot convert a Ch length into an absolute length");
             }
+
+            LengthUnit::Q => length / Q_PER_INCH * POINTS_PER_INCH,
+
+            LengthUnit::Rem
+            | LengthUnit::Vw
+            | LengthUnit::Vh
+            | LengthUnit::Vmin
+            | LengthUnit::Vmax
+            | LengthUnit::Lh
+            | LengthUnit::Rlh
+            | LengthUnit::Ic
+            | LengthUnit::Cap => {
+                panic!("Cannot convert a {unit} length into an absolute length");
+            }
         }
     }
 
@@ -508,29 +907,6 @@ impl<N: Normalize, V: Validate> CssLength<N, V> {
     }
 }
 
-fn font_size_from_values(values: &NormalizeValues, dpi: Dpi) -> f64 {
-    let v = values.font_size.value();
-
-    match v.unit {
-        LengthUnit::Percent => unreachable!("ComputedValues can't have a relative font size"),
-
-        LengthUnit::Px => v.length,
-
-        // The following implies that our default font size is 12, which
-        // matches the default from the FontSize property.
-        LengthUnit::Em => v.length * 12.0,
-        LengthUnit::Ex => v.length * 12.0 / 2.0,
-        LengthUnit::Ch => v.length * 12.0 / 2.0,
-
-        // FontSize always is a Both, per properties.rs
-        LengthUnit::In => v.length * Both::normalize(dpi.x, dpi.y),
-        LengthUnit::Cm => v.length * Both::normalize(dpi.x, dpi.y) / CM_PER_INCH,
-        LengthUnit::Mm => v.length * Both::normalize(dpi.x, dpi.y) / MM_PER_INCH,
-        LengthUnit::Pt => v.length * Both::normalize(dpi.x, dpi.y) / POINTS_PER_INCH,
-        LengthUnit::Pc => v.length * Both::normalize(dpi.x, dpi.y) / PICA_PER_INCH,
-    }
-}
-
 fn viewport_percentage(x: f64, y: f64) -> f64 {
     // https://www.w3.org/TR/SVG/coords.html#Units
     // "For any other length value expressed as a percentage of the viewport, the
@@ -545,7 +921,7 @@ pub type Length<N> = CssLength<N, Signed>;
 /// Alias for `CssLength` types that are non negative
 pub type ULength<N> = CssLength<N, Unsigned>;
 
-#[derive(Debug, Default, PartialEq, Copy, Clone)]
+#[derive(Debug, Default, PartialEq, Clone)]
 pub enum LengthOrAuto<N: Normalize> {
     #[default]
     Auto,
@@ -565,6 +941,34 @@ impl<N: Normalize> Parse for LengthOrAuto<N> {
     }
 }
 
+impl<N: Normalize, V: Validate> ToCssValue for CssLength<N, V> {
+    fn to_css_value(&self) -> Option<String> {
+        // Math functions are not serialized yet.
+        if self.is_calc() {
+            return None;
+        }
+
+        let number = if self.unit == LengthUnit::Percent {
+            self.length * 100.0
+        } else {
+            self.length
+        };
+
+        number
+            .to_css_value()
+            .map(|number| format!("{number}{}", self.unit))
+    }
+}
+
+impl<N: Normalize> ToCssValue for LengthOrAuto<N> {
+    fn to_css_value(&self) -> Option<String> {
+        match *self {
+            LengthOrAuto::Auto => Some(String::from("auto")),
+            LengthOrAuto::Length(ref l) => l.to_css_value(),
+        }
+    }
+}
+
 impl fmt::Display for LengthUnit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let unit = match &self {
@@ -578,6 +982,16 @@ impl fmt::Display for LengthUnit {
             LengthUnit::Pt => "pt",
             LengthUnit::Pc => "pc",
             LengthUnit::Ch => "ch",
+            LengthUnit::Rem => "rem",
+            LengthUnit::Vw => "vw",
+            LengthUnit::Vh => "vh",
+            LengthUnit::Vmin => "vmin",
+            LengthUnit::Vmax => "vmax",
+            LengthUnit::Lh => "lh",
+            LengthUnit::Rlh => "rlh",
+            LengthUnit::Ic => "ic",
+            LengthUnit::Cap => "cap",
+            LengthUnit::Q => "Q",
         };
 
         write!(f, "{unit}")
@@ -662,6 +1076,62 @@ mod tests {
             Length::<Both>::parse_str("60pc").unwrap(),
             Length::<Both>::new(60.0, LengthUnit::Pc)
         );
+
+        assert_eq!(
+            Length::<Both>::parse_str("40Q").unwrap(),
+            Length::<Both>::new(40.0, LengthUnit::Q)
+        );
+    }
+
+    #[test]
+    fn parses_font_relative_units() {
+        assert_eq!(
+            Length::<Both>::parse_str("1.5rem").unwrap(),
+            Length::<Both>::new(1.5, LengthUnit::Rem)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("2lh").unwrap(),
+            Length::<Both>::new(2.0, LengthUnit::Lh)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("2rlh").unwrap(),
+            Length::<Both>::new(2.0, LengthUnit::Rlh)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("3ic").unwrap(),
+            Length::<Both>::new(3.0, LengthUnit::Ic)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("3cap").unwrap(),
+            Length::<Both>::new(3.0, LengthUnit::Cap)
+        );
+    }
+
+    #[test]
+    fn parses_viewport_units() {
+        assert_eq!(
+            Length::<Horizontal>::parse_str("50vw").unwrap(),
+            Length::<Horizontal>::new(50.0, LengthUnit::Vw)
+        );
+
+        assert_eq!(
+            Length::<Vertical>::parse_str("50vh").unwrap(),
+            Length::<Vertical>::new(50.0, LengthUnit::Vh)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("10vmin").unwrap(),
+            Length::<Both>::new(10.0, LengthUnit::Vmin)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("10VMAX").unwrap(),
+            Length::<Both>::new(10.0, LengthUnit::Vmax)
+        );
     }
 
     #[test]
@@ -679,6 +1149,45 @@ mod tests {
         assert!(ULength::<Horizontal>::parse_str("-42px").is_err());
     }
 
+    #[test]
+    fn parses_math_functions_with_a_single_unit() {
+        assert_eq!(
+            Length::<Both>::parse_str("calc(1px + 2px)").unwrap(),
+            Length::<Both>::new(3.0, LengthUnit::Px)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("calc(2 * 3)").unwrap(),
+            Length::<Both>::new(6.0, LengthUnit::Px)
+        );
+
+        assert_eq!(
+            Length::<Both>::parse_str("max(10%, 20%)").unwrap(),
+            Length::<Both>::new(0.2, LengthUnit::Percent)
+        );
+
+        // Math functions get clamped to the allowed range instead of being rejected
+        assert_eq!(
+            ULength::<Both>::parse_str("calc(1px - 2px)").unwrap(),
+            ULength::<Both>::new(0.0, LengthUnit::Px)
+        );
+    }
+
+    #[test]
+    fn parses_math_functions_with_mixed_units() {
+        let l = Length::<Horizontal>::parse_str("calc(100% - 2em)").unwrap();
+        assert!(l.is_calc());
+        assert!(l.has_percentage());
+
+        let l = Length::<Horizontal>::parse_str("min(1in, 2em)").unwrap();
+        assert!(l.is_calc());
+        assert!(!l.has_percentage());
+
+        assert!(Length::<Both>::parse_str("calc(1px + 2)").is_err());
+        assert!(Length::<Both>::parse_str("calc(1px * 2px)").is_err());
+        assert!(Length::<Both>::parse_str("foo(1px)").is_err());
+    }
+
     #[test]
     fn empty_length_yields_error() {
         assert!(Length::<Both>::parse_str("").is_err());
@@ -732,6 +1241,10 @@ mod tests {
             Length::<Horizontal>::new(10.0, LengthUnit::Pc).to_user(&params),
             400.0 / PICA_PER_INCH
         );
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(10.0, LengthUnit::Q).to_user(&params),
+            400.0 / Q_PER_INCH
+        );
     }
 
     #[test]
@@ -790,6 +1303,140 @@ mod tests {
         );
     }
 
+    #[test]
+    fn normalize_root_and_line_height_units_works() {
+        let viewport = Viewport::new(Dpi::new(40.0, 40.0), 100.0, 200.0);
+
+        let mut root = ComputedValues::default();
+        let mut specified = SpecifiedValues::default();
+        specified.set_parsed_property(&ParsedProperty::FontSize(SpecifiedValue::Specified(
+            FontSize::Value(Length::new(20.0, LengthUnit::Px)),
+        )));
+        specified.to_computed_values(&mut root);
+
+        let mut child = root.clone();
+        let mut specified = SpecifiedValues::default();
+        specified.set_parsed_property(&ParsedProperty::FontSize(SpecifiedValue::Specified(
+            FontSize::Value(Length::new(10.0, LengthUnit::Px)),
+        )));
+        specified.set_parsed_property(&ParsedProperty::LineHeight(SpecifiedValue::Specified(
+            LineHeight::Number(3.0),
+        )));
+        specified.to_computed_values(&mut child);
+
+        let params = NormalizeParams::new(&child, &viewport);
+
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(1.0, LengthUnit::Em).to_user(&params),
+            10.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(1.0, LengthUnit::Rem).to_user(&params),
+            20.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(1.0, LengthUnit::Lh).to_user(&params),
+            30.0
+        );
+
+        // The root's line-height is "normal", which we take as 1em
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(1.0, LengthUnit::Rlh).to_user(&params),
+            20.0
+        );
+
+        // font-size itself can be in rem units
+        let mut grandchild = child.clone();
+        let mut specified = SpecifiedValues::default();
+        specified.set_parsed_property(&ParsedProperty::FontSize(SpecifiedValue::Specified(
+            FontSize::Value(Length::new(2.0, LengthUnit::Rem)),
+        )));
+        specified.to_computed_values(&mut grandchild);
+
+        let params = NormalizeParams::new(&grandchild, &viewport);
+
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(1.0, LengthUnit::Em).to_user(&params),
+            40.0
+        );
+    }
+
+    #[test]
+    fn normalize_viewport_units_works() {
+        let viewport = Viewport::new(Dpi::new(40.0, 40.0), 100.0, 200.0);
+
+        // Nested viewports don't change the viewport units
+        let viewport = viewport.with_view_box(10.0, 10.0);
+
+        let values = ComputedValues::default();
+        let params = NormalizeParams::new(&values, &viewport);
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(10.0, LengthUnit::Vw).to_user(&params),
+            10.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(10.0, LengthUnit::Vh).to_user(&params),
+            20.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Vertical>::new(10.0, LengthUnit::Vmin).to_user(&params),
+            10.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Vertical>::new(10.0, LengthUnit::Vmax).to_user(&params),
+            20.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::parse_str("calc(50vw + 10%)")
+                .unwrap()
+                .to_user(&params),
+            51.0
+        );
+    }
+
+    #[test]
+    fn normalize_math_functions_works() {
+        let viewport = Viewport::new(Dpi::new(40.0, 40.0), 100.0, 200.0);
+        let values = ComputedValues::default();
+        let params = NormalizeParams::new(&values, &viewport);
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::parse_str("calc(50% - 1em)")
+                .unwrap()
+                .to_user(&params),
+            38.0
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Vertical>::parse_str("calc(50% - 1em)")
+                .unwrap()
+                .to_user(&params),
+            88.0
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::parse_str("clamp(1em, 10% + 1in, 60px)")
+                .unwrap()
+                .to_user(&params),
+            50.0
+        );
+
+        assert_approx_eq_cairo!(
+            ULength::<Horizontal>::parse_str("calc(10% - 2em)")
+                .unwrap()
+                .to_user(&params),
+            0.0
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::parse_str("calc(100% - 2em)")
+                .unwrap()
+                .to_user_relative_to(&params, 50.0),
+            26.0
+        );
+    }
+
     #[test]
     fn to_points_works() {
         let params = NormalizeParams::from_dpi(Dpi::new(40.0, 96.0));
@@ -173,6 +173,7 @@ mod api;
 mod aspect_ratio;
 mod bbox;
 mod cairo_path;
+mod calc;
 mod color;
 mod cond;
 mod coord_units;
@@ -188,7 +189,9 @@ mod filter_func;
 mod filters;
 mod float_eq_cairo;
 mod font_props;
+mod foreign_object;
 mod gradient;
+mod gradient_func;
 mod href;
 mod image;
 mod io;
@@ -198,12 +201,14 @@ mod length;
 mod limits;
 mod log;
 mod marker;
+mod mask_props;
 mod node;
 mod paint_server;
 mod parsers;
 mod path_builder;
 mod path_parser;
 mod pattern;
+mod position;
 mod properties;
 mod property_defs;
 mod property_macros;
@@ -211,6 +216,7 @@ mod rect;
 mod session;
 mod shapes;
 mod space;
+mod stroke_join;
 mod structure;
 mod style;
 mod surface_utils;
@@ -54,3 +54,22 @@ pub const MAX_FILE_LOADING_DEPTH: usize = 20;
 /// This is a mitigation for malicious SVGs which try to have layers that are nested
 /// extremely deep, as this could cause stack exhaustion.
 pub const MAX_LAYER_NESTING_DEPTH: u16 = 50;
+
+/// Maximum width or height in pixels of a single tile for `mask-image`.
+///
+/// A small `mask-size` with `mask-repeat: space` or a huge `mask-size` could otherwise
+/// make librsvg allocate an enormous surface for a single tile of the mask image.
+pub const MAX_MASK_TILE_SIZE: i32 = 32767;
+
+/// Maximum width or height in pixels of the image for a `conic-gradient()`.
+///
+/// Cairo has no conic gradients, so librsvg renders them pixel by pixel to an image that
+/// covers the painted shape.  Shapes larger than this get a lower-resolution image that
+/// is scaled up, to limit the memory and time spent on it.
+pub const MAX_CONIC_GRADIENT_SIZE: i32 = 2048;
+
+/// Maximum level of nesting of parentheses and functions inside a CSS math function.
+///
+/// Math functions like `calc()` are parsed recursively, so a malicious SVG with
+/// `calc((((((...` could otherwise cause stack exhaustion.
+pub const MAX_CALC_NESTING_DEPTH: usize = 32;
@@ -0,0 +1,584 @@
+//! CSS masking properties: the `mask` shorthand and its `mask-*` longhands.
+//!
+//! Do not import things directly from this module; use the `properties` module instead,
+//! which re-exports things from here.
+//!
+//! CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#positioned-masks>
+//!
+//! Each of the longhands is a comma-separated list with one value per mask layer.  The
+//! number of layers is given by the number of values in `mask-image`; the values of the
+//! other longhands get repeated as needed to match that number, or the extra ones are
+//! ignored.  The first layer in the list is the topmost one.
+//!
+//! The `mask-origin` and `mask-clip` longhands are not supported.  The mask images are
+//! positioned relative to the object bounding box of the element (`fill-box`), and they
+//! are clipped to its stroke bounding box.
+
+use cssparser::Parser;
+
+use crate::document::NodeId;
+use crate::error::*;
+use crate::gradient_func::GradientFunction;
+use crate::length::*;
+use crate::parse_identifiers;
+use crate::parsers::Parse;
+use crate::position::Position;
+
+/// Value for a single layer of `mask-image`.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#typedef-mask-reference>
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaskReference {
+    None,
+
+    /// A reference to a `<mask>` element, like `url(#foo)` or `url(file.svg#foo)`.
+    Element(NodeId),
+
+    /// A URL without a fragment, like `url(image.png)`.  It is loaded as a raster image.
+    Image(String),
+
+    /// A gradient function, like `linear-gradient(...)`.
+    Gradient(Box<GradientFunction>),
+}
+
+impl Parse for MaskReference {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskReference, ParseError<'i>> {
+        if parser
+            .try_parse(|p| p.expect_ident_matching("none"))
+            .is_ok()
+        {
+            return Ok(MaskReference::None);
+        }
+
+        if let Ok(url) = parser.try_parse(|p| p.expect_url().map(|u| u.as_ref().to_string())) {
+            return match NodeId::parse(&url) {
+                Ok(node_id) => Ok(MaskReference::Element(node_id)),
+                Err(_) => Ok(MaskReference::Image(url)),
+            };
+        }
+
+        Ok(MaskReference::Gradient(Box::new(GradientFunction::parse(
+            parser,
+        )?)))
+    }
+}
+
+/// Value for a single layer of `mask-mode`.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#typedef-masking-mode>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MaskingMode {
+    Alpha,
+    Luminance,
+
+    /// Use the `mask-type` of a `<mask>` element, or `alpha` for images.
+    MatchSource,
+}
+
+impl Parse for MaskingMode {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskingMode, ParseError<'i>> {
+        Ok(parse_identifiers!(
+            parser,
+            "alpha" => MaskingMode::Alpha,
+            "luminance" => MaskingMode::Luminance,
+            "match-source" => MaskingMode::MatchSource,
+        )?)
+    }
+}
+
+/// Value for a single layer of `mask-size`.
+///
+/// CSS Backgrounds 3: <https://www.w3.org/TR/css-backgrounds-3/#typedef-bg-size>
+#[derive(Debug, Clone, PartialEq)]
+pub enum BgSize {
+    Cover,
+    Contain,
+    Size(LengthOrAuto<Horizontal>, LengthOrAuto<Vertical>),
+}
+
+impl Parse for BgSize {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<BgSize, ParseError<'i>> {
+        if parser
+            .try_parse(|p| p.expect_ident_matching("cover"))
+            .is_ok()
+        {
+            return Ok(BgSize::Cover);
+        }
+
+        if parser
+            .try_parse(|p| p.expect_ident_matching("contain"))
+            .is_ok()
+        {
+            return Ok(BgSize::Contain);
+        }
+
+        let width = LengthOrAuto::<Horizontal>::parse(parser)?;
+        let height = parser
+            .try_parse(LengthOrAuto::<Vertical>::parse)
+            .unwrap_or(LengthOrAuto::Auto);
+
+        Ok(BgSize::Size(width, height))
+    }
+}
+
+/// How mask images get repeated along one axis.
+///
+/// CSS Backgrounds 3: <https://www.w3.org/TR/css-backgrounds-3/#background-repeat>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Repetition {
+    Repeat,
+    Space,
+    Round,
+    NoRepeat,
+}
+
+impl Parse for Repetition {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Repetition, ParseError<'i>> {
+        Ok(parse_identifiers!(
+            parser,
+            "repeat" => Repetition::Repeat,
+            "space" => Repetition::Space,
+            "round" => Repetition::Round,
+            "no-repeat" => Repetition::NoRepeat,
+        )?)
+    }
+}
+
+/// Value for a single layer of `mask-repeat`.
+///
+/// CSS Backgrounds 3: <https://www.w3.org/TR/css-backgrounds-3/#typedef-repeat-style>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RepeatStyle {
+    pub x: Repetition,
+    pub y: Repetition,
+}
+
+impl Parse for RepeatStyle {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<RepeatStyle, ParseError<'i>> {
+        if parser
+            .try_parse(|p| p.expect_ident_matching("repeat-x"))
+            .is_ok()
+        {
+            return Ok(RepeatStyle {
+                x: Repetition::Repeat,
+                y: Repetition::NoRepeat,
+            });
+        }
+
+        if parser
+            .try_parse(|p| p.expect_ident_matching("repeat-y"))
+            .is_ok()
+        {
+            return Ok(RepeatStyle {
+                x: Repetition::NoRepeat,
+                y: Repetition::Repeat,
+            });
+        }
+
+        let x = Repetition::parse(parser)?;
+        let y = parser.try_parse(Repetition::parse).unwrap_or(x);
+
+        Ok(RepeatStyle { x, y })
+    }
+}
+
+/// Value for a single layer of `mask-composite`.
+///
+/// The layer is the source, and the composite of the layers below it is the destination.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#typedef-compositing-operator>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CompositingOperator {
+    Add,
+    Subtract,
+    Intersect,
+    Exclude,
+}
+
+impl Parse for CompositingOperator {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<CompositingOperator, ParseError<'i>> {
+        Ok(parse_identifiers!(
+            parser,
+            "add" => CompositingOperator::Add,
+            "subtract" => CompositingOperator::Subtract,
+            "intersect" => CompositingOperator::Intersect,
+            "exclude" => CompositingOperator::Exclude,
+        )?)
+    }
+}
+
+fn parse_layers<'i, T: Parse>(parser: &mut Parser<'i, '_>) -> Result<Vec<T>, ParseError<'i>> {
+    parser.parse_comma_separated(T::parse)
+}
+
+/// Gets the value for a layer in one of the `mask-*` longhands, repeating the list if
+/// it has fewer values than there are layers.
+pub fn layer_value<T: Clone>(values: &[T], layer: usize) -> T {
+    values[layer % values.len()].clone()
+}
+
+/// `mask-image` property.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask-image>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskImage(pub Vec<MaskReference>);
+
+impl Parse for MaskImage {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskImage, ParseError<'i>> {
+        Ok(MaskImage(parse_layers(parser)?))
+    }
+}
+
+/// `mask-mode` property.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask-mode>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskMode(pub Vec<MaskingMode>);
+
+impl Parse for MaskMode {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskMode, ParseError<'i>> {
+        Ok(MaskMode(parse_layers(parser)?))
+    }
+}
+
+/// `mask-position` property.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask-position>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskPosition(pub Vec<Position>);
+
+impl Parse for MaskPosition {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskPosition, ParseError<'i>> {
+        Ok(MaskPosition(parse_layers(parser)?))
+    }
+}
+
+/// `mask-size` property.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask-size>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskSize(pub Vec<BgSize>);
+
+impl Parse for MaskSize {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskSize, ParseError<'i>> {
+        Ok(MaskSize(parse_layers(parser)?))
+    }
+}
+
+/// `mask-repeat` property.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask-repeat>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskRepeat(pub Vec<RepeatStyle>);
+
+impl Parse for MaskRepeat {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskRepeat, ParseError<'i>> {
+        Ok(MaskRepeat(parse_layers(parser)?))
+    }
+}
+
+/// `mask-composite` property.
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask-composite>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskComposite(pub Vec<CompositingOperator>);
+
+impl Parse for MaskComposite {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskComposite, ParseError<'i>> {
+        Ok(MaskComposite(parse_layers(parser)?))
+    }
+}
+
+/// `mask` shorthand property.
+///
+/// SVG1.1: <https://www.w3.org/TR/SVG11/masking.html#MaskProperty>
+///
+/// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask>
+///
+/// This is a shorthand, which expands to the `mask-image`, `mask-mode`, `mask-position`,
+/// `mask-size`, `mask-repeat`, and `mask-composite` longhands.  The SVG1.1 syntax,
+/// `mask="url(#foo)"`, is just a single layer with a reference to a `<mask>` element.
+///
+/// Unlike the other shorthands, this one can also be used as a presentation attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    pub image: MaskImage,
+    pub mode: MaskMode,
+    pub position: MaskPosition,
+    pub size: MaskSize,
+    pub repeat: MaskRepeat,
+    pub composite: MaskComposite,
+}
+
+/// Values for one layer of the `mask` shorthand.
+struct MaskLayerSpec {
+    image: MaskReference,
+    mode: MaskingMode,
+    position: Position,
+    size: BgSize,
+    repeat: RepeatStyle,
+    composite: CompositingOperator,
+}
+
+fn parse_geometry_box<'i>(parser: &mut Parser<'i, '_>) -> Result<(), ParseError<'i>> {
+    Ok(parse_identifiers!(
+        parser,
+        "content-box" => (),
+        "padding-box" => (),
+        "border-box" => (),
+        "margin-box" => (),
+        "fill-box" => (),
+        "stroke-box" => (),
+        "view-box" => (),
+        "no-clip" => (),
+    )?)
+}
+
+fn parse_mask_layer<'i>(parser: &mut Parser<'i, '_>) -> Result<MaskLayerSpec, ParseError<'i>> {
+    let loc = parser.current_source_location();
+
+    let mut image = None;
+    let mut mode = None;
+    let mut position = None;
+    let mut size = None;
+    let mut repeat = None;
+    let mut composite = None;
+    let mut num_geometry_boxes = 0;
+
+    while !parser.is_exhausted() {
+        if image.is_none()
+            && let Ok(v) = parser.try_parse(MaskReference::parse)
+        {
+            image = Some(v);
+            continue;
+        }
+
+        if position.is_none()
+            && let Ok(v) = parser.try_parse(Position::parse)
+        {
+            position = Some(v);
+
+            if parser.try_parse(|p| p.expect_delim('/')).is_ok() {
+                size = Some(BgSize::parse(parser)?);
+            }
+            continue;
+        }
+
+        if repeat.is_none()
+            && let Ok(v) = parser.try_parse(RepeatStyle::parse)
+        {
+            repeat = Some(v);
+            continue;
+        }
+
+        if composite.is_none()
+            && let Ok(v) = parser.try_parse(CompositingOperator::parse)
+        {
+            composite = Some(v);
+            continue;
+        }
+
+        if mode.is_none()
+            && let Ok(v) = parser.try_parse(MaskingMode::parse)
+        {
+            mode = Some(v);
+            continue;
+        }
+
+        // mask-origin and mask-clip are not supported; accept and ignore them.
+        if num_geometry_boxes < 2 && parser.try_parse(parse_geometry_box).is_ok() {
+            num_geometry_boxes += 1;
+            continue;
+        }
+
+        return Err(loc.new_custom_error(ValueErrorKind::parse_error(
+            "invalid syntax for 'mask' property",
+        )));
+    }
+
+    Ok(MaskLayerSpec {
+        image: image.unwrap_or(MaskReference::None),
+        mode: mode.unwrap_or(MaskingMode::MatchSource),
+        position: position.unwrap_or_else(Position::top_left),
+        size: size.unwrap_or(BgSize::Size(LengthOrAuto::Auto, LengthOrAuto::Auto)),
+        repeat: repeat.unwrap_or(RepeatStyle {
+            x: Repetition::Repeat,
+            y: Repetition::Repeat,
+        }),
+        composite: composite.unwrap_or(CompositingOperator::Add),
+    })
+}
+
+impl Parse for Mask {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Mask, ParseError<'i>> {
+        let layers = parser.parse_comma_separated(parse_mask_layer)?;
+
+        Ok(Mask {
+            image: MaskImage(layers.iter().map(|l| l.image.clone()).collect()),
+            mode: MaskMode(layers.iter().map(|l| l.mode).collect()),
+            position: MaskPosition(layers.iter().map(|l| l.position.clone()).collect()),
+            size: MaskSize(layers.iter().map(|l| l.size.clone()).collect()),
+            repeat: MaskRepeat(layers.iter().map(|l| l.repeat).collect()),
+            composite: MaskComposite(layers.iter().map(|l| l.composite).collect()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::properties::{ParsedProperty, SpecifiedValue, SpecifiedValues};
+
+    #[test]
+    fn parses_mask_image() {
+        assert_eq!(
+            MaskImage::parse_str("none").unwrap(),
+            MaskImage(vec![MaskReference::None])
+        );
+
+        assert_eq!(
+            MaskImage::parse_str("url(#foo), url(image.png), none").unwrap(),
+            MaskImage(vec![
+                MaskReference::Element(NodeId::Internal("foo".to_string())),
+                MaskReference::Image("image.png".to_string()),
+                MaskReference::None,
+            ])
+        );
+
+        assert!(matches!(
+            MaskImage::parse_str("linear-gradient(black, transparent)")
+                .unwrap()
+                .0[..],
+            [MaskReference::Gradient(_)]
+        ));
+
+        assert!(MaskImage::parse_str("").is_err());
+        assert!(MaskImage::parse_str("foo").is_err());
+    }
+
+    #[test]
+    fn parses_mask_longhands() {
+        assert_eq!(
+            MaskMode::parse_str("alpha, luminance, match-source").unwrap(),
+            MaskMode(vec![
+                MaskingMode::Alpha,
+                MaskingMode::Luminance,
+                MaskingMode::MatchSource
+            ])
+        );
+
+        assert_eq!(
+            MaskSize::parse_str("cover, 10px, 50% auto").unwrap(),
+            MaskSize(vec![
+                BgSize::Cover,
+                BgSize::Size(
+                    LengthOrAuto::Length(ULength::new(10.0, LengthUnit::Px)),
+                    LengthOrAuto::Auto
+                ),
+                BgSize::Size(
+                    LengthOrAuto::Length(ULength::new(0.5, LengthUnit::Percent)),
+                    LengthOrAuto::Auto
+                ),
+            ])
+        );
+
+        assert_eq!(
+            MaskRepeat::parse_str("repeat-x, space round, no-repeat").unwrap(),
+            MaskRepeat(vec![
+                RepeatStyle {
+                    x: Repetition::Repeat,
+                    y: Repetition::NoRepeat,
+                },
+                RepeatStyle {
+                    x: Repetition::Space,
+                    y: Repetition::Round,
+                },
+                RepeatStyle {
+                    x: Repetition::NoRepeat,
+                    y: Repetition::NoRepeat,
+                },
+            ])
+        );
+
+        assert_eq!(
+            MaskComposite::parse_str("add, subtract, intersect, exclude").unwrap(),
+            MaskComposite(vec![
+                CompositingOperator::Add,
+                CompositingOperator::Subtract,
+                CompositingOperator::Intersect,
+                CompositingOperator::Exclude,
+            ])
+        );
+
+        assert!(MaskSize::parse_str("-10px").is_err());
+        assert!(MaskRepeat::parse_str("repeat-x repeat").is_err());
+    }
+
+    #[test]
+    fn parses_mask_shorthand() {
+        let mask = Mask::parse_str("url(a.png) center / 50% no-repeat luminance, url(#m) subtract")
+            .unwrap();
+
+        assert_eq!(
+            mask.image,
+            MaskImage(vec![
+                MaskReference::Image("a.png".to_string()),
+                MaskReference::Element(NodeId::Internal("m".to_string())),
+            ])
+        );
+        assert_eq!(
+            mask.mode,
+            MaskMode(vec![MaskingMode::Luminance, MaskingMode::MatchSource])
+        );
+        assert_eq!(
+            mask.position,
+            MaskPosition(vec![Position::center(), Position::top_left()])
+        );
+        assert_eq!(
+            mask.size.0[0],
+            BgSize::Size(
+                LengthOrAuto::Length(ULength::new(0.5, LengthUnit::Percent)),
+                LengthOrAuto::Auto
+            )
+        );
+        assert_eq!(
+            mask.repeat.0[0],
+            RepeatStyle {
+                x: Repetition::NoRepeat,
+                y: Repetition::NoRepeat,
+            }
+        );
+        assert_eq!(
+            mask.composite,
+            MaskComposite(vec![
+                CompositingOperator::Add,
+                CompositingOperator::Subtract
+            ])
+        );
+
+        assert!(Mask::parse_str("url(#m) url(#n)").is_err());
+        assert!(Mask::parse_str("url(#m) / 50%").is_err());
+        assert!(Mask::parse_str("url(#m) fill-box no-clip").is_ok());
+    }
+
+    #[test]
+    fn expands_mask_shorthand() {
+        let mut specified = SpecifiedValues::default();
+        let mask = Mask::parse_str("url(#foo) luminance").unwrap();
+        specified.set_parsed_property(&ParsedProperty::Mask(SpecifiedValue::Specified(mask)));
+
+        let mut computed = crate::properties::ComputedValues::default();
+        specified.to_computed_values(&mut computed);
+
+        assert_eq!(
+            computed.mask_image(),
+            MaskImage(vec![MaskReference::Element(NodeId::Internal(
+                "foo".to_string()
+            ))])
+        );
+        assert_eq!(computed.mask_mode(), MaskMode(vec![MaskingMode::Luminance]));
+        assert_eq!(computed.mask_position(), MaskPosition::default());
+        assert_eq!(computed.mask_repeat(), MaskRepeat::default());
+    }
+}
@@ -339,7 +339,13 @@ impl NodeDraw for Node {
                 draw_ctx.print_stack_depth("Node::draw");
                 let res = match e.draw(self, acquired_nodes, cascaded, viewport, draw_ctx, clipping)
                 {
-                    Ok(bbox) => Ok(bbox),
+                    Ok(bbox) => {
+                        if !clipping {
+                            draw_ctx.record_geometry(self, cascaded.get(), viewport, &bbox);
+                        }
+
+                        Ok(bbox)
+                    }
 
                     Err(boxed_e) => match *boxed_e {
                         // https://www.w3.org/TR/css-transforms-1/#transform-function-lists
@@ -10,10 +10,12 @@ use crate::drawing_ctx::Viewport;
 use crate::element::ElementData;
 use crate::error::{AcquireError, NodeIdError, ParseError, ValueErrorKind};
 use crate::gradient::{ResolvedGradient, UserSpaceGradient};
-use crate::length::NormalizeValues;
+use crate::gradient_func::GradientFunction;
+use crate::length::{NormalizeParams, NormalizeValues};
 use crate::node::NodeBorrow;
 use crate::parsers::Parse;
 use crate::pattern::{ResolvedPattern, UserSpacePattern};
+use crate::property_macros::ToCssValue;
 use crate::rect::Rect;
 use crate::rsvg_log;
 use crate::session::Session;
@@ -23,7 +25,8 @@ use crate::unit_interval::UnitInterval;
 ///
 /// This is either a solid color (which if `currentColor` needs to be extracted from the
 /// `ComputedValues`), or a paint server like a gradient or pattern which is referenced by
-/// a URL that points to a certain document node.
+/// a URL that points to a certain document node, or a CSS gradient function written
+/// inline.
 ///
 /// Use [`PaintServer.resolve`](#method.resolve) to turn this into a [`PaintSource`].
 #[derive(Debug, Clone, PartialEq)]
@@ -45,6 +48,9 @@ pub enum PaintServer {
 
     /// For example, `fill="context-stroke"`
     ContextStroke,
+
+    /// For example, `fill: linear-gradient(45deg, red, blue)`.
+    GradientFunction(Box<GradientFunction>),
 }
 
 /// Paint server with resolved references, with unnormalized lengths.
@@ -56,6 +62,13 @@ pub enum PaintSource {
     Gradient(ResolvedGradient, Option<Color>),
     Pattern(ResolvedPattern, Option<Color>),
     SolidColor(Color),
+
+    /// A CSS gradient function; it gets resolved against the object bounding box later.
+    GradientFunction {
+        gradient: Box<GradientFunction>,
+        opacity: UnitInterval,
+        current_color: Color,
+    },
 }
 
 /// Fully resolved paint server, in user-space units.
@@ -114,12 +127,43 @@ impl Parse for PaintServer {
                 ),
                 alternate,
             })
+        } else if let Ok(gradient) = parser.try_parse(GradientFunction::parse) {
+            Ok(PaintServer::GradientFunction(Box::new(gradient)))
         } else {
             <Color as Parse>::parse(parser).map(PaintServer::SolidColor)
         }
     }
 }
 
+impl ToCssValue for PaintServer {
+    fn to_css_value(&self) -> Option<String> {
+        match *self {
+            PaintServer::None => Some(String::from("none")),
+
+            PaintServer::Iri {
+                ref iri,
+                alternate: None,
+            } => Some(format!("url({iri})")),
+
+            PaintServer::Iri {
+                ref iri,
+                alternate: Some(ref color),
+            } => color
+                .to_css_value()
+                .map(|color| format!("url({iri}) {color}")),
+
+            PaintServer::SolidColor(ref color) => color.to_css_value(),
+
+            PaintServer::ContextFill => Some(String::from("context-fill")),
+
+            PaintServer::ContextStroke => Some(String::from("context-stroke")),
+
+            // Gradient functions are not serialized yet.
+            PaintServer::GradientFunction(_) => None,
+        }
+    }
+}
+
 impl PaintServer {
     /// Resolves colors, plus node references for gradients and patterns.
     ///
@@ -240,6 +284,12 @@ impl PaintServer {
                 }
             }
 
+            PaintServer::GradientFunction(gradient) => Rc::new(PaintSource::GradientFunction {
+                gradient: gradient.clone(),
+                opacity,
+                current_color,
+            }),
+
             PaintServer::None => Rc::new(PaintSource::None),
         }
     }
@@ -272,6 +322,20 @@ impl PaintSource {
                     (None, None) => UserSpacePaintSource::None,
                 }
             }
+
+            PaintSource::GradientFunction {
+                ref gradient,
+                opacity,
+                ref current_color,
+            } => {
+                let params = NormalizeParams::from_values(values, viewport);
+
+                object_bbox
+                    .as_ref()
+                    .and_then(|rect| gradient.to_user_space(rect, &params, opacity, current_color))
+                    .map(|g| UserSpacePaintSource::Gradient(g, None))
+                    .unwrap_or(UserSpacePaintSource::None)
+            }
         }
     }
 }
@@ -283,6 +347,7 @@ impl std::fmt::Debug for PaintSource {
             PaintSource::Gradient(_, _) => f.write_str("PaintSource::Gradient"),
             PaintSource::Pattern(_, _) => f.write_str("PaintSource::Pattern"),
             PaintSource::SolidColor(_) => f.write_str("PaintSource::SolidColor"),
+            PaintSource::GradientFunction { .. } => f.write_str("PaintSource::GradientFunction"),
         }
     }
 }
@@ -363,6 +428,21 @@ mod tests {
         assert!(PaintServer::parse_str("url(#link) invalid").is_err());
     }
 
+    #[test]
+    fn parses_gradient_function() {
+        assert!(matches!(
+            PaintServer::parse_str("linear-gradient(45deg, red, blue)").unwrap(),
+            PaintServer::GradientFunction(_)
+        ));
+
+        assert!(matches!(
+            PaintServer::parse_str("conic-gradient(from 90deg, red, blue)").unwrap(),
+            PaintServer::GradientFunction(_)
+        ));
+
+        assert!(PaintServer::parse_str("linear-gradient(red)").is_err());
+    }
+
     #[test]
     fn resolves_explicit_color() {
         assert_eq!(
@@ -70,6 +70,37 @@ impl CubicBezierCurve {
         coords.push(self.to.1);
         PackedCommand::CurveTo
     }
+
+    /// Computes the arc length of the curve, which starts at the point `from`.
+    pub fn length(&self, from: (f64, f64)) -> f64 {
+        let (x0, y0) = from;
+        let (x1, y1) = self.pt1;
+        let (x2, y2) = self.pt2;
+        let (x3, y3) = self.to;
+
+        // The curve lies within its control polygon, so this is an upper bound for its length.
+        let polygon_length =
+            distance(from, self.pt1) + distance(self.pt1, self.pt2) + distance(self.pt2, self.to);
+
+        if polygon_length == 0.0 {
+            return 0.0;
+        }
+
+        // Magnitude of the derivative of the curve at t.
+        let speed = |t: f64| {
+            let mt = 1.0 - t;
+            let a = 3.0 * mt * mt;
+            let b = 6.0 * mt * t;
+            let c = 3.0 * t * t;
+
+            let dx = a * (x1 - x0) + b * (x2 - x1) + c * (x3 - x2);
+            let dy = a * (y1 - y0) + b * (y2 - y1) + c * (y3 - y2);
+
+            dx.hypot(dy)
+        };
+
+        integrate(&speed, 0.0, 1.0, polygon_length * ARC_LENGTH_TOLERANCE)
+    }
 }
 
 /// Conversion from endpoint parameterization to center parameterization.
@@ -239,6 +270,39 @@ impl EllipticalArc {
         }
     }
 
+    /// Computes the arc length of the elliptical arc.
+    pub fn length(&self) -> f64 {
+        match self.center_parameterization() {
+            ArcParameterization::CenterParameters {
+                radii: (rx, ry),
+                theta1,
+                delta_theta,
+                ..
+            } => {
+                // Magnitude of the derivative of the ellipse at angle θ; the rotation of
+                // the ellipse does not change it.
+                let speed = |theta: f64| {
+                    let (sin, cos) = theta.sin_cos();
+                    (rx * sin).hypot(ry * cos)
+                };
+
+                let (start, end) = if delta_theta >= 0.0 {
+                    (theta1, theta1 + delta_theta)
+                } else {
+                    (theta1 + delta_theta, theta1)
+                };
+
+                let tolerance = rx.max(ry) * delta_theta.abs() * ARC_LENGTH_TOLERANCE;
+
+                integrate(&speed, start, end, tolerance)
+            }
+
+            ArcParameterization::LineTo => distance(self.from, self.to),
+
+            ArcParameterization::Omit => 0.0,
+        }
+    }
+
     /// Consumes 7 coordinates and creates an arc segment.
     fn from_coords(
         large_arc: LargeArc,
@@ -720,6 +784,112 @@ impl Path {
     pub fn is_empty(&self) -> bool {
         self.commands.is_empty()
     }
+
+    /// Computes the total length of the path, as used for the `pathLength` attribute.
+    ///
+    /// Per the spec, this includes the length of the segments added by `ClosePath`, but
+    /// not the gaps between subpaths.
+    pub fn length(&self) -> f64 {
+        let mut length = 0.0;
+        let mut current = (0.0, 0.0);
+        let mut subpath_start = (0.0, 0.0);
+
+        for cmd in self.iter() {
+            match cmd {
+                PathCommand::MoveTo(x, y) => {
+                    current = (x, y);
+                    subpath_start = current;
+                }
+
+                PathCommand::LineTo(x, y) => {
+                    length += distance(current, (x, y));
+                    current = (x, y);
+                }
+
+                PathCommand::CurveTo(ref curve) => {
+                    length += curve.length(current);
+                    current = curve.to;
+                }
+
+                PathCommand::Arc(ref arc) => {
+                    length += arc.length();
+                    current = arc.to;
+                }
+
+                PathCommand::ClosePath => {
+                    length += distance(current, subpath_start);
+                    current = subpath_start;
+                }
+            }
+        }
+
+        length
+    }
+}
+
+/// Relative error allowed when computing the length of curves and arcs.
+const ARC_LENGTH_TOLERANCE: f64 = 1e-9;
+
+/// Maximum number of times that `integrate` will subdivide an interval.
+const MAX_INTEGRATION_DEPTH: u32 = 20;
+
+fn distance((x0, y0): (f64, f64), (x1, y1): (f64, f64)) -> f64 {
+    (x1 - x0).hypot(y1 - y0)
+}
+
+/// Integrates `f` over `[a, b]` with adaptive Gauss-Legendre quadrature.
+///
+/// The interval gets subdivided until the estimated error is below `tolerance`.
+fn integrate(f: &dyn Fn(f64) -> f64, a: f64, b: f64, tolerance: f64) -> f64 {
+    fn integrate_rec(
+        f: &dyn Fn(f64) -> f64,
+        a: f64,
+        b: f64,
+        whole: f64,
+        tolerance: f64,
+        depth: u32,
+    ) -> f64 {
+        let mid = (a + b) / 2.0;
+        let left = gauss_legendre(f, a, mid);
+        let right = gauss_legendre(f, mid, b);
+
+        if depth == 0 || (left + right - whole).abs() <= tolerance {
+            left + right
+        } else {
+            integrate_rec(f, a, mid, left, tolerance / 2.0, depth - 1)
+                + integrate_rec(f, mid, b, right, tolerance / 2.0, depth - 1)
+        }
+    }
+
+    integrate_rec(
+        f,
+        a,
+        b,
+        gauss_legendre(f, a, b),
+        tolerance,
+        MAX_INTEGRATION_DEPTH,
+    )
+}
+
+/// Integrates `f` over `[a, b]` with a 5-point Gauss-Legendre rule.
+fn gauss_legendre(f: &dyn Fn(f64) -> f64, a: f64, b: f64) -> f64 {
+    // (abscissa, weight) pairs for the interval [-1, 1]
+    const POINTS: [(f64, f64); 5] = [
+        (0.0, 0.5688888888888889),
+        (-0.5384693101056831, 0.47862867049936647),
+        (0.5384693101056831, 0.47862867049936647),
+        (-0.906179845938664, 0.23692688538459962),
+        (0.906179845938664, 0.23692688538459962),
+    ];
+
+    let half_width = (b - a) / 2.0;
+    let center = (a + b) / 2.0;
+
+    half_width
+        * POINTS
+            .iter()
+            .map(|&(x, w)| w * f(center + half_width * x))
+            .sum::<f64>()
 }
 
 fn take_one(iter: &mut slice::Iter<'_, f64>) -> f64 {
@@ -870,4 +1040,106 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn computes_length_of_lines() {
+        let mut builder = PathBuilder::default();
+        builder.move_to(10.0, 10.0);
+        builder.line_to(40.0, 10.0);
+        builder.line_to(40.0, 50.0);
+        builder.close_path();
+
+        // The gap between subpaths does not count
+        builder.move_to(100.0, 100.0);
+        builder.line_to(100.0, 110.0);
+
+        let path = builder.into_path();
+        assert!((path.length() - 130.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn computes_length_of_curves() {
+        let mut builder = PathBuilder::default();
+        builder.move_to(0.0, 0.0);
+        builder.curve_to(10.0, 0.0, 20.0, 0.0, 30.0, 0.0);
+
+        let path = builder.into_path();
+        assert!((path.length() - 30.0).abs() < 1e-9);
+
+        // Four curves that approximate a circle of radius 10
+        let mut builder = PathBuilder::default();
+        let k = 10.0 * 0.5522847498;
+        builder.move_to(10.0, 0.0);
+        builder.curve_to(10.0, k, k, 10.0, 0.0, 10.0);
+        builder.curve_to(-k, 10.0, -10.0, k, -10.0, 0.0);
+        builder.curve_to(-10.0, -k, -k, -10.0, 0.0, -10.0);
+        builder.curve_to(k, -10.0, 10.0, -k, 10.0, 0.0);
+        builder.close_path();
+
+        let path = builder.into_path();
+        assert!((path.length() - 20.0 * PI).abs() < 0.01);
+    }
+
+    #[test]
+    fn computes_length_of_arcs() {
+        let mut builder = PathBuilder::default();
+        builder.move_to(0.0, 0.0);
+        builder.arc(
+            0.0,
+            0.0,
+            10.0,
+            10.0,
+            0.0,
+            LargeArc(false),
+            Sweep::Positive,
+            20.0,
+            0.0,
+        );
+
+        let path = builder.into_path();
+        assert!((path.length() - 10.0 * PI).abs() < 1e-9);
+
+        // An ellipse made of two arcs
+        let mut builder = PathBuilder::default();
+        builder.move_to(-20.0, 0.0);
+        for (x1, x2) in [(-20.0, 20.0), (20.0, -20.0)] {
+            builder.arc(
+                x1,
+                0.0,
+                20.0,
+                10.0,
+                0.0,
+                LargeArc(false),
+                Sweep::Positive,
+                x2,
+                0.0,
+            );
+        }
+
+        let path = builder.into_path();
+
+        // Ramanujan's approximation is very precise for this ellipse
+        let h: f64 = (10.0_f64 / 30.0).powi(2);
+        let expected = PI * 30.0 * (1.0 + 3.0 * h / (10.0 + (4.0 - 3.0 * h).sqrt()));
+
+        assert!((path.length() - expected).abs() < 1e-6);
+
+        // Arcs with a zero radius are straight lines
+        let mut builder = PathBuilder::default();
+        builder.move_to(0.0, 0.0);
+        builder.arc(
+            0.0,
+            0.0,
+            0.0,
+            10.0,
+            0.0,
+            LargeArc(false),
+            Sweep::Positive,
+            30.0,
+            40.0,
+        );
+
+        let path = builder.into_path();
+        assert!((path.length() - 50.0).abs() < 1e-9);
+    }
 }
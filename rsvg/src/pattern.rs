@@ -217,10 +217,18 @@ impl UnresolvedPattern {
             .preserve_aspect_ratio
             .or(fallback.common.preserve_aspect_ratio);
         let transform = self.common.transform.or(fallback.common.transform);
-        let x = self.common.x.or(fallback.common.x);
-        let y = self.common.y.or(fallback.common.y);
-        let width = self.common.width.or(fallback.common.width);
-        let height = self.common.height.or(fallback.common.height);
+        let x = self.common.x.clone().or_else(|| fallback.common.x.clone());
+        let y = self.common.y.clone().or_else(|| fallback.common.y.clone());
+        let width = self
+            .common
+            .width
+            .clone()
+            .or_else(|| fallback.common.width.clone());
+        let height = self
+            .common
+            .height
+            .clone()
+            .or_else(|| fallback.common.height.clone());
         let children = self.children.resolve_from_fallback(&fallback.children);
 
         UnresolvedPattern {
@@ -254,10 +262,18 @@ impl UnresolvedPattern {
             .common
             .transform
             .or_else(|| Some(TransformAttribute::default()));
-        let x = self.common.x.or_else(|| Some(Default::default()));
-        let y = self.common.y.or_else(|| Some(Default::default()));
-        let width = self.common.width.or_else(|| Some(Default::default()));
-        let height = self.common.height.or_else(|| Some(Default::default()));
+        let x = self.common.x.clone().or_else(|| Some(Default::default()));
+        let y = self.common.y.clone().or_else(|| Some(Default::default()));
+        let width = self
+            .common
+            .width
+            .clone()
+            .or_else(|| Some(Default::default()));
+        let height = self
+            .common
+            .height
+            .clone()
+            .or_else(|| Some(Default::default()));
         let children = self.children.resolve_from_defaults();
 
         UnresolvedPattern {
@@ -0,0 +1,201 @@
+//! CSS `<position>` values, as used in `mask-position` and in gradient functions.
+//!
+//! CSS Values and Units 3: <https://www.w3.org/TR/css-values-3/#position>
+
+use cssparser::Parser;
+
+use crate::error::*;
+use crate::length::*;
+use crate::parse_identifiers;
+use crate::parsers::Parse;
+
+/// A two-dimensional position inside a box.
+///
+/// Keywords like `left` or `bottom` get translated to percentages at parsing time, so
+/// `right bottom` is the same as `100% 100%`.
+///
+/// Only the one- and two-value syntaxes are supported; the three- and four-value syntaxes
+/// with offsets from the edges (like `right 10px bottom 20px`) are not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub x: Length<Horizontal>,
+    pub y: Length<Vertical>,
+}
+
+/// One of the values in a `<position>`.
+#[derive(Clone)]
+enum Component {
+    Length(Length<Both>),
+    Left,
+    Center,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Component {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Component, ParseError<'i>> {
+        if let Ok(l) = parser.try_parse(Length::<Both>::parse) {
+            return Ok(Component::Length(l));
+        }
+
+        Ok(parse_identifiers!(
+            parser,
+            "left" => Component::Left,
+            "center" => Component::Center,
+            "right" => Component::Right,
+            "top" => Component::Top,
+            "bottom" => Component::Bottom,
+        )?)
+    }
+
+    fn horizontal(&self) -> Option<Length<Horizontal>> {
+        match self {
+            Component::Length(l) => Some(l.cast()),
+            Component::Left => Some(Length::new(0.0, LengthUnit::Percent)),
+            Component::Center => Some(Length::new(0.5, LengthUnit::Percent)),
+            Component::Right => Some(Length::new(1.0, LengthUnit::Percent)),
+            Component::Top | Component::Bottom => None,
+        }
+    }
+
+    fn vertical(&self) -> Option<Length<Vertical>> {
+        match self {
+            Component::Length(l) => Some(l.cast()),
+            Component::Top => Some(Length::new(0.0, LengthUnit::Percent)),
+            Component::Center => Some(Length::new(0.5, LengthUnit::Percent)),
+            Component::Bottom => Some(Length::new(1.0, LengthUnit::Percent)),
+            Component::Left | Component::Right => None,
+        }
+    }
+
+    fn is_keyword(&self) -> bool {
+        !matches!(self, Component::Length(_))
+    }
+}
+
+impl Position {
+    /// The `center` position, i.e. `50% 50%`.
+    pub fn center() -> Position {
+        Position {
+            x: Length::new(0.5, LengthUnit::Percent),
+            y: Length::new(0.5, LengthUnit::Percent),
+        }
+    }
+
+    /// The `left top` position, i.e. `0% 0%`.
+    pub fn top_left() -> Position {
+        Position {
+            x: Length::new(0.0, LengthUnit::Percent),
+            y: Length::new(0.0, LengthUnit::Percent),
+        }
+    }
+
+    /// Resolves the position to an offset from the top-left corner of a box.
+    ///
+    /// Percentages are relative to the `width` and `height`.  Note that for positioning
+    /// areas like in `mask-position`, those are the size of the area minus the size of
+    /// the object being positioned.
+    pub fn to_user(&self, params: &NormalizeParams, width: f64, height: f64) -> (f64, f64) {
+        (
+            self.x.to_user_relative_to(params, width),
+            self.y.to_user_relative_to(params, height),
+        )
+    }
+}
+
+impl Parse for Position {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Position, ParseError<'i>> {
+        let loc = parser.current_source_location();
+
+        let first = Component::parse(parser)?;
+        let second = parser.try_parse(Component::parse).ok();
+
+        let position = match second {
+            None => match first {
+                Component::Top | Component::Bottom => Some(Position {
+                    x: Length::new(0.5, LengthUnit::Percent),
+                    y: first.vertical().unwrap(),
+                }),
+
+                _ => first.horizontal().map(|x| Position {
+                    x,
+                    y: Length::new(0.5, LengthUnit::Percent),
+                }),
+            },
+
+            Some(second) => match (first.horizontal(), second.vertical()) {
+                (Some(x), Some(y)) => Some(Position { x, y }),
+
+                // Keywords can come in any order, as in "top left"
+                _ if first.is_keyword() && second.is_keyword() => {
+                    match (second.horizontal(), first.vertical()) {
+                        (Some(x), Some(y)) => Some(Position { x, y }),
+                        _ => None,
+                    }
+                }
+
+                _ => None,
+            },
+        };
+
+        position
+            .ok_or_else(|| loc.new_custom_error(ValueErrorKind::parse_error("invalid position")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: f64, xu: LengthUnit, y: f64, yu: LengthUnit) -> Position {
+        Position {
+            x: Length::new(x, xu),
+            y: Length::new(y, yu),
+        }
+    }
+
+    #[test]
+    fn parses_position() {
+        use LengthUnit::*;
+
+        assert_eq!(Position::parse_str("center").unwrap(), Position::center());
+        assert_eq!(
+            Position::parse_str("left top").unwrap(),
+            Position::top_left()
+        );
+        assert_eq!(
+            Position::parse_str("top left").unwrap(),
+            Position::top_left()
+        );
+        assert_eq!(
+            Position::parse_str("bottom").unwrap(),
+            pos(0.5, Percent, 1.0, Percent)
+        );
+        assert_eq!(
+            Position::parse_str("right").unwrap(),
+            pos(1.0, Percent, 0.5, Percent)
+        );
+        assert_eq!(
+            Position::parse_str("10px 20%").unwrap(),
+            pos(10.0, Px, 0.2, Percent)
+        );
+        assert_eq!(
+            Position::parse_str("10px bottom").unwrap(),
+            pos(10.0, Px, 1.0, Percent)
+        );
+        assert_eq!(
+            Position::parse_str("2em").unwrap(),
+            pos(2.0, Em, 0.5, Percent)
+        );
+    }
+
+    #[test]
+    fn invalid_position_yields_error() {
+        assert!(Position::parse_str("").is_err());
+        assert!(Position::parse_str("top bottom").is_err());
+        assert!(Position::parse_str("left right").is_err());
+        assert!(Position::parse_str("top 10px").is_err());
+        assert!(Position::parse_str("foo").is_err());
+    }
+}
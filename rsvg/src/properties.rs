@@ -19,6 +19,7 @@ use cssparser::{
 };
 use markup5ever::{ExpandedName, LocalName, QualName, expanded_name, local_name, ns};
 use std::collections::HashSet;
+use std::rc::Rc;
 
 #[cfg(doc)]
 use crate::make_property;
@@ -26,14 +27,16 @@ use crate::make_property;
 use crate::css::{DeclParser, Declaration, Origin, RuleBodyItem};
 use crate::error::*;
 use crate::parsers::{Parse, ParseValue};
-use crate::property_macros::Property;
+use crate::property_macros::{Property, ToCssValue};
 use crate::rsvg_log;
 use crate::session::Session;
+use crate::text::FontSelection;
 use crate::transform::{Transform, TransformAttribute, TransformProperty};
 use crate::xml::Attributes;
 
 // Re-export the actual properties so they are easy to find from a single place `properties::*`.
 pub use crate::font_props::*;
+pub use crate::mask_props::*;
 pub use crate::property_defs::*;
 
 /// Representation of a single CSS property value.
@@ -149,6 +152,79 @@ impl ComputedValues {
     pub fn is_visible(&self) -> bool {
         self.visibility() == Visibility::Visible
     }
+
+    /// The `text-orientation` to use for vertical text.
+    ///
+    /// The obsolete `glyph-orientation-vertical` property is a shorthand for
+    /// `text-orientation`, but SVG1.1 files still use it as a presentation attribute.
+    /// If it is set to something other than `auto`, it takes precedence.
+    pub fn resolved_text_orientation(&self) -> TextOrientation {
+        match self.glyph_orientation_vertical() {
+            GlyphOrientationVertical::Auto => self.text_orientation(),
+            o => TextOrientation::from(o),
+        }
+    }
+
+    /// The computed `font-size` of the root element, for the `rem` unit.
+    pub fn root_font_size(&self) -> FontSize {
+        match self.root_font {
+            Some(ref root) => root.font_size.clone(),
+            None => self.font_size(),
+        }
+    }
+
+    /// The font values of the root element, for the `rem` and `rlh` units.
+    ///
+    /// Returns `None` for the root element itself, whose own values are the root's.
+    pub fn root_font(&self) -> Option<Rc<RootFontValues>> {
+        self.root_font.clone()
+    }
+
+    /// The properties that select the font, for the `ic` and `cap` units.
+    ///
+    /// This is shared among all the elements that don't change those properties, so
+    /// it is cheap to get.
+    pub fn font_selection(&self) -> Option<Rc<FontSelection>> {
+        self.font_selection.clone()
+    }
+
+    /// Serializes the computed value of a longhand property, given its name.
+    ///
+    /// Returns `None` for unknown property names, and for values that can't be
+    /// serialized yet.
+    pub fn to_css_string(&self, name: &str) -> Option<String> {
+        match name {
+            // The `transform` attribute from SVG1.1 ends up in the computed transform
+            // too, not just the `transform` property, so report that.
+            "transform" => self.transform.to_css_value(),
+            _ => self.longhand_to_css_string(name),
+        }
+    }
+
+    /// Serializes the computed values of all the longhand properties.
+    ///
+    /// The result is sorted by property name.  Properties whose values can't be
+    /// serialized yet are still listed, with a value of `None`.
+    pub fn to_css_strings(&self) -> Vec<(&'static str, Option<String>)> {
+        let mut names = Self::LONGHAND_NAMES.to_vec();
+        names.sort_unstable();
+
+        names
+            .into_iter()
+            .map(|name| (name, self.to_css_string(name)))
+            .collect()
+    }
+}
+
+/// Font values of the root element, which the `rem` and `rlh` units refer to.
+///
+/// These get recorded in the [`ComputedValues`] of the root element while cascading, and
+/// are inherited by all of its descendants.  Before that happens, the values of the
+/// element itself are used.
+#[derive(Debug, Clone)]
+pub struct RootFontValues {
+    pub font_size: FontSize,
+    pub line_height: LineHeight,
 }
 
 /// Macro to generate all the machinery for properties.
@@ -265,6 +341,10 @@ macro_rules! make_properties {
             )+
 
             transform: Transform,
+
+            root_font: Option<Rc<RootFontValues>>,
+
+            font_selection: Option<Rc<FontSelection>>,
         }
 
         impl ParsedProperty {
@@ -322,6 +402,19 @@ macro_rules! make_properties {
                 }
             )+
 
+            const LONGHAND_NAMES: &[&str] = &[
+                $($long_str,)+
+                $($long_m5e_str,)+
+            ];
+
+            fn longhand_to_css_string(&self, name: &str) -> Option<String> {
+                match name {
+                    $($long_str => self.$long_field.to_css_string(),)+
+                    $($long_m5e_str => self.$long_m5e_field.to_css_string(),)+
+                    _ => None,
+                }
+            }
+
             fn set_value(&mut self, computed: ComputedValue) {
                 match computed {
                     $(ComputedValue::$long_name(v) => self.$long_field = v,)+
@@ -374,10 +467,7 @@ macro_rules! make_properties {
                 )+
 
                 $(
-                    expanded_name!("", $short_str) if parse_as == ParseAs::Property => {
-                        // No shorthand has a presentation attribute.
-                        assert!($short_presentation_attr == PresentationAttr::No);
-
+                    expanded_name!("", $short_str) if !(parse_as == ParseAs::PresentationAttr && $short_presentation_attr == PresentationAttr::No) => {
                         Ok(ParsedProperty::$short_name(parse_input(input)?))
                     }
                 )+
@@ -394,9 +484,12 @@ macro_rules! make_properties {
 #[rustfmt::skip]
 make_properties! {
     shorthands: {
-        // No shorthand has a presentation attribute.
-        "font"    => (PresentationAttr::No, font   : Font),
-        "marker"  => (PresentationAttr::No, marker : Marker),
+        "font"    => (PresentationAttr::No,  font   : Font),
+        "marker"  => (PresentationAttr::No,  marker : Marker),
+
+        // SVG1.1 has a mask="url(#foo)" presentation attribute, which is now the CSS
+        // Masking shorthand.
+        "mask"    => (PresentationAttr::Yes, mask   : Mask),
     }
 
     // longhands that are presentation attributes right now, but need to be turned into properties:
@@ -454,10 +547,9 @@ make_properties! {
         "marker-end"                  => (PresentationAttr::Yes, marker_end                  : MarkerEnd),
         "marker-mid"                  => (PresentationAttr::Yes, marker_mid                  : MarkerMid),
         "marker-start"                => (PresentationAttr::Yes, marker_start                : MarkerStart),
-        "mask"                        => (PresentationAttr::Yes, mask                        : Mask),
         "opacity"                     => (PresentationAttr::Yes, opacity                     : Opacity),
         "overflow"                    => (PresentationAttr::Yes, overflow                    : Overflow),
-        // "pointer-events"           => (PresentationAttr::Yes, unimplemented),
+        "pointer-events"              => (PresentationAttr::Yes, pointer_events              : PointerEvents),
         "r"                           => (PresentationAttr::Yes, r: R),
         "rx"                          => (PresentationAttr::Yes, rx: RX),
         "ry"                          => (PresentationAttr::Yes, ry: RY),
@@ -495,11 +587,20 @@ make_properties! {
     }
     
     longhands_not_supported_by_markup5ever: {
+        "backdrop-filter"             => (PresentationAttr::No,  backdrop_filter             : BackdropFilter),
         "isolation"                   => (PresentationAttr::No,  isolation                   : Isolation),
         "line-height"                 => (PresentationAttr::No,  line_height                 : LineHeight),
+        "mask-composite"              => (PresentationAttr::No,  mask_composite              : MaskComposite),
+        "mask-image"                  => (PresentationAttr::No,  mask_image                  : MaskImage),
+        "mask-mode"                   => (PresentationAttr::No,  mask_mode                   : MaskMode),
+        "mask-position"               => (PresentationAttr::No,  mask_position               : MaskPosition),
+        "mask-repeat"                 => (PresentationAttr::No,  mask_repeat                 : MaskRepeat),
+        "mask-size"                   => (PresentationAttr::No,  mask_size                   : MaskSize),
         "mask-type"                   => (PresentationAttr::Yes, mask_type                   : MaskType),
         "mix-blend-mode"              => (PresentationAttr::No,  mix_blend_mode              : MixBlendMode),
         "paint-order"                 => (PresentationAttr::Yes, paint_order                 : PaintOrder),
+        "text-align"                  => (PresentationAttr::No,  text_align                  : TextAlign),
+        "text-combine-upright"        => (PresentationAttr::No,  text_combine_upright        : TextCombineUpright),
         "text-orientation"            => (PresentationAttr::No,  text_orientation            : TextOrientation),
         "vector-effect"               => (PresentationAttr::Yes, vector_effect               : VectorEffect),
         "white-space"                 => (PresentationAttr::Yes, white_space                 : WhiteSpace),
@@ -558,12 +659,18 @@ impl SpecifiedValues {
             ParsedProperty::Marker(SpecifiedValue::Specified(ref m)) => {
                 self.expand_marker_shorthand(m, replace)
             }
+            ParsedProperty::Mask(SpecifiedValue::Specified(ref m)) => {
+                self.expand_mask_shorthand(m, replace)
+            }
             ParsedProperty::Font(SpecifiedValue::Inherit) => {
                 self.expand_font_shorthand_inherit(replace)
             }
             ParsedProperty::Marker(SpecifiedValue::Inherit) => {
                 self.expand_marker_shorthand_inherit(replace)
             }
+            ParsedProperty::Mask(SpecifiedValue::Inherit) => {
+                self.expand_mask_shorthand_inherit(replace)
+            }
 
             _ => self.set_property(prop, replace),
         }
@@ -627,6 +734,42 @@ impl SpecifiedValues {
         );
     }
 
+    fn expand_mask_shorthand(&mut self, mask: &Mask, replace: bool) {
+        let Mask {
+            image,
+            mode,
+            position,
+            size,
+            repeat,
+            composite,
+        } = mask;
+
+        self.set_property(
+            &ParsedProperty::MaskImage(SpecifiedValue::Specified(image.clone())),
+            replace,
+        );
+        self.set_property(
+            &ParsedProperty::MaskMode(SpecifiedValue::Specified(mode.clone())),
+            replace,
+        );
+        self.set_property(
+            &ParsedProperty::MaskPosition(SpecifiedValue::Specified(position.clone())),
+            replace,
+        );
+        self.set_property(
+            &ParsedProperty::MaskSize(SpecifiedValue::Specified(size.clone())),
+            replace,
+        );
+        self.set_property(
+            &ParsedProperty::MaskRepeat(SpecifiedValue::Specified(repeat.clone())),
+            replace,
+        );
+        self.set_property(
+            &ParsedProperty::MaskComposite(SpecifiedValue::Specified(composite.clone())),
+            replace,
+        );
+    }
+
     fn expand_font_shorthand_inherit(&mut self, replace: bool) {
         self.set_property(&ParsedProperty::FontStyle(SpecifiedValue::Inherit), replace);
         self.set_property(
@@ -661,6 +804,24 @@ impl SpecifiedValues {
         self.set_property(&ParsedProperty::MarkerEnd(SpecifiedValue::Inherit), replace);
     }
 
+    fn expand_mask_shorthand_inherit(&mut self, replace: bool) {
+        self.set_property(&ParsedProperty::MaskImage(SpecifiedValue::Inherit), replace);
+        self.set_property(&ParsedProperty::MaskMode(SpecifiedValue::Inherit), replace);
+        self.set_property(
+            &ParsedProperty::MaskPosition(SpecifiedValue::Inherit),
+            replace,
+        );
+        self.set_property(&ParsedProperty::MaskSize(SpecifiedValue::Inherit), replace);
+        self.set_property(
+            &ParsedProperty::MaskRepeat(SpecifiedValue::Inherit),
+            replace,
+        );
+        self.set_property(
+            &ParsedProperty::MaskComposite(SpecifiedValue::Inherit),
+            replace,
+        );
+    }
+
     pub fn set_parsed_property(&mut self, prop: &ParsedProperty) {
         self.set_property_expanding_shorthands(prop, true);
     }
@@ -697,6 +858,7 @@ impl SpecifiedValues {
 
         // Then, do all the other properties.
 
+        compute!(BackdropFilter, backdrop_filter);
         compute!(BaselineShift, baseline_shift);
         compute!(ClipPath, clip_path);
         compute!(ClipRule, clip_rule);
@@ -728,12 +890,18 @@ impl SpecifiedValues {
         compute!(MarkerEnd, marker_end);
         compute!(MarkerMid, marker_mid);
         compute!(MarkerStart, marker_start);
-        compute!(Mask, mask);
+        compute!(MaskComposite, mask_composite);
+        compute!(MaskImage, mask_image);
+        compute!(MaskMode, mask_mode);
+        compute!(MaskPosition, mask_position);
+        compute!(MaskRepeat, mask_repeat);
+        compute!(MaskSize, mask_size);
         compute!(MaskType, mask_type);
         compute!(MixBlendMode, mix_blend_mode);
         compute!(Opacity, opacity);
         compute!(Overflow, overflow);
         compute!(PaintOrder, paint_order);
+        compute!(PointerEvents, pointer_events);
         compute!(R, r);
         compute!(RX, rx);
         compute!(RY, ry);
@@ -748,7 +916,9 @@ impl SpecifiedValues {
         compute!(StrokeOpacity, stroke_opacity);
         compute!(StrokeMiterlimit, stroke_miterlimit);
         compute!(StrokeWidth, stroke_width);
+        compute!(TextAlign, text_align);
         compute!(TextAnchor, text_anchor);
+        compute!(TextCombineUpright, text_combine_upright);
         compute!(TextDecoration, text_decoration);
         compute!(TextOrientation, text_orientation);
         compute!(TextRendering, text_rendering);
@@ -772,6 +942,28 @@ impl SpecifiedValues {
                 _ => Transform::identity(),
             }
         });
+
+        if computed.root_font.is_none() {
+            computed.root_font = Some(Rc::new(RootFontValues {
+                font_size: computed.font_size(),
+                line_height: computed.line_height(),
+            }));
+        }
+
+        // Keep sharing the parent's font selection unless this element changes it.
+        let changes_font = [
+            PropertyId::FontFamily,
+            PropertyId::FontStyle,
+            PropertyId::FontVariant,
+            PropertyId::FontWeight,
+            PropertyId::FontStretch,
+        ]
+        .into_iter()
+        .any(|id| self.property_index(id).is_some());
+
+        if changes_font || computed.font_selection.is_none() {
+            computed.font_selection = Some(Rc::new(FontSelection::new(computed)));
+        }
     }
 
     /// This is a somewhat egregious hack to allow xml:lang to be stored as a presentational
@@ -1121,4 +1313,50 @@ mod tests {
 
         assert_eq!(computed.opacity(), half_opacity.clone());
     }
+
+    #[test]
+    fn serializes_computed_values() {
+        let mut specified = SpecifiedValues::default();
+
+        specified.set_parsed_property(&ParsedProperty::Fill(SpecifiedValue::Specified(
+            Fill::parse_str("url(#foo) red").unwrap(),
+        )));
+        specified.set_parsed_property(&ParsedProperty::Display(SpecifiedValue::Specified(
+            Display::None,
+        )));
+        specified.set_parsed_property(&ParsedProperty::StrokeDasharray(SpecifiedValue::Specified(
+            StrokeDasharray::parse_str("1 50% 2.5mm").unwrap(),
+        )));
+        specified.set_parsed_property(&ParsedProperty::StrokeWidth(SpecifiedValue::Specified(
+            StrokeWidth::parse_str("calc(1px + 1em)").unwrap(),
+        )));
+
+        let mut computed = ComputedValues::default();
+        specified.to_computed_values(&mut computed);
+
+        assert_eq!(
+            computed.to_css_string("fill").as_deref(),
+            Some("url(#foo) rgb(255, 0, 0)")
+        );
+        assert_eq!(computed.to_css_string("display").as_deref(), Some("none"));
+        assert_eq!(
+            computed.to_css_string("stroke-dasharray").as_deref(),
+            Some("1px, 50%, 2.5mm")
+        );
+        assert_eq!(
+            computed.to_css_string("stroke-opacity").as_deref(),
+            Some("1")
+        );
+        assert_eq!(computed.to_css_string("transform").as_deref(), Some("none"));
+        assert_eq!(computed.to_css_string("font-size").as_deref(), Some("12px"));
+
+        assert_eq!(computed.to_css_string("stroke-width"), None);
+        assert_eq!(computed.to_css_string("font"), None);
+        assert_eq!(computed.to_css_string("foo"), None);
+
+        let all = computed.to_css_strings();
+        assert!(all.is_sorted_by_key(|(name, _)| *name));
+        assert!(all.contains(&("display", Some(String::from("none")))));
+        assert!(all.contains(&("stroke-width", None)));
+    }
 }
@@ -40,6 +40,7 @@ use std::str::FromStr;
 use cssparser::{Parser, Token};
 use language_tags::LanguageTag;
 
+use crate::calc::CalcNode;
 use crate::dasharray::Dasharray;
 use crate::error::*;
 use crate::filter::FilterValueList;
@@ -48,16 +49,70 @@ use crate::font_props::{
 };
 use crate::iri::Iri;
 use crate::length::*;
+use crate::mask_props::{
+    BgSize, CompositingOperator, Mask, MaskComposite, MaskImage, MaskMode, MaskPosition,
+    MaskReference, MaskRepeat, MaskSize, MaskingMode, RepeatStyle, Repetition,
+};
 use crate::paint_server::PaintServer;
 use crate::parse_identifiers;
 use crate::parsers::Parse;
+use crate::position::Position;
 use crate::properties::ComputedValues;
-use crate::property_macros::Property;
+use crate::property_macros::{Property, ToCssValue};
 use crate::rect::Rect;
-use crate::transform::TransformProperty;
+use crate::transform::{Transform as Affine, TransformProperty};
 use crate::unit_interval::UnitInterval;
 use crate::{impl_default, impl_property, make_property};
 
+/// `backdrop-filter` property.
+///
+/// Filter Effects 2: <https://drafts.fxtf.org/filter-effects-2/#BackdropFilterProperty>
+///
+/// This takes the same values as the [`Filter`] property, but the filters run on what is
+/// behind the element instead of on the element itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackdropFilter {
+    None,
+    List(FilterValueList),
+}
+
+make_property!(
+    BackdropFilter,
+    default: BackdropFilter::None,
+    inherits_automatically: false,
+    parse_impl: {
+        impl Parse for BackdropFilter {
+            fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, crate::error::ParseError<'i>> {
+
+                if parser
+                    .try_parse(|p| p.expect_ident_matching("none"))
+                    .is_ok()
+                {
+                    return Ok(BackdropFilter::None);
+                }
+
+                Ok(BackdropFilter::List(FilterValueList::parse(parser)?))
+            }
+        }
+    }
+);
+
+#[cfg(test)]
+#[test]
+fn parses_backdrop_filter() {
+    assert_eq!(
+        BackdropFilter::parse_str("none").unwrap(),
+        BackdropFilter::None
+    );
+
+    assert!(matches!(
+        BackdropFilter::parse_str("blur(8px) saturate(150%)").unwrap(),
+        BackdropFilter::List(_)
+    ));
+
+    assert!(BackdropFilter::parse_str("plonk").is_err());
+}
+
 make_property!(
     /// `baseline-shift` property.
     ///
@@ -78,6 +133,20 @@ make_property!(
                 let parent = v.baseline_shift();
 
                 match (self.0.unit, parent.0.unit) {
+                    // Math functions can hold terms with different units, so just add the
+                    // lengths together; percentages refer to the font size.
+                    _ if self.0.is_calc() || parent.0.is_calc() || font_size.is_calc() => {
+                        let shift = self.0.to_calc_node().map_lengths(&|l, unit| match unit {
+                            LengthUnit::Percent => font_size.scaled(l).to_calc_node(),
+                            _ => CalcNode::Length(l, unit),
+                        });
+
+                        BaselineShift(Length::<Both>::from_calc_node(CalcNode::Sum(vec![
+                            shift,
+                            parent.0.to_calc_node(),
+                        ])))
+                    }
+
                     (LengthUnit::Percent, _) => {
                         BaselineShift(Length::<Both>::new(self.0.length * font_size.length + parent.0.length, font_size.unit))
                     }
@@ -281,8 +350,9 @@ make_property!(
 ///
 /// SVG1.1: <https://www.w3.org/TR/SVG11/filters.html#EnableBackgroundProperty>
 ///
-/// This is deprecated in SVG2.  We just have a parser for it to avoid setting elements in
-/// error if they have this property.  Librsvg does not use the value of this property.
+/// This is deprecated in SVG2, but librsvg still honors `new` like `isolation: isolate`:
+/// the element starts a new background for the `BackgroundImage` and `BackgroundAlpha`
+/// filter inputs of its descendants.  The optional rectangle after `new` is ignored.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EnableBackground {
     Accumulate,
@@ -458,7 +528,21 @@ make_property!(
     // docs are in font_props.rs
     FontFamily,
     default: FontFamily("Times New Roman".to_string()),
-    inherits_automatically: true,
+    property_impl: {
+        impl Property for FontFamily {
+            fn inherits_automatically() -> bool {
+                true
+            }
+
+            fn compute(&self, _v: &ComputedValues) -> Self {
+                self.clone()
+            }
+
+            fn to_css_string(&self) -> Option<String> {
+                self.to_css_value()
+            }
+        }
+    }
 );
 
 make_property!(
@@ -474,6 +558,10 @@ make_property!(
             fn compute(&self, v: &ComputedValues) -> Self {
                 self.compute(v)
             }
+
+            fn to_css_string(&self) -> Option<String> {
+                self.to_css_value()
+            }
         }
     }
 );
@@ -548,6 +636,10 @@ make_property!(
             fn compute(&self, v: &ComputedValues) -> Self {
                 self.compute(&v.font_weight())
             }
+
+            fn to_css_string(&self) -> Option<String> {
+                self.to_css_value()
+            }
         }
     }
 );
@@ -558,9 +650,12 @@ make_property!(
     // Although https://www.w3.org/TR/css-writing-modes-3/#propdef-glyph-orientation-vertical specifies
     // "n/a" for both the initial value (default) and inheritance, we'll use Auto here for the default,
     // since it translates to TextOrientation::Mixed - which is text-orientation's initial value.
+    //
+    // Since this is a shorthand for text-orientation, which is inherited, we make it
+    // inherit as well.  See ComputedValues::resolved_text_orientation().
     GlyphOrientationVertical,
     default: GlyphOrientationVertical::Auto,
-    inherits_automatically: false,
+    inherits_automatically: true,
 );
 
 make_property!(
@@ -623,6 +718,10 @@ make_property!(
             fn compute(&self, _v: &ComputedValues) -> Self {
                 self.compute()
             }
+
+            fn to_css_string(&self) -> Option<String> {
+                self.to_css_value()
+            }
         }
     }
 );
@@ -631,7 +730,21 @@ make_property!(
     // docs are in font_props.rs
     LineHeight,
     default: LineHeight::Normal,
-    inherits_automatically: true,
+    property_impl: {
+        impl Property for LineHeight {
+            fn inherits_automatically() -> bool {
+                true
+            }
+
+            fn compute(&self, _v: &ComputedValues) -> Self {
+                self.clone()
+            }
+
+            fn to_css_string(&self) -> Option<String> {
+                self.to_css_value()
+            }
+        }
+    }
 );
 
 make_property!(
@@ -690,17 +803,62 @@ make_property!(
 );
 
 make_property!(
-    /// `mask` shorthand property.
-    ///
-    /// SVG1.1: <https://www.w3.org/TR/SVG11/masking.html#MaskProperty>
-    ///
-    /// CSS Masking 1: <https://www.w3.org/TR/css-masking-1/#the-mask>
-    ///
-    /// Note that librsvg implements SVG1.1 semantics, where this is not a shorthand.
+    // docs are in mask_props.rs
     Mask,
-    default: Iri::None,
+    default: Mask {
+        image: Default::default(),
+        mode: Default::default(),
+        position: Default::default(),
+        size: Default::default(),
+        repeat: Default::default(),
+        composite: Default::default(),
+    },
+    inherits_automatically: false,
+);
+
+make_property!(
+    // docs are in mask_props.rs
+    MaskComposite,
+    default: MaskComposite(vec![CompositingOperator::Add]),
+    inherits_automatically: false,
+);
+
+make_property!(
+    // docs are in mask_props.rs
+    MaskImage,
+    default: MaskImage(vec![MaskReference::None]),
+    inherits_automatically: false,
+);
+
+make_property!(
+    // docs are in mask_props.rs
+    MaskMode,
+    default: MaskMode(vec![MaskingMode::MatchSource]),
+    inherits_automatically: false,
+);
+
+make_property!(
+    // docs are in mask_props.rs
+    MaskPosition,
+    default: MaskPosition(vec![Position::top_left()]),
+    inherits_automatically: false,
+);
+
+make_property!(
+    // docs are in mask_props.rs
+    MaskRepeat,
+    default: MaskRepeat(vec![RepeatStyle {
+        x: Repetition::Repeat,
+        y: Repetition::Repeat,
+    }]),
+    inherits_automatically: false,
+);
+
+make_property!(
+    // docs are in mask_props.rs
+    MaskSize,
+    default: MaskSize(vec![BgSize::Size(LengthOrAuto::Auto, LengthOrAuto::Auto)]),
     inherits_automatically: false,
-    newtype_parse: Iri,
 );
 
 make_property!(
@@ -867,6 +1025,139 @@ fn parses_paint_order() {
     assert!(PaintOrder::parse_str("markers stroke fill hello").is_err());
 }
 
+make_property!(
+    /// `pointer-events` property.
+    ///
+    /// SVG1.1: <https://www.w3.org/TR/SVG11/interact.html#PointerEventsProperty>
+    ///
+    /// SVG2: <https://svgwg.org/svg2-draft/interact.html#PointerEventsProperty>
+    ///
+    /// The `auto` value from CSS is not in the SVG specifications, but it is common in
+    /// SVG documents; browsers treat it like `visiblePainted` for SVG elements.
+    PointerEvents,
+    default: VisiblePainted,
+    inherits_automatically: true,
+
+    identifiers:
+    "auto" => Auto,
+    "bounding-box" => BoundingBox,
+    "visiblePainted" => VisiblePainted,
+    "visibleFill" => VisibleFill,
+    "visibleStroke" => VisibleStroke,
+    "visible" => Visible,
+    "painted" => Painted,
+    "fill" => Fill,
+    "stroke" => Stroke,
+    "all" => All,
+    "none" => None,
+);
+
+/// Regions of an element that can be the target of pointer events; see [`PointerEvents::targets`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PointerTargets {
+    pub fill: bool,
+    pub stroke: bool,
+    pub bounding_box: bool,
+}
+
+impl PointerEvents {
+    /// Computes which regions of an element receive pointer events.
+    ///
+    /// The arguments say whether the element's `visibility` is `visible`, and whether
+    /// its `fill` and `stroke` are something other than `none`.
+    pub fn targets(
+        &self,
+        is_visible: bool,
+        fill_painted: bool,
+        stroke_painted: bool,
+    ) -> PointerTargets {
+        let (fill, stroke) = match *self {
+            PointerEvents::BoundingBox => {
+                return PointerTargets {
+                    bounding_box: true,
+                    ..Default::default()
+                };
+            }
+
+            PointerEvents::Auto | PointerEvents::VisiblePainted => {
+                (is_visible && fill_painted, is_visible && stroke_painted)
+            }
+            PointerEvents::VisibleFill => (is_visible, false),
+            PointerEvents::VisibleStroke => (false, is_visible),
+            PointerEvents::Visible => (is_visible, is_visible),
+            PointerEvents::Painted => (fill_painted, stroke_painted),
+            PointerEvents::Fill => (true, false),
+            PointerEvents::Stroke => (false, true),
+            PointerEvents::All => (true, true),
+            PointerEvents::None => (false, false),
+        };
+
+        PointerTargets {
+            fill,
+            stroke,
+            bounding_box: false,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn computes_pointer_targets() {
+    let targets = |s: &str, is_visible, fill_painted, stroke_painted| {
+        PointerEvents::parse_str(s)
+            .unwrap()
+            .targets(is_visible, fill_painted, stroke_painted)
+    };
+
+    let target = |fill, stroke, bounding_box| PointerTargets {
+        fill,
+        stroke,
+        bounding_box,
+    };
+
+    assert_eq!(
+        targets("visiblePainted", true, true, false),
+        target(true, false, false)
+    );
+    assert_eq!(
+        targets("visiblepainted", false, true, true),
+        target(false, false, false)
+    );
+    assert_eq!(
+        targets("visibleStroke", true, false, false),
+        target(false, true, false)
+    );
+    assert_eq!(
+        targets("visible", false, true, true),
+        target(false, false, false)
+    );
+    assert_eq!(
+        targets("painted", false, false, true),
+        target(false, true, false)
+    );
+    assert_eq!(
+        targets("all", false, false, false),
+        target(true, true, false)
+    );
+    assert_eq!(
+        targets("none", true, true, true),
+        target(false, false, false)
+    );
+    assert_eq!(
+        targets("bounding-box", false, false, false),
+        target(false, false, true)
+    );
+
+    assert_eq!(
+        targets("auto", true, false, true),
+        target(false, true, false)
+    );
+    assert_eq!(
+        targets("auto", false, true, true),
+        target(false, false, false)
+    );
+}
+
 make_property!(
     /// `r` property.
     ///
@@ -994,6 +1285,8 @@ make_property!(
     "miter" => Miter,
     "round" => Round,
     "bevel" => Bevel,
+    "arcs" => Arcs,
+    "miter-clip" => MiterClip,
 );
 
 make_property!(
@@ -1026,6 +1319,25 @@ make_property!(
     newtype_parse: Length::<Both>,
 );
 
+make_property!(
+    /// `text-align` property.
+    ///
+    /// This only applies to the XHTML content inside `<foreignObject>` elements.
+    ///
+    /// CSS Text 3: <https://www.w3.org/TR/css-text-3/#text-align-property>
+    TextAlign,
+    default: Start,
+    inherits_automatically: true,
+
+    identifiers:
+    "start" => Start,
+    "end" => End,
+    "left" => Left,
+    "right" => Right,
+    "center" => Center,
+    "justify" => Justify,
+);
+
 make_property!(
     /// `text-anchor` property.
     ///
@@ -1040,6 +1352,22 @@ make_property!(
     "end" => End,
 );
 
+make_property!(
+    /// `text-combine-upright` property.
+    ///
+    /// CSS Writing Modes 3: <https://www.w3.org/TR/css-writing-modes-3/#propdef-text-combine-upright>
+    ///
+    /// This only has an effect in vertical writing modes.  The `digits <integer>` value
+    /// from CSS Writing Modes 4 is not supported.
+    TextCombineUpright,
+    default: None,
+    inherits_automatically: true,
+
+    identifiers:
+    "none" => None,
+    "all" => All,
+);
+
 make_property!(
     /// `text-decoration` shorthand property.
     ///
@@ -1225,15 +1553,187 @@ make_property!(
     ///
     /// SVG2: <https://svgwg.org/svg2-draft/coords.html#VectorEffectProperty>
     VectorEffect,
-    default: None,
     inherits_automatically: false,
 
-    identifiers:
-    "none" => None,
-    "non-scaling-stroke" => NonScalingStroke,
-    // non-scaling-size, non-rotation, fixed-position not implemented
+    fields: {
+        non_scaling_stroke: bool, default: false,
+        non_scaling_size: bool, default: false,
+        non_rotation: bool, default: false,
+        fixed_position: bool, default: false,
+    }
+
+    parse_impl: {
+        impl Parse for VectorEffect {
+            fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<VectorEffect, ParseError<'i>> {
+                let mut effect = VectorEffect::default();
+
+                if parser.try_parse(|p| p.expect_ident_matching("none")).is_ok() {
+                    return Ok(effect);
+                }
+
+                loop {
+                    let loc = parser.current_source_location();
+                    let token = parser.next()?;
+
+                    match token {
+                        Token::Ident(cow) if cow.eq_ignore_ascii_case("non-scaling-stroke") => {
+                            effect.non_scaling_stroke = true
+                        }
+                        Token::Ident(cow) if cow.eq_ignore_ascii_case("non-scaling-size") => {
+                            effect.non_scaling_size = true
+                        }
+                        Token::Ident(cow) if cow.eq_ignore_ascii_case("non-rotation") => {
+                            effect.non_rotation = true
+                        }
+                        Token::Ident(cow) if cow.eq_ignore_ascii_case("fixed-position") => {
+                            effect.fixed_position = true
+                        }
+                        _ => return Err(loc.new_basic_unexpected_token_error(token.clone()).into()),
+                    }
+
+                    if parser.is_exhausted() {
+                        break;
+                    }
+                }
+
+                Ok(effect)
+            }
+        }
+    }
 );
 
+impl VectorEffect {
+    /// Modifies an element's user space per the `non-scaling-size`, `non-rotation`, and
+    /// `fixed-position` effects.
+    ///
+    /// `ctm` is the transform from the element's user space to device space, and
+    /// `own_transform` is the part of it that comes from the element itself.  The
+    /// effects are computed relative to the `host` coordinate space, which is the initial
+    /// viewport's; see <https://svgwg.org/svg2-draft/coords.html#VectorEffects>.
+    ///
+    /// Using both `non-scaling-size` and `non-rotation` leaves just the translation, so
+    /// the element keeps its size and orientation in the host coordinate space.
+    pub fn user_space_transform(
+        &self,
+        ctm: &Affine,
+        own_transform: &Affine,
+        host: &Affine,
+    ) -> Affine {
+        if !(self.non_scaling_size || self.non_rotation || self.fixed_position) {
+            return *ctm;
+        }
+
+        let Some(host_inverse) = host.invert() else {
+            return *ctm;
+        };
+
+        // Transform from user space to the host coordinate space.
+        let mut m = Affine::multiply(ctm, &host_inverse);
+
+        if self.non_rotation {
+            let sx = m.xx.hypot(m.yx);
+
+            if sx > 0.0 {
+                let det = m.xx * m.yy - m.xy * m.yx;
+                m = Affine::new_unchecked(sx, 0.0, 0.0, det / sx, m.x0, m.y0);
+            }
+        }
+
+        if self.non_scaling_size {
+            let scale = (m.xx * m.yy - m.xy * m.yx).abs().sqrt();
+
+            if scale > 0.0 {
+                m = Affine::new_unchecked(
+                    m.xx / scale,
+                    m.yx / scale,
+                    m.xy / scale,
+                    m.yy / scale,
+                    m.x0,
+                    m.y0,
+                );
+            }
+        }
+
+        if self.fixed_position {
+            m.x0 = own_transform.x0;
+            m.y0 = own_transform.y0;
+        }
+
+        Affine::multiply(&m, host)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn parses_vector_effect() {
+    assert_eq!(
+        VectorEffect::parse_str("none").unwrap(),
+        VectorEffect::default()
+    );
+
+    assert_eq!(
+        VectorEffect::parse_str("non-scaling-stroke").unwrap(),
+        VectorEffect {
+            non_scaling_stroke: true,
+            ..Default::default()
+        }
+    );
+
+    assert_eq!(
+        VectorEffect::parse_str("non-scaling-size non-rotation fixed-position").unwrap(),
+        VectorEffect {
+            non_scaling_stroke: false,
+            non_scaling_size: true,
+            non_rotation: true,
+            fixed_position: true,
+        }
+    );
+
+    assert!(VectorEffect::parse_str("").is_err());
+    assert!(VectorEffect::parse_str("none non-rotation").is_err());
+    assert!(VectorEffect::parse_str("non-scaling-size foo").is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn computes_vector_effect_transforms() {
+    let host = Affine::new_scale(2.0, 2.0);
+    let own = Affine::new_translate(10.0, 20.0);
+
+    // User space scaled by 3 and rotated by 90 degrees under the host, with the origin
+    // mapped to (30, 40) in the host coordinate space.
+    let ctm = Affine::new_unchecked(0.0, 3.0, -3.0, 0.0, 30.0, 40.0).post_transform(&host);
+
+    let apply = |s: &str| {
+        VectorEffect::parse_str(s)
+            .unwrap()
+            .user_space_transform(&ctm, &own, &host)
+    };
+
+    assert_eq!(apply("none"), ctm);
+    assert_eq!(apply("non-scaling-stroke"), ctm);
+
+    assert_eq!(
+        apply("non-scaling-size"),
+        Affine::new_unchecked(0.0, 1.0, -1.0, 0.0, 30.0, 40.0).post_transform(&host)
+    );
+
+    assert_eq!(
+        apply("non-rotation"),
+        Affine::new_unchecked(3.0, 0.0, 0.0, 3.0, 30.0, 40.0).post_transform(&host)
+    );
+
+    assert_eq!(
+        apply("non-scaling-size non-rotation"),
+        Affine::new_translate(30.0, 40.0).post_transform(&host)
+    );
+
+    assert_eq!(
+        apply("fixed-position"),
+        Affine::new_unchecked(0.0, 3.0, -3.0, 0.0, 10.0, 20.0).post_transform(&host)
+    );
+}
+
 make_property!(
     /// `visibility` property.
     ///
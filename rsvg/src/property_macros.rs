@@ -17,6 +17,32 @@ pub trait Property {
     ///
     /// The CSS or SVG specs say how to derive this for each property.
     fn compute(&self, _: &ComputedValues) -> Self;
+
+    /// Serializes the property's value in CSS syntax.
+    ///
+    /// This is used to report computed values through the public API.  Properties
+    /// that don't know how to serialize themselves yet return `None`.
+    fn to_css_string(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Trait for general-purpose value types that properties are made of.
+///
+/// The newtype properties generated by `make_property!` use this to implement
+/// [`Property::to_css_string`] in terms of their inner type.
+pub trait ToCssValue {
+    /// Serializes the value in CSS syntax, or returns `None` if this particular
+    /// value can't be serialized yet.
+    fn to_css_value(&self) -> Option<String>;
+}
+
+impl ToCssValue for f64 {
+    fn to_css_value(&self) -> Option<String> {
+        // Values are parsed as f32, so print them as such to avoid noise like
+        // 0.30000001192092896.
+        Some((*self as f32).to_string())
+    }
 }
 
 /// Generates a type for a CSS property.
@@ -119,7 +145,24 @@ macro_rules! make_property {
         }
 
         impl_default!($name, $name::$default);
-        impl_property!($name, $inherits_automatically);
+
+        impl $crate::property_macros::Property for $name {
+            fn inherits_automatically() -> bool {
+                $inherits_automatically
+            }
+
+            fn compute(&self, _v: &$crate::properties::ComputedValues) -> Self {
+                *self
+            }
+
+            fn to_css_string(&self) -> Option<String> {
+                let s = match *self {
+                    $($name::$variant => $str_prop,)+
+                };
+
+                Some(String::from(s))
+            }
+        }
 
         impl $crate::parsers::Parse for $name {
             fn parse<'i>(parser: &mut ::cssparser::Parser<'i, '_>) -> Result<$name, $crate::error::ParseError<'i>> {
@@ -168,7 +211,20 @@ macro_rules! make_property {
         pub struct $name(pub $type);
 
         impl_default!($name, $name($default));
-        impl_property!($name, $inherits_automatically);
+
+        impl $crate::property_macros::Property for $name {
+            fn inherits_automatically() -> bool {
+                $inherits_automatically
+            }
+
+            fn compute(&self, _v: &$crate::properties::ComputedValues) -> Self {
+                self.clone()
+            }
+
+            fn to_css_string(&self) -> Option<String> {
+                $crate::property_macros::ToCssValue::to_css_value(&self.0)
+            }
+        }
 
         impl $crate::parsers::Parse for $name {
             fn parse<'i>(parser: &mut ::cssparser::Parser<'i, '_>) -> Result<$name, $crate::error::ParseError<'i>> {
@@ -16,37 +16,23 @@ use crate::is_element_of_type;
 use crate::layout::{Layer, LayerKind, Marker, Shape, StackingContext, Stroke};
 use crate::length::*;
 use crate::node::{CascadedValues, Node, NodeBorrow};
-use crate::parsers::{Parse, ParseValue, optional_comma};
+use crate::parsers::{NonNegative, Parse, ParseValue, optional_comma};
 use crate::path_builder::{LargeArc, Path as SvgPath, PathBuilder, Sweep};
 use crate::properties::ComputedValues;
 use crate::rsvg_log;
 use crate::session::Session;
 use crate::xml::Attributes;
 
-#[derive(PartialEq)]
-enum Markers {
-    No,
-    Yes,
-}
-
-struct ShapeDef {
-    path: Rc<SvgPath>,
-    markers: Markers,
-}
-
-impl ShapeDef {
-    fn new(path: Rc<SvgPath>, markers: Markers) -> ShapeDef {
-        ShapeDef { path, markers }
-    }
-}
-
 trait BasicShape {
-    /// Creates a complete shape definition.
+    /// Creates the path for the shape.
     ///
-    /// This function can simply call `ShapeDef::new(self.make_path(params, values), Markers::*)`
-    /// with the `Markers` being set to the appropriate value, depending on whether the shape
-    /// produces markers or not.
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef;
+    /// Per SVG2, all the basic shapes get markers at the vertices of this path, so for
+    /// `rect`, `circle`, and `ellipse` it must have the segments of the equivalent path
+    /// from the specification.
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath>;
+
+    /// Returns the value of the `pathLength` attribute, if the shape has it.
+    fn path_length(&self) -> Option<NonNegative>;
 }
 
 fn draw_basic_shape(
@@ -61,10 +47,15 @@ fn draw_basic_shape(
 
     let values = cascaded.get();
     let params = NormalizeParams::new(values, viewport);
-    let shape_def = basic_shape.make_shape(&params, values);
+    let svg_path = basic_shape.make_shape(&params, values);
+
+    let mut stroke = Stroke::new(values, &params);
 
-    let stroke = Stroke::new(values, &params);
-    let path = match validate_path(&shape_def.path, &stroke, viewport)? {
+    if let Some(NonNegative(path_length)) = basic_shape.path_length() {
+        scale_dashes_to_path_length(&mut stroke, &svg_path, path_length);
+    }
+
+    let path = match validate_path(&svg_path, &stroke, viewport)? {
         ValidatedPath::Invalid(ref reason) => {
             rsvg_log!(session, "will not render {node}: {reason}");
             return Ok(None);
@@ -100,34 +91,24 @@ fn draw_basic_shape(
     let clip_rule = values.clip_rule();
     let shape_rendering = values.shape_rendering();
 
-    let marker_start_node;
-    let marker_mid_node;
-    let marker_end_node;
-
-    if shape_def.markers == Markers::Yes {
-        marker_start_node = acquire_marker(
-            session,
-            &shape_element_name,
-            acquired_nodes,
-            &values.marker_start().0,
-        );
-        marker_mid_node = acquire_marker(
-            session,
-            &shape_element_name,
-            acquired_nodes,
-            &values.marker_mid().0,
-        );
-        marker_end_node = acquire_marker(
-            session,
-            &shape_element_name,
-            acquired_nodes,
-            &values.marker_end().0,
-        );
-    } else {
-        marker_start_node = None;
-        marker_mid_node = None;
-        marker_end_node = None;
-    }
+    let marker_start_node = acquire_marker(
+        session,
+        &shape_element_name,
+        acquired_nodes,
+        &values.marker_start().0,
+    );
+    let marker_mid_node = acquire_marker(
+        session,
+        &shape_element_name,
+        acquired_nodes,
+        &values.marker_mid().0,
+    );
+    let marker_end_node = acquire_marker(
+        session,
+        &shape_element_name,
+        acquired_nodes,
+        &values.marker_end().0,
+    );
 
     let marker_start = Marker {
         node_ref: marker_start_node,
@@ -218,6 +199,40 @@ macro_rules! impl_draw {
     };
 }
 
+/// Scales the stroke's dashes so that the path's length corresponds to `path_length`.
+///
+/// The `pathLength` attribute lets authors specify the length of a path in their own
+/// units, and `stroke-dasharray` and `stroke-dashoffset` are interpreted in those units.
+fn scale_dashes_to_path_length(stroke: &mut Stroke, path: &SvgPath, path_length: f64) {
+    // Computing the length of curves is not free, so only do it if there are dashes.
+    if stroke.dashes.is_empty() {
+        return;
+    }
+
+    let length = path.length();
+
+    if path_length > 0.0 {
+        let scale = length / path_length;
+
+        for dash in stroke.dashes.iter_mut() {
+            *dash *= scale;
+        }
+
+        stroke.dash_offset *= scale;
+    } else {
+        // Per the spec, pathLength="0" is a scale factor of infinity: zero-length dashes
+        // remain zero, and the others become infinite.  Anything longer than the path
+        // itself behaves as infinity.
+        for dash in stroke.dashes.iter_mut() {
+            if *dash > 0.0 {
+                *dash = length + 1.0;
+            }
+        }
+
+        stroke.dash_offset = 0.0;
+    }
+}
+
 fn acquire_marker(
     session: &Session,
     referencing_element_name: &str,
@@ -244,6 +259,15 @@ fn acquire_marker(
     })
 }
 
+/// Parses the `pathLength` attribute, which all the basic shapes have.
+fn set_path_length(path_length: &mut Option<NonNegative>, attrs: &Attributes, session: &Session) {
+    for (attr, value) in attrs.iter() {
+        if attr.expanded() == expanded_name!("", "pathLength") {
+            set_attribute(path_length, attr.parse(value), session);
+        }
+    }
+}
+
 fn make_ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> SvgPath {
     let mut builder = PathBuilder::default();
 
@@ -303,10 +327,13 @@ fn make_ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> SvgPath {
 #[derive(Default)]
 pub struct Path {
     path: Rc<SvgPath>,
+    path_length: Option<NonNegative>,
 }
 
 impl ElementTrait for Path {
     fn set_attributes(&mut self, attrs: &Attributes, session: &Session) {
+        set_path_length(&mut self.path_length, attrs, session);
+
         for (attr, value) in attrs.iter() {
             if attr.expanded() == expanded_name!("", "d") {
                 let mut builder = PathBuilder::default();
@@ -325,8 +352,12 @@ impl ElementTrait for Path {
 }
 
 impl BasicShape for Path {
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef {
-        ShapeDef::new(self.make_path(params, values), Markers::Yes)
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath> {
+        self.make_path(params, values)
+    }
+
+    fn path_length(&self) -> Option<NonNegative> {
+        self.path_length
     }
 }
 
@@ -398,10 +429,13 @@ fn make_poly(points: &Points, closed: bool) -> SvgPath {
 #[derive(Default)]
 pub struct Polygon {
     points: Points,
+    path_length: Option<NonNegative>,
 }
 
 impl ElementTrait for Polygon {
     fn set_attributes(&mut self, attrs: &Attributes, session: &Session) {
+        set_path_length(&mut self.path_length, attrs, session);
+
         for (attr, value) in attrs.iter() {
             if attr.expanded() == expanded_name!("", "points") {
                 set_attribute(&mut self.points, attr.parse(value), session);
@@ -413,8 +447,12 @@ impl ElementTrait for Polygon {
 }
 
 impl BasicShape for Polygon {
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef {
-        ShapeDef::new(self.make_path(params, values), Markers::Yes)
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath> {
+        self.make_path(params, values)
+    }
+
+    fn path_length(&self) -> Option<NonNegative> {
+        self.path_length
     }
 }
 
@@ -427,10 +465,13 @@ impl Polygon {
 #[derive(Default)]
 pub struct Polyline {
     points: Points,
+    path_length: Option<NonNegative>,
 }
 
 impl ElementTrait for Polyline {
     fn set_attributes(&mut self, attrs: &Attributes, session: &Session) {
+        set_path_length(&mut self.path_length, attrs, session);
+
         for (attr, value) in attrs.iter() {
             if attr.expanded() == expanded_name!("", "points") {
                 set_attribute(&mut self.points, attr.parse(value), session);
@@ -442,8 +483,12 @@ impl ElementTrait for Polyline {
 }
 
 impl BasicShape for Polyline {
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef {
-        ShapeDef::new(self.make_path(params, values), Markers::Yes)
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath> {
+        self.make_path(params, values)
+    }
+
+    fn path_length(&self) -> Option<NonNegative> {
+        self.path_length
     }
 }
 
@@ -459,10 +504,13 @@ pub struct Line {
     y1: Length<Vertical>,
     x2: Length<Horizontal>,
     y2: Length<Vertical>,
+    path_length: Option<NonNegative>,
 }
 
 impl ElementTrait for Line {
     fn set_attributes(&mut self, attrs: &Attributes, session: &Session) {
+        set_path_length(&mut self.path_length, attrs, session);
+
         for (attr, value) in attrs.iter() {
             match attr.expanded() {
                 expanded_name!("", "x1") => set_attribute(&mut self.x1, attr.parse(value), session),
@@ -478,8 +526,12 @@ impl ElementTrait for Line {
 }
 
 impl BasicShape for Line {
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef {
-        ShapeDef::new(self.make_path(params, values), Markers::Yes)
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath> {
+        self.make_path(params, values)
+    }
+
+    fn path_length(&self) -> Option<NonNegative> {
+        self.path_length
     }
 }
 
@@ -504,15 +556,25 @@ impl Line {
 /// Note that its x/y/width/height/rx/ry are properties in SVG2, so they are
 /// defined as part of [the properties machinery](properties.rs).
 #[derive(Default)]
-pub struct Rect {}
+pub struct Rect {
+    path_length: Option<NonNegative>,
+}
 
 impl ElementTrait for Rect {
+    fn set_attributes(&mut self, attrs: &Attributes, session: &Session) {
+        set_path_length(&mut self.path_length, attrs, session);
+    }
+
     impl_draw!();
 }
 
 impl BasicShape for Rect {
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef {
-        ShapeDef::new(self.make_path(params, values), Markers::No)
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath> {
+        self.make_path(params, values)
+    }
+
+    fn path_length(&self) -> Option<NonNegative> {
+        self.path_length
     }
 }
 
@@ -590,12 +652,12 @@ impl Rect {
         }
 
         if rx == 0.0 {
-            // Easy case, no rounded corners
+            // Easy case, no rounded corners.  The close_path() below draws the left
+            // side; an explicit line would add an extra vertex for markers.
             builder.move_to(x, y);
             builder.line_to(x + w, y);
             builder.line_to(x + w, y + h);
             builder.line_to(x, y + h);
-            builder.line_to(x, y);
         } else {
             /* Hard case, rounded corners
              *
@@ -703,15 +765,25 @@ impl Rect {
 /// Note that its cx/cy/r are properties in SVG2, so they are
 /// defined as part of [the properties machinery](properties.rs).
 #[derive(Default)]
-pub struct Circle {}
+pub struct Circle {
+    path_length: Option<NonNegative>,
+}
 
 impl ElementTrait for Circle {
+    fn set_attributes(&mut self, attrs: &Attributes, session: &Session) {
+        set_path_length(&mut self.path_length, attrs, session);
+    }
+
     impl_draw!();
 }
 
 impl BasicShape for Circle {
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef {
-        ShapeDef::new(self.make_path(params, values), Markers::No)
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath> {
+        self.make_path(params, values)
+    }
+
+    fn path_length(&self) -> Option<NonNegative> {
+        self.path_length
     }
 }
 
@@ -730,15 +802,25 @@ impl Circle {
 /// Note that its cx/cy/rx/ry are properties in SVG2, so they are
 /// defined as part of [the properties machinery](properties.rs).
 #[derive(Default)]
-pub struct Ellipse {}
+pub struct Ellipse {
+    path_length: Option<NonNegative>,
+}
 
 impl ElementTrait for Ellipse {
+    fn set_attributes(&mut self, attrs: &Attributes, session: &Session) {
+        set_path_length(&mut self.path_length, attrs, session);
+    }
+
     impl_draw!();
 }
 
 impl BasicShape for Ellipse {
-    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> ShapeDef {
-        ShapeDef::new(self.make_path(params, values), Markers::No)
+    fn make_shape(&self, params: &NormalizeParams, values: &ComputedValues) -> Rc<SvgPath> {
+        self.make_path(params, values)
+    }
+
+    fn path_length(&self) -> Option<NonNegative> {
+        self.path_length
     }
 }
 
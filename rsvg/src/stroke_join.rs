@@ -0,0 +1,954 @@
+//! Outlines for the `arcs` and `miter-clip` values of `stroke-linejoin`.
+//!
+//! Cairo only knows how to draw miter, round, and bevel joins.  For the two joins
+//! that SVG2 adds, we ask Cairo to stroke the path with bevel joins, and this module
+//! computes the geometry that goes beyond each bevel as a set of polygons.  The drawing
+//! code fills those polygons along with the stroke, so the result looks the same in
+//! raster and vector output.
+//!
+//! See <https://www.w3.org/TR/SVG2/painting.html#LineJoin> for the shape of the joins.
+
+use std::f64::consts::PI;
+
+use cairo::PathSegment;
+
+use crate::cairo_path::CairoPath;
+use crate::layout::Stroke;
+use crate::path_builder::CubicBezierCurve;
+use crate::properties::StrokeLinejoin;
+use crate::rect::Rect;
+use crate::transform::Transform;
+
+type Point = (f64, f64);
+
+/// Maximum angle subtended by each of the line segments that approximate the circular
+/// edges of an `arcs` join.
+const ARC_SAMPLE_ANGLE: f64 = PI / 32.0;
+
+/// Sine of the smallest turn between two segments that gets a join.
+///
+/// Curves and arcs get lowered to several Bézier segments that meet without a turn;
+/// those don't need join geometry.
+const MIN_TURN: f64 = 1e-6;
+
+/// Fill geometry for the joins of a stroke.
+pub struct JoinOutlines {
+    /// One closed polygon per join, in the coordinate system of the stroke.
+    pub path: CairoPath,
+
+    /// Bounds of all the polygons.
+    pub extents: Rect,
+}
+
+/// Direction and signed curvature of a path at one end of a segment.
+#[derive(Debug, Copy, Clone)]
+struct Tangent {
+    /// Unit vector in the direction of the path.
+    dir: Point,
+
+    /// Positive values mean that the path turns counterclockwise.
+    curvature: f64,
+}
+
+/// A segment of a subpath, reduced to what is needed to compute joins.
+struct Piece {
+    end: Point,
+    start_tangent: Tangent,
+    end_tangent: Tangent,
+    length: f64,
+}
+
+/// One of the outer edges of the stroke, extended beyond the join point.
+struct Edge {
+    /// Where the edge meets the bevel.
+    start: Point,
+
+    /// Unit vector in the direction in which the edge gets extended.
+    dir: Point,
+
+    /// Center and radius of the edge, or `None` for a straight edge.
+    circle: Option<(Point, f64)>,
+}
+
+/// Computes the join outlines for a stroke with a `stroke-linejoin` that Cairo can't draw.
+///
+/// `path` is the path as fed to Cairo, and `transform` converts its coordinates to the
+/// ones where the stroke gets drawn; those differ for `vector-effect="non-scaling-stroke"`.
+///
+/// Returns `None` if the stroke uses one of Cairo's own joins, or if there are no joins
+/// to draw.
+pub fn join_outlines(
+    path: &CairoPath,
+    stroke: &Stroke,
+    transform: &Transform,
+) -> Option<JoinOutlines> {
+    let arcs = match stroke.line_join {
+        StrokeLinejoin::Arcs => true,
+        StrokeLinejoin::MiterClip => false,
+        StrokeLinejoin::Miter | StrokeLinejoin::Round | StrokeLinejoin::Bevel => return None,
+    };
+
+    let half_width = stroke.width / 2.0;
+    if half_width <= 0.0 {
+        return None;
+    }
+
+    let join = JoinParams {
+        half_width,
+        limit: stroke.miter_limit.0 * half_width,
+        arcs,
+    };
+
+    let dashes: &[f64] = if stroke.dashes.iter().sum::<f64>() > 0.0 {
+        &stroke.dashes
+    } else {
+        &[]
+    };
+
+    let mut polygons = Vec::new();
+
+    for subpath in subpaths(path, transform) {
+        subpath_joins(&subpath, &join, dashes, stroke.dash_offset, &mut polygons);
+    }
+
+    if polygons.is_empty() {
+        return None;
+    }
+
+    let mut segments = Vec::new();
+    let (mut x0, mut y0) = (f64::INFINITY, f64::INFINITY);
+    let (mut x1, mut y1) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+    for polygon in polygons {
+        for (i, &(x, y)) in polygon.iter().enumerate() {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+
+            if i == 0 {
+                segments.push(PathSegment::MoveTo((x, y)));
+            } else {
+                segments.push(PathSegment::LineTo((x, y)));
+            }
+        }
+
+        segments.push(PathSegment::ClosePath);
+    }
+
+    Some(JoinOutlines {
+        path: CairoPath::from_segments(segments),
+        extents: Rect::new(x0, y0, x1, y1),
+    })
+}
+
+/// The parts of a [`Stroke`] that determine the shape of a join.
+struct JoinParams {
+    half_width: f64,
+
+    /// Distance from the join point at which joins get clipped, per `stroke-miterlimit`.
+    limit: f64,
+
+    /// Whether to compute `arcs` joins instead of `miter-clip` ones.
+    arcs: bool,
+}
+
+/// A subpath with its degenerate segments removed.
+struct Subpath {
+    start: Point,
+    pieces: Vec<Piece>,
+    closed: bool,
+}
+
+/// Splits a path into subpaths of non-degenerate pieces, transforming all the points.
+fn subpaths(path: &CairoPath, transform: &Transform) -> Vec<Subpath> {
+    let mut subpaths = Vec::new();
+    let mut current: Option<Subpath> = None;
+    let mut current_point = (0.0, 0.0);
+
+    let tr = |(x, y): Point| transform.transform_point(x, y);
+
+    for segment in path.segments() {
+        match *segment {
+            PathSegment::MoveTo(p) => {
+                subpaths.extend(current.take());
+                current_point = tr(p);
+            }
+
+            PathSegment::LineTo(p) => {
+                let to = tr(p);
+                let subpath = current.get_or_insert_with(|| Subpath::new(current_point));
+                subpath.pieces.extend(Piece::line(current_point, to));
+                current_point = to;
+            }
+
+            PathSegment::CurveTo(p1, p2, p3) => {
+                let curve = CubicBezierCurve {
+                    pt1: tr(p1),
+                    pt2: tr(p2),
+                    to: tr(p3),
+                };
+                let subpath = current.get_or_insert_with(|| Subpath::new(current_point));
+                subpath.pieces.extend(Piece::curve(current_point, &curve));
+                current_point = curve.to;
+            }
+
+            PathSegment::ClosePath => {
+                if let Some(mut subpath) = current.take() {
+                    subpath
+                        .pieces
+                        .extend(Piece::line(current_point, subpath.start));
+                    subpath.closed = true;
+                    current_point = subpath.start;
+                    subpaths.push(subpath);
+                }
+            }
+        }
+    }
+
+    subpaths.extend(current);
+    subpaths
+}
+
+impl Subpath {
+    fn new(start: Point) -> Subpath {
+        Subpath {
+            start,
+            pieces: Vec::new(),
+            closed: false,
+        }
+    }
+}
+
+impl Piece {
+    fn line(from: Point, to: Point) -> Option<Piece> {
+        let dir = normalize(sub(to, from))?;
+        let tangent = Tangent {
+            dir,
+            curvature: 0.0,
+        };
+
+        Some(Piece {
+            end: to,
+            start_tangent: tangent,
+            end_tangent: tangent,
+            length: distance(from, to),
+        })
+    }
+
+    fn curve(from: Point, curve: &CubicBezierCurve) -> Option<Piece> {
+        let p = [from, curve.pt1, curve.pt2, curve.to];
+
+        // The end tangent is the start tangent of the reversed curve, pointing the other
+        // way, and with a curvature of the opposite sign.
+        let start_tangent = curve_tangent(p[0], p[1], p[2], p[3])?;
+        let end_tangent = curve_tangent(p[3], p[2], p[1], p[0]).map(|t| Tangent {
+            dir: scale(t.dir, -1.0),
+            curvature: -t.curvature,
+        })?;
+
+        Some(Piece {
+            end: curve.to,
+            start_tangent,
+            end_tangent,
+            length: curve.length(from),
+        })
+    }
+}
+
+/// Computes the tangent at `p0` of a Bézier curve with control points `p0` to `p3`.
+///
+/// Coincident control points make the derivative vanish at the ends; the direction is
+/// then given by the next distinct point.  Returns `None` if all the points are the same.
+fn curve_tangent(p0: Point, p1: Point, p2: Point, p3: Point) -> Option<Tangent> {
+    if let Some(dir) = normalize(sub(p1, p0)) {
+        // Curvature at t=0 is cross(B', B'') / |B'|³, which simplifies to this.
+        let d1 = sub(p1, p0);
+        let len = hypot(d1);
+        let curvature = (2.0 / 3.0) * cross(d1, sub(p2, p1)) / (len * len * len);
+
+        Some(Tangent { dir, curvature })
+    } else {
+        let dir = normalize(sub(p2, p0)).or_else(|| normalize(sub(p3, p0)))?;
+
+        Some(Tangent {
+            dir,
+            curvature: 0.0,
+        })
+    }
+}
+
+/// Appends the polygons for the joins of a subpath to `polygons`.
+///
+/// Like Cairo, this restarts the dash pattern at the beginning of each subpath, and
+/// only adds joins at the points where the dash pattern is on.
+fn subpath_joins(
+    subpath: &Subpath,
+    join: &JoinParams,
+    dashes: &[f64],
+    dash_offset: f64,
+    polygons: &mut Vec<Vec<Point>>,
+) {
+    let pieces = &subpath.pieces;
+    let mut distance = 0.0;
+
+    for pair in pieces.windows(2) {
+        let (incoming, outgoing) = (&pair[0], &pair[1]);
+        distance += incoming.length;
+
+        if dash_is_on(dashes, dash_offset, distance) {
+            polygons.extend(join_polygon(
+                incoming.end,
+                &incoming.end_tangent,
+                &outgoing.start_tangent,
+                join,
+            ));
+        }
+    }
+
+    if subpath.closed && pieces.len() > 1 {
+        distance += pieces.last().unwrap().length;
+
+        if dash_is_on(dashes, dash_offset, 0.0) && dash_is_on(dashes, dash_offset, distance) {
+            polygons.extend(join_polygon(
+                subpath.start,
+                &pieces.last().unwrap().end_tangent,
+                &pieces[0].start_tangent,
+                join,
+            ));
+        }
+    }
+}
+
+/// Returns whether the dash pattern is on at `distance` from the start of a subpath.
+///
+/// As in Cairo, an odd number of dashes gets repeated to alternate on and off.
+fn dash_is_on(dashes: &[f64], dash_offset: f64, distance: f64) -> bool {
+    if dashes.is_empty() {
+        return true;
+    }
+
+    let total: f64 = dashes.iter().sum();
+    let period = if dashes.len() % 2 == 0 {
+        total
+    } else {
+        total * 2.0
+    };
+
+    let mut pos = (distance + dash_offset).rem_euclid(period);
+    let mut on = true;
+
+    for &dash in dashes.iter().cycle().take(dashes.len() * 2) {
+        if pos < dash {
+            break;
+        }
+
+        pos -= dash;
+        on = !on;
+    }
+
+    on
+}
+
+/// Computes the polygon that goes beyond the bevel of a single join.
+///
+/// The polygon starts at the join point `p` and goes around the outer side of the join.
+fn join_polygon(
+    p: Point,
+    incoming: &Tangent,
+    outgoing: &Tangent,
+    join: &JoinParams,
+) -> Option<Vec<Point>> {
+    let (d0, d1) = (incoming.dir, outgoing.dir);
+    let turn = cross(d0, d1);
+
+    if turn.abs() < MIN_TURN {
+        // No join for straight continuations.
+        if dot(d0, d1) > 0.0 {
+            return None;
+        }
+
+        return Some(reversal_polygon(p, d0, join));
+    }
+
+    // The outer side of the join is opposite to the direction of the turn.
+    let side = -turn.signum();
+    let n0 = scale(left_normal(d0), side);
+    let n1 = scale(left_normal(d1), side);
+    let bisector = normalize(add(n0, n1))?;
+
+    let h = join.half_width;
+
+    // Per the spec, a join clipped within the bevel is just a bevel.
+    if join.limit <= dot(n0, bisector) * h {
+        return None;
+    }
+
+    let edge0 = Edge::new(p, incoming, side, h, d0);
+    let edge1 = Edge::new(p, outgoing, side, h, scale(d1, -1.0));
+
+    // For arcs, the edges get extended with their curvature; per the spec, if the
+    // extended edges don't meet, the join falls back to miter-clip.
+    let meeting = if join.arcs {
+        edge0.meeting_point(&edge1, p, bisector)
+    } else {
+        None
+    };
+
+    let mut polygon = vec![p];
+
+    match meeting {
+        Some((x, angle0, angle1)) => {
+            polygon.push(edge0.start);
+            edge0.sample(angle0, &mut polygon);
+            polygon.push(x);
+
+            let mut back = Vec::new();
+            edge1.sample(angle1, &mut back);
+            polygon.extend(back.into_iter().rev());
+            polygon.push(edge1.start);
+        }
+
+        None => {
+            let tip = Edge::straight(edge0.start, d0).meeting_point(
+                &Edge::straight(edge1.start, scale(d1, -1.0)),
+                p,
+                bisector,
+            )?;
+
+            polygon.push(edge0.start);
+            polygon.push(tip.0);
+            polygon.push(edge1.start);
+        }
+    }
+
+    let clipped = clip_polygon(&polygon, p, bisector, join.limit);
+
+    if clipped.len() >= 3 {
+        Some(clipped)
+    } else {
+        None
+    }
+}
+
+/// Computes the polygon for a join where the path turns back on itself.
+///
+/// The miter of a reversal is infinitely long, so it always gets clipped at the limit,
+/// which leaves a rectangle beyond the end of the incoming segment.  The outer edges of
+/// an `arcs` join are parallel there, so it falls back to `miter-clip` as well; the
+/// curvature of the edges is not taken into account.
+fn reversal_polygon(p: Point, dir: Point, join: &JoinParams) -> Vec<Point> {
+    let normal = scale(left_normal(dir), join.half_width);
+    let reach = scale(dir, join.limit);
+
+    vec![
+        p,
+        add(p, normal),
+        add(add(p, normal), reach),
+        sub(add(p, reach), normal),
+        sub(p, normal),
+    ]
+}
+
+impl Edge {
+    fn straight(start: Point, dir: Point) -> Edge {
+        Edge {
+            start,
+            dir,
+            circle: None,
+        }
+    }
+
+    /// Creates the outer edge of the stroke that corresponds to a path's `tangent` at `p`.
+    ///
+    /// The edge is offset by `half_width` to the `side` of the path, and it gets
+    /// extended in the direction `dir`.
+    fn new(p: Point, tangent: &Tangent, side: f64, half_width: f64, dir: Point) -> Edge {
+        let normal = left_normal(tangent.dir);
+        let start = add(p, scale(normal, side * half_width));
+        let k = tangent.curvature;
+
+        // The offset of a circle of radius 1/k is a concentric circle, unless the
+        // offset goes past the center.
+        let circle = if (k * half_width).abs() > 1e-9 {
+            let radius = 1.0 / k;
+            let offset_radius = radius - side * half_width;
+
+            if offset_radius * radius > 0.0 {
+                Some((add(p, scale(normal, radius)), offset_radius.abs()))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        Edge { start, dir, circle }
+    }
+
+    /// Finds the closest point where this edge meets `other` on the outer side of the join.
+    ///
+    /// Returns the point and the angles that each edge sweeps to get to it; straight
+    /// edges have an angle of 0.
+    fn meeting_point(&self, other: &Edge, p: Point, bisector: Point) -> Option<(Point, f64, f64)> {
+        let candidates = match (self.circle, other.circle) {
+            (None, None) => line_line(self.start, self.dir, other.start, other.dir)
+                .into_iter()
+                .collect(),
+            (None, Some((c, r))) => line_circle(self.start, self.dir, c, r),
+            (Some((c, r)), None) => line_circle(other.start, other.dir, c, r),
+            (Some((c0, r0)), Some((c1, r1))) => circle_circle(c0, r0, c1, r1),
+        };
+
+        let mut best: Option<(Point, f64, f64, f64)> = None;
+
+        for x in candidates {
+            if dot(sub(x, p), bisector) <= 0.0 {
+                continue;
+            }
+
+            let (Some((len0, angle0)), Some((len1, angle1))) = (self.travel(x), other.travel(x))
+            else {
+                continue;
+            };
+
+            let len = len0 + len1;
+            if best.is_none_or(|b| len < b.3) {
+                best = Some((x, angle0, angle1, len));
+            }
+        }
+
+        best.map(|(x, angle0, angle1, _)| (x, angle0, angle1))
+    }
+
+    /// Computes how far the edge must be extended to reach `x`, which must be on it.
+    ///
+    /// Returns the length along the edge and the angle swept, or `None` if `x` is
+    /// behind the start of the edge or more than half a turn away.
+    fn travel(&self, x: Point) -> Option<(f64, f64)> {
+        match self.circle {
+            None => {
+                let t = dot(sub(x, self.start), self.dir);
+                (t >= -1e-9).then_some((t.max(0.0), 0.0))
+            }
+
+            Some((c, r)) => {
+                let u = sub(self.start, c);
+                let v = sub(x, c);
+                let rotation = cross(u, self.dir).signum();
+
+                let mut angle = cross(u, v).atan2(dot(u, v)) * rotation;
+                if angle < 0.0 {
+                    angle += 2.0 * PI;
+                }
+
+                (angle <= PI).then_some((angle * r, angle))
+            }
+        }
+    }
+
+    /// Appends points along a circular edge, excluding both ends, up to `angle`.
+    fn sample(&self, angle: f64, points: &mut Vec<Point>) {
+        if let Some((c, _)) = self.circle {
+            let u = sub(self.start, c);
+            let rotation = cross(u, self.dir).signum();
+            let n = (angle / ARC_SAMPLE_ANGLE).ceil() as usize;
+
+            for i in 1..n {
+                let a = rotation * angle * i as f64 / n as f64;
+                let (sin, cos) = a.sin_cos();
+                points.push(add(c, (u.0 * cos - u.1 * sin, u.0 * sin + u.1 * cos)));
+            }
+        }
+    }
+}
+
+fn line_line(p0: Point, d0: Point, p1: Point, d1: Point) -> Option<Point> {
+    let denom = cross(d0, d1);
+    if denom.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let t = cross(sub(p1, p0), d1) / denom;
+    Some(add(p0, scale(d0, t)))
+}
+
+fn line_circle(p: Point, d: Point, c: Point, r: f64) -> Vec<Point> {
+    let f = sub(p, c);
+    let b = dot(f, d);
+    let disc = b * b - (dot(f, f) - r * r);
+
+    if disc < 0.0 {
+        return Vec::new();
+    }
+
+    let sq = disc.sqrt();
+    vec![add(p, scale(d, -b - sq)), add(p, scale(d, -b + sq))]
+}
+
+fn circle_circle(c0: Point, r0: f64, c1: Point, r1: f64) -> Vec<Point> {
+    let between = sub(c1, c0);
+    let dist = hypot(between);
+
+    if dist < f64::EPSILON || dist > r0 + r1 || dist < (r0 - r1).abs() {
+        return Vec::new();
+    }
+
+    let a = (r0 * r0 - r1 * r1 + dist * dist) / (2.0 * dist);
+    let h = (r0 * r0 - a * a).max(0.0).sqrt();
+    let dir = scale(between, 1.0 / dist);
+    let mid = add(c0, scale(dir, a));
+    let normal = left_normal(dir);
+
+    vec![add(mid, scale(normal, h)), add(mid, scale(normal, -h))]
+}
+
+/// Clips a polygon to the half-plane whose points are at most `limit` from `p` along `dir`.
+fn clip_polygon(polygon: &[Point], p: Point, dir: Point, limit: f64) -> Vec<Point> {
+    let dist = |q: Point| dot(sub(q, p), dir) - limit;
+    let mut result = Vec::with_capacity(polygon.len() + 1);
+
+    for (i, &cur) in polygon.iter().enumerate() {
+        let prev = polygon[(i + polygon.len() - 1) % polygon.len()];
+        let (d_prev, d_cur) = (dist(prev), dist(cur));
+
+        if (d_prev <= 0.0) != (d_cur <= 0.0) {
+            let t = d_prev / (d_prev - d_cur);
+            result.push(add(prev, scale(sub(cur, prev), t)));
+        }
+
+        if d_cur <= 0.0 {
+            result.push(cur);
+        }
+    }
+
+    result
+}
+
+fn add(a: Point, b: Point) -> Point {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn scale(a: Point, s: f64) -> Point {
+    (a.0 * s, a.1 * s)
+}
+
+fn dot(a: Point, b: Point) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn hypot(a: Point) -> f64 {
+    a.0.hypot(a.1)
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    hypot(sub(b, a))
+}
+
+fn left_normal(a: Point) -> Point {
+    (-a.1, a.0)
+}
+
+fn normalize(a: Point) -> Option<Point> {
+    let len = hypot(a);
+
+    if len > f64::EPSILON {
+        Some(scale(a, 1.0 / len))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_cmp::approx_eq;
+
+    use crate::path_builder::PathBuilder;
+    use crate::properties::{StrokeLinecap, StrokeMiterlimit};
+
+    fn stroke(line_join: StrokeLinejoin, width: f64, miter_limit: f64) -> Stroke {
+        Stroke {
+            width,
+            miter_limit: StrokeMiterlimit(miter_limit),
+            line_cap: StrokeLinecap::Butt,
+            line_join,
+            dash_offset: 0.0,
+            dashes: Box::new([]),
+            non_scaling: false,
+        }
+    }
+
+    fn right_angle() -> CairoPath {
+        let mut builder = PathBuilder::default();
+        builder.move_to(0.0, 0.0);
+        builder.line_to(10.0, 0.0);
+        builder.line_to(10.0, 10.0);
+        builder.into_path().to_cairo_path(false)
+    }
+
+    fn assert_rect(r: &Rect, x0: f64, y0: f64, x1: f64, y1: f64) {
+        assert!(approx_eq!(f64, r.x0, x0, epsilon = 1e-6), "{r:?}");
+        assert!(approx_eq!(f64, r.y0, y0, epsilon = 1e-6), "{r:?}");
+        assert!(approx_eq!(f64, r.x1, x1, epsilon = 1e-6), "{r:?}");
+        assert!(approx_eq!(f64, r.y1, y1, epsilon = 1e-6), "{r:?}");
+    }
+
+    fn assert_point(p: Point, x: f64, y: f64) {
+        assert!(
+            approx_eq!(f64, p.0, x, epsilon = 1e-6) && approx_eq!(f64, p.1, y, epsilon = 1e-6),
+            "{p:?} != {:?}",
+            (x, y)
+        );
+    }
+
+    const RIGHT: Tangent = Tangent {
+        dir: (1.0, 0.0),
+        curvature: 0.0,
+    };
+
+    const DOWN: Tangent = Tangent {
+        dir: (0.0, 1.0),
+        curvature: 0.0,
+    };
+
+    #[test]
+    fn cairo_joins_have_no_outlines() {
+        for join in [
+            StrokeLinejoin::Miter,
+            StrokeLinejoin::Round,
+            StrokeLinejoin::Bevel,
+        ] {
+            assert!(
+                join_outlines(
+                    &right_angle(),
+                    &stroke(join, 2.0, 4.0),
+                    &Transform::identity()
+                )
+                .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn miter_clip_within_limit_is_a_miter() {
+        let join = JoinParams {
+            half_width: 1.0,
+            limit: 4.0,
+            arcs: false,
+        };
+
+        let polygon = join_polygon((0.0, 0.0), &RIGHT, &DOWN, &join).unwrap();
+        assert_eq!(polygon.len(), 4);
+        assert_point(polygon[0], 0.0, 0.0);
+        assert_point(polygon[1], 0.0, -1.0);
+        assert_point(polygon[2], 1.0, -1.0);
+        assert_point(polygon[3], 1.0, 0.0);
+    }
+
+    #[test]
+    fn miter_clip_gets_clipped_at_limit() {
+        let join = JoinParams {
+            half_width: 1.0,
+            limit: 1.2,
+            arcs: false,
+        };
+
+        // The clipping line is perpendicular to the bisector (1, -1) / √2, at 1.2 units
+        // from the join point, so it satisfies x - y = 1.2√2.
+        let clip = 1.2 * 2.0f64.sqrt();
+
+        let polygon = join_polygon((0.0, 0.0), &RIGHT, &DOWN, &join).unwrap();
+        assert_eq!(polygon.len(), 5);
+        assert_point(polygon[0], 0.0, 0.0);
+        assert_point(polygon[1], 0.0, -1.0);
+        assert_point(polygon[2], clip - 1.0, -1.0);
+        assert_point(polygon[3], 1.0, 1.0 - clip);
+        assert_point(polygon[4], 1.0, 0.0);
+    }
+
+    #[test]
+    fn limit_inside_bevel_yields_no_join() {
+        let join = JoinParams {
+            half_width: 1.0,
+            limit: 0.5,
+            arcs: false,
+        };
+
+        assert!(join_polygon((0.0, 0.0), &RIGHT, &DOWN, &join).is_none());
+    }
+
+    #[test]
+    fn reversal_gets_clipped_at_limit() {
+        let left = Tangent {
+            dir: (-1.0, 0.0),
+            curvature: 0.0,
+        };
+
+        for arcs in [false, true] {
+            let join = JoinParams {
+                half_width: 1.0,
+                limit: 3.0,
+                arcs,
+            };
+
+            let polygon = join_polygon((10.0, 0.0), &RIGHT, &left, &join).unwrap();
+            assert_eq!(polygon.len(), 5);
+            assert_point(polygon[0], 10.0, 0.0);
+            assert_point(polygon[1], 10.0, 1.0);
+            assert_point(polygon[2], 13.0, 1.0);
+            assert_point(polygon[3], 13.0, -1.0);
+            assert_point(polygon[4], 10.0, -1.0);
+        }
+    }
+
+    #[test]
+    fn arcs_between_lines_is_a_miter() {
+        let join = JoinParams {
+            half_width: 1.0,
+            limit: 4.0,
+            arcs: true,
+        };
+
+        let polygon = join_polygon((0.0, 0.0), &RIGHT, &DOWN, &join).unwrap();
+        assert_eq!(polygon.len(), 4);
+        assert_point(polygon[2], 1.0, -1.0);
+    }
+
+    #[test]
+    fn arcs_follow_curvature() {
+        let join = JoinParams {
+            half_width: 1.0,
+            limit: 100.0,
+            arcs: true,
+        };
+
+        let incoming = Tangent {
+            dir: (1.0, 0.0),
+            curvature: 0.1,
+        };
+
+        let outgoing = Tangent {
+            dir: (0.0, 1.0),
+            curvature: 0.1,
+        };
+
+        // The outer edges are circles of radius 11 centered at (0, 10) and (-10, 0);
+        // they meet at (x, -x) before reaching the miter tip at (1, -1).
+        let x = 35.5f64.sqrt() - 5.0;
+
+        let polygon = join_polygon((0.0, 0.0), &incoming, &outgoing, &join).unwrap();
+        let tip = polygon
+            .iter()
+            .position(|p| {
+                approx_eq!(f64, p.0, x, epsilon = 1e-6) && approx_eq!(f64, p.1, -x, epsilon = 1e-6)
+            })
+            .unwrap();
+
+        // Points along the edges stay on their circles.
+        let on_circle =
+            |p: &Point, c: Point| approx_eq!(f64, distance(*p, c), 11.0, epsilon = 1e-6);
+        assert!(polygon[1..=tip].iter().all(|p| on_circle(p, (0.0, 10.0))));
+        assert!(polygon[tip..].iter().all(|p| on_circle(p, (-10.0, 0.0))));
+    }
+
+    #[test]
+    fn no_joins_between_tangent_segments() {
+        let mut builder = PathBuilder::default();
+        builder.move_to(0.0, 0.0);
+        builder.line_to(10.0, 0.0);
+        builder.line_to(20.0, 0.0);
+        let path = builder.into_path().to_cairo_path(false);
+
+        assert!(
+            join_outlines(
+                &path,
+                &stroke(StrokeLinejoin::MiterClip, 2.0, 4.0),
+                &Transform::identity()
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn computes_outline_extents() {
+        let outlines = join_outlines(
+            &right_angle(),
+            &stroke(StrokeLinejoin::MiterClip, 2.0, 4.0),
+            &Transform::identity(),
+        )
+        .unwrap();
+
+        assert_rect(&outlines.extents, 10.0, -1.0, 11.0, 0.0);
+    }
+
+    #[test]
+    fn closed_subpaths_join_at_the_start() {
+        let mut builder = PathBuilder::default();
+        builder.move_to(0.0, 0.0);
+        builder.line_to(10.0, 0.0);
+        builder.line_to(10.0, 10.0);
+        builder.line_to(0.0, 10.0);
+        builder.close_path();
+        let path = builder.into_path().to_cairo_path(false);
+
+        let outlines = join_outlines(
+            &path,
+            &stroke(StrokeLinejoin::MiterClip, 2.0, 4.0),
+            &Transform::identity(),
+        )
+        .unwrap();
+
+        assert_rect(&outlines.extents, -1.0, -1.0, 11.0, 11.0);
+    }
+
+    #[test]
+    fn joins_follow_dashes() {
+        let mut dashed = stroke(StrokeLinejoin::MiterClip, 2.0, 4.0);
+
+        // The corner at distance 10 falls in a gap.
+        dashed.dashes = Box::new([5.0, 10.0]);
+        assert!(join_outlines(&right_angle(), &dashed, &Transform::identity()).is_none());
+
+        // The corner falls in a dash.
+        dashed.dash_offset = 6.0;
+        assert!(join_outlines(&right_angle(), &dashed, &Transform::identity()).is_some());
+    }
+
+    #[test]
+    fn computes_dash_state() {
+        assert!(dash_is_on(&[], 0.0, 42.0));
+
+        assert!(dash_is_on(&[5.0, 10.0], 0.0, 2.0));
+        assert!(!dash_is_on(&[5.0, 10.0], 0.0, 7.0));
+        assert!(dash_is_on(&[5.0, 10.0], 0.0, 17.0));
+        assert!(!dash_is_on(&[5.0, 10.0], 3.0, 4.0));
+        assert!(dash_is_on(&[5.0, 10.0], -3.0, 4.0));
+
+        // An odd number of dashes alternates between on and off.
+        assert!(dash_is_on(&[5.0], 0.0, 2.0));
+        assert!(!dash_is_on(&[5.0], 0.0, 7.0));
+        assert!(dash_is_on(&[5.0], 0.0, 12.0));
+    }
+
+    #[test]
+    fn applies_transform() {
+        let outlines = join_outlines(
+            &right_angle(),
+            &stroke(StrokeLinejoin::MiterClip, 2.0, 4.0),
+            &Transform::new_scale(2.0, 2.0),
+        )
+        .unwrap();
+
+        assert_rect(&outlines.extents, 20.0, -1.0, 21.0, 0.0);
+    }
+}
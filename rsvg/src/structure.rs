@@ -2,6 +2,7 @@
 
 use markup5ever::{expanded_name, local_name, ns};
 
+use crate::accept_language::UserLanguage;
 use crate::aspect_ratio::*;
 use crate::bbox::BoundingBox;
 use crate::coord_units;
@@ -173,12 +174,14 @@ impl ElementTrait for Switch {
         let values = cascaded.get();
 
         let switch_elt = node.borrow_element();
-        let child_that_matches = node.children().filter(|c| c.is_element()).find(|c| {
-            let elt = c.borrow_element();
-            elt.get_cond(draw_ctx.user_language(), draw_ctx.session())
-        });
+        let child_that_matches = Switch::matching_child(
+            node,
+            draw_ctx.user_language(),
+            draw_ctx.supported_extensions(),
+            draw_ctx.session(),
+        );
 
-        if let Some(child) = child_that_matches {
+        if let Some((_, child)) = child_that_matches {
             let stacking_ctx = Box::new(StackingContext::new(
                 draw_ctx,
                 acquired_nodes,
@@ -211,6 +214,47 @@ impl ElementTrait for Switch {
     }
 }
 
+impl Switch {
+    /// Finds the first element child whose conditional processing attributes evaluate to true.
+    ///
+    /// Returns the child and its index among the element children of the `<switch>`.
+    pub fn matching_child(
+        node: &Node,
+        user_language: &UserLanguage,
+        supported_extensions: &[String],
+        session: &Session,
+    ) -> Option<(usize, Node)> {
+        node.children()
+            .filter(|c| c.is_element())
+            .enumerate()
+            .find(|(_, c)| {
+                c.borrow_element()
+                    .get_cond(user_language, supported_extensions, session)
+            })
+    }
+}
+
+/// Which child of a `<switch>` element gets rendered.
+///
+/// You can obtain a list of these from [`CairoRenderer::switch_branches`][crate::CairoRenderer::switch_branches].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SwitchBranch {
+    /// The `id` of the `<switch>` element, if it has one.
+    pub switch_id: Option<String>,
+
+    /// Index of the chosen child among the element children of the `<switch>`, or `None`
+    /// if the conditions of none of the children evaluate to true.  In that case,
+    /// the `<switch>` renders nothing.
+    pub child_index: Option<usize>,
+
+    /// Local name of the chosen child element, like `"g"` or `"text"`.
+    pub child_element_name: Option<String>,
+
+    /// The `id` of the chosen child element, if it has one.
+    pub child_id: Option<String>,
+}
+
 /// Intrinsic dimensions of an SVG document fragment: its `width/height` properties and  `viewBox` attribute.
 ///
 /// Note that in SVG2, `width` and `height` are properties, not
@@ -218,7 +262,7 @@ impl ElementTrait for Switch {
 /// computes to `100%`.
 ///
 /// The `viewBox` attribute can also be omitted, hence an `Option`.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IntrinsicDimensions {
     /// Computed value of the `width` property.
     pub width: ULength<Horizontal>,
@@ -842,6 +886,11 @@ mod tests {
             svg_nesting: SvgNesting::Standalone,
             measuring: false,
             testing: true,
+            text_mode: None,
+            supported_extensions: Vec::new(),
+            thread_pool: None,
+            quality: Default::default(),
+            filter_resolution: Default::default(),
         };
 
         let mut draw_ctx = DrawingCtx::new(Session::default(), &cr, &viewport, config, Vec::new());
@@ -22,6 +22,7 @@ use rgb::alt::ARGB8;
 pub type CairoARGB = ARGB8;
 
 use rgb::ColorComponentMap;
+use wide::f64x4;
 
 /// Analogous to `rgb::FromSlice`, to convert from `[T]` to `[CairoARGB]`
 #[allow(clippy::upper_case_acronyms)]
@@ -132,6 +133,8 @@ pub trait PixelOps {
     fn to_luminance_mask(&self) -> Self;
     fn to_u32(&self) -> u32;
     fn from_u32(x: u32) -> Self;
+    fn to_f64x4(&self) -> f64x4;
+    fn from_f64x4(x: f64x4) -> Self;
 }
 
 impl PixelOps for Pixel {
@@ -221,6 +224,30 @@ impl PixelOps for Pixel {
             a: ((x >> 24) & 0xFF) as u8,
         }
     }
+
+    /// Returns the R, G, B and A values in the lanes of a SIMD vector, in that order.
+    #[inline]
+    fn to_f64x4(&self) -> f64x4 {
+        f64x4::new([
+            f64::from(self.r),
+            f64::from(self.g),
+            f64::from(self.b),
+            f64::from(self.a),
+        ])
+    }
+
+    /// Converts the lanes of a SIMD vector into a `Pixel`, clamping and rounding each value.
+    #[inline]
+    fn from_f64x4(x: f64x4) -> Self {
+        let [r, g, b, a] = (x.clamp(f64x4::splat(0.0), f64x4::splat(255.0)) + 0.5).to_array();
+
+        Self {
+            r: r as u8,
+            g: g as u8,
+            b: b as u8,
+            a: a as u8,
+        }
+    }
 }
 
 impl<'a> ImageSurfaceDataExt for cairo::ImageSurfaceData<'a> {
@@ -302,5 +329,16 @@ mod tests {
                 prop_assert_eq!(pixel.a, roundtrip.a);
             }
        }
+
+        #[test]
+        fn pixel_f64x4_roundtrip(pixel in arbitrary_pixel()) {
+            prop_assert_eq!(Pixel::from_f64x4(pixel.to_f64x4()), pixel);
+        }
+    }
+
+    #[test]
+    fn pixel_from_f64x4_clamps_and_rounds() {
+        let x = f64x4::new([-10.0, 12.4, 12.6, 300.0]);
+        assert_eq!(Pixel::from_f64x4(x), Pixel::new(0, 12, 13, 255));
     }
 }
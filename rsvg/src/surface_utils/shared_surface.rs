@@ -6,6 +6,8 @@ use std::slice;
 
 use cast::i32;
 use nalgebra::{Dim, Matrix, storage::Storage};
+use rayon::prelude::*;
+use wide::f64x4;
 
 use crate::color::{Color, color_to_rgba};
 use crate::drawing_ctx::set_source_color_on_cairo;
@@ -19,6 +21,13 @@ use super::{
     iterators::{PixelRectangle, Pixels},
 };
 
+/// Minimum number of rows that get processed together on a thread by
+/// [`ExclusiveImageSurface::modify_rows`].
+///
+/// Splitting the work into bands smaller than this costs more in scheduling than what
+/// is gained from parallelism.
+pub const MIN_ROWS_PER_BAND: usize = 8;
+
 /// Interpolation when scaling images.
 ///
 /// This is meant to be translated from the `ImageRendering` property.  We don't use
@@ -30,6 +39,7 @@ use super::{
 pub enum Interpolation {
     Nearest,
     Smooth,
+    Best,
 }
 
 impl From<Interpolation> for cairo::Filter {
@@ -39,6 +49,7 @@ impl From<Interpolation> for cairo::Filter {
         match i {
             Interpolation::Nearest => cairo::Filter::Nearest,
             Interpolation::Smooth => cairo::Filter::Good,
+            Interpolation::Best => cairo::Filter::Best,
         }
     }
 }
@@ -601,10 +612,8 @@ impl ImageSurface<Shared> {
                         y as i32 - target.1 + kernel.nrows() as i32,
                     );
 
-                    let mut r = 0.0;
-                    let mut g = 0.0;
-                    let mut b = 0.0;
-                    let mut a = 0.0;
+                    // The four channels are accumulated together, one per SIMD lane.
+                    let mut sum = f64x4::splat(0.0);
 
                     for (x, y, pixel) in
                         PixelRectangle::within(self, bounds, kernel_bounds, edge_mode)
@@ -613,20 +622,10 @@ impl ImageSurface<Shared> {
                         let kernel_y = (kernel_bounds.y1 - y - 1) as usize;
                         let factor = kernel[(kernel_y, kernel_x)];
 
-                        r += f64::from(pixel.r) * factor;
-                        g += f64::from(pixel.g) * factor;
-                        b += f64::from(pixel.b) * factor;
-                        a += f64::from(pixel.a) * factor;
+                        sum += pixel.to_f64x4() * factor;
                     }
 
-                    let convert = |x: f64| (clamp(x, 0.0, 255.0) + 0.5) as u8;
-
-                    let output_pixel = Pixel {
-                        r: convert(r),
-                        g: convert(g),
-                        b: convert(b),
-                        a: convert(a),
-                    };
+                    let output_pixel = Pixel::from_f64x4(sum);
 
                     output_data.set_pixel(output_stride, output_pixel, x, y);
                 }
@@ -1140,12 +1139,16 @@ impl ImageSurface<Shared> {
     /// Each pixel of the resulting image is computed using the following formula:
     /// `res = k1*i1*i2 + k2*i1 + k3*i2 + k4`
     ///
+    /// The rows are processed in parallel on the `thread_pool`, or on rayon's global thread
+    /// pool if it is `None`.
+    ///
     /// # Panics
     /// Panics if the two surface types are not compatible.
     #[inline]
     pub fn compose_arithmetic(
         &self,
         other: &SharedImageSurface,
+        thread_pool: Option<&rayon::ThreadPool>,
         bounds: IRect,
         k1: f64,
         k2: f64,
@@ -1158,7 +1161,17 @@ impl ImageSurface<Shared> {
             self.surface_type.combine(other.surface_type),
         )?;
 
-        composite_arithmetic(self, other, &mut output_surface, bounds, k1, k2, k3, k4);
+        composite_arithmetic(
+            self,
+            other,
+            &mut output_surface,
+            thread_pool,
+            bounds,
+            k1,
+            k2,
+            k3,
+            k4,
+        );
 
         output_surface.share()
     }
@@ -1248,16 +1261,19 @@ pub fn composite_arithmetic(
     surface1: &SharedImageSurface,
     surface2: &SharedImageSurface,
     output_surface: &mut ExclusiveImageSurface,
+    thread_pool: Option<&rayon::ThreadPool>,
     bounds: IRect,
     k1: f64,
     k2: f64,
     k3: f64,
     k4: f64,
 ) {
-    output_surface.modify(&mut |data, stride| {
-        for (x, y, pixel, pixel_2) in
-            Pixels::within(surface1, bounds).map(|(x, y, p)| (x, y, p, surface2.get_pixel(x, y)))
-        {
+    output_surface.modify_rows(thread_pool, bounds, &|row, y| {
+        for x in bounds.x_range() {
+            let x = x as u32;
+            let pixel = surface1.get_pixel(x, y);
+            let pixel_2 = surface2.get_pixel(x, y);
+
             let i1a = f64::from(pixel.a) / 255f64;
             let i2a = f64::from(pixel_2.a) / 255f64;
             let oa = k1 * i1a * i2a + k2 * i1a + k3 * i2a + k4;
@@ -1283,7 +1299,7 @@ pub fn composite_arithmetic(
                     a: ((oa * 255f64) + 0.5) as u8,
                 };
 
-                data.set_pixel(stride, output_pixel, x, y);
+                row.set_pixel(0, output_pixel, x, 0);
             }
         }
     });
@@ -1345,6 +1361,46 @@ impl ImageSurface<Exclusive> {
         draw_fn(&mut data, stride)
     }
 
+    /// Modify the image data one row at a time, in parallel.
+    ///
+    /// Only the rows within `bounds` get processed.  They are split into bands of at
+    /// least [`MIN_ROWS_PER_BAND`] rows, which run on the `thread_pool`, or on rayon's
+    /// global thread pool if it is `None`.  The `row_fn` gets called with the data for a
+    /// whole row of the surface and with that row's y coordinate; to set pixels in the row,
+    /// call [`ImageSurfaceDataExt::set_pixel`] on the row data with a y coordinate of 0.
+    ///
+    /// This only spreads the work across threads; the `row_fn` is responsible for its own
+    /// per-pixel code.  The four channels of a pixel fit in the lanes of a `wide::f64x4`,
+    /// see [`PixelOps::to_f64x4`].
+    pub fn modify_rows(
+        &mut self,
+        thread_pool: Option<&rayon::ThreadPool>,
+        bounds: IRect,
+        row_fn: &(dyn Fn(&mut [u8], u32) + Sync),
+    ) {
+        let stride = self.stride() as usize;
+        let mut data = self.data();
+
+        let first_row = bounds.y0 as u32;
+        let one_past_last_row = bounds.y1 as u32;
+        let first_pixel = (first_row as usize) * stride;
+        let one_past_last_pixel = (one_past_last_row as usize) * stride;
+
+        let rows = &mut data[first_pixel..one_past_last_pixel];
+
+        let process = || {
+            rows.par_chunks_mut(stride)
+                .with_min_len(MIN_ROWS_PER_BAND)
+                .zip(first_row..one_past_last_row)
+                .for_each(|(row, y)| row_fn(row, y))
+        };
+
+        match thread_pool {
+            Some(pool) => pool.install(process),
+            None => process(),
+        }
+    }
+
     /// Draw on the surface using cairo
     #[inline]
     pub fn draw(
@@ -1445,4 +1501,32 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn modify_rows_only_touches_bounds() {
+        const WIDTH: i32 = 32;
+        const HEIGHT: i32 = 64;
+
+        let bounds = IRect::new(8, 3, 16, 45);
+        let full_bounds = IRect::from_size(WIDTH, HEIGHT);
+
+        let mut surface = ExclusiveImageSurface::new(WIDTH, HEIGHT, SurfaceType::SRgb).unwrap();
+
+        surface.modify_rows(None, bounds, &|row, y| {
+            for x in bounds.x_range() {
+                let pixel = Pixel::new(x as u8, y as u8, 0, 255);
+                row.set_pixel(0, pixel, x as u32, 0);
+            }
+        });
+
+        let surface = surface.share().unwrap();
+
+        for (x, y, p) in Pixels::within(&surface, full_bounds) {
+            if bounds.contains(x as i32, y as i32) {
+                assert_eq!(p, Pixel::new(x as u8, y as u8, 0, 255));
+            } else {
+                assert_eq!(p, Pixel::default());
+            }
+        }
+    }
 }
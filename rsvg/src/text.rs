@@ -1,5 +1,6 @@
 //! Text elements: `text`, `tspan`, `tref`.
 
+use cssparser::Parser;
 use markup5ever::{QualName, expanded_name, local_name, ns};
 use pango::IsAttribute;
 use pango::prelude::FontExt;
@@ -7,6 +8,7 @@ use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::rc::Rc;
 
+use crate::angle::Angle;
 use crate::document::{AcquiredNodes, NodeId};
 use crate::drawing_ctx::{DrawingCtx, FontOptions, Viewport, create_pango_context};
 use crate::element::{DrawResult, ElementData, ElementTrait, set_attribute};
@@ -15,15 +17,18 @@ use crate::layout::{self, FontProperties, Layer, LayerKind, StackingContext, Str
 use crate::length::*;
 use crate::node::{CascadedValues, Node, NodeBorrow};
 use crate::paint_server::PaintSource;
+use crate::parse_identifiers;
 use crate::parsers::{CommaSeparatedList, Parse, ParseValue};
 use crate::properties::{
-    ComputedValues, Direction, DominantBaseline, FontStretch, FontStyle, FontVariant, FontWeight,
-    PaintOrder, TextAnchor, TextRendering, UnicodeBidi, WritingMode, XmlLang, XmlSpace,
+    ComputedValues, Direction, DominantBaseline, FontFamily, FontStretch, FontStyle, FontVariant,
+    FontWeight, PaintOrder, TextAnchor, TextCombineUpright, TextOrientation, TextRendering,
+    UnicodeBidi, WritingMode, XmlLang, XmlSpace,
 };
 use crate::rect::Rect;
 use crate::rsvg_log;
 use crate::session::Session;
 use crate::space::{NormalizeDefault, XmlSpaceNormalize, xml_space_normalize};
+use crate::transform::{Transform, ValidTransform};
 use crate::xml::Attributes;
 
 /// The state of a text layout operation.
@@ -41,6 +46,198 @@ struct LayoutContext {
     session: Session,
 }
 
+/// Enum for the `lengthAdjust` attribute
+///
+/// <https://svgwg.org/svg2-draft/text.html#LengthAdjustProperty>
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum LengthAdjust {
+    #[default]
+    Spacing,
+    SpacingAndGlyphs,
+}
+
+impl Parse for LengthAdjust {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
+        Ok(parse_identifiers!(
+            parser,
+            "spacing" => LengthAdjust::Spacing,
+            "spacingAndGlyphs" => LengthAdjust::SpacingAndGlyphs,
+        )?)
+    }
+}
+
+/// A `<text>` or `<tspan>` element with a `textLength` attribute.
+///
+/// All the spans inside such an element get adjusted so that their total advance
+/// matches `text_length`.  These can be nested; each span refers to the innermost group
+/// that contains it, and each group refers to its parent group.  Parents are always
+/// created before their children, so a parent's index is lower than its children's.
+///
+/// <https://svgwg.org/svg2-draft/text.html#TextLayoutAlgorithm>, step "Apply textLength".
+struct TextLengthGroup {
+    text_length: f64,
+    length_adjust: LengthAdjust,
+    parent: Option<usize>,
+}
+
+impl TextLengthGroup {
+    /// Pushes a new group for an element with a `textLength` attribute and returns the
+    /// group that should be used for the element's children.
+    fn push(
+        groups: &mut Vec<TextLengthGroup>,
+        parent: Option<usize>,
+        text_length: Option<f64>,
+        length_adjust: LengthAdjust,
+    ) -> Option<usize> {
+        match text_length {
+            // A textLength of zero would collapse all the glyphs onto a single point;
+            // ignore it just like an invalid value.
+            Some(text_length) if text_length > 0.0 => {
+                groups.push(TextLengthGroup {
+                    text_length,
+                    length_adjust,
+                    parent,
+                });
+                Some(groups.len() - 1)
+            }
+
+            _ => parent,
+        }
+    }
+}
+
+/// Resolves a `textLength` attribute along the inline direction of the `writing_mode`.
+///
+/// Percentages refer to the viewport's width for horizontal text, and to its height for
+/// vertical text.
+fn text_length_to_user(
+    text_length: &ULength<Both>,
+    writing_mode: WritingMode,
+    params: &NormalizeParams,
+) -> f64 {
+    if writing_mode.is_horizontal() {
+        text_length.cast::<Horizontal, Unsigned>().to_user(params)
+    } else {
+        text_length.cast::<Vertical, Unsigned>().to_user(params)
+    }
+}
+
+/// Type for the `rotate` attribute of the `<text>` and `<tspan>` elements.
+///
+/// 4096 is an arbitrary limit on the number of values, as a mitigation against malicious
+/// files which may want to have millions of elements to exhaust memory.
+type RotateList = Option<CommaSeparatedList<f64, 1, 4096>>;
+
+/// Values from a `rotate` attribute, and the index of the next character that they apply to.
+struct RotateValues {
+    values: Vec<f64>,
+    next_char: usize,
+}
+
+/// Per-character rotations from the `rotate` attributes of a text element and its ancestors.
+///
+/// An element with a `rotate` attribute pushes its list of angles while its children are
+/// processed.  The angles apply to each of its descendant characters in turn, unless a
+/// descendant has its own `rotate` attribute.  Characters past the end of the list use
+/// the last angle.
+///
+/// <https://svgwg.org/svg2-draft/text.html#TSpanAttributes>
+#[derive(Default)]
+struct CharRotations {
+    stack: Vec<RotateValues>,
+}
+
+impl CharRotations {
+    fn push(&mut self, values: &[f64]) {
+        assert!(!values.is_empty());
+
+        self.stack.push(RotateValues {
+            values: values.to_vec(),
+            next_char: 0,
+        });
+    }
+
+    fn pop(&mut self) {
+        self.stack.pop();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Returns the rotation in degrees for the next character.
+    ///
+    /// Ancestors of the innermost element with a `rotate` attribute also count the
+    /// character, since their lists are indexed by all their descendant characters.
+    fn next_rotation(&mut self) -> f64 {
+        let rotation = self
+            .stack
+            .last()
+            .map_or(0.0, |r| r.values[r.next_char.min(r.values.len() - 1)]);
+
+        for r in &mut self.stack {
+            r.next_char += 1;
+        }
+
+        rotation
+    }
+}
+
+/// Splits a string into its grapheme clusters, per Pango's cursor positions.
+fn grapheme_clusters(text: &str) -> Vec<&str> {
+    // There is one attribute per character, plus one for the end of the text.
+    let attrs = pango::log_attrs(text, -1, &pango::Language::default());
+
+    let mut clusters = Vec::new();
+    let mut start = 0;
+
+    for ((index, _), attr) in text.char_indices().zip(attrs.iter()).skip(1) {
+        if attr.is_cursor_position() {
+            clusters.push(&text[start..index]);
+            start = index;
+        }
+    }
+
+    clusters.push(&text[start..]);
+    clusters
+}
+
+/// Splits a string into pieces that can be laid out as individual spans, and the rotation
+/// for each piece.
+///
+/// Rotations apply to whole grapheme clusters, so that combining marks stay with their
+/// base character: a cluster uses the rotation of its first character, and the values for
+/// the rest of its characters are skipped.
+///
+/// Each rotated cluster is rotated around its own origin, so it needs to be in its own
+/// span.  Consecutive clusters without rotation are kept together, so that Pango can shape
+/// them as usual.
+fn split_by_rotation(text: &str, rotations: &mut CharRotations) -> Vec<(String, f64)> {
+    if rotations.is_empty() || text.is_empty() {
+        return vec![(text.to_string(), 0.0)];
+    }
+
+    let mut pieces: Vec<(String, f64)> = Vec::new();
+
+    for cluster in grapheme_clusters(text) {
+        let rotation = rotations.next_rotation();
+
+        for _ in cluster.chars().skip(1) {
+            rotations.next_rotation();
+        }
+
+        match pieces.last_mut() {
+            Some((piece, last_rotation)) if rotation == 0.0 && *last_rotation == 0.0 => {
+                piece.push_str(cluster);
+            }
+
+            _ => pieces.push((cluster.to_string(), rotation)),
+        }
+    }
+
+    pieces
+}
+
 /// An absolutely-positioned array of `Span`s
 ///
 /// SVG defines a "[text chunk]" to occur when a text-related element
@@ -84,10 +281,15 @@ struct Span {
     _depth: usize,
     link_target: Option<String>,
     span_element_name: Rc<String>,
+    text_length_group: Option<usize>,
+
+    /// Rotation of the glyphs around the span's origin, in degrees, from the `rotate` attribute.
+    rotate: f64,
 }
 
 struct MeasuredSpan {
     values: Rc<ComputedValues>,
+    orientation: GlyphOrientation,
     layout: pango::Layout,
     layout_size: (f64, f64),
     advance: (f64, f64),
@@ -95,11 +297,28 @@ struct MeasuredSpan {
     dy: f64,
     link_target: Option<String>,
     span_element_name: Rc<String>,
+    text_length_group: Option<usize>,
+
+    /// Number of characters in the span, for distributing `textLength` adjustments.
+    num_chars: usize,
+
+    /// Letter spacing in user-space units, including `textLength` adjustments.
+    letter_spacing: f64,
+
+    /// Extra space after the span in the inline direction, from `textLength` adjustments.
+    extra_advance: f64,
+
+    /// Stretch factor for the glyphs in the inline direction, from `lengthAdjust="spacingAndGlyphs"`.
+    inline_scale: f64,
+
+    /// Rotation of the glyphs around the span's origin, in degrees.
+    rotate: f64,
 }
 
 struct PositionedSpan {
     layout: pango::Layout,
     values: Rc<ComputedValues>,
+    glyph_transform: ValidTransform,
     rendered_position: (f64, f64),
     next_span_position: (f64, f64),
     link_target: Option<String>,
@@ -114,6 +333,7 @@ struct PositionedSpan {
 struct LayoutSpan {
     layout: pango::Layout,
     gravity: pango::Gravity,
+    glyph_transform: ValidTransform,
     extents: Option<Rect>,
     is_visible: bool,
     x: f64,
@@ -202,7 +422,7 @@ impl PositionedChunk {
             let values = mspan.values.clone();
             let dx = mspan.dx;
             let dy = mspan.dy;
-            let advance = mspan.advance;
+            let advance = mspan.total_advance();
 
             let baseline_offset = compute_baseline_offset(&layout, &values, &params);
 
@@ -216,14 +436,22 @@ impl PositionedChunk {
                 Direction::Rtl => (-advance.0, advance.1),
             };
 
-            let rendered_position = if layout_context.writing_mode.is_horizontal() {
-                (start_pos.0 + dx, start_pos.1 - baseline_offset + dy)
-            } else {
-                (start_pos.0 + baseline_offset + dx, start_pos.1 + dy)
+            let rendered_position = match mspan.orientation {
+                GlyphOrientation::Horizontal => {
+                    (start_pos.0 + dx, start_pos.1 - baseline_offset + dy)
+                }
+
+                // The horizontal layout gets centered on the vertical line, and inside
+                // the 1em advance that it takes up.
+                GlyphOrientation::CombinedUpright => (
+                    start_pos.0 + dx - layout_size.0 / 2.0,
+                    start_pos.1 + dy + (advance.1 - layout_size.1) / 2.0,
+                ),
+
+                _ => (start_pos.0 + baseline_offset + dx, start_pos.1 + dy),
             };
 
-            let span_bounds =
-                Rect::from_size(layout_size.0, layout_size.1).translate(rendered_position);
+            let span_bounds = layout_bounds(mspan.orientation, layout_size, rendered_position);
 
             // We take the union here
 
@@ -239,6 +467,7 @@ impl PositionedChunk {
             let positioned_span = PositionedSpan {
                 layout,
                 values,
+                glyph_transform: mspan.glyph_transform(),
                 rendered_position,
                 next_span_position: (x, y),
                 link_target: mspan.link_target.clone(),
@@ -377,11 +606,107 @@ fn text_anchor_offset(
             (TextAnchor::End,    Direction::Rtl) => (-x0, 0.0),
         }
     } else {
+        let y0 = chunk_bounds.y0;
+
         // FIXME: we don't deal with text direction for vertical text yet.
         match anchor {
-            TextAnchor::Start => (0.0, 0.0),
-            TextAnchor::Middle => (0.0, -h / 2.0),
-            TextAnchor::End => (0.0, -h),
+            TextAnchor::Start => (0.0, -y0),
+            TextAnchor::Middle => (0.0, -y0 - h / 2.0),
+            TextAnchor::End => (0.0, -y0 - h),
+        }
+    }
+}
+
+/// Computes the area that a span's layout covers in the chunk, given its rendered position.
+///
+/// Layouts for vertical text are rotated by 90 degrees clockwise around their rendered
+/// position (see `pango_layout_to_cairo()`), so their width runs along the Y axis.
+fn layout_bounds(
+    orientation: GlyphOrientation,
+    layout_size: (f64, f64),
+    rendered_position: (f64, f64),
+) -> Rect {
+    let (w, h) = layout_size;
+    let (x, y) = rendered_position;
+
+    if orientation.is_rotated() {
+        Rect::new(x - h, y, x, y + w)
+    } else {
+        Rect::from_size(w, h).translate(rendered_position)
+    }
+}
+
+/// How the glyphs in a span are oriented with respect to the line.
+///
+/// See <https://www.w3.org/TR/css-writing-modes-3/#text-orientation> for the details.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GlyphOrientation {
+    /// Horizontal writing modes; glyphs are laid out normally.
+    Horizontal,
+
+    /// Vertical text with `text-orientation: mixed`.  Pango decides the orientation of
+    /// each run based on its script: typographic characters from vertical scripts like
+    /// CJK are set upright, and characters from horizontal scripts are set sideways.
+    Mixed,
+
+    /// Vertical text with `text-orientation: upright`; all glyphs are set upright.
+    Upright,
+
+    /// Vertical text with `text-orientation: sideways`; all glyphs are set sideways,
+    /// rotated 90 degrees clockwise.
+    Sideways,
+
+    /// Vertical text with `text-combine-upright: all`.  The span is laid out horizontally
+    /// and takes a single 1em advance in the vertical line, for example, for two-digit
+    /// numbers in Japanese text ("tate-chu-yoko").
+    CombinedUpright,
+}
+
+impl GlyphOrientation {
+    fn new(
+        writing_mode: WritingMode,
+        text_orientation: TextOrientation,
+        text_combine_upright: TextCombineUpright,
+    ) -> GlyphOrientation {
+        if writing_mode.is_horizontal() {
+            return GlyphOrientation::Horizontal;
+        }
+
+        match (text_combine_upright, text_orientation) {
+            (TextCombineUpright::All, _) => GlyphOrientation::CombinedUpright,
+            (TextCombineUpright::None, TextOrientation::Mixed) => GlyphOrientation::Mixed,
+            (TextCombineUpright::None, TextOrientation::Upright) => GlyphOrientation::Upright,
+            (TextCombineUpright::None, TextOrientation::Sideways) => GlyphOrientation::Sideways,
+        }
+    }
+
+    /// Whether the span's Pango layout gets rotated to run along a vertical line.
+    fn is_rotated(self) -> bool {
+        !matches!(
+            self,
+            GlyphOrientation::Horizontal | GlyphOrientation::CombinedUpright
+        )
+    }
+
+    /// The base gravity for the Pango context; this determines the rotation of the layout.
+    fn base_gravity(self, writing_mode: WritingMode) -> pango::Gravity {
+        if self.is_rotated() {
+            pango::Gravity::from(writing_mode)
+        } else {
+            pango::Gravity::South
+        }
+    }
+
+    /// Tells Pango how to choose the gravity for each run with respect to the base gravity.
+    ///
+    /// With `Natural`, Pango sets CJK and other vertical scripts upright, and rotates the
+    /// rest, which is exactly `text-orientation: mixed`.  With `Strong`, all glyphs use the
+    /// base gravity, so they are upright.  Sideways text is handled with an explicit
+    /// gravity attribute instead; see `add_pango_attributes()`.
+    fn gravity_hint(self) -> pango::GravityHint {
+        match self {
+            GlyphOrientation::Upright => pango::GravityHint::Strong,
+            _ => pango::GravityHint::Natural,
         }
     }
 }
@@ -395,6 +720,8 @@ impl Span {
         dy: f64,
         depth: usize,
         link_target: Option<String>,
+        text_length_group: Option<usize>,
+        rotate: f64,
     ) -> Span {
         Span {
             values,
@@ -404,15 +731,17 @@ impl Span {
             _depth: depth,
             link_target,
             span_element_name,
+            text_length_group,
+            rotate,
         }
     }
 }
 
 /// Use as `PangoUnits::from_pixels()` so that we can check for overflow.
-struct PangoUnits(i32);
+pub struct PangoUnits(pub i32);
 
 impl PangoUnits {
-    fn from_pixels(v: f64) -> Option<Self> {
+    pub fn from_pixels(v: f64) -> Option<Self> {
         // We want (v * f64::from(pango::SCALE) + 0.5) as i32
         //
         // But check for overflow.
@@ -423,6 +752,90 @@ impl PangoUnits {
     }
 }
 
+/// The properties that select a font, for measuring font-relative length units.
+///
+/// Most of those units get approximated from the font size, but the `ic` and `cap` units
+/// are measured from the actual font; see [`FontSelection::measure_units`].
+#[derive(Debug, Clone)]
+pub struct FontSelection {
+    family: FontFamily,
+    style: FontStyle,
+    variant: FontVariant,
+    weight: FontWeight,
+    stretch: FontStretch,
+}
+
+/// Sizes of the `ic` and `cap` length units for a font, in pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FontUnits {
+    /// Advance of the "水" (CJK water ideograph, U+6C34) glyph.
+    pub ic: f64,
+
+    /// Height of capital letters above the baseline.
+    pub cap: f64,
+}
+
+impl FontSelection {
+    pub fn new(values: &ComputedValues) -> FontSelection {
+        FontSelection {
+            family: values.font_family(),
+            style: values.font_style(),
+            variant: values.font_variant(),
+            weight: values.font_weight(),
+            stretch: values.font_stretch(),
+        }
+    }
+
+    /// Measures the `ic` and `cap` units with Pango, for a font size in pixels.
+    ///
+    /// If the font does not have an ideograph for `ic`, this falls back to `1em`; if it
+    /// does not have a glyph for capital letters, `cap` falls back to the font's ascent.
+    pub fn measure_units(&self, font_size: f64) -> FontUnits {
+        let fallback = FontUnits {
+            ic: font_size,
+            cap: font_size,
+        };
+
+        let Some(size) = PangoUnits::from_pixels(font_size) else {
+            return fallback;
+        };
+
+        let mut font_desc = pango::FontDescription::new();
+        font_desc.set_family(self.family.as_str());
+        font_desc.set_style(pango::Style::from(self.style));
+        font_desc.set_variant(pango::Variant::from(self.variant));
+        font_desc.set_weight(pango::Weight::from(self.weight));
+        font_desc.set_stretch(pango::Stretch::from(self.stretch));
+        font_desc.set_size(size.0);
+
+        // Font metrics don't depend on the rendering options, since we turn off hinting.
+        let pango_context = create_pango_context(&FontOptions::new(false));
+        let layout = pango::Layout::new(&pango_context);
+        layout.set_font_description(Some(&font_desc));
+
+        let pango_scale = f64::from(pango::SCALE);
+
+        layout.set_text("\u{6C34}");
+        let ic = if layout.unknown_glyphs_count() == 0 {
+            let (_, logical) = layout.extents();
+            f64::from(logical.width()) / pango_scale
+        } else {
+            fallback.ic
+        };
+
+        layout.set_text("H");
+        let (ink, _) = layout.extents();
+        let cap = if layout.unknown_glyphs_count() == 0 && ink.height() > 0 {
+            f64::from(layout.baseline() - ink.y()) / pango_scale
+        } else {
+            let metrics = pango_context.metrics(Some(&font_desc), None);
+            f64::from(metrics.ascent()) / pango_scale
+        };
+
+        FontUnits { ic, cap }
+    }
+}
+
 impl MeasuredSpan {
     fn from_span(layout_context: &LayoutContext, span: &Span) -> Option<MeasuredSpan> {
         let values = span.values.clone();
@@ -438,21 +851,36 @@ impl MeasuredSpan {
 
         let with_control_chars = wrap_with_direction_control_chars(&span.text, &bidi_control);
 
-        if let Some(layout) = create_pango_layout(layout_context, &properties, &with_control_chars)
-        {
+        let orientation = GlyphOrientation::new(
+            layout_context.writing_mode,
+            properties.text_orientation,
+            properties.text_combine_upright,
+        );
+
+        if let Some(layout) = create_pango_layout(
+            layout_context,
+            &properties,
+            orientation,
+            &with_control_chars,
+        ) {
             let (w, h) = layout.size();
 
             let w = f64::from(w) / f64::from(pango::SCALE);
             let h = f64::from(h) / f64::from(pango::SCALE);
 
-            let advance = if layout_context.writing_mode.is_horizontal() {
-                (w, 0.0)
-            } else {
-                (0.0, w)
+            let advance = match orientation {
+                GlyphOrientation::Horizontal => (w, 0.0),
+
+                // Combined text takes up a single em in the vertical line, regardless of
+                // its actual width.
+                GlyphOrientation::CombinedUpright => (0.0, properties.font_size),
+
+                _ => (0.0, w),
             };
 
             Some(MeasuredSpan {
                 values,
+                orientation,
                 layout,
                 layout_size: (w, h),
                 advance,
@@ -460,11 +888,240 @@ impl MeasuredSpan {
                 dy: span.dy,
                 link_target: span.link_target.clone(),
                 span_element_name: span.span_element_name.clone(),
+                text_length_group: span.text_length_group,
+                num_chars: span.text.chars().count(),
+                letter_spacing: properties.letter_spacing,
+                extra_advance: 0.0,
+                inline_scale: 1.0,
+                rotate: span.rotate,
             })
         } else {
             None
         }
     }
+
+    /// The span's advance, plus any extra space that `textLength` adds after it.
+    fn total_advance(&self) -> (f64, f64) {
+        let (x, y) = self.advance;
+
+        if self.orientation == GlyphOrientation::Horizontal {
+            (x + self.extra_advance, y)
+        } else {
+            (x, y + self.extra_advance)
+        }
+    }
+
+    /// Length of the span in the inline direction.
+    fn inline_advance(&self) -> f64 {
+        let (x, y) = self.total_advance();
+
+        if self.orientation == GlyphOrientation::Horizontal {
+            x
+        } else {
+            y
+        }
+    }
+
+    /// Number of places where `lengthAdjust="spacing"` can add space in the span.
+    fn num_spacing_units(&self) -> usize {
+        // Combined upright text is a single unit in the vertical line.
+        if self.orientation == GlyphOrientation::CombinedUpright {
+            1
+        } else {
+            self.num_chars
+        }
+    }
+
+    /// Adds `delta` to the space after each character in the span.
+    fn add_spacing(&mut self, delta: f64, session: &Session) {
+        if self.orientation == GlyphOrientation::CombinedUpright {
+            self.extra_advance += delta;
+            return;
+        }
+
+        let letter_spacing = self.letter_spacing + delta;
+
+        let Some(pango_letter_spacing) = PangoUnits::from_pixels(letter_spacing) else {
+            rsvg_log!(
+                session,
+                "letter-spacing {} from textLength is out of bounds; ignoring it",
+                letter_spacing
+            );
+            return;
+        };
+
+        self.letter_spacing = letter_spacing;
+
+        let attr_list = self
+            .layout
+            .attributes()
+            .unwrap_or_else(pango::AttrList::new);
+        attr_list.change(pango::AttrInt::new_letter_spacing(pango_letter_spacing.0).upcast());
+        self.layout.set_attributes(Some(&attr_list));
+
+        let (w, h) = self.layout.size();
+        let w = f64::from(w) / f64::from(pango::SCALE) * self.inline_scale;
+        let h = f64::from(h) / f64::from(pango::SCALE);
+
+        self.layout_size = (w, h);
+        self.advance = if self.orientation == GlyphOrientation::Horizontal {
+            (w, 0.0)
+        } else {
+            (0.0, w)
+        };
+    }
+
+    /// Stretches the span's glyphs and spacing in the inline direction.
+    fn stretch(&mut self, factor: f64) {
+        self.inline_scale *= factor;
+        self.extra_advance *= factor;
+
+        match self.orientation {
+            GlyphOrientation::Horizontal => {
+                self.layout_size.0 *= factor;
+                self.advance.0 *= factor;
+            }
+
+            GlyphOrientation::CombinedUpright => {
+                self.layout_size.1 *= factor;
+                self.advance.1 *= factor;
+            }
+
+            _ => {
+                self.layout_size.0 *= factor;
+                self.advance.1 *= factor;
+            }
+        }
+    }
+
+    /// Transformation for the glyphs relative to the span's origin, for `inline_scale` and
+    /// `rotate`.
+    fn glyph_transform(&self) -> ValidTransform {
+        let (sx, sy) = if self.orientation == GlyphOrientation::Horizontal {
+            (self.inline_scale, 1.0)
+        } else {
+            (1.0, self.inline_scale)
+        };
+
+        let transform = Transform::new_rotate(Angle::from_degrees(self.rotate)).pre_scale(sx, sy);
+
+        ValidTransform::try_from(transform).unwrap_or_default()
+    }
+}
+
+/// Returns whether a span in `span_group` is inside the group with index `group`.
+fn is_in_text_length_group(
+    groups: &[TextLengthGroup],
+    span_group: Option<usize>,
+    group: usize,
+) -> bool {
+    let mut g = span_group;
+
+    while let Some(index) = g {
+        if index == group {
+            return true;
+        }
+
+        g = groups[index].parent;
+    }
+
+    false
+}
+
+/// Returns the child group of `group` which contains `span_group`, or `None` if the span
+/// is directly inside `group`.
+fn child_text_length_group(
+    groups: &[TextLengthGroup],
+    span_group: Option<usize>,
+    group: usize,
+) -> Option<usize> {
+    let mut g = span_group;
+
+    while let Some(index) = g {
+        if groups[index].parent == Some(group) {
+            return Some(index);
+        }
+
+        g = groups[index].parent;
+    }
+
+    None
+}
+
+/// Adjusts the spans in each `textLength` group so that they take up exactly the
+/// requested length.
+///
+/// Nested groups are processed first.  For `lengthAdjust="spacing"`, the extra space is
+/// distributed evenly after each character that is directly inside the group, and after
+/// each nested group as a whole, so that nested groups keep their own length.  For
+/// `lengthAdjust="spacingAndGlyphs"`, all the spans inside the group get stretched by the
+/// same factor.
+fn apply_text_length(
+    measured_chunks: &mut [MeasuredChunk],
+    groups: &[TextLengthGroup],
+    session: &Session,
+) {
+    for (index, group) in groups.iter().enumerate().rev() {
+        let mut spans: Vec<&mut MeasuredSpan> = measured_chunks
+            .iter_mut()
+            .flat_map(|chunk| chunk.spans.iter_mut())
+            .filter(|span| is_in_text_length_group(groups, span.text_length_group, index))
+            .collect();
+
+        let current_length: f64 = spans.iter().map(|span| span.inline_advance()).sum();
+
+        match group.length_adjust {
+            LengthAdjust::SpacingAndGlyphs => {
+                if current_length > 0.0 {
+                    let factor = group.text_length / current_length;
+
+                    for span in spans {
+                        span.stretch(factor);
+                    }
+                }
+            }
+
+            LengthAdjust::Spacing => {
+                let child_groups: Vec<Option<usize>> = spans
+                    .iter()
+                    .map(|span| child_text_length_group(groups, span.text_length_group, index))
+                    .collect();
+
+                let num_children = {
+                    let mut children: Vec<usize> = child_groups.iter().flatten().copied().collect();
+                    children.dedup();
+                    children.len()
+                };
+
+                let num_units = spans
+                    .iter()
+                    .zip(&child_groups)
+                    .filter(|(_, child)| child.is_none())
+                    .map(|(span, _)| span.num_spacing_units())
+                    .sum::<usize>()
+                    + num_children;
+
+                if num_units == 0 {
+                    continue;
+                }
+
+                let delta = (group.text_length - current_length) / num_units as f64;
+
+                for (i, span) in spans.iter_mut().enumerate() {
+                    match child_groups[i] {
+                        None => span.add_spacing(delta, session),
+
+                        // Nested groups get the extra space after their last span.
+                        Some(child) => {
+                            if child_groups.get(i + 1) != Some(&Some(child)) {
+                                span.extra_advance += delta;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 // FIXME: should the pango crate provide this like PANGO_GRAVITY_IS_VERTICAL() ?
@@ -472,7 +1129,7 @@ fn gravity_is_vertical(gravity: pango::Gravity) -> bool {
     matches!(gravity, pango::Gravity::East | pango::Gravity::West)
 }
 
-fn compute_text_box(
+pub fn compute_text_box(
     layout: &pango::Layout,
     x: f64,
     y: f64,
@@ -526,7 +1183,13 @@ impl PositionedSpan {
 
         let gravity = layout.context().gravity();
 
-        let extents = compute_text_box(&layout, x, y, gravity);
+        let extents = compute_text_box(&layout, x, y, gravity).map(|extents| {
+            // The glyph transform is relative to the span's origin.
+            Transform::new_translate(x, y)
+                .pre_transform(&self.glyph_transform)
+                .pre_translate(-x, -y)
+                .transform_rect(&extents)
+        });
 
         let stroke_paint = self.values.stroke().0.resolve(
             acquired_nodes,
@@ -554,6 +1217,7 @@ impl PositionedSpan {
         LayoutSpan {
             layout,
             gravity,
+            glyph_transform: self.glyph_transform,
             extents,
             is_visible,
             x,
@@ -574,6 +1238,7 @@ impl PositionedSpan {
 /// array.
 fn children_to_chunks(
     chunks: &mut Vec<Chunk>,
+    text_length_groups: &mut Vec<TextLengthGroup>,
     node: &Node,
     acquired_nodes: &mut AcquiredNodes<'_>,
     cascaded: &CascadedValues<'_>,
@@ -582,6 +1247,8 @@ fn children_to_chunks(
     dy: f64,
     depth: usize,
     link: Option<String>,
+    text_length_group: Option<usize>,
+    rotations: &mut CharRotations,
 ) {
     let mut dx = dx;
     let mut dy = dy;
@@ -600,6 +1267,8 @@ fn children_to_chunks(
                 dy,
                 depth,
                 link.clone(),
+                text_length_group,
+                rotations,
             );
         } else {
             assert!(child.is_element());
@@ -613,10 +1282,13 @@ fn children_to_chunks(
                         &cascaded,
                         layout_context,
                         chunks,
+                        text_length_groups,
                         dx,
                         dy,
                         depth + 1,
                         link.clone(),
+                        text_length_group,
+                        rotations,
                     );
                 }
 
@@ -637,10 +1309,13 @@ fn children_to_chunks(
                         &cascaded,
                         layout_context,
                         chunks,
+                        text_length_groups,
                         dx,
                         dy,
                         depth + 1,
                         link.link.clone(),
+                        text_length_group,
+                        rotations,
                     );
                 }
 
@@ -653,6 +1328,8 @@ fn children_to_chunks(
                         chunks,
                         depth + 1,
                         layout_context,
+                        text_length_group,
+                        rotations,
                     );
                 }
 
@@ -729,29 +1406,6 @@ impl Chars {
         }
     }
 
-    fn make_span(
-        &self,
-        node: &Node,
-        span_element_name: Rc<String>,
-        values: Rc<ComputedValues>,
-        dx: f64,
-        dy: f64,
-        depth: usize,
-        link_target: Option<String>,
-    ) -> Span {
-        self.ensure_normalized_string(node, &values);
-
-        Span::new(
-            self.space_normalized.borrow().as_ref().unwrap(),
-            span_element_name,
-            values,
-            dx,
-            dy,
-            depth,
-            link_target,
-        )
-    }
-
     fn to_chunks(
         &self,
         node: &Node,
@@ -762,12 +1416,39 @@ impl Chars {
         dy: f64,
         depth: usize,
         link_target: Option<String>,
+        text_length_group: Option<usize>,
+        rotations: &mut CharRotations,
     ) {
-        let span = self.make_span(node, span_element_name, values, dx, dy, depth, link_target);
+        self.ensure_normalized_string(node, &values);
+
         let num_chunks = chunks.len();
         assert!(num_chunks > 0);
 
-        chunks[num_chunks - 1].spans.push(span);
+        let normalized = self.space_normalized.borrow();
+        let pieces = split_by_rotation(normalized.as_ref().unwrap(), rotations);
+
+        // Only the first piece gets the dx/dy offsets.
+        let mut dx = dx;
+        let mut dy = dy;
+
+        for (text, rotate) in pieces {
+            let span = Span::new(
+                &text,
+                span_element_name.clone(),
+                values.clone(),
+                dx,
+                dy,
+                depth,
+                link_target.clone(),
+                text_length_group,
+                rotate,
+            );
+
+            chunks[num_chunks - 1].spans.push(span);
+
+            dx = 0.0;
+            dy = 0.0;
+        }
     }
 
     pub fn get_string(&self) -> String {
@@ -781,6 +1462,9 @@ pub struct Text {
     y: Length<Vertical>,
     dx: Length<Horizontal>,
     dy: Length<Vertical>,
+    rotate: RotateList,
+    text_length: Option<ULength<Both>>,
+    length_adjust: LengthAdjust,
 }
 
 impl Text {
@@ -790,6 +1474,7 @@ impl Text {
         acquired_nodes: &mut AcquiredNodes<'_>,
         cascaded: &CascadedValues<'_>,
         layout_context: &LayoutContext,
+        text_length_groups: &mut Vec<TextLengthGroup>,
         x: f64,
         y: f64,
     ) -> Vec<Chunk> {
@@ -803,8 +1488,23 @@ impl Text {
         let dx = self.dx.to_user(&params);
         let dy = self.dy.to_user(&params);
 
+        let text_length_group = TextLengthGroup::push(
+            text_length_groups,
+            None,
+            self.text_length
+                .as_ref()
+                .map(|l| text_length_to_user(l, layout_context.writing_mode, &params)),
+            self.length_adjust,
+        );
+
+        let mut rotations = CharRotations::default();
+        if let Some(ref rotate) = self.rotate {
+            rotations.push(&rotate.0);
+        }
+
         children_to_chunks(
             &mut chunks,
+            text_length_groups,
             node,
             acquired_nodes,
             cascaded,
@@ -813,6 +1513,8 @@ impl Text {
             dy,
             0,
             None,
+            text_length_group,
+            &mut rotations,
         );
         chunks
     }
@@ -839,13 +1541,25 @@ impl Text {
         let mut x = self.x.to_user(&params);
         let mut y = self.y.to_user(&params);
 
-        let chunks = self.make_chunks(node, acquired_nodes, cascaded, &layout_context, x, y);
+        let mut text_length_groups = Vec::new();
+
+        let chunks = self.make_chunks(
+            node,
+            acquired_nodes,
+            cascaded,
+            &layout_context,
+            &mut text_length_groups,
+            x,
+            y,
+        );
 
         let mut measured_chunks = Vec::new();
         for chunk in &chunks {
             measured_chunks.push(MeasuredChunk::from_chunk(&layout_context, chunk));
         }
 
+        apply_text_length(&mut measured_chunks, &text_length_groups, session);
+
         let mut positioned_chunks = Vec::new();
         for chunk in &measured_chunks {
             let chunk_x = chunk.x.unwrap_or(x);
@@ -896,6 +1610,7 @@ impl Text {
             let text_span = TextSpan {
                 layout: span.layout,
                 gravity: span.gravity,
+                glyph_transform: span.glyph_transform,
                 extents: span.extents,
                 is_visible: span.is_visible,
                 x: span.x,
@@ -924,7 +1639,7 @@ impl Text {
 // of text layout only supports a single value for the x/y/dx/dy attributes.  However,
 // we need to be able to parse values with multiple lengths.  So, we'll do that, but just
 // use the first value from each attribute.
-fn parse_list_and_extract_first<T: Copy + Default + Parse>(
+fn parse_list_and_extract_first<T: Clone + Default + Parse>(
     dest: &mut T,
     attr: QualName,
     value: &str,
@@ -936,7 +1651,7 @@ fn parse_list_and_extract_first<T: Copy + Default + Parse>(
     if list.0.is_empty() {
         *dest = Default::default();
     } else {
-        *dest = list.0[0]; // ignore all but the first element
+        *dest = list.0[0].clone(); // ignore all but the first element
     }
 }
 
@@ -956,6 +1671,15 @@ impl ElementTrait for Text {
                 expanded_name!("", "dy") => {
                     parse_list_and_extract_first(&mut self.dy, attr, value, session)
                 }
+                expanded_name!("", "rotate") => {
+                    set_attribute(&mut self.rotate, attr.parse(value), session)
+                }
+                expanded_name!("", "textLength") => {
+                    set_attribute(&mut self.text_length, attr.parse(value), session)
+                }
+                expanded_name!("", "lengthAdjust") => {
+                    set_attribute(&mut self.length_adjust, attr.parse(value), session)
+                }
                 _ => (),
             }
         }
@@ -1031,6 +1755,8 @@ impl TRef {
         chunks: &mut Vec<Chunk>,
         depth: usize,
         layout_context: &LayoutContext,
+        text_length_group: Option<usize>,
+        rotations: &mut CharRotations,
     ) {
         if self.link.is_none() {
             return;
@@ -1047,7 +1773,14 @@ impl TRef {
 
         if let Ok(acquired) = acquired_nodes.acquire(&tref_element_name, link) {
             let c = acquired.get();
-            extract_chars_children_to_chunks_recursively(chunks, c, Rc::new(values.clone()), depth);
+            extract_chars_children_to_chunks_recursively(
+                chunks,
+                c,
+                Rc::new(values.clone()),
+                depth,
+                text_length_group,
+                rotations,
+            );
         } else {
             rsvg_log!(
                 layout_context.session,
@@ -1064,6 +1797,8 @@ fn extract_chars_children_to_chunks_recursively(
     node: &Node,
     values: Rc<ComputedValues>,
     depth: usize,
+    text_length_group: Option<usize>,
+    rotations: &mut CharRotations,
 ) {
     for child in node.children() {
         let values = values.clone();
@@ -1079,9 +1814,18 @@ fn extract_chars_children_to_chunks_recursively(
                 0.0,
                 depth,
                 None,
+                text_length_group,
+                rotations,
             )
         } else {
-            extract_chars_children_to_chunks_recursively(chunks, &child, values, depth + 1)
+            extract_chars_children_to_chunks_recursively(
+                chunks,
+                &child,
+                values,
+                depth + 1,
+                text_length_group,
+                rotations,
+            )
         }
     }
 }
@@ -1104,6 +1848,9 @@ pub struct TSpan {
     y: Option<Length<Vertical>>,
     dx: Length<Horizontal>,
     dy: Length<Vertical>,
+    rotate: RotateList,
+    text_length: Option<ULength<Both>>,
+    length_adjust: LengthAdjust,
 }
 
 impl TSpan {
@@ -1114,10 +1861,13 @@ impl TSpan {
         cascaded: &CascadedValues<'_>,
         layout_context: &LayoutContext,
         chunks: &mut Vec<Chunk>,
+        text_length_groups: &mut Vec<TextLengthGroup>,
         dx: f64,
         dy: f64,
         depth: usize,
         link: Option<String>,
+        text_length_group: Option<usize>,
+        rotations: &mut CharRotations,
     ) {
         let values = cascaded.get();
         if !values.is_displayed() {
@@ -1126,8 +1876,8 @@ impl TSpan {
 
         let params = NormalizeParams::new(values, &layout_context.viewport);
 
-        let x = self.x.map(|l| l.to_user(&params));
-        let y = self.y.map(|l| l.to_user(&params));
+        let x = self.x.as_ref().map(|l| l.to_user(&params));
+        let y = self.y.as_ref().map(|l| l.to_user(&params));
 
         let span_dx = dx + self.dx.to_user(&params);
         let span_dy = dy + self.dy.to_user(&params);
@@ -1136,8 +1886,22 @@ impl TSpan {
             chunks.push(Chunk::new(values, x, y));
         }
 
+        let text_length_group = TextLengthGroup::push(
+            text_length_groups,
+            text_length_group,
+            self.text_length
+                .as_ref()
+                .map(|l| text_length_to_user(l, layout_context.writing_mode, &params)),
+            self.length_adjust,
+        );
+
+        if let Some(ref rotate) = self.rotate {
+            rotations.push(&rotate.0);
+        }
+
         children_to_chunks(
             chunks,
+            text_length_groups,
             node,
             acquired_nodes,
             cascaded,
@@ -1146,7 +1910,13 @@ impl TSpan {
             span_dy,
             depth,
             link,
+            text_length_group,
+            rotations,
         );
+
+        if self.rotate.is_some() {
+            rotations.pop();
+        }
     }
 }
 
@@ -1166,6 +1936,15 @@ impl ElementTrait for TSpan {
                 expanded_name!("", "dy") => {
                     parse_list_and_extract_first(&mut self.dy, attr, value, session)
                 }
+                expanded_name!("", "rotate") => {
+                    set_attribute(&mut self.rotate, attr.parse(value), session)
+                }
+                expanded_name!("", "textLength") => {
+                    set_attribute(&mut self.text_length, attr.parse(value), session)
+                }
+                expanded_name!("", "lengthAdjust") => {
+                    set_attribute(&mut self.length_adjust, attr.parse(value), session)
+                }
                 _ => (),
             }
         }
@@ -1358,6 +2137,7 @@ fn wrap_with_direction_control_chars(s: &str, bidi_control: &BidiControl) -> Str
 fn create_pango_layout(
     layout_context: &LayoutContext,
     props: &FontProperties,
+    orientation: GlyphOrientation,
     text: &str,
 ) -> Option<pango::Layout> {
     let pango_context = create_pango_context(&layout_context.font_options);
@@ -1366,7 +2146,8 @@ fn create_pango_layout(
         pango_context.set_language(Some(&pango::Language::from_string(lang.as_str())));
     }
 
-    pango_context.set_base_gravity(pango::Gravity::from(layout_context.writing_mode));
+    pango_context.set_base_gravity(orientation.base_gravity(layout_context.writing_mode));
+    pango_context.set_gravity_hint(orientation.gravity_hint());
 
     match (props.unicode_bidi, props.direction) {
         (UnicodeBidi::BidiOverride, _) | (UnicodeBidi::Embed, _) => {
@@ -1405,7 +2186,15 @@ fn create_pango_layout(
 
     if let (Some(font_size), Some(letter_spacing)) = (font_size, letter_spacing) {
         let attr_list = pango::AttrList::new();
-        add_pango_attributes(&attr_list, props, 0, text.len(), font_size, letter_spacing);
+        add_pango_attributes(
+            &attr_list,
+            props,
+            orientation,
+            0,
+            text.len(),
+            font_size,
+            letter_spacing,
+        );
 
         layout.set_attributes(Some(&attr_list));
         layout.set_text(text);
@@ -1418,9 +2207,10 @@ fn create_pango_layout(
 }
 
 /// Adds Pango attributes, suitable for a span of text, to an `AttrList`.
-fn add_pango_attributes(
+pub fn add_pango_attributes(
     attr_list: &pango::AttrList,
     props: &FontProperties,
+    orientation: GlyphOrientation,
     start_index: usize,
     end_index: usize,
     font_size: PangoUnits,
@@ -1459,6 +2249,12 @@ fn add_pango_attributes(
         attributes.push(pango::AttrInt::new_strikethrough(true).upcast());
     }
 
+    // Glyphs with South gravity inside a rotated layout end up rotated along with the
+    // line, i.e. sideways, even for scripts that Pango would otherwise set upright.
+    if orientation == GlyphOrientation::Sideways {
+        attributes.push(pango::AttrInt::new_gravity(pango::Gravity::South).upcast());
+    }
+
     // Set the range in each attribute
 
     for attr in &mut attributes {
@@ -1589,6 +2385,273 @@ mod tests {
         );
     }
 
+    #[test]
+    fn adjusted_advance_vertical_offset_chunk() {
+        use Direction::*;
+        use TextAnchor::*;
+
+        let bounds = Rect::from_size(2.0, 4.0).translate((-2.0, 3.0));
+
+        assert_eq!(
+            text_anchor_offset(Start, Ltr, WritingMode::VerticalRl, bounds),
+            (0.0, -3.0)
+        );
+
+        assert_eq!(
+            text_anchor_offset(Middle, Ltr, WritingMode::VerticalRl, bounds),
+            (0.0, -5.0)
+        );
+
+        assert_eq!(
+            text_anchor_offset(End, Ltr, WritingMode::VerticalRl, bounds),
+            (0.0, -7.0)
+        );
+    }
+
+    #[test]
+    fn glyph_orientation_from_properties() {
+        use GlyphOrientation::*;
+
+        let orientation = GlyphOrientation::new;
+
+        assert_eq!(
+            orientation(
+                WritingMode::HorizontalTb,
+                TextOrientation::Upright,
+                TextCombineUpright::All
+            ),
+            Horizontal
+        );
+
+        assert_eq!(
+            orientation(
+                WritingMode::VerticalRl,
+                TextOrientation::Mixed,
+                TextCombineUpright::None
+            ),
+            Mixed
+        );
+
+        assert_eq!(
+            orientation(
+                WritingMode::VerticalLr,
+                TextOrientation::Upright,
+                TextCombineUpright::None
+            ),
+            Upright
+        );
+
+        assert_eq!(
+            orientation(
+                WritingMode::VerticalRl,
+                TextOrientation::Sideways,
+                TextCombineUpright::None
+            ),
+            Sideways
+        );
+
+        assert_eq!(
+            orientation(
+                WritingMode::VerticalRl,
+                TextOrientation::Sideways,
+                TextCombineUpright::All
+            ),
+            CombinedUpright
+        );
+    }
+
+    #[test]
+    fn glyph_orientation_maps_to_pango_gravity() {
+        use GlyphOrientation::*;
+
+        assert_eq!(
+            Mixed.base_gravity(WritingMode::VerticalRl),
+            pango::Gravity::East
+        );
+        assert_eq!(
+            Upright.base_gravity(WritingMode::VerticalLr),
+            pango::Gravity::West
+        );
+        assert_eq!(
+            CombinedUpright.base_gravity(WritingMode::VerticalRl),
+            pango::Gravity::South
+        );
+        assert_eq!(
+            Horizontal.base_gravity(WritingMode::HorizontalTb),
+            pango::Gravity::South
+        );
+
+        assert_eq!(Mixed.gravity_hint(), pango::GravityHint::Natural);
+        assert_eq!(Upright.gravity_hint(), pango::GravityHint::Strong);
+        assert_eq!(Sideways.gravity_hint(), pango::GravityHint::Natural);
+    }
+
+    #[test]
+    fn layout_bounds_for_vertical_text_are_rotated() {
+        assert_eq!(
+            layout_bounds(GlyphOrientation::Mixed, (10.0, 2.0), (5.0, 1.0)),
+            Rect::new(3.0, 1.0, 5.0, 11.0)
+        );
+
+        assert_eq!(
+            layout_bounds(GlyphOrientation::CombinedUpright, (10.0, 2.0), (5.0, 1.0)),
+            Rect::new(5.0, 1.0, 15.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn glyph_orientation_vertical_overrides_text_orientation() {
+        use crate::properties::{ParsedProperty, SpecifiedValue, SpecifiedValues};
+
+        let mut specified = SpecifiedValues::default();
+        specified.set_parsed_property(&ParsedProperty::TextOrientation(SpecifiedValue::Specified(
+            TextOrientation::Sideways,
+        )));
+
+        let mut values = ComputedValues::default();
+        specified.to_computed_values(&mut values);
+        assert_eq!(
+            values.resolved_text_orientation(),
+            TextOrientation::Sideways
+        );
+
+        specified.set_parsed_property(&ParsedProperty::GlyphOrientationVertical(
+            SpecifiedValue::Specified(crate::properties::GlyphOrientationVertical::Angle0),
+        ));
+
+        let mut values = ComputedValues::default();
+        specified.to_computed_values(&mut values);
+        assert_eq!(values.resolved_text_orientation(), TextOrientation::Upright);
+    }
+
+    #[test]
+    fn parses_length_adjust() {
+        assert_eq!(
+            LengthAdjust::parse_str("spacing").unwrap(),
+            LengthAdjust::Spacing
+        );
+        assert_eq!(
+            LengthAdjust::parse_str("spacingAndGlyphs").unwrap(),
+            LengthAdjust::SpacingAndGlyphs
+        );
+        assert!(LengthAdjust::parse_str("glyphs").is_err());
+    }
+
+    #[test]
+    fn text_length_groups_nest() {
+        let mut groups = Vec::new();
+
+        let outer = TextLengthGroup::push(&mut groups, None, Some(100.0), LengthAdjust::Spacing);
+        assert_eq!(outer, Some(0));
+
+        // Elements without textLength, or with a zero one, use their parent's group.
+        assert_eq!(
+            TextLengthGroup::push(&mut groups, outer, None, LengthAdjust::Spacing),
+            outer
+        );
+        assert_eq!(
+            TextLengthGroup::push(&mut groups, outer, Some(0.0), LengthAdjust::Spacing),
+            outer
+        );
+
+        let inner = TextLengthGroup::push(
+            &mut groups,
+            outer,
+            Some(50.0),
+            LengthAdjust::SpacingAndGlyphs,
+        );
+        assert_eq!(inner, Some(1));
+
+        let innermost =
+            TextLengthGroup::push(&mut groups, inner, Some(10.0), LengthAdjust::Spacing);
+        assert_eq!(innermost, Some(2));
+
+        assert!(is_in_text_length_group(&groups, innermost, 0));
+        assert!(is_in_text_length_group(&groups, innermost, 1));
+        assert!(is_in_text_length_group(&groups, inner, 1));
+        assert!(!is_in_text_length_group(&groups, outer, 1));
+        assert!(!is_in_text_length_group(&groups, None, 0));
+
+        assert_eq!(child_text_length_group(&groups, innermost, 0), Some(1));
+        assert_eq!(child_text_length_group(&groups, innermost, 1), Some(2));
+        assert_eq!(child_text_length_group(&groups, outer, 0), None);
+    }
+
+    #[test]
+    fn rotations_use_last_value_for_remaining_chars() {
+        let mut rotations = CharRotations::default();
+        rotations.push(&[10.0, 20.0]);
+
+        assert_eq!(
+            split_by_rotation("abcd", &mut rotations),
+            vec![
+                (String::from("a"), 10.0),
+                (String::from("b"), 20.0),
+                (String::from("c"), 20.0),
+                (String::from("d"), 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn unrotated_chars_are_kept_together() {
+        let mut rotations = CharRotations::default();
+        assert_eq!(
+            split_by_rotation("abc", &mut rotations),
+            vec![(String::from("abc"), 0.0)]
+        );
+
+        rotations.push(&[0.0, 0.0, 45.0, 0.0]);
+        assert_eq!(
+            split_by_rotation("abcde", &mut rotations),
+            vec![
+                (String::from("ab"), 0.0),
+                (String::from("c"), 45.0),
+                (String::from("de"), 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn rotations_apply_to_grapheme_clusters() {
+        let mut rotations = CharRotations::default();
+
+        // "e" with a combining acute accent, then "a"
+        rotations.push(&[30.0, 80.0, 0.0, 45.0]);
+        assert_eq!(
+            split_by_rotation("e\u{301}ab", &mut rotations),
+            vec![
+                (String::from("e\u{301}"), 30.0),
+                (String::from("a"), 0.0),
+                (String::from("b"), 45.0),
+            ]
+        );
+
+        rotations.pop();
+        rotations.push(&[0.0, 90.0, 0.0]);
+        assert_eq!(
+            split_by_rotation("e\u{301}a", &mut rotations),
+            vec![(String::from("e\u{301}a"), 0.0)]
+        );
+    }
+
+    #[test]
+    fn nested_rotations_advance_ancestor_lists() {
+        let mut rotations = CharRotations::default();
+
+        // <text rotate="1 2 3 4 5">a<tspan rotate="90">bc</tspan>de</text>
+        rotations.push(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(rotations.next_rotation(), 1.0);
+
+        rotations.push(&[90.0]);
+        assert_eq!(rotations.next_rotation(), 90.0);
+        assert_eq!(rotations.next_rotation(), 90.0);
+        rotations.pop();
+
+        assert_eq!(rotations.next_rotation(), 4.0);
+        assert_eq!(rotations.next_rotation(), 5.0);
+    }
+
     #[test]
     fn pango_units_works() {
         assert_eq!(PangoUnits::from_pixels(10.0).unwrap().0, pango::SCALE * 10);
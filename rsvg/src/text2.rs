@@ -1,20 +1,18 @@
 // ! development file for text2
-use cssparser::Parser;
 use markup5ever::{expanded_name, local_name, ns};
 use pango::IsAttribute;
 use rctree::NodeEdge;
 
 use crate::element::{Element, ElementData, ElementTrait, set_attribute};
-use crate::error::ParseError;
 use crate::layout::FontProperties;
 use crate::length::{Horizontal, Length, NormalizeParams, Vertical};
 use crate::node::{Node, NodeData};
-use crate::parsers::{CommaSeparatedList, Parse, ParseValue};
+use crate::parsers::{CommaSeparatedList, ParseValue};
 use crate::properties::WhiteSpace;
+use crate::rsvg_log;
 use crate::session::Session;
-use crate::text::BidiControl;
+use crate::text::{BidiControl, LengthAdjust};
 use crate::xml;
-use crate::{parse_identifiers, rsvg_log};
 
 /// Type for the `x/y/dx/dy` attributes of the `<text>` and `<tspan>` elements
 ///
@@ -40,26 +38,6 @@ type OptionalLengthList<N> = Option<CommaSeparatedList<Length<N>, 1, 4096>>;
 /// See [`OptionalLengthList`] for a description of the structure of the type.
 type OptionalRotateList = Option<CommaSeparatedList<f64, 1, 4096>>;
 
-/// Enum for the `lengthAdjust` attribute
-///
-/// <https://svgwg.org/svg2-draft/text.html#LengthAdjustProperty>
-#[derive(Debug, Default, Copy, Clone, PartialEq)]
-enum LengthAdjust {
-    #[default]
-    Spacing,
-    SpacingAndGlyphs,
-}
-
-impl Parse for LengthAdjust {
-    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
-        Ok(parse_identifiers!(
-            parser,
-            "spacing" => LengthAdjust::Spacing,
-            "spacingAndGlyphs" => LengthAdjust::SpacingAndGlyphs,
-        )?)
-    }
-}
-
 #[allow(dead_code)]
 #[derive(Default)]
 pub struct Text2 {
@@ -20,7 +20,7 @@ use crate::error::*;
 use crate::length::*;
 use crate::parsers::{Parse, optional_comma};
 use crate::properties::ComputedValues;
-use crate::property_macros::Property;
+use crate::property_macros::{Property, ToCssValue};
 use crate::rect::Rect;
 
 /// A transform that has been checked to be invertible.
@@ -235,7 +235,7 @@ fn parse_prop_matrix_args<'i>(
 }
 
 fn length_is_in_pixels<N: Normalize>(l: &Length<N>) -> bool {
-    l.unit == LengthUnit::Px
+    !l.is_calc() && l.unit == LengthUnit::Px
 }
 
 fn only_pixels_error<'i>(loc: cssparser::SourceLocation) -> ParseError<'i> {
@@ -561,6 +561,32 @@ impl Default for Transform {
     }
 }
 
+impl ToCssValue for Transform {
+    fn to_css_value(&self) -> Option<String> {
+        if *self == Transform::identity() {
+            return Some(String::from("none"));
+        }
+
+        [self.xx, self.yx, self.xy, self.yy, self.x0, self.y0]
+            .iter()
+            .map(|n| n.to_css_value())
+            .collect::<Option<Vec<_>>>()
+            .map(|numbers| format!("matrix({})", numbers.join(", ")))
+    }
+}
+
+impl ToCssValue for TransformProperty {
+    fn to_css_value(&self) -> Option<String> {
+        match self {
+            TransformProperty::None => Some(String::from("none")),
+
+            // Like getComputedStyle(), serialize the list of functions as the
+            // equivalent matrix.
+            TransformProperty::List(_) => self.to_transform().to_css_value(),
+        }
+    }
+}
+
 impl TransformAttribute {
     pub fn to_transform(self) -> Transform {
         self.0
@@ -964,6 +990,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serializes_transform_property() {
+        assert_eq!(
+            parse_transform_prop("none")
+                .unwrap()
+                .to_css_value()
+                .as_deref(),
+            Some("none")
+        );
+
+        assert_eq!(
+            parse_transform_prop("translate(10px, 20px) scale(2)")
+                .unwrap()
+                .to_css_value()
+                .as_deref(),
+            Some("matrix(2, 0, 0, 2, 10, 20)")
+        );
+    }
+
     #[test]
     fn empty_transform_property_is_error() {
         // https://www.w3.org/TR/css-transforms-1/#transform-property
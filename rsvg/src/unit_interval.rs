@@ -5,6 +5,7 @@ use cssparser::Parser;
 use crate::error::*;
 use crate::length::*;
 use crate::parsers::Parse;
+use crate::property_macros::ToCssValue;
 use crate::util;
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, PartialOrd)]
@@ -21,7 +22,9 @@ impl Parse for UnitInterval {
         let loc = parser.current_source_location();
         let l: Length<Both> = Parse::parse(parser)?;
         match l.unit {
-            LengthUnit::Px | LengthUnit::Percent => Ok(UnitInterval::clamp(l.length)),
+            LengthUnit::Px | LengthUnit::Percent if !l.is_calc() => {
+                Ok(UnitInterval::clamp(l.length))
+            }
             _ => Err(loc.new_custom_error(ValueErrorKind::value_error(
                 "<unit-interval> must be in default or percent units",
             ))),
@@ -29,6 +32,12 @@ impl Parse for UnitInterval {
     }
 }
 
+impl ToCssValue for UnitInterval {
+    fn to_css_value(&self) -> Option<String> {
+        self.0.to_css_value()
+    }
+}
+
 impl From<UnitInterval> for u8 {
     fn from(val: UnitInterval) -> u8 {
         let UnitInterval(x) = val;
@@ -1,8 +1,9 @@
 use gio::prelude::*;
 
 use rsvg::tests_only::{SharedImageSurface, SurfaceType};
-use rsvg::{CairoRenderer, RenderingError};
+use rsvg::{CairoRenderer, FilterResolution, RenderingError};
 
+use rsvg::test_utils::compare_surfaces::{BufferDiff, compare_surfaces};
 use rsvg::test_utils::load_svg;
 use rsvg::test_utils::reference_utils::{Compare, Evaluate, Reference};
 
@@ -259,3 +260,422 @@ fn cancellation_works() {
         .compare(&output_surf)
         .evaluate(&output_surf, "cancellation_works");
 }
+
+#[test]
+fn switch_branches_honor_supported_extensions() {
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <switch id="variant">
+    <rect id="pro" requiredExtensions="http://example.com/pro" width="10" height="10"/>
+    <circle id="basic" r="10"/>
+  </switch>
+  <switch>
+    <g requiredExtensions="http://example.com/unknown"/>
+  </switch>
+</svg>
+"##,
+    )
+    .unwrap();
+
+    let branches = CairoRenderer::new(&svg).switch_branches();
+    assert_eq!(branches.len(), 2);
+
+    assert_eq!(branches[0].switch_id.as_deref(), Some("variant"));
+    assert_eq!(branches[0].child_index, Some(1));
+    assert_eq!(branches[0].child_element_name.as_deref(), Some("circle"));
+    assert_eq!(branches[0].child_id.as_deref(), Some("basic"));
+
+    assert_eq!(branches[1].switch_id, None);
+    assert_eq!(branches[1].child_index, None);
+    assert_eq!(branches[1].child_element_name, None);
+    assert_eq!(branches[1].child_id, None);
+
+    let renderer = CairoRenderer::new(&svg).with_supported_extensions(&["http://example.com/pro"]);
+    let branches = renderer.switch_branches();
+    assert_eq!(branches[0].child_index, Some(0));
+    assert_eq!(branches[0].child_id.as_deref(), Some("pro"));
+
+    let (ink_r, _) = renderer
+        .geometry_for_layer(
+            Some("#variant"),
+            &cairo::Rectangle::new(0.0, 0.0, 100.0, 100.0),
+        )
+        .unwrap();
+    assert_eq!(ink_r, cairo::Rectangle::new(0.0, 0.0, 10.0, 10.0));
+}
+
+#[test]
+fn filter_cache_renders_the_same_output() {
+    let svg_bytes = br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter" x="0" y="0" width="1" height="1">
+    <feTurbulence baseFrequency="0.05" numOctaves="2"/>
+    <feComposite in2="SourceAlpha" operator="in"/>
+  </filter>
+  <rect x="10" y="10" width="80" height="80" fill="black" filter="url(#filter)"/>
+</svg>
+"##;
+
+    let render = |svg: &rsvg::SvgHandle| {
+        let renderer = CairoRenderer::new(svg);
+        let output = cairo::ImageSurface::create(cairo::Format::ARgb32, 100, 100).unwrap();
+
+        {
+            let cr = cairo::Context::new(&output).expect("Failed to create cairo context");
+            let viewport = cairo::Rectangle::new(0.0, 0.0, 100.0, 100.0);
+
+            renderer.render_document(&cr, &viewport).unwrap();
+        }
+
+        output
+    };
+
+    let uncached = load_svg(svg_bytes).unwrap();
+
+    let bytes = glib::Bytes::from_static(svg_bytes);
+    let stream = gio::MemoryInputStream::from_bytes(&bytes);
+    let cached = rsvg::Loader::new()
+        .with_filter_cache(1024 * 1024)
+        .read_stream(&stream, None::<&gio::File>, None::<&gio::Cancellable>)
+        .unwrap();
+
+    // The first render fills the cache, the second one uses it.
+    for _ in 0..2 {
+        let output_surf = SharedImageSurface::wrap(render(&cached), SurfaceType::SRgb).unwrap();
+
+        Reference::from_surface(render(&uncached))
+            .compare(&output_surf)
+            .evaluate(&output_surf, "filter_cache_renders_the_same_output");
+    }
+
+    cached.clear_caches();
+
+    let output_surf = SharedImageSurface::wrap(render(&cached), SurfaceType::SRgb).unwrap();
+
+    Reference::from_surface(render(&uncached))
+        .compare(&output_surf)
+        .evaluate(&output_surf, "filter_cache_renders_the_same_output");
+}
+
+#[test]
+fn filter_resolution_can_be_controlled() {
+    // The edges of the rectangle are not aligned to multiples of the pixel size at lower
+    // filter resolutions, so those get smudged when the filter's result is scaled up.
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20">
+  <filter id="filter">
+    <feOffset dx="0" dy="0"/>
+  </filter>
+  <rect x="1" y="1" width="9" height="9" fill="lime" filter="url(#filter)"/>
+</svg>
+"##,
+    )
+    .unwrap();
+
+    let render = |filter_resolution| {
+        let renderer = CairoRenderer::new(&svg).with_filter_resolution(filter_resolution);
+        let output = cairo::ImageSurface::create(cairo::Format::ARgb32, 20, 20).unwrap();
+
+        {
+            let cr = cairo::Context::new(&output).expect("Failed to create cairo context");
+            let viewport = cairo::Rectangle::new(0.0, 0.0, 20.0, 20.0);
+
+            renderer.render_document(&cr, &viewport).unwrap();
+        }
+
+        SharedImageSurface::wrap(output, SurfaceType::SRgb).unwrap()
+    };
+
+    let device = render(FilterResolution::Device);
+
+    let is_like_device =
+        |filter_resolution| match compare_surfaces(&device, &render(filter_resolution)).unwrap() {
+            BufferDiff::Diff(diff) => diff.num_pixels_changed == 0,
+            BufferDiff::DifferentSizes => unreachable!("surfaces have the same size"),
+        };
+
+    // The document is rendered at its natural size, so one pixel per CSS pixel is the
+    // device's resolution.
+    assert!(is_like_device(FilterResolution::Fixed(1.0)));
+    assert!(!is_like_device(FilterResolution::Fixed(0.25)));
+
+    assert!(is_like_device(FilterResolution::Capped(4.0)));
+    assert!(!is_like_device(FilterResolution::Capped(0.25)));
+
+    assert!(is_like_device(FilterResolution::MaxPixels(u32::MAX)));
+    assert!(!is_like_device(FilterResolution::MaxPixels(16)));
+
+    // Zero means no limit, instead of making the filtered element disappear.
+    assert!(is_like_device(FilterResolution::MaxPixels(0)));
+}
+
+#[test]
+fn element_tree_can_be_walked() {
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"
+     id="root" width="50" height="50">
+  <g id="group" class="foo  bar">
+    <rect id="rect" x="10" y="10" width="30" height="30" fill="red"/>
+    <use xlink:href="#rect"/>
+  </g>
+  <text id="text">Hello <tspan>world</tspan></text>
+</svg>
+"##,
+    )
+    .unwrap();
+
+    let root = svg.root();
+    assert_eq!(root.name(), "svg");
+    assert_eq!(root.namespace(), "http://www.w3.org/2000/svg");
+    assert_eq!(root.id().as_deref(), Some("root"));
+    assert!(root.parent().is_none());
+
+    let children: Vec<_> = root.children().collect();
+    assert_eq!(children.len(), 2);
+
+    let group = &children[0];
+    assert_eq!(group.name(), "g");
+    assert_eq!(group.classes(), vec!["foo", "bar"]);
+    assert_eq!(group.parent().as_ref(), Some(&root));
+    assert_eq!(group.next_sibling().as_ref(), Some(&children[1]));
+    assert!(children[1].next_sibling().is_none());
+
+    let rect = group.children().next().unwrap();
+    assert_eq!(rect.name(), "rect");
+    assert!(rect.classes().is_empty());
+    assert_eq!(rect.attribute("fill").as_deref(), Some("red"));
+    assert_eq!(rect.attribute("stroke"), None);
+    assert_eq!(
+        rect.attributes(),
+        vec![
+            ("id".to_string(), "rect".to_string()),
+            ("x".to_string(), "10".to_string()),
+            ("y".to_string(), "10".to_string()),
+            ("width".to_string(), "30".to_string()),
+            ("height".to_string(), "30".to_string()),
+            ("fill".to_string(), "red".to_string()),
+        ]
+    );
+
+    let use_ = rect.next_sibling().unwrap();
+    assert_eq!(use_.attribute("xlink:href").as_deref(), Some("#rect"));
+    assert_eq!(use_.id(), None);
+
+    let text = &children[1];
+    assert_eq!(text.text_content(), "Hello world");
+    assert_eq!(text.children().next().unwrap().name(), "tspan");
+}
+
+#[test]
+fn element_ref_outlives_handle() {
+    let svg = load_svg(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="50" height="50">
+  <rect id="foo" x="10" y="10" width="30" height="30"/>
+</svg>
+"#,
+    )
+    .unwrap();
+
+    let rect = svg.root().children().next().unwrap();
+    drop(svg);
+
+    assert_eq!(rect.id().as_deref(), Some("foo"));
+    assert_eq!(rect.parent().unwrap().name(), "svg");
+}
+
+#[test]
+fn computed_style_reflects_the_cascade() {
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="50" height="50">
+  <style>
+    .thick { stroke-width: 4px; }
+  </style>
+  <g fill="#00ff00" opacity="0.5" visibility="hidden">
+    <rect id="foo" class="thick" x="10" y="10" width="30" height="30"
+          transform="translate(5, 10)" style="fill-opacity: 0.25"/>
+  </g>
+</svg>
+"##,
+    )
+    .unwrap();
+
+    let style = svg.computed_style("#foo").unwrap();
+
+    assert_eq!(style.property("fill").as_deref(), Some("rgb(0, 255, 0)"));
+    assert_eq!(style.property("stroke-width").as_deref(), Some("4px"));
+    assert_eq!(style.property("fill-opacity").as_deref(), Some("0.25"));
+    assert_eq!(style.property("font"), None);
+    assert_eq!(style.property("foo"), None);
+
+    // opacity is not inherited
+    assert_eq!(style.opacity(), 1.0);
+    assert_eq!(style.fill_opacity(), 0.25);
+
+    assert!(style.is_displayed());
+    assert!(!style.is_visible());
+    assert_eq!(
+        style.transform(),
+        cairo::Matrix::new(1.0, 0.0, 0.0, 1.0, 5.0, 10.0)
+    );
+
+    assert!(
+        style
+            .properties()
+            .iter()
+            .any(|(name, value)| name == "fill" && value.as_deref() == Some("rgb(0, 255, 0)"))
+    );
+
+    // Values that can't be serialized yet are still listed.
+    assert!(
+        style
+            .properties()
+            .iter()
+            .any(|(name, value)| name == "filter" && value.is_none())
+    );
+
+    assert!(matches!(
+        svg.computed_style("#bar"),
+        Err(RenderingError::IdNotFound)
+    ));
+}
+
+#[test]
+fn elements_at_point_honors_pointer_events() {
+    let svg = load_svg(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <clipPath id="clip">
+    <rect x="0" y="80" width="10" height="10"/>
+  </clipPath>
+  <rect id="back" x="0" y="0" width="100" height="100" fill="blue"/>
+  <g transform="translate(50, 0)">
+    <rect id="moved" x="0" y="0" width="20" height="20" fill="red"/>
+  </g>
+  <rect id="hidden" x="0" y="0" width="20" height="20" fill="red" visibility="hidden"/>
+  <rect id="unfilled" x="0" y="0" width="20" height="20" fill="none" stroke="black" stroke-width="2"/>
+  <rect id="all" x="0" y="40" width="20" height="20" fill="none" pointer-events="all"/>
+  <rect id="clipped" x="0" y="80" width="20" height="20" fill="red" clip-path="url(#clip)"/>
+  <rect x="0" y="0" width="100" height="100" fill="green" opacity="0.5" pointer-events="none"/>
+</svg>
+"#,
+    )
+    .unwrap();
+
+    let renderer = CairoRenderer::new(&svg);
+    let viewport = cairo::Rectangle::new(0.0, 0.0, 100.0, 100.0);
+
+    let at = |x, y| renderer.elements_at_point(&viewport, x, y).unwrap();
+
+    assert_eq!(at(10.0, 10.0), vec!["back"]);
+    assert_eq!(at(60.0, 10.0), vec!["moved", "back"]);
+    assert_eq!(at(0.5, 10.0), vec!["unfilled", "back"]);
+    assert_eq!(at(10.0, 50.0), vec!["all", "back"]);
+    assert_eq!(at(5.0, 85.0), vec!["clipped", "back"]);
+    assert_eq!(at(15.0, 85.0), vec!["back"]);
+    assert!(at(150.0, 150.0).is_empty());
+
+    let viewport = cairo::Rectangle::new(0.0, 0.0, 200.0, 200.0);
+    assert_eq!(
+        renderer.elements_at_point(&viewport, 120.0, 20.0).unwrap(),
+        vec!["moved", "back"]
+    );
+}
+
+#[test]
+fn elements_at_point_reports_use_instances() {
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="100" height="100">
+  <defs>
+    <symbol id="pin" width="20" height="20">
+      <circle id="head" cx="10" cy="10" r="10" fill="red"/>
+      <rect id="dot" x="8" y="8" width="4" height="4" fill="white"/>
+    </symbol>
+    <g id="group-of-pins">
+      <use id="inner" xlink:href="#pin"/>
+    </g>
+  </defs>
+  <use id="pin-1" xlink:href="#pin" x="0" y="0" width="20" height="20"/>
+  <use id="pin-2" xlink:href="#pin" x="50" y="0" width="20" height="20"/>
+  <use id="pin-3" xlink:href="#group-of-pins" x="0" y="50"/>
+</svg>
+"##,
+    )
+    .unwrap();
+
+    let renderer = CairoRenderer::new(&svg);
+    let viewport = cairo::Rectangle::new(0.0, 0.0, 100.0, 100.0);
+
+    let at = |x, y| renderer.elements_at_point(&viewport, x, y).unwrap();
+
+    // Both the circle and the rect of the symbol are hit, but the instance is reported once.
+    assert_eq!(at(10.0, 10.0), vec!["pin-1"]);
+    assert_eq!(at(60.0, 10.0), vec!["pin-2"]);
+    assert_eq!(at(10.0, 60.0), vec!["pin-3"]);
+    assert!(at(30.0, 10.0).is_empty());
+}
+
+#[test]
+fn geometry_for_all_elements_in_one_pass() {
+    let svg = load_svg(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <defs>
+    <rect id="template" width="5" height="5"/>
+  </defs>
+  <g id="group" transform="translate(10, 20)">
+    <rect id="a" x="0" y="0" width="10" height="10"/>
+    <rect x="20" y="0" width="10" height="10" stroke="black" stroke-width="2"/>
+  </g>
+  <rect id="hidden" x="0" y="0" width="10" height="10" display="none"/>
+</svg>
+"#,
+    )
+    .unwrap();
+
+    let renderer = CairoRenderer::new(&svg);
+    let viewport = cairo::Rectangle::new(0.0, 0.0, 100.0, 100.0);
+
+    let geometries = renderer.geometry_for_all_elements(&viewport, true).unwrap();
+
+    let ids: Vec<_> = geometries.iter().map(|g| g.element.id().unwrap()).collect();
+    assert_eq!(ids, vec!["a", "group"]);
+
+    let a = &geometries[0];
+    assert_eq!(
+        a.logical_rect_in_user_space,
+        cairo::Rectangle::new(0.0, 0.0, 10.0, 10.0)
+    );
+    assert_eq!(
+        a.ink_rect_in_user_space,
+        cairo::Rectangle::new(0.0, 0.0, 10.0, 10.0)
+    );
+    assert_eq!(
+        a.logical_rect_in_viewport,
+        cairo::Rectangle::new(10.0, 20.0, 10.0, 10.0)
+    );
+
+    let group = &geometries[1];
+    assert_eq!(
+        group.logical_rect_in_user_space,
+        cairo::Rectangle::new(0.0, 0.0, 30.0, 10.0)
+    );
+    assert_eq!(
+        group.logical_rect_in_viewport,
+        cairo::Rectangle::new(10.0, 20.0, 30.0, 10.0)
+    );
+
+    // The stroke of the anonymous rectangle only counts for the ink rectangle.
+    assert!(group.ink_rect_in_viewport.width() > 30.0);
+
+    let all = renderer
+        .geometry_for_all_elements(&viewport, false)
+        .unwrap();
+    let names: Vec<_> = all.iter().map(|g| g.element.name()).collect();
+    assert_eq!(names, vec!["rect", "rect", "g", "svg"]);
+}
@@ -1,6 +1,7 @@
 use rsvg::test_utils::reference_utils::{Compare, Evaluate, Reference};
 use rsvg::test_utils::{SurfaceSize, load_svg, render_document};
-use rsvg::{test_compare_render_output, test_svg_reference};
+use rsvg::tests_only::{SharedImageSurface, SurfaceType};
+use rsvg::{CairoRenderer, RenderQuality, test_compare_render_output, test_svg_reference};
 
 #[test]
 fn invalid_filter_reference_cancels_filter_chain() {
@@ -367,3 +368,301 @@ test_svg_reference!(
     "tests/fixtures/reftests/svg2/bug743-fe-drop-shadow.svg",
     "tests/fixtures/reftests/svg2/bug743-fe-drop-shadow-ref.svg"
 );
+
+test_compare_render_output!(
+    filter_region_away_from_origin,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg version="1.1" xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <defs>
+    <filter id="filter" filterUnits="userSpaceOnUse" x="300" y="200" width="50" height="60">
+      <feFlood flood-color="lime"/>
+    </filter>
+  </defs>
+
+  <rect x="10" y="10" width="10" height="10" filter="url(#filter)"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg version="1.1" xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="300" y="200" width="50" height="60" fill="lime"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    filter_reads_content_outside_of_clip,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg version="1.1" xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <defs>
+    <clipPath id="clip">
+      <rect x="200" y="200" width="100" height="100"/>
+    </clipPath>
+    <filter id="filter" filterUnits="userSpaceOnUse" x="0" y="0" width="400" height="400">
+      <feOffset dx="150" dy="150"/>
+    </filter>
+  </defs>
+
+  <g clip-path="url(#clip)">
+    <rect x="50" y="50" width="100" height="100" fill="lime" filter="url(#filter)"/>
+  </g>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg version="1.1" xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="200" y="200" width="100" height="100" fill="lime"/>
+</svg>
+"##,
+);
+
+// A filterRes larger than the output would need a surface of 100000x100000 pixels, which
+// Cairo can't even create; the filter runs at the output's resolution instead.
+test_compare_render_output!(
+    filter_res_does_not_raise_resolution,
+    200,
+    200,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+  <filter id="filter" x="0" y="0" width="1" height="1" filterRes="100000">
+    <feFlood flood-color="lime"/>
+  </filter>
+  <rect x="50" y="50" width="100" height="100" filter="url(#filter)"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+  <rect x="50" y="50" width="100" height="100" fill="lime"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    filter_res_zero_disables_rendering,
+    200,
+    200,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+  <filter id="filter" filterRes="0">
+    <feFlood flood-color="lime"/>
+  </filter>
+  <rect x="50" y="50" width="100" height="100" filter="url(#filter)"/>
+  <rect x="0" y="0" width="20" height="20" fill="blue"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+  <rect x="0" y="0" width="20" height="20" fill="blue"/>
+</svg>
+"##,
+);
+
+// The filtered rectangle turns the alpha of its background into black.  Only the blue
+// rectangle is in the background, since the group starts a new one.
+test_compare_render_output!(
+    background_image_starts_at_enable_background_new,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter" x="0" y="0" width="1" height="1">
+    <feColorMatrix in="BackgroundImage" type="matrix"
+                   values="0 0 0 0 0  0 0 0 0 0  0 0 0 0 0  0 0 0 1 0"/>
+  </filter>
+  <rect width="100" height="100" fill="lime"/>
+  <g enable-background="new">
+    <rect width="100" height="50" fill="blue"/>
+    <rect width="100" height="100" filter="url(#filter)"/>
+  </g>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect width="100" height="100" fill="lime"/>
+  <rect width="100" height="50" fill="black"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    background_image_starts_at_isolation_isolate,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter" x="0" y="0" width="1" height="1">
+    <feColorMatrix in="BackgroundImage" type="matrix"
+                   values="0 0 0 0 0  0 0 0 0 0  0 0 0 0 0  0 0 0 1 0"/>
+  </filter>
+  <rect width="100" height="100" fill="lime"/>
+  <g style="isolation: isolate">
+    <rect width="100" height="50" fill="blue"/>
+    <rect width="100" height="100" filter="url(#filter)"/>
+  </g>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect width="100" height="100" fill="lime"/>
+  <rect width="100" height="50" fill="black"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    background_image_accumulates_without_backdrop_root,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter" x="0" y="0" width="1" height="1">
+    <feColorMatrix in="BackgroundImage" type="matrix"
+                   values="0 0 0 0 0  0 0 0 0 0  0 0 0 0 0  0 0 0 1 0"/>
+  </filter>
+  <rect width="100" height="100" fill="lime"/>
+  <g opacity="0.999">
+    <rect width="100" height="50" fill="blue"/>
+    <rect width="100" height="100" filter="url(#filter)"/>
+  </g>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect width="100" height="100" fill="lime"/>
+  <rect width="100" height="100" fill="black" opacity="0.999"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    backdrop_filter_filters_what_is_behind,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect width="100" height="100" fill="lime"/>
+  <rect x="25" y="25" width="50" height="50" fill-opacity="0" style="backdrop-filter: invert(1)"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect width="100" height="100" fill="lime"/>
+  <rect x="25" y="25" width="50" height="50" fill="magenta"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    backdrop_filter_stops_at_backdrop_root,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect width="100" height="100" fill="lime"/>
+  <g style="isolation: isolate">
+    <rect width="50" height="100" fill="blue"/>
+    <rect width="100" height="100" fill-opacity="0" style="backdrop-filter: invert(1)"/>
+  </g>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect width="100" height="100" fill="lime"/>
+  <rect width="50" height="100" fill="yellow"/>
+</svg>
+"##,
+);
+
+fn render_with_quality(svg: &[u8], quality: RenderQuality) -> SharedImageSurface {
+    let svg = load_svg(svg).unwrap();
+
+    let output = cairo::ImageSurface::create(cairo::Format::ARgb32, 100, 100).unwrap();
+
+    {
+        let cr = cairo::Context::new(&output).expect("Failed to create a cairo context");
+        CairoRenderer::new(&svg)
+            .with_quality(quality)
+            .render_document(&cr, &cairo::Rectangle::new(0.0, 0.0, 100.0, 100.0))
+            .unwrap();
+    }
+
+    SharedImageSurface::wrap(output, SurfaceType::SRgb).unwrap()
+}
+
+#[test]
+fn best_quality_blur_duplicates_edges_in_transformed_user_space() {
+    // The content covers the whole filter region, so blurring it with duplicated edges
+    // must not change it, whether the user space is aligned with the pixels, rotated, or
+    // skewed.  The transformed cases resample the surface to blur it along the user
+    // space's axes.
+    for transform in ["", "rotate(30 50 50)", "skewX(20)"] {
+        let document = |primitive: &str| {
+            format!(
+                r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter" filterUnits="userSpaceOnUse" x="20" y="20" width="40" height="40">
+    {primitive}
+  </filter>
+  <g transform="{transform}">
+    <rect x="-100" y="-100" width="300" height="300" fill="blue" filter="url(#filter)"/>
+  </g>
+</svg>
+"##
+            )
+        };
+
+        let output_surf = render_with_quality(
+            document(r#"<feGaussianBlur stdDeviation="4" edgeMode="duplicate"/>"#).as_bytes(),
+            RenderQuality::Best,
+        );
+
+        let reference_surf = render_with_quality(
+            document(r#"<feOffset dx="0" dy="0"/>"#).as_bytes(),
+            RenderQuality::Best,
+        );
+
+        Reference::from_surface(reference_surf.into_image_surface().unwrap())
+            .compare(&output_surf)
+            .evaluate(
+                &output_surf,
+                "best_quality_blur_duplicates_edges_in_transformed_user_space",
+            );
+    }
+}
+
+#[test]
+fn best_quality_blur_follows_rotated_user_space() {
+    // Blurring along the x axis of a user space rotated by 90 degrees is the same as
+    // blurring along the y axis of an unrotated one.
+    let output_surf = render_with_quality(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter">
+    <feGaussianBlur stdDeviation="8 0"/>
+  </filter>
+  <g transform="rotate(90 50 50)">
+    <rect x="30" y="40" width="40" height="20" fill="blue" filter="url(#filter)"/>
+  </g>
+</svg>
+"##,
+        RenderQuality::Best,
+    );
+
+    let reference_surf = render_with_quality(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter">
+    <feGaussianBlur stdDeviation="0 8"/>
+  </filter>
+  <rect x="40" y="30" width="20" height="40" fill="blue" filter="url(#filter)"/>
+</svg>
+"##,
+        RenderQuality::Best,
+    );
+
+    Reference::from_surface(reference_surf.into_image_surface().unwrap())
+        .compare(&output_surf)
+        .evaluate(&output_surf, "best_quality_blur_follows_rotated_user_space");
+}
@@ -92,6 +92,44 @@ fn no_intrinsic_size_in_pixels_with_one_missing_dimension() {
     assert_eq!(CairoRenderer::new(&svg).intrinsic_size_in_pixels(), None);
 }
 
+#[test]
+fn intrinsic_dimensions_with_math_functions() {
+    let svg = load_svg(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="calc(1in + 4px)" height="calc(50% + 10px)"/>
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        CairoRenderer::new(&svg).intrinsic_dimensions(),
+        IntrinsicDimensions {
+            width: Length::new(100.0, LengthUnit::Px),
+            height: Length::new(1.0, LengthUnit::Percent),
+            vbox: None,
+        }
+    );
+}
+
+#[test]
+fn intrinsic_dimensions_with_new_units() {
+    let svg = load_svg(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" font-size="10" width="2rem" height="10vh"/>
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        CairoRenderer::new(&svg).intrinsic_dimensions(),
+        IntrinsicDimensions {
+            width: Length::new(20.0, LengthUnit::Px),
+            height: Length::new(1.0, LengthUnit::Percent),
+            vbox: None,
+        }
+    );
+}
+
 #[test]
 fn root_geometry_with_percent_viewport() {
     let svg = load_svg(
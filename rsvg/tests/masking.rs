@@ -0,0 +1,157 @@
+use rsvg::test_compare_render_output;
+
+test_compare_render_output!(
+    mask_image_gradient,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="200" fill="lime"
+        style="mask-image: linear-gradient(to right, transparent, black)"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <defs>
+    <linearGradient id="gradient" x1="0" y1="0" x2="1" y2="0">
+      <stop offset="0" stop-color="black" stop-opacity="0"/>
+      <stop offset="1" stop-color="black"/>
+    </linearGradient>
+    <mask id="mask" maskContentUnits="objectBoundingBox" style="mask-type: alpha">
+      <rect x="0" y="0" width="1" height="1" fill="url(#gradient)"/>
+    </mask>
+  </defs>
+
+  <rect x="100" y="100" width="200" height="200" fill="lime" mask="url(#mask)"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    mask_shorthand_positions_and_sizes_layer,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="200" fill="lime"
+        style="mask: linear-gradient(black, black) right bottom / 50% 50% no-repeat"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="200" y="200" width="100" height="100" fill="lime"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    mask_repeat_tiles_layer,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="200" fill="lime"
+        style="mask-image: linear-gradient(to right, black 50%, transparent 50%);
+               mask-size: 50px 100%;
+               mask-repeat: repeat-x"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="25" height="200" fill="lime"/>
+  <rect x="150" y="100" width="25" height="200" fill="lime"/>
+  <rect x="200" y="100" width="25" height="200" fill="lime"/>
+  <rect x="250" y="100" width="25" height="200" fill="lime"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    mask_composite_exclude,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="200" fill="lime"
+        style="mask-image: linear-gradient(black, black), linear-gradient(black, black);
+               mask-size: 50% 100%, 100% 50%;
+               mask-repeat: no-repeat;
+               mask-composite: exclude"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="200" y="100" width="100" height="100" fill="lime"/>
+  <rect x="100" y="200" width="100" height="100" fill="lime"/>
+</svg>
+"##,
+);
+
+// The temporary surface for a masked group only covers its content and the mask's
+// rectangle; the parts of both that stick out of each other must not be lost.
+test_compare_render_output!(
+    mask_element_smaller_than_content,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <mask id="mask" maskUnits="userSpaceOnUse" x="150" y="50" width="100" height="300">
+    <rect x="0" y="0" width="400" height="400" fill="white"/>
+  </mask>
+  <g mask="url(#mask)" opacity="0.5">
+    <rect x="-100" y="100" width="600" height="100" fill="lime"/>
+    <rect x="100" y="250" width="100" height="50" fill="blue"/>
+  </g>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <g opacity="0.5">
+    <rect x="150" y="100" width="100" height="100" fill="lime"/>
+    <rect x="150" y="250" width="50" height="50" fill="blue"/>
+  </g>
+</svg>
+"##,
+);
+
+// A mask reference to a missing element gets ignored, so the element is not masked.
+test_compare_render_output!(
+    mask_reference_to_nonexistent_element_is_ignored,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="0" y="0" width="200" height="200" fill="lime" mask="url(#nonexistent)"/>
+  <rect x="200" y="0" width="200" height="200" fill="lime"
+        style="mask-image: linear-gradient(black, black), url(#nonexistent);
+               mask-composite: intersect"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="0" y="0" width="400" height="200" fill="lime"/>
+</svg>
+"##,
+);
+
+// A none layer counts as a transparent black layer, so it takes part in mask-composite.
+test_compare_render_output!(
+    none_mask_layer_is_transparent,
+    400,
+    200,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="200">
+  <rect x="0" y="0" width="200" height="200" fill="lime"
+        style="mask-image: linear-gradient(black, black), none;
+               mask-composite: add"/>
+  <rect x="200" y="0" width="200" height="200" fill="lime"
+        style="mask-image: linear-gradient(black, black), none;
+               mask-composite: intersect"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="200">
+  <rect x="0" y="0" width="200" height="200" fill="lime"/>
+</svg>
+"##,
+);
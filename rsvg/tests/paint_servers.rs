@@ -0,0 +1,108 @@
+use rsvg::CairoRenderer;
+use rsvg::test_compare_render_output;
+use rsvg::test_utils::reference_utils::{Compare, Evaluate, Reference};
+use rsvg::test_utils::{SurfaceSize, load_svg, render_document};
+
+test_compare_render_output!(
+    linear_gradient_function_fill,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="100"
+        style="fill: linear-gradient(to right, lime, blue)"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <defs>
+    <linearGradient id="gradient" x1="0" y1="0" x2="1" y2="0">
+      <stop offset="0" stop-color="lime"/>
+      <stop offset="1" stop-color="blue"/>
+    </linearGradient>
+  </defs>
+
+  <rect x="100" y="100" width="200" height="100" fill="url(#gradient)"/>
+</svg>
+"##,
+);
+
+test_compare_render_output!(
+    conic_gradient_function_fill,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="200"
+        style="fill: conic-gradient(lime 0deg 90deg, blue 90deg)"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="200" fill="blue"/>
+  <rect x="200" y="100" width="100" height="100" fill="lime"/>
+</svg>
+"##,
+);
+
+// Recording surfaces without extents have an enormous clip region; the image for a conic
+// gradient must only cover the shape, not the clip region.
+#[test]
+fn conic_gradient_on_unbounded_recording_surface() {
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="100" y="100" width="200" height="200"
+        style="fill: conic-gradient(lime 0deg 90deg, blue 90deg)"/>
+</svg>
+"##,
+    )
+    .unwrap();
+
+    let viewport = cairo::Rectangle::new(0.0, 0.0, 400.0, 400.0);
+
+    let recording = cairo::RecordingSurface::create(cairo::Content::ColorAlpha, None).unwrap();
+
+    {
+        let cr = cairo::Context::new(&recording).expect("Failed to create a cairo context");
+        CairoRenderer::new(&svg)
+            .render_document(&cr, &viewport)
+            .unwrap();
+    }
+
+    let reference_surf = cairo::ImageSurface::create(cairo::Format::ARgb32, 400, 400).unwrap();
+
+    {
+        let cr = cairo::Context::new(&reference_surf).expect("Failed to create a cairo context");
+        cr.set_source_surface(&recording, 0.0, 0.0).unwrap();
+        cr.paint().unwrap();
+    }
+
+    let output_surf = render_document(&svg, SurfaceSize(400, 400), |_| (), viewport).unwrap();
+
+    Reference::from_surface(reference_surf)
+        .compare(&output_surf)
+        .evaluate(
+            &output_surf,
+            "conic_gradient_on_unbounded_recording_surface",
+        );
+}
+
+// Shapes much larger than the target get a gradient image that only covers the target.
+test_compare_render_output!(
+    conic_gradient_on_huge_shape,
+    400,
+    400,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="-999800" y="-999800" width="2000000" height="2000000"
+        style="fill: conic-gradient(lime 0deg 90deg, blue 90deg)"/>
+</svg>
+"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="400" height="400">
+  <rect x="0" y="0" width="400" height="400" fill="blue"/>
+  <rect x="200" y="0" width="200" height="200" fill="lime"/>
+</svg>
+"##,
+);
@@ -364,6 +364,168 @@ test_compare_render_output!(
 </svg>"##,
 );
 
+test_compare_render_output!(
+    lengths_with_math_functions,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" font-size="10">
+  <rect x="calc(50% - 10px)" y="min(10px, 1em)" width="calc(2 * (5px + 0.5em))" height="clamp(0px, 50% - 1em, 30px)"/>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect x="40" y="10" width="20" height="30"/>
+</svg>"##,
+);
+
+#[test]
+fn lengths_with_font_and_viewport_units() {
+    // Render at twice the document's natural size.  Viewport units must be resolved
+    // against the outermost <svg>'s viewport, not against the rendering viewport.
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" font-size="10">
+  <g font-size="20">
+    <rect x="4rem" y="10vh" width="1lh" height="calc(20vw + 1rem)"/>
+  </g>
+</svg>"##,
+    )
+    .unwrap();
+
+    let output_surf = render_document(
+        &svg,
+        SurfaceSize(200, 200),
+        |_| (),
+        cairo::Rectangle::new(0.0, 0.0, 200.0, 200.0),
+    )
+    .unwrap();
+
+    let reference_surf = cairo::ImageSurface::create(cairo::Format::ARgb32, 200, 200).unwrap();
+
+    {
+        let cr = cairo::Context::new(&reference_surf).expect("Failed to create a cairo context");
+
+        cr.scale(2.0, 2.0);
+        cr.rectangle(40.0, 10.0, 20.0, 30.0);
+        cr.fill().unwrap();
+    }
+
+    Reference::from_surface(reference_surf)
+        .compare(&output_surf)
+        .evaluate(&output_surf, "lengths_with_font_and_viewport_units");
+}
+
+test_compare_render_output!(
+    path_length_scales_dashes,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <line x1="0" y1="10" x2="100" y2="10" stroke="black" stroke-width="10"
+        pathLength="10" stroke-dasharray="2 3" stroke-dashoffset="1"/>
+  <rect x="10" y="30" width="80" height="60" fill="none" stroke="black" stroke-width="4"
+        pathLength="28" stroke-dasharray="8 6"/>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <line x1="0" y1="10" x2="100" y2="10" stroke="black" stroke-width="10"
+        stroke-dasharray="20 30" stroke-dashoffset="10"/>
+  <rect x="10" y="30" width="80" height="60" fill="none" stroke="black" stroke-width="4"
+        stroke-dasharray="80 60"/>
+</svg>"##,
+);
+
+test_compare_render_output!(
+    miter_clip_and_arcs_joins_within_limit_are_miters,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <polyline points="10 40, 40 40, 40 10" fill="none" stroke="blue" stroke-width="10"
+            stroke-opacity="0.5" stroke-linejoin="miter-clip"/>
+  <polygon points="60 60, 90 60, 90 90, 60 90" fill="none" stroke="blue" stroke-width="10"
+           stroke-opacity="0.5" stroke-linejoin="arcs"/>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <polyline points="10 40, 40 40, 40 10" fill="none" stroke="blue" stroke-width="10"
+            stroke-opacity="0.5" stroke-linejoin="miter"/>
+  <polygon points="60 60, 90 60, 90 90, 60 90" fill="none" stroke="blue" stroke-width="10"
+           stroke-opacity="0.5" stroke-linejoin="miter"/>
+</svg>"##,
+);
+
+test_compare_render_output!(
+    markers_on_basic_shapes,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <defs>
+    <marker id="dot" markerWidth="4" markerHeight="4" refX="2" refY="2" markerUnits="userSpaceOnUse">
+      <rect width="4" height="4" fill="blue"/>
+    </marker>
+  </defs>
+  <g fill="none" stroke="none" marker-start="url(#dot)" marker-mid="url(#dot)" marker-end="url(#dot)">
+    <rect x="10" y="10" width="30" height="20"/>
+    <circle cx="70" cy="25" r="15"/>
+    <ellipse cx="50" cy="70" rx="30" ry="15"/>
+  </g>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <defs>
+    <marker id="dot" markerWidth="4" markerHeight="4" refX="2" refY="2" markerUnits="userSpaceOnUse">
+      <rect width="4" height="4" fill="blue"/>
+    </marker>
+  </defs>
+  <g fill="none" stroke="none" marker-start="url(#dot)" marker-mid="url(#dot)" marker-end="url(#dot)">
+    <path d="M 10 10 H 40 V 30 H 10 Z"/>
+    <path d="M 85 25 A 15 15 0 0 1 70 40 A 15 15 0 0 1 55 25 A 15 15 0 0 1 70 10 A 15 15 0 0 1 85 25 Z"/>
+    <path d="M 80 70 A 30 15 0 0 1 50 85 A 30 15 0 0 1 20 70 A 30 15 0 0 1 50 55 A 30 15 0 0 1 80 70 Z"/>
+  </g>
+</svg>"##,
+);
+
+test_compare_render_output!(
+    vector_effect_non_scaling_size_and_non_rotation,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <defs>
+    <path id="pin" d="M -5 -10 h 10 v 10 h -10 z" fill="blue"/>
+  </defs>
+  <g transform="translate(20 10) scale(3)">
+    <use href="#pin" x="10" y="10" vector-effect="non-scaling-size"/>
+  </g>
+  <g transform="translate(50 50) rotate(180) scale(2)">
+    <use href="#pin" x="10" y="0" vector-effect="non-scaling-size non-rotation"/>
+  </g>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect x="45" y="30" width="10" height="10" fill="blue"/>
+  <rect x="25" y="40" width="10" height="10" fill="blue"/>
+</svg>"##,
+);
+
+test_compare_render_output!(
+    vector_effect_fixed_position,
+    100,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <g transform="translate(30 30) scale(2)">
+    <rect width="10" height="10" transform="translate(5 5)" vector-effect="fixed-position" fill="blue"/>
+  </g>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <rect x="5" y="5" width="20" height="20" fill="blue"/>
+</svg>"##,
+);
+
 test_svg_reference!(
     invalid_element_type_for_paint_server,
     "tests/fixtures/reftests/invalid-element-type-for-paint-server.svg",
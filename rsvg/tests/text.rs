@@ -28,6 +28,130 @@ test_compare_render_output!(
 </svg>"##,
 );
 
+test_compare_render_output!(
+    text_length_spacing_and_glyphs,
+    500,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <text style="font: 50px Ahem;" x="50" y="50" fill="black"
+        textLength="300" lengthAdjust="spacingAndGlyphs">abc</text>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <rect x="50" y="10" width="300" height="50" fill="black"/>
+</svg>"##,
+);
+
+test_compare_render_output!(
+    text_length_spacing_and_glyphs_nested_tspan,
+    500,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <text style="font: 50px Ahem;" x="50" y="50" fill="black"
+        textLength="400" lengthAdjust="spacingAndGlyphs">ab<tspan
+        textLength="50" lengthAdjust="spacingAndGlyphs">cd</tspan></text>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <rect x="50" y="10" width="400" height="50" fill="black"/>
+</svg>"##,
+);
+
+// With lengthAdjust="spacing", the extra space gets added after each character, just
+// like with letter-spacing.
+test_compare_render_output!(
+    text_length_spacing,
+    500,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <text style="font: 50px Ahem;" x="50" y="50" fill="black"
+        textLength="300" lengthAdjust="spacing">abc</text>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <text style="font: 50px Ahem; letter-spacing: 50px;" x="50" y="50" fill="black">abc</text>
+</svg>"##,
+);
+
+// In vertical text, textLength applies to the vertical advance, and percentages refer to
+// the viewport's height: 60% of 500 is 300, twice the length of the text.
+test_compare_render_output!(
+    text_length_spacing_and_glyphs_vertical,
+    200,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="500">
+  <text style="font: 50px Ahem; writing-mode: vertical-rl; text-orientation: upright;"
+        x="100" y="50" fill="black"
+        textLength="60%" lengthAdjust="spacingAndGlyphs">abc</text>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="500">
+  <text style="font: 50px Ahem; writing-mode: vertical-rl; text-orientation: upright;"
+        x="100" y="50" fill="black"
+        transform="translate(0 50) scale(1 2) translate(0 -50)">abc</text>
+</svg>"##,
+);
+
+test_compare_render_output!(
+    text_length_spacing_vertical,
+    200,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="500">
+  <text style="font: 50px Ahem; writing-mode: vertical-rl; text-orientation: upright;"
+        x="100" y="50" fill="black"
+        textLength="60%" lengthAdjust="spacing">abc</text>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="500">
+  <text style="font: 50px Ahem; writing-mode: vertical-rl; text-orientation: upright;
+               letter-spacing: 50px;"
+        x="100" y="50" fill="black">abc</text>
+</svg>"##,
+);
+
+// Each glyph in Ahem is a box that goes from -0.8em to 0.2em vertically relative to the
+// baseline; rotating it by 90 degrees around its origin at the baseline moves it to the
+// right by 0.2em, and below the baseline.
+test_compare_render_output!(
+    rotate_per_glyph,
+    500,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <text style="font: 50px Ahem;" x="50" y="50" fill="black" rotate="90 0">a<tspan rotate="90">bc</tspan>d</text>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <rect x="40" y="50" width="50" height="50" fill="black"/>
+  <rect x="90" y="50" width="50" height="50" fill="black"/>
+  <rect x="140" y="50" width="50" height="50" fill="black"/>
+  <rect x="200" y="10" width="50" height="50" fill="black"/>
+</svg>"##,
+);
+
+// A combining mark is rotated along with its base character, so the rotation value for
+// the mark itself is not used.
+test_compare_render_output!(
+    rotate_per_grapheme_cluster,
+    500,
+    200,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="200">
+  <text style="font: 50px sans-serif;" x="50" y="100" fill="black" rotate="30 80 0 -20">e&#x301;ab</text>
+  <text style="font: 50px sans-serif;" x="250" y="100" fill="black" rotate="0 90 0">&#x644;&#x64e;&#x627;</text>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="200">
+  <text style="font: 50px sans-serif;" x="50" y="100" fill="black" rotate="30 30 0 -20">e&#x301;ab</text>
+  <text style="font: 50px sans-serif;" x="250" y="100" fill="black">&#x644;&#x64e;&#x627;</text>
+</svg>"##,
+);
+
 test_svg_reference!(
     text_anchor_chunk_806,
     "tests/fixtures/text/bug806-text-anchor-chunk.svg",
@@ -129,3 +253,102 @@ fn test_text_layer_geometry() {
         );
     }
 }
+
+test_compare_render_output!(
+    foreign_object_xhtml_paragraphs,
+    500,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <foreignObject x="50" y="50" width="400" height="400">
+    <div xmlns="http://www.w3.org/1999/xhtml" style="font: 50px Ahem; color: black;">
+      ab<br/>c
+      <p style="text-align: center;">de</p>
+      <div style="text-align: right;"><span style="color: lime;">f</span></div>
+    </div>
+  </foreignObject>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <rect x="50" y="50" width="100" height="50" fill="black"/>
+  <rect x="50" y="100" width="50" height="50" fill="black"/>
+  <rect x="200" y="150" width="100" height="50" fill="black"/>
+  <rect x="400" y="200" width="50" height="50" fill="lime"/>
+</svg>"##,
+);
+
+test_compare_render_output!(
+    foreign_object_xhtml_wraps_and_clips,
+    500,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <foreignObject x="50" y="50" width="200" height="100">
+    <div xmlns="http://www.w3.org/1999/xhtml" style="font: 50px Ahem; color: black;">
+      ab cd ef
+    </div>
+  </foreignObject>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <rect x="50" y="50" width="100" height="50" fill="black"/>
+  <rect x="50" y="100" width="100" height="50" fill="black"/>
+</svg>"##,
+);
+
+// Justification stretches the space between "aa" and "bb" so that the first line fills the
+// width of the viewport.  The last line is not justified.
+test_compare_render_output!(
+    foreign_object_xhtml_justify,
+    500,
+    500,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <foreignObject x="50" y="50" width="350" height="400">
+    <div xmlns="http://www.w3.org/1999/xhtml"
+         style="font: 50px Ahem; color: black; text-align: justify;">
+      aa bb ccc
+    </div>
+  </foreignObject>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="500" height="500">
+  <rect x="50" y="50" width="100" height="50" fill="black"/>
+  <rect x="300" y="50" width="100" height="50" fill="black"/>
+  <rect x="50" y="100" width="150" height="50" fill="black"/>
+</svg>"##,
+);
+
+// This is how draw.io exports a shape's label: an XHTML label that uses flexbox and
+// padding for positioning, inside a <switch> with a plain <text> fallback.  Librsvg
+// cannot lay out that XHTML, so it must pick the fallback.
+test_compare_render_output!(
+    drawio_switch_picks_text_fallback,
+    200,
+    100,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+  <g>
+    <rect x="20" y="20" width="160" height="60" fill="none" stroke="black"/>
+  </g>
+  <g>
+    <g transform="translate(-0.5 -0.5)">
+      <switch>
+        <foreignObject style="overflow: visible; text-align: left;" pointer-events="none" width="100%" height="100%" requiredFeatures="http://www.w3.org/TR/SVG11/feature#Extensibility">
+          <div xmlns="http://www.w3.org/1999/xhtml" style="display: flex; align-items: unsafe center; justify-content: unsafe center; width: 158px; height: 1px; padding-top: 50px; margin-left: 21px;">
+            <div data-drawio-colors="color: rgb(0, 0, 0); " style="box-sizing: border-box; font-size: 0px; text-align: center;">
+              <div style="display: inline-block; font-size: 20px; font-family: Ahem; color: rgb(0, 0, 0); line-height: 1.2; pointer-events: all; white-space: normal; overflow-wrap: normal;">Label</div>
+            </div>
+          </div>
+        </foreignObject>
+        <text x="100" y="56" fill="rgb(0, 0, 0)" font-family="Ahem" font-size="20px" text-anchor="middle">Label</text>
+      </switch>
+    </g>
+  </g>
+</svg>"##,
+    br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+  <rect x="20" y="20" width="160" height="60" fill="none" stroke="black"/>
+  <text x="99.5" y="55.5" fill="rgb(0, 0, 0)" font-family="Ahem" font-size="20px" text-anchor="middle">Label</text>
+</svg>"##,
+);
@@ -200,6 +200,13 @@ pub fn build_cli() -> clap::Command {
                 .help("SVG id of object to export [default is to export all objects]")
                 .action(clap::ArgAction::Set),
         )
+        .arg(
+            clap::Arg::new("computed_style")
+                .long("computed-style")
+                .help("Print the computed CSS properties of the object given with --export-id instead of rendering it")
+                .requires("export_id")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             clap::Arg::new("accept-language")
                 .short('l')
@@ -258,6 +265,21 @@ pub fn build_cli() -> clap::Command {
                 .conflicts_with("keep_image_data")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            clap::Arg::new("text_to_path")
+                .long("text-to-path")
+                .help("Convert text to paths")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            clap::Arg::new("quality")
+                .long("quality")
+                .num_args(1)
+                .value_parser(["draft", "normal", "best"])
+                .default_value("normal")
+                .help("Trade-off between rendering speed and accuracy")
+                .action(clap::ArgAction::Set),
+        )
         .arg(
             clap::Arg::new("testing")
                 .long("testing")
@@ -309,8 +331,8 @@ fn is_absolute_unit(u: LengthUnit) -> bool {
     use LengthUnit::*;
 
     match u {
-        Percent | Em | Ex | Ch => false,
-        Px | In | Cm | Mm | Pt | Pc => true,
+        Percent | Em | Ex | Ch | Rem | Vw | Vh | Vmin | Vmax | Lh | Rlh | Ic | Cap => false,
+        Px | In | Cm | Mm | Pt | Pc | Q => true,
 
         // coverage: the following is because LengthUnit is marked non_exhaustive, but
         // the cases above should really test all the units librsvg knows about.
@@ -322,11 +344,12 @@ fn parse_length<N: Normalize, V: Validate>(s: &str) -> Result<CssLength<N, V>, S
     <CssLength<N, V> as Parse>::parse_str(s)
         .map_err(|_| format!("Invalid value: The argument '{s}' can not be parsed as a length"))
         .and_then(|l| {
-            if is_absolute_unit(l.unit) {
+            // Math functions that mix units may need a viewport or a font size.
+            if !l.is_calc() && is_absolute_unit(l.unit) {
                 Ok(l)
             } else {
                 Err(format!(
-                    "Invalid value '{s}': supported units are px, in, cm, mm, pt, pc"
+                    "Invalid value '{s}': supported units are px, in, cm, mm, Q, pt, pc"
                 ))
             }
         })
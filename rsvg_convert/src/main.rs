@@ -24,7 +24,10 @@ use rsvg::rsvg_convert_only::{
     AspectRatio, Color, CssLength, Dpi, Horizontal, Length, Normalize, NormalizeParams, Parse,
     Rect, ULength, Validate, Vertical, ViewBox, set_source_color_on_cairo,
 };
-use rsvg::{AcceptLanguage, CairoRenderer, Language, LengthUnit, Loader, RenderingError};
+use rsvg::{
+    AcceptLanguage, CairoRenderer, Language, LengthUnit, Loader, RenderQuality, RenderingError,
+    SvgHandle, TextMode,
+};
 
 use rsvg_convert::*;
 
@@ -517,12 +520,15 @@ struct Converter {
     pub page_size: Option<(ULength<Horizontal>, ULength<Vertical>)>,
     pub format: Format,
     pub export_id: Option<String>,
+    pub computed_style: bool,
     pub keep_aspect_ratio: bool,
     pub background_color: Option<Color>,
     pub stylesheet: Option<PathBuf>,
     pub language: Language,
     pub unlimited: bool,
     pub keep_image_data: bool,
+    pub text_to_path: bool,
+    pub quality: RenderQuality,
     pub input: Vec<Input>,
     pub output: Output,
     pub testing: bool,
@@ -539,11 +545,14 @@ impl Converter {
 
         let mut surface: Option<Surface> = None;
 
+        // With --computed-style, the declarations for all the inputs go to the same output.
+        let mut style_stream: Option<OutputStream> = None;
+
         // Use user units per default
         let mut unit = LengthUnit::Px;
 
         fn set_unit<N: Normalize, V: Validate>(
-            l: CssLength<N, V>,
+            l: &CssLength<N, V>,
             p: &NormalizeParams,
             u: LengthUnit,
         ) -> f64 {
@@ -591,11 +600,26 @@ impl Converter {
                     .map_err(|e| error!("Error applying stylesheet: {}", e))?;
             }
 
-            let renderer = CairoRenderer::new(&handle)
+            if self.computed_style {
+                let stream = match &mut style_stream {
+                    Some(stream) => stream,
+                    stream @ None => stream.insert(self.create_output_stream()?),
+                };
+
+                self.write_computed_style(&handle, input, stream)?;
+                continue;
+            }
+
+            let mut renderer = CairoRenderer::new(&handle)
                 .with_dpi(self.dpi_x.0, self.dpi_y.0)
                 .with_language(&self.language)
+                .with_quality(self.quality)
                 .test_mode(self.testing);
 
+            if self.text_to_path {
+                renderer = renderer.with_text_rendering_mode(TextMode::Outlines);
+            }
+
             let geometry = natural_geometry(&renderer, input, self.export_id.as_deref())?;
 
             let natural_size = Size::new(geometry.width(), geometry.height());
@@ -608,9 +632,9 @@ impl Converter {
                     // PNG surface requires units in pixels
                     (
                         natural_size,
-                        self.width.map(|l| l.to_user(&params)),
-                        self.height.map(|l| l.to_user(&params)),
-                        self.page_size.map(|(w, h)| Size {
+                        self.width.as_ref().map(|l| l.to_user(&params)),
+                        self.height.as_ref().map(|l| l.to_user(&params)),
+                        self.page_size.as_ref().map(|(w, h)| Size {
                             w: w.to_user(&params),
                             h: h.to_user(&params),
                         }),
@@ -634,9 +658,9 @@ impl Converter {
                             h: ULength::<Vertical>::new(natural_size.h, LengthUnit::Px)
                                 .to_points(&params),
                         },
-                        self.width.map(|l| l.to_points(&params)),
-                        self.height.map(|l| l.to_points(&params)),
-                        self.page_size.map(|(w, h)| Size {
+                        self.width.as_ref().map(|l| l.to_points(&params)),
+                        self.height.as_ref().map(|l| l.to_points(&params)),
+                        self.page_size.as_ref().map(|(w, h)| Size {
                             w: w.to_points(&params),
                             h: h.to_points(&params),
                         }),
@@ -644,8 +668,10 @@ impl Converter {
                 }
 
                 Format::Svg => {
-                    let (w_unit, h_unit) =
-                        (self.width.map(|l| l.unit), self.height.map(|l| l.unit));
+                    let (w_unit, h_unit) = (
+                        self.width.as_ref().map(|l| l.unit),
+                        self.height.as_ref().map(|l| l.unit),
+                    );
 
                     unit = match (w_unit, h_unit) {
                         (None, None) => LengthUnit::Px,
@@ -663,19 +689,19 @@ impl Converter {
                     (
                         Size {
                             w: set_unit(
-                                ULength::<Horizontal>::new(natural_size.w, LengthUnit::Px),
+                                &ULength::<Horizontal>::new(natural_size.w, LengthUnit::Px),
                                 &params,
                                 unit,
                             ),
                             h: set_unit(
-                                ULength::<Vertical>::new(natural_size.h, LengthUnit::Px),
+                                &ULength::<Vertical>::new(natural_size.h, LengthUnit::Px),
                                 &params,
                                 unit,
                             ),
                         },
-                        self.width.map(|l| set_unit(l, &params, unit)),
-                        self.height.map(|l| set_unit(l, &params, unit)),
-                        self.page_size.map(|(w, h)| Size {
+                        self.width.as_ref().map(|l| set_unit(l, &params, unit)),
+                        self.height.as_ref().map(|l| set_unit(l, &params, unit)),
+                        self.page_size.as_ref().map(|(w, h)| Size {
                             w: set_unit(w, &params, unit),
                             h: set_unit(h, &params, unit),
                         }),
@@ -739,8 +765,16 @@ impl Converter {
                 surface @ None => surface.insert(self.create_surface(page_size, unit)?),
             };
 
-            let left = self.left.map(|l| set_unit(l, &params, unit)).unwrap_or(0.0);
-            let top = self.top.map(|l| set_unit(l, &params, unit)).unwrap_or(0.0);
+            let left = self
+                .left
+                .as_ref()
+                .map(|l| set_unit(l, &params, unit))
+                .unwrap_or(0.0);
+            let top = self
+                .top
+                .as_ref()
+                .map(|l| set_unit(l, &params, unit))
+                .unwrap_or(0.0);
 
             s.render(
                 &renderer,
@@ -774,18 +808,57 @@ impl Converter {
     }
 
     fn create_surface(&self, size: Size, unit: LengthUnit) -> Result<Surface, Error> {
-        let output_stream = match self.output {
-            Output::Stdout => Stdout::stream(),
+        let output_stream = self.create_output_stream()?;
+
+        Surface::new(self.format, size, output_stream, unit)
+    }
+
+    fn create_output_stream(&self) -> Result<OutputStream, Error> {
+        match self.output {
+            Output::Stdout => Ok(Stdout::stream()),
             Output::Path(ref p) => {
                 let file = gio::File::for_path(p);
                 let stream = file
                     .replace(None, false, FileCreateFlags::NONE, None::<&Cancellable>)
                     .map_err(|e| error!("Error opening output \"{}\": {}", self.output, e))?;
-                stream.upcast::<OutputStream>()
+                Ok(stream.upcast::<OutputStream>())
             }
-        };
+        }
+    }
 
-        Surface::new(self.format, size, output_stream, unit)
+    /// Writes the computed values of the CSS properties for the element given
+    /// with `--export-id` to `stream`, one `name: value;` declaration per line.
+    fn write_computed_style(
+        &self,
+        handle: &SvgHandle,
+        input: &Input,
+        stream: &OutputStream,
+    ) -> Result<(), Error> {
+        // clap ensures that --computed-style is only accepted along with --export-id
+        let id = self.export_id.as_deref().unwrap();
+
+        let style = handle.computed_style(id).map_err(|e| match e {
+            RenderingError::IdNotFound => {
+                error!("File {} does not have an object with id \"{}\"", input, id)
+            }
+            _ => error!("Error computing style for {}: {}", input, e),
+        })?;
+
+        let css: String = style
+            .properties()
+            .into_iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{name}: {value};\n"),
+                None => format!("/* {name}: unserializable */\n"),
+            })
+            .collect();
+
+        stream
+            .write_all(css.as_bytes(), None::<&Cancellable>)
+            .and_then(|_| stream.flush(None::<&Cancellable>))
+            .map_err(|e| error!("Error writing output \"{}\": {}", self.output, e))?;
+
+        Ok(())
     }
 }
 
@@ -914,6 +987,17 @@ fn parse_args() -> Result<Converter, Error> {
         _ => matches.get_flag("keep_image_data"),
     };
 
+    let quality_str: &String = matches
+        .get_one("quality")
+        .expect("already provided default_value");
+
+    let quality = match quality_str.as_str() {
+        "draft" => RenderQuality::Draft,
+        "normal" => RenderQuality::Normal,
+        "best" => RenderQuality::Best,
+        _ => unreachable!("clap should already have the list of possible values"),
+    };
+
     let language = match matches.get_one::<String>("accept-language") {
         None => Language::FromEnvironment,
         Some(s) => AcceptLanguage::parse(s)
@@ -938,14 +1022,14 @@ fn parse_args() -> Result<Converter, Error> {
         }
     };
 
-    let width: Option<ULength<Horizontal>> = matches.get_one("size_x").copied();
-    let height: Option<ULength<Vertical>> = matches.get_one("size_y").copied();
+    let width: Option<ULength<Horizontal>> = matches.get_one("size_x").cloned();
+    let height: Option<ULength<Vertical>> = matches.get_one("size_y").cloned();
 
-    let left: Option<Length<Horizontal>> = matches.get_one("left").copied();
-    let top: Option<Length<Vertical>> = matches.get_one("top").copied();
+    let left: Option<Length<Horizontal>> = matches.get_one("left").cloned();
+    let top: Option<Length<Vertical>> = matches.get_one("top").cloned();
 
-    let page_width: Option<ULength<Horizontal>> = matches.get_one("page_width").copied();
-    let page_height: Option<ULength<Vertical>> = matches.get_one("page_height").copied();
+    let page_width: Option<ULength<Horizontal>> = matches.get_one("page_width").cloned();
+    let page_height: Option<ULength<Vertical>> = matches.get_one("page_height").cloned();
 
     let page_size = match (page_width, page_height) {
         (None, None) => None,
@@ -984,7 +1068,12 @@ fn parse_args() -> Result<Converter, Error> {
         return Err(error!("Only one input file can be read from stdin."));
     }
 
-    if input.len() > 1 && !matches!(format, Format::Ps | Format::Eps | Format::Pdf) {
+    let computed_style = matches.get_flag("computed_style");
+
+    if input.len() > 1
+        && !computed_style
+        && !matches!(format, Format::Ps | Format::Eps | Format::Pdf)
+    {
         return Err(error!(
             "Multiple SVG files are only allowed for PDF and (E)PS output."
         ));
@@ -1011,11 +1100,14 @@ fn parse_args() -> Result<Converter, Error> {
         page_size,
         format,
         export_id,
+        computed_style,
         keep_aspect_ratio: matches.get_flag("keep_aspect"),
         background_color,
         stylesheet: matches.get_one("stylesheet").cloned(),
         unlimited: matches.get_flag("unlimited"),
         keep_image_data,
+        text_to_path: matches.get_flag("text_to_path"),
+        quality,
         language,
         input,
         output,
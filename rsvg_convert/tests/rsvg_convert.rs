@@ -1503,6 +1503,59 @@ fn export_id_option_error() {
         .stderr(starts_with("File stdin does not have an object with id \""));
 }
 
+#[test]
+fn computed_style_option() {
+    let mut rsvg_convert = RsvgConvert::new_with_input("tests/fixtures/geometry-element.svg");
+
+    rsvg_convert
+        .command
+        .arg("--export-id=foo")
+        .arg("--computed-style")
+        .assert()
+        .success()
+        .stdout(
+            contains("fill: rgb(0, 0, 255);\n")
+                .and(contains("opacity: 1;\n"))
+                .and(contains("/* filter: unserializable */\n")),
+        );
+}
+
+#[test]
+fn computed_style_option_writes_all_inputs_to_output_file() {
+    let input = Path::new("tests/fixtures/geometry-element.svg");
+    let output = {
+        let tempfile = Builder::new().suffix(".css").tempfile().unwrap();
+        tempfile.path().to_path_buf()
+    };
+    let mut rsvg_convert = RsvgConvert::new();
+
+    rsvg_convert
+        .command
+        .arg("--export-id=foo")
+        .arg("--computed-style")
+        .arg(format!("--output={}", output.display()))
+        .arg(input)
+        .arg(input)
+        .assert()
+        .success()
+        .stdout(is_empty());
+
+    let css = std::fs::read_to_string(&output).unwrap();
+    assert_eq!(css.matches("fill: rgb(0, 0, 255);\n").count(), 2);
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn computed_style_requires_export_id() {
+    let mut rsvg_convert = RsvgConvert::new_with_input("tests/fixtures/geometry-element.svg");
+
+    rsvg_convert
+        .command
+        .arg("--computed-style")
+        .assert()
+        .failure();
+}
+
 #[test]
 fn unlimited_option() {
     RsvgConvert::accepts_arg("--unlimited");
@@ -1626,6 +1679,72 @@ fn no_keep_image_data_option() {
     RsvgConvert::accepts_arg("--no-keep-image-data");
 }
 
+#[test]
+fn text_to_path_option() {
+    RsvgConvert::accepts_arg("--text-to-path");
+}
+
+#[cfg(system_deps_have_cairo_pdf)]
+#[test]
+fn text_to_path_option_leaves_no_text_in_pdf() {
+    let input = Path::new("tests/fixtures/hello-world.svg");
+    let mut rsvg_convert = RsvgConvert::new();
+
+    rsvg_convert
+        .command
+        .arg("--format=pdf")
+        .arg("--text-to-path")
+        .arg(input)
+        .assert()
+        .success()
+        .stdout(
+            file::is_pdf()
+                .and(file::is_pdf().with_text("Hello world!").not())
+                .and(file::is_pdf().with_text("Hello again!").not()),
+        );
+}
+
+#[test]
+fn quality_option() {
+    RsvgConvert::accepts_arg("--quality=draft");
+    RsvgConvert::accepts_arg("--quality=normal");
+    RsvgConvert::accepts_arg("--quality=best");
+}
+
+#[test]
+fn quality_option_changes_output() {
+    let render = |quality: &str| {
+        RsvgConvert::new_with_input("tests/fixtures/blur.svg")
+            .command
+            .arg(quality)
+            .assert()
+            .success()
+            .stdout(file::is_png().with_size(100, 100))
+            .get_output()
+            .stdout
+            .clone()
+    };
+
+    let draft = render("--quality=draft");
+    let normal = render("--quality=normal");
+    let best = render("--quality=best");
+
+    // Draft renders the blur at a lower resolution, normal approximates it with box
+    // blurs, and best computes it exactly.
+    assert_ne!(draft, normal);
+    assert_ne!(draft, best);
+    assert_ne!(best, normal);
+}
+
+#[test]
+fn invalid_quality_option() {
+    RsvgConvert::new_with_input("tests/fixtures/dpi.svg")
+        .command
+        .arg("--quality=fastest")
+        .assert()
+        .failure();
+}
+
 fn is_version_output() -> RegexPredicate {
     predicates::str::is_match(r"rsvg-convert version \d+\.\d+\.\d+").unwrap()
 }